@@ -1,5 +1,6 @@
+use crate::models::api_keys::API_KEY_PREFIX;
 use crate::models::crypto::get_current_timestamp;
-use crate::models::types::SessionDetails;
+use crate::models::types::AppEnv;
 
 use super::dtos::Claims;
 use super::error::AuthError;
@@ -13,14 +14,13 @@ use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     Error,
 };
-use dashmap::DashMap;
 use futures_util::future::LocalBoxFuture;
 
 // There are two steps in middleware processing.
 // 1. Middleware initialization, middleware factory gets called with
 //    next service in chain as parameter.
 // 2. Middleware's call method gets called with normal request.
-pub(crate) struct AuthenticationMiddleware(pub Arc<DashMap<String, SessionDetails>>);
+pub(crate) struct AuthenticationMiddleware(pub Arc<AppEnv>);
 
 // Middleware factory is `Transform` trait
 // `S` - type of the next service
@@ -40,14 +40,14 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(AuthenticationMiddlewareService {
             service,
-            active_sessions: self.0.clone(),
+            ain_env: self.0.clone(),
         }))
     }
 }
 
 pub(crate) struct AuthenticationMiddlewareService<S> {
     service: S,
-    active_sessions: Arc<DashMap<String, SessionDetails>>,
+    ain_env: Arc<AppEnv>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthenticationMiddlewareService<S>
@@ -63,7 +63,7 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let claims = authentication_middleware(&req, &self.active_sessions);
+        let claims = authentication_middleware(&req, &self.ain_env);
 
         let claims = match claims {
             Ok(claims) => claims,
@@ -80,29 +80,60 @@ where
     }
 }
 
-fn authentication_middleware(
-    req: &ServiceRequest,
-    active_sessions: &DashMap<String, SessionDetails>,
-) -> Result<Claims, AuthError> {
+fn authentication_middleware(req: &ServiceRequest, ain_env: &AppEnv) -> Result<Claims, AuthError> {
     let auth_header = req.headers().get(header::AUTHORIZATION);
     let auth_header = auth_header.ok_or(AuthError::InvalidToken)?;
     let auth_header = auth_header.to_str().map_err(|_| AuthError::InvalidToken)?;
 
     let mut header = auth_header.split_whitespace();
     let (_, access_token) = (header.next(), header.next().ok_or(AuthError::InvalidToken)?);
-    let session = active_sessions
+    validate_access_token(access_token, ain_env)
+}
+
+/// Resolves an access token, which is either a session token or an API key,
+/// to its claims. Expired sessions are kept around until their refresh token
+/// expires, so that they can be refreshed.
+pub(crate) fn validate_access_token(
+    access_token: &str,
+    ain_env: &AppEnv,
+) -> Result<Claims, AuthError> {
+    if access_token.starts_with(API_KEY_PREFIX) {
+        return validate_api_key(access_token, ain_env);
+    }
+    let session = ain_env
+        .active_sessions
         .get(access_token)
         .ok_or(AuthError::InvalidToken)?;
     let current_time = get_current_timestamp();
     if current_time >= session.expires_at {
-        drop(session);
-        active_sessions.remove(access_token);
         return Err(AuthError::InvalidToken);
     }
     let claims = Claims {
         iat: session.created_at,
         exp: session.expires_at,
-        username: session.user.username.clone(),
+        username: session.username,
+        api_key: None,
     };
     Ok(claims)
 }
+
+fn validate_api_key(key: &str, ain_env: &AppEnv) -> Result<Claims, AuthError> {
+    let api_key = ain_env
+        .api_keys
+        .validate_key(key)
+        .ok_or(AuthError::InvalidToken)?;
+    // the key is only as good as its user
+    let user = ain_env
+        .users_map
+        .get_user(&api_key.username)
+        .ok_or(AuthError::InvalidToken)?;
+    if user.disabled {
+        return Err(AuthError::Forbidden);
+    }
+    Ok(Claims {
+        iat: api_key.created_at,
+        exp: api_key.expires_at.unwrap_or(u64::MAX),
+        username: api_key.username,
+        api_key: Some(api_key.id),
+    })
+}
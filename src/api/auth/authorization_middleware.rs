@@ -0,0 +1,133 @@
+use crate::app_context::AppContext;
+use crate::models::rbac::{Permission, GLOBAL_SCOPE};
+
+use super::dtos::Claims;
+use super::error::AuthError;
+
+use std::future::{ready, Ready};
+
+use actix_web::web;
+use actix_web::HttpMessage;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+
+// Route level middleware that checks if the authenticated user has the
+// given permission on the collection in the request path.
+//
+// It must run after `AuthenticationMiddleware`, which is registered on the
+// enclosing scope and puts the `Claims` into the request extensions. Routes
+// without a `{collection_id}` segment are checked against the global scope.
+pub(crate) struct AuthorizationMiddleware(pub Permission);
+
+impl<S, B> Transform<S, ServiceRequest> for AuthorizationMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuthorizationMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthorizationMiddlewareService {
+            service,
+            permission: self.0,
+        }))
+    }
+}
+
+pub(crate) struct AuthorizationMiddlewareService<S> {
+    service: S,
+    permission: Permission,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthorizationMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Err(e) = authorization_middleware(&req, self.permission) {
+            return Box::pin(async move { Err(e.into()) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res)
+        })
+    }
+}
+
+fn authorization_middleware(req: &ServiceRequest, permission: Permission) -> Result<(), AuthError> {
+    let ctx = req
+        .app_data::<web::Data<AppContext>>()
+        .ok_or_else(|| AuthError::ServerError("App context not found".to_string()))?;
+    let extensions = req.extensions();
+    let claims = extensions
+        .get::<Claims>()
+        .ok_or(AuthError::FailedToExtractTokenFromRequest)?;
+    let collection = req
+        .match_info()
+        .get("collection_id")
+        .unwrap_or(GLOBAL_SCOPE);
+
+    authorize(ctx, claims, collection, permission)
+}
+
+/// Checks if the user of the claims has the permission on the collection (or
+/// on `GLOBAL_SCOPE`), and if the API key used to authenticate, if any, is
+/// scoped to allow it
+pub(crate) fn is_authorized(
+    ctx: &AppContext,
+    claims: &Claims,
+    collection: &str,
+    permission: Permission,
+) -> bool {
+    let key_allows = match &claims.api_key {
+        Some(id) => ctx
+            .ain_env
+            .api_keys
+            .get_key(id)
+            .is_some_and(|api_key| api_key.allows(collection, permission)),
+        None => true,
+    };
+    key_allows
+        && ctx
+            .ain_env
+            .rbac
+            .has_permission(&claims.username, collection, permission)
+}
+
+/// Same as `is_authorized`, logging and returning an error on denial
+pub(crate) fn authorize(
+    ctx: &AppContext,
+    claims: &Claims,
+    collection: &str,
+    permission: Permission,
+) -> Result<(), AuthError> {
+    if is_authorized(ctx, claims, collection, permission) {
+        Ok(())
+    } else {
+        log::warn!(
+            "Denied {:?} on '{}' for user '{}'",
+            permission,
+            collection,
+            claims.username
+        );
+        Err(AuthError::Forbidden)
+    }
+}
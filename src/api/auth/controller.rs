@@ -6,7 +6,11 @@ use actix_web::{
 use crate::app_context::AppContext;
 
 use super::{
-    dtos::{CreateSessionDTO, Session},
+    dtos::{
+        ApiKeyDto, ChangePasswordDto, Claims, CreateApiKeyDto, CreateApiKeyResponseDto,
+        CreateRoleDto, CreateSessionDTO, CreateUserDto, RefreshSessionDto, RevokeSessionDto,
+        RoleDto, Session, UserDto,
+    },
     service,
 };
 
@@ -30,3 +34,420 @@ pub(crate) async fn create_session(
     let res = service::create_session(create_session_dto, ctx.into_inner()).await?;
     Ok(HttpResponse::Ok().json(res))
 }
+
+/// Refresh a session
+///
+/// Returns a new session in exchange for the refresh token of an existing
+/// one. The old access and refresh tokens are revoked.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh-session",
+    request_body = RefreshSessionDto,
+    responses(
+        (status = 200, description = "Session refreshed successfully", body = Session),
+        (status = 401, description = "Invalid or expired refresh token"),
+        (status = 403, description = "User is disabled"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn refresh_session(
+    web::Json(refresh_session_dto): web::Json<RefreshSessionDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let res = service::refresh_session(refresh_session_dto, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(res))
+}
+
+/// Revoke a session (logout)
+///
+/// Accepts either the access token or the refresh token of the session.
+#[utoipa::path(
+    post,
+    path = "/auth/revoke-session",
+    request_body = RevokeSessionDto,
+    responses(
+        (status = 204, description = "Session revoked successfully"),
+        (status = 401, description = "Unknown token"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn revoke_session(
+    web::Json(revoke_session_dto): web::Json<RevokeSessionDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    service::revoke_session(revoke_session_dto, ctx.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Create a new role
+#[utoipa::path(
+    post,
+    path = "/auth/roles",
+    request_body = CreateRoleDto,
+    responses(
+        (status = 201, description = "Role created successfully", body = RoleDto),
+        (status = 400, description = "Invalid role or role already exists"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn create_role(
+    web::Json(create_role_dto): web::Json<CreateRoleDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let role = service::create_role(create_role_dto, ctx.into_inner()).await?;
+    Ok(HttpResponse::Created().json(role))
+}
+
+/// List all roles
+#[utoipa::path(
+    get,
+    path = "/auth/roles",
+    responses(
+        (status = 200, description = "List of roles", body = Vec<RoleDto>),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn list_roles(ctx: web::Data<AppContext>) -> Result<HttpResponse> {
+    let roles = service::list_roles(ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+/// Get role details
+#[utoipa::path(
+    get,
+    path = "/auth/roles/{role_name}",
+    params(
+        ("role_name" = String, Path, description = "Name of the role")
+    ),
+    responses(
+        (status = 200, description = "Role details", body = RoleDto),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Role not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn get_role(
+    role_name: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let role = service::get_role(&role_name, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(role))
+}
+
+/// Delete a role
+///
+/// Deleting a role also revokes it from every user it was assigned to.
+/// Built-in roles can't be deleted.
+#[utoipa::path(
+    delete,
+    path = "/auth/roles/{role_name}",
+    params(
+        ("role_name" = String, Path, description = "Name of the role")
+    ),
+    responses(
+        (status = 204, description = "Role deleted successfully"),
+        (status = 400, description = "Built-in roles can't be deleted"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Role not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn delete_role(
+    role_name: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    service::delete_role(&role_name, ctx.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Create a new user
+#[utoipa::path(
+    post,
+    path = "/auth/users",
+    request_body = CreateUserDto,
+    responses(
+        (status = 201, description = "User created successfully", body = UserDto),
+        (status = 400, description = "Invalid user or user already exists"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn create_user(
+    web::Json(create_user_dto): web::Json<CreateUserDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let user = service::create_user(create_user_dto, ctx.into_inner()).await?;
+    Ok(HttpResponse::Created().json(user))
+}
+
+/// List all users
+#[utoipa::path(
+    get,
+    path = "/auth/users",
+    responses(
+        (status = 200, description = "List of users", body = Vec<UserDto>),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn list_users(ctx: web::Data<AppContext>) -> Result<HttpResponse> {
+    let users = service::list_users(ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(users))
+}
+
+/// Get user details
+#[utoipa::path(
+    get,
+    path = "/auth/users/{username}",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    responses(
+        (status = 200, description = "User details", body = UserDto),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn get_user(
+    username: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let user = service::get_user(&username, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Delete a user
+///
+/// Deleting a user also revokes all their roles and active sessions.
+/// The admin user can't be deleted.
+#[utoipa::path(
+    delete,
+    path = "/auth/users/{username}",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    responses(
+        (status = 204, description = "User deleted successfully"),
+        (status = 400, description = "The admin user can't be deleted"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn delete_user(
+    username: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    service::delete_user(&username, ctx.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Disable a user
+///
+/// Disabled users can't create new sessions, their active sessions are revoked.
+#[utoipa::path(
+    post,
+    path = "/auth/users/{username}/disable",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    responses(
+        (status = 200, description = "User disabled successfully", body = UserDto),
+        (status = 400, description = "The admin user can't be disabled"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn disable_user(
+    username: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let user = service::set_user_disabled(&username, true, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Enable a previously disabled user
+#[utoipa::path(
+    post,
+    path = "/auth/users/{username}/enable",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    responses(
+        (status = 200, description = "User enabled successfully", body = UserDto),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn enable_user(
+    username: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let user = service::set_user_disabled(&username, false, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Revoke all sessions of a user
+#[utoipa::path(
+    delete,
+    path = "/auth/users/{username}/sessions",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    responses(
+        (status = 204, description = "Sessions revoked successfully"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn revoke_user_sessions(
+    username: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    service::revoke_user_sessions(&username, ctx.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Change the password of a user
+///
+/// Users can change their own password, changing the password of other users
+/// requires the `manage_permissions` permission. Active sessions of the user
+/// are revoked.
+#[utoipa::path(
+    put,
+    path = "/auth/users/{username}/password",
+    params(
+        ("username" = String, Path, description = "Username of the user")
+    ),
+    request_body = ChangePasswordDto,
+    responses(
+        (status = 200, description = "Password changed successfully", body = UserDto),
+        (status = 400, description = "Invalid password"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn change_password(
+    username: web::Path<String>,
+    web::Json(change_password_dto): web::Json<ChangePasswordDto>,
+    claims: Claims,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let user =
+        service::change_password(&username, change_password_dto, &claims, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+/// Create a new API key
+///
+/// API keys are long-lived credentials acting on behalf of a user, to be
+/// used as bearer tokens instead of session tokens. They can be restricted
+/// to some collections and/or to read-only operations. The key is only
+/// returned in this response.
+#[utoipa::path(
+    post,
+    path = "/auth/api-keys",
+    request_body = CreateApiKeyDto,
+    responses(
+        (status = 201, description = "API key created successfully", body = CreateApiKeyResponseDto),
+        (status = 400, description = "Invalid API key"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn create_api_key(
+    web::Json(create_api_key_dto): web::Json<CreateApiKeyDto>,
+    claims: Claims,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let api_key = service::create_api_key(create_api_key_dto, &claims, ctx.into_inner()).await?;
+    Ok(HttpResponse::Created().json(api_key))
+}
+
+/// List all API keys
+#[utoipa::path(
+    get,
+    path = "/auth/api-keys",
+    responses(
+        (status = 200, description = "List of API keys", body = Vec<ApiKeyDto>),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn list_api_keys(ctx: web::Data<AppContext>) -> Result<HttpResponse> {
+    let api_keys = service::list_api_keys(ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(api_keys))
+}
+
+/// Get API key details
+#[utoipa::path(
+    get,
+    path = "/auth/api-keys/{key_id}",
+    params(
+        ("key_id" = String, Path, description = "Id of the API key")
+    ),
+    responses(
+        (status = 200, description = "API key details", body = ApiKeyDto),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "API key not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn get_api_key(
+    key_id: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let api_key = service::get_api_key(&key_id, ctx.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(api_key))
+}
+
+/// Revoke an API key
+#[utoipa::path(
+    delete,
+    path = "/auth/api-keys/{key_id}",
+    params(
+        ("key_id" = String, Path, description = "Id of the API key")
+    ),
+    responses(
+        (status = 204, description = "API key revoked successfully"),
+        (status = 401, description = "Invalid authentication token"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "API key not found")
+    ),
+    tag = "auth"
+)]
+pub(crate) async fn revoke_api_key(
+    key_id: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    service::revoke_api_key(&key_id, ctx.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -3,6 +3,11 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use super::error::AuthError;
+use crate::models::{
+    api_keys::ApiKey,
+    rbac::{Permission, Role},
+    types::User,
+};
 use futures_util::future::{err, ok, Ready};
 
 /// DTO for creating a user session (login)
@@ -23,6 +28,24 @@ pub(crate) struct Session {
     pub created_at: u64,
     /// Timestamp when the session will expire
     pub expires_at: u64,
+    /// Token to get a new session with once this one has expired
+    pub refresh_token: String,
+    /// Timestamp when the refresh token will expire
+    pub refresh_expires_at: u64,
+}
+
+/// DTO for refreshing a session
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct RefreshSessionDto {
+    /// Refresh token of the session
+    pub refresh_token: String,
+}
+
+/// DTO for revoking a session (logout)
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct RevokeSessionDto {
+    /// Either the access token or the refresh token of the session
+    pub token: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -32,6 +55,9 @@ pub struct Claims {
     pub exp: u64,         // Expiry time of the token
     pub iat: u64,         // Issued at time of the token
     pub username: String, // Email associated with the token
+    // Id of the API key, if authenticated with one instead of a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
 }
 
 impl FromRequest for Claims {
@@ -47,3 +73,134 @@ impl FromRequest for Claims {
         }
     }
 }
+
+/// DTO for creating a role
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct CreateRoleDto {
+    /// Unique name of the role
+    pub name: String,
+    /// Optional description of the role
+    pub description: Option<String>,
+    /// Permissions granted by the role
+    pub permissions: Vec<Permission>,
+}
+
+/// Role details
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct RoleDto {
+    /// Name of the role
+    pub name: String,
+    /// Description of the role
+    pub description: Option<String>,
+    /// Permissions granted by the role
+    pub permissions: Vec<Permission>,
+    /// Whether this is one of the built-in roles, which can't be deleted
+    pub builtin: bool,
+}
+
+impl From<Role> for RoleDto {
+    fn from(role: Role) -> Self {
+        Self {
+            builtin: Role::is_builtin(&role.name),
+            name: role.name,
+            description: role.description,
+            permissions: role.permissions,
+        }
+    }
+}
+
+/// DTO for creating a user
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct CreateUserDto {
+    /// Unique username
+    pub username: String,
+    /// Password of the user
+    pub password: String,
+}
+
+/// DTO for changing the password of a user
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct ChangePasswordDto {
+    /// New password of the user
+    pub password: String,
+}
+
+/// User details, never includes the password hash
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct UserDto {
+    /// Username of the user
+    pub username: String,
+    /// Disabled users can't create sessions
+    pub disabled: bool,
+    /// Timestamp when the user was created
+    pub created_at: u64,
+}
+
+impl From<User> for UserDto {
+    fn from(user: User) -> Self {
+        Self {
+            username: user.username,
+            disabled: user.disabled,
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// DTO for creating an API key
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct CreateApiKeyDto {
+    /// Name describing what the key is used for
+    pub name: String,
+    /// User the key acts on behalf of, defaults to the user creating the key
+    pub username: Option<String>,
+    /// Restricts the key to these collections, unrestricted if omitted
+    pub collections: Option<Vec<String>>,
+    /// Restricts the key to read-only operations
+    #[serde(default)]
+    pub read_only: bool,
+    /// Lifetime of the key in seconds, the key never expires if omitted
+    pub expires_in: Option<u64>,
+}
+
+/// API key details, never includes the key itself
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct ApiKeyDto {
+    /// Id of the key
+    pub id: String,
+    /// Name of the key
+    pub name: String,
+    /// User the key acts on behalf of
+    pub username: String,
+    /// Collections the key is restricted to, if any
+    pub collections: Option<Vec<String>>,
+    /// Whether the key is restricted to read-only operations
+    pub read_only: bool,
+    /// Timestamp when the key was created
+    pub created_at: u64,
+    /// Timestamp when the key will expire, if ever
+    pub expires_at: Option<u64>,
+}
+
+impl From<ApiKey> for ApiKeyDto {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            username: api_key.username,
+            collections: api_key.collections,
+            read_only: api_key.read_only,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+        }
+    }
+}
+
+/// Response after creating an API key
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct CreateApiKeyResponseDto {
+    /// The API key, to be used as a bearer token. It is only returned once
+    /// and can't be retrieved later.
+    pub key: String,
+    /// Details of the key
+    pub details: ApiKeyDto,
+}
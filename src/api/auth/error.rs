@@ -4,11 +4,17 @@ use actix_web::{
 };
 use std::fmt::Display;
 
+use crate::models::common::WaCustomError;
+
 #[derive(Debug)]
 pub enum AuthError {
     WrongCredentials,
     InvalidToken,
     FailedToExtractTokenFromRequest,
+    Forbidden,
+    NotFound(String),
+    InvalidRequest(String),
+    ServerError(String),
 }
 
 impl Display for AuthError {
@@ -19,6 +25,20 @@ impl Display for AuthError {
             Self::FailedToExtractTokenFromRequest => {
                 write!(f, "Failed to extract token from request!")
             }
+            Self::Forbidden => write!(f, "Permission denied!"),
+            Self::NotFound(what) => write!(f, "Not found: {}", what),
+            Self::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            Self::ServerError(msg) => write!(f, "Server error: {}", msg),
+        }
+    }
+}
+
+impl From<WaCustomError> for AuthError {
+    fn from(error: WaCustomError) -> Self {
+        match error {
+            WaCustomError::NotFound(what) => Self::NotFound(what),
+            WaCustomError::InvalidData(msg) => Self::InvalidRequest(msg),
+            error => Self::ServerError(error.to_string()),
         }
     }
 }
@@ -34,6 +54,10 @@ impl ResponseError for AuthError {
             Self::WrongCredentials => StatusCode::BAD_REQUEST,
             Self::InvalidToken => StatusCode::UNAUTHORIZED,
             Self::FailedToExtractTokenFromRequest => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::ServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
@@ -1,13 +1,138 @@
+use std::sync::Arc;
+
 use actix_web::{web, Scope};
+use authentication_middleware::AuthenticationMiddleware;
+use authorization_middleware::AuthorizationMiddleware;
+
+use crate::models::{rbac::Permission, types::AppEnv};
+
 pub(crate) mod authentication_middleware;
+pub(crate) mod authorization_middleware;
 pub mod controller;
 pub mod dtos;
-mod error;
-mod service;
-
-pub(crate) fn auth_module() -> Scope {
-    web::scope("/auth").route(
-        "/create-session",
-        web::post().to(controller::create_session),
-    )
+pub(crate) mod error;
+pub(crate) mod service;
+
+pub(crate) fn auth_module(ain_env: Arc<AppEnv>) -> Scope {
+    web::scope("/auth")
+        .route(
+            "/create-session",
+            web::post().to(controller::create_session),
+        )
+        .route(
+            "/refresh-session",
+            web::post().to(controller::refresh_session),
+        )
+        .route(
+            "/revoke-session",
+            web::post().to(controller::revoke_session),
+        )
+        .service(
+            web::scope("/roles")
+                .wrap(AuthenticationMiddleware(ain_env.clone()))
+                .route(
+                    "",
+                    web::post()
+                        .to(controller::create_role)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "",
+                    web::get()
+                        .to(controller::list_roles)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{role_name}",
+                    web::get()
+                        .to(controller::get_role)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{role_name}",
+                    web::delete()
+                        .to(controller::delete_role)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                ),
+        )
+        .service(
+            web::scope("/users")
+                .wrap(AuthenticationMiddleware(ain_env.clone()))
+                .route(
+                    "",
+                    web::post()
+                        .to(controller::create_user)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "",
+                    web::get()
+                        .to(controller::list_users)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{username}",
+                    web::get()
+                        .to(controller::get_user)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{username}",
+                    web::delete()
+                        .to(controller::delete_user)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{username}/disable",
+                    web::post()
+                        .to(controller::disable_user)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{username}/enable",
+                    web::post()
+                        .to(controller::enable_user)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{username}/sessions",
+                    web::delete()
+                        .to(controller::revoke_user_sessions)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                // users can change their own password, the permission check
+                // is done by the service
+                .route(
+                    "/{username}/password",
+                    web::put().to(controller::change_password),
+                ),
+        )
+        .service(
+            web::scope("/api-keys")
+                .wrap(AuthenticationMiddleware(ain_env))
+                .route(
+                    "",
+                    web::post()
+                        .to(controller::create_api_key)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "",
+                    web::get()
+                        .to(controller::list_api_keys)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{key_id}",
+                    web::get()
+                        .to(controller::get_api_key)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                )
+                .route(
+                    "/{key_id}",
+                    web::delete()
+                        .to(controller::revoke_api_key)
+                        .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+                ),
+        )
 }
@@ -4,16 +4,57 @@ use crate::{
     app_context::AppContext,
     models::{
         crypto::{self, SingleSHA256Hash},
-        types::SessionDetails,
+        rbac::{Permission, Role, GLOBAL_SCOPE},
+        sessions::SessionDetails,
+        types::ADMIN_USERNAME,
     },
 };
 
 use super::{
-    dtos::{CreateSessionDTO, Session},
+    authorization_middleware::authorize,
+    dtos::{
+        ApiKeyDto, ChangePasswordDto, Claims, CreateApiKeyDto, CreateApiKeyResponseDto,
+        CreateRoleDto, CreateSessionDTO, CreateUserDto, RefreshSessionDto, RevokeSessionDto,
+        RoleDto, Session, UserDto,
+    },
     error::AuthError,
 };
 
 const TOKEN_LIFETIME: u64 = 3600; // 1 hour
+const REFRESH_TOKEN_LIFETIME: u64 = 7 * 24 * 3600; // 1 week
+
+// Stores a new session for the user along with a fresh refresh token
+fn start_session(
+    username: String,
+    access_token: String,
+    created_at: u64,
+    ctx: &AppContext,
+) -> Result<Session, AuthError> {
+    let refresh_token = crypto::generate_random_token();
+    let expires_at = created_at + TOKEN_LIFETIME;
+    let refresh_expires_at = created_at + REFRESH_TOKEN_LIFETIME;
+
+    let sessions = &ctx.ain_env.active_sessions;
+    sessions.purge_expired()?;
+    sessions.insert(
+        &access_token,
+        &refresh_token,
+        SessionDetails {
+            created_at,
+            expires_at,
+            refresh_expires_at,
+            username,
+        },
+    )?;
+
+    Ok(Session {
+        access_token,
+        created_at,
+        expires_at,
+        refresh_token,
+        refresh_expires_at,
+    })
+}
 
 pub(crate) async fn create_session(
     create_session_dto: CreateSessionDTO,
@@ -30,6 +71,9 @@ pub(crate) async fn create_session(
     if !password_double_hash.verify_eq(&user.password_hash) {
         return Err(AuthError::WrongCredentials)?;
     }
+    if user.disabled {
+        return Err(AuthError::Forbidden);
+    }
 
     let (access_token, timestamp) = crypto::create_session(
         &create_session_dto.username,
@@ -37,21 +81,294 @@ pub(crate) async fn create_session(
         &password_hash,
     );
 
-    let created_at = timestamp;
-    let expires_at = timestamp + TOKEN_LIFETIME;
+    start_session(user.username, access_token, timestamp, &ctx)
+}
 
-    ctx.ain_env.active_sessions.insert(
-        access_token.clone(),
-        SessionDetails {
-            created_at,
-            expires_at,
-            user,
-        },
-    );
+/// Replaces the session of the refresh token with a new one, the old access
+/// and refresh tokens are revoked
+pub(crate) async fn refresh_session(
+    refresh_session_dto: RefreshSessionDto,
+    ctx: Arc<AppContext>,
+) -> Result<Session, AuthError> {
+    let session = ctx
+        .ain_env
+        .active_sessions
+        .take_by_refresh_token(&refresh_session_dto.refresh_token)?
+        .ok_or(AuthError::InvalidToken)?;
+    let user = ctx
+        .ain_env
+        .users_map
+        .get_user(&session.username)
+        .ok_or(AuthError::InvalidToken)?;
+    if user.disabled {
+        return Err(AuthError::Forbidden);
+    }
 
-    Ok(Session {
+    // there is no password to derive the token from, so a random one is used
+    let access_token = crypto::generate_random_token();
+    start_session(
+        user.username,
         access_token,
-        created_at,
+        crypto::get_current_timestamp(),
+        &ctx,
+    )
+}
+
+/// Revokes the session of an access or refresh token (logout)
+pub(crate) async fn revoke_session(
+    revoke_session_dto: RevokeSessionDto,
+    ctx: Arc<AppContext>,
+) -> Result<(), AuthError> {
+    ctx.ain_env
+        .active_sessions
+        .revoke(&revoke_session_dto.token)?
+        .ok_or(AuthError::InvalidToken)?;
+    Ok(())
+}
+
+pub(crate) async fn create_role(
+    create_role_dto: CreateRoleDto,
+    ctx: Arc<AppContext>,
+) -> Result<RoleDto, AuthError> {
+    if create_role_dto.name.is_empty() {
+        return Err(AuthError::InvalidRequest(
+            "Role name must not be empty".to_string(),
+        ));
+    }
+    let mut permissions = create_role_dto.permissions;
+    permissions.sort_unstable();
+    permissions.dedup();
+
+    let role = Role {
+        name: create_role_dto.name,
+        description: create_role_dto.description,
+        permissions,
+    };
+    ctx.ain_env.rbac.create_role(role.clone())?;
+    Ok(role.into())
+}
+
+pub(crate) async fn list_roles(ctx: Arc<AppContext>) -> Result<Vec<RoleDto>, AuthError> {
+    Ok(ctx
+        .ain_env
+        .rbac
+        .list_roles()
+        .into_iter()
+        .map(RoleDto::from)
+        .collect())
+}
+
+pub(crate) async fn get_role(role_name: &str, ctx: Arc<AppContext>) -> Result<RoleDto, AuthError> {
+    ctx.ain_env
+        .rbac
+        .get_role(role_name)
+        .map(RoleDto::from)
+        .ok_or_else(|| AuthError::NotFound(format!("role '{}'", role_name)))
+}
+
+pub(crate) async fn delete_role(role_name: &str, ctx: Arc<AppContext>) -> Result<(), AuthError> {
+    ctx.ain_env
+        .rbac
+        .delete_role(role_name)?
+        .ok_or_else(|| AuthError::NotFound(format!("role '{}'", role_name)))?;
+    Ok(())
+}
+
+fn validate_username(username: &str) -> Result<(), AuthError> {
+    if username.is_empty() || username.contains('/') {
+        return Err(AuthError::InvalidRequest(
+            "Username must be non-empty and must not contain '/'".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_password(password: &str) -> Result<(), AuthError> {
+    if password.is_empty() {
+        return Err(AuthError::InvalidRequest(
+            "Password must not be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Revokes all the sessions of a user, which is also done whenever the user
+/// is changed in a way that should log them out
+pub(crate) async fn revoke_user_sessions(
+    username: &str,
+    ctx: Arc<AppContext>,
+) -> Result<(), AuthError> {
+    if ctx.ain_env.users_map.get_user(username).is_none() {
+        return Err(AuthError::NotFound(format!("user '{}'", username)));
+    }
+    ctx.ain_env.active_sessions.revoke_user_sessions(username)?;
+    Ok(())
+}
+
+pub(crate) async fn create_user(
+    create_user_dto: CreateUserDto,
+    ctx: Arc<AppContext>,
+) -> Result<UserDto, AuthError> {
+    validate_username(&create_user_dto.username)?;
+    validate_password(&create_user_dto.password)?;
+    let password_hash = SingleSHA256Hash::from_str(&create_user_dto.password)
+        .unwrap()
+        .hash_again();
+    let user = ctx
+        .ain_env
+        .users_map
+        .create_user(create_user_dto.username, password_hash)?;
+    Ok(user.into())
+}
+
+pub(crate) async fn list_users(ctx: Arc<AppContext>) -> Result<Vec<UserDto>, AuthError> {
+    Ok(ctx
+        .ain_env
+        .users_map
+        .list_users()
+        .into_iter()
+        .map(UserDto::from)
+        .collect())
+}
+
+pub(crate) async fn get_user(username: &str, ctx: Arc<AppContext>) -> Result<UserDto, AuthError> {
+    ctx.ain_env
+        .users_map
+        .get_user(username)
+        .map(UserDto::from)
+        .ok_or_else(|| AuthError::NotFound(format!("user '{}'", username)))
+}
+
+pub(crate) async fn delete_user(username: &str, ctx: Arc<AppContext>) -> Result<(), AuthError> {
+    if username == ADMIN_USERNAME {
+        return Err(AuthError::InvalidRequest(
+            "The admin user can't be deleted".to_string(),
+        ));
+    }
+    ctx.ain_env
+        .users_map
+        .delete_user(username)?
+        .ok_or_else(|| AuthError::NotFound(format!("user '{}'", username)))?;
+    ctx.ain_env.rbac.remove_user(username)?;
+    ctx.ain_env.api_keys.remove_user(username)?;
+    ctx.ain_env.active_sessions.revoke_user_sessions(username)?;
+    Ok(())
+}
+
+pub(crate) async fn set_user_disabled(
+    username: &str,
+    disabled: bool,
+    ctx: Arc<AppContext>,
+) -> Result<UserDto, AuthError> {
+    if username == ADMIN_USERNAME && disabled {
+        return Err(AuthError::InvalidRequest(
+            "The admin user can't be disabled".to_string(),
+        ));
+    }
+    let user = ctx.ain_env.users_map.set_disabled(username, disabled)?;
+    if disabled {
+        ctx.ain_env.active_sessions.revoke_user_sessions(username)?;
+    }
+    Ok(user.into())
+}
+
+/// Users can change their own password when authenticated with a session,
+/// changing the password of any other user (or with an API key) requires the
+/// `manage_permissions` permission
+pub(crate) async fn change_password(
+    username: &str,
+    change_password_dto: ChangePasswordDto,
+    requested_by: &Claims,
+    ctx: Arc<AppContext>,
+) -> Result<UserDto, AuthError> {
+    if username != requested_by.username || requested_by.api_key.is_some() {
+        authorize(
+            &ctx,
+            requested_by,
+            GLOBAL_SCOPE,
+            Permission::ManagePermissions,
+        )?;
+    }
+    // the admin password is reset to the admin key on every startup
+    if username == ADMIN_USERNAME {
+        return Err(AuthError::InvalidRequest(
+            "The admin password can't be changed".to_string(),
+        ));
+    }
+    validate_password(&change_password_dto.password)?;
+    let password_hash = SingleSHA256Hash::from_str(&change_password_dto.password)
+        .unwrap()
+        .hash_again();
+    let user = ctx
+        .ain_env
+        .users_map
+        .set_password(username, password_hash)?;
+    ctx.ain_env.active_sessions.revoke_user_sessions(username)?;
+    Ok(user.into())
+}
+
+pub(crate) async fn create_api_key(
+    create_api_key_dto: CreateApiKeyDto,
+    requested_by: &Claims,
+    ctx: Arc<AppContext>,
+) -> Result<CreateApiKeyResponseDto, AuthError> {
+    if create_api_key_dto.name.is_empty() {
+        return Err(AuthError::InvalidRequest(
+            "API key name must not be empty".to_string(),
+        ));
+    }
+    let username = create_api_key_dto
+        .username
+        .unwrap_or_else(|| requested_by.username.clone());
+    if ctx.ain_env.users_map.get_user(&username).is_none() {
+        return Err(AuthError::NotFound(format!("user '{}'", username)));
+    }
+    if let Some(collections) = &create_api_key_dto.collections {
+        if collections.is_empty() {
+            return Err(AuthError::InvalidRequest(
+                "A scoped API key needs at least one collection".to_string(),
+            ));
+        }
+    }
+    let expires_at = create_api_key_dto
+        .expires_in
+        .map(|expires_in| crypto::get_current_timestamp() + expires_in);
+
+    let (api_key, key) = ctx.ain_env.api_keys.create_key(
+        create_api_key_dto.name,
+        username,
+        create_api_key_dto.collections,
+        create_api_key_dto.read_only,
         expires_at,
+    )?;
+    Ok(CreateApiKeyResponseDto {
+        key,
+        details: api_key.into(),
     })
 }
+
+pub(crate) async fn list_api_keys(ctx: Arc<AppContext>) -> Result<Vec<ApiKeyDto>, AuthError> {
+    Ok(ctx
+        .ain_env
+        .api_keys
+        .list_keys()
+        .into_iter()
+        .map(ApiKeyDto::from)
+        .collect())
+}
+
+pub(crate) async fn get_api_key(id: &str, ctx: Arc<AppContext>) -> Result<ApiKeyDto, AuthError> {
+    ctx.ain_env
+        .api_keys
+        .get_key(id)
+        .map(ApiKeyDto::from)
+        .ok_or_else(|| AuthError::NotFound(format!("API key '{}'", id)))
+}
+
+pub(crate) async fn revoke_api_key(id: &str, ctx: Arc<AppContext>) -> Result<(), AuthError> {
+    ctx.ain_env
+        .api_keys
+        .revoke_key(id)?
+        .ok_or_else(|| AuthError::NotFound(format!("API key '{}'", id)))?;
+    Ok(())
+}
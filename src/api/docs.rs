@@ -1,6 +1,6 @@
 use crate::api::openapi::{
-    AuthApiDoc, CollectionsApiDoc, CombinedApiDoc, IndexesApiDoc, SearchApiDoc, StreamingApiDoc,
-    TransactionsApiDoc, VectorsApiDoc, VersionsApiDoc,
+    AccessApiDoc, AuthApiDoc, CollectionsApiDoc, CombinedApiDoc, IndexesApiDoc, SearchApiDoc,
+    StreamingApiDoc, TransactionsApiDoc, VectorsApiDoc, VersionsApiDoc,
 };
 use actix_web::{web, HttpResponse, Scope};
 use utoipa::OpenApi;
@@ -9,6 +9,7 @@ pub(crate) fn api_docs_module() -> Scope {
     web::scope("/api-docs")
         .route("/openapi.json", web::get().to(openapi_json))
         .route("/auth/openapi.json", web::get().to(auth_openapi_json))
+        .route("/access/openapi.json", web::get().to(access_openapi_json))
         .route(
             "/collections/openapi.json",
             web::get().to(collections_openapi_json),
@@ -38,6 +39,10 @@ async fn auth_openapi_json() -> HttpResponse {
     HttpResponse::Ok().json(AuthApiDoc::openapi())
 }
 
+async fn access_openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(AccessApiDoc::openapi())
+}
+
 async fn collections_openapi_json() -> HttpResponse {
     HttpResponse::Ok().json(CollectionsApiDoc::openapi())
 }
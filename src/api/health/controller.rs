@@ -0,0 +1,56 @@
+use super::dtos::{HealthResponseDto, ReadinessResponseDto, ServerInfoDto};
+use super::service;
+use crate::app_context::AppContext;
+use actix_web::{web, HttpResponse};
+
+/// Liveness of the server
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is running", body = HealthResponseDto)
+    )
+)]
+pub(crate) async fn health() -> HttpResponse {
+    HttpResponse::Ok().json(HealthResponseDto {
+        status: "ok".to_string(),
+    })
+}
+
+/// Readiness of the server to serve requests
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is ready", body = ReadinessResponseDto),
+        (status = 503, description = "The restart recovery is still running or has failed", body = ReadinessResponseDto)
+    )
+)]
+pub(crate) async fn ready(ctx: web::Data<AppContext>) -> HttpResponse {
+    let ready = service::is_ready(&ctx);
+    let mut response = if ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    response.json(ReadinessResponseDto {
+        ready,
+        reason: ctx.not_ready_reason(),
+    })
+}
+
+/// Version, build and runtime information of the server
+#[utoipa::path(
+    get,
+    path = "/info",
+    tag = "health",
+    responses(
+        (status = 200, description = "Server information", body = ServerInfoDto),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+pub(crate) async fn get_info(ctx: web::Data<AppContext>) -> HttpResponse {
+    HttpResponse::Ok().json(service::get_info(&ctx))
+}
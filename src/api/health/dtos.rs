@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct HealthResponseDto {
+    pub status: String,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ReadinessResponseDto {
+    pub ready: bool,
+    /// Why the server isn't ready, e.g. the cause of a failed restart
+    /// recovery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ServerInfoDto {
+    pub version: String,
+    /// Cargo features the server was built with, e.g. `grpc-server`
+    pub features: Vec<String>,
+    pub uptime_seconds: u64,
+    pub data_path: String,
+    pub loaded_collections: Vec<String>,
+    /// Whether any collection is indexing a transaction
+    pub indexing_in_progress: bool,
+    pub ready: bool,
+}
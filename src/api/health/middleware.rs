@@ -0,0 +1,65 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::{ErrorInternalServerError, ErrorServiceUnavailable},
+    web, Error,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::app_context::AppContext;
+
+/// Rejects the requests with `503 Service Unavailable` until the restart
+/// recovery completes, so that they don't run against partly recovered
+/// indexes or race the replay of the unindexed versions. If the recovery
+/// failed, its cause is the message.
+pub(crate) struct RecoveryGateMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RecoveryGateMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RecoveryGateMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RecoveryGateMiddlewareService { service }))
+    }
+}
+
+pub(crate) struct RecoveryGateMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RecoveryGateMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(ctx) = req.app_data::<web::Data<AppContext>>() else {
+            return Box::pin(async { Err(ErrorInternalServerError("App context not found")) });
+        };
+        if let Some(reason) = ctx.not_ready_reason() {
+            return Box::pin(async { Err(ErrorServiceUnavailable(reason)) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use actix_web::web;
+
+use crate::api::auth::authentication_middleware::AuthenticationMiddleware;
+use crate::models::types::AppEnv;
+
+pub mod controller;
+pub(crate) mod dtos;
+pub(crate) mod middleware;
+mod service;
+
+// `/health` and `/ready` are meant for orchestrators and load balancers, so
+// unlike `/info` they don't require authentication
+pub(crate) fn health_module(cfg: &mut web::ServiceConfig, ain_env: Arc<AppEnv>) {
+    cfg.route("/health", web::get().to(controller::health))
+        .route("/ready", web::get().to(controller::ready))
+        .service(
+            web::resource("/info")
+                .wrap(AuthenticationMiddleware(ain_env))
+                .route(web::get().to(controller::get_info)),
+        );
+}
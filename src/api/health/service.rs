@@ -0,0 +1,32 @@
+use std::sync::atomic::Ordering;
+
+use super::dtos::ServerInfoDto;
+use crate::app_context::AppContext;
+use crate::models::paths::get_data_path;
+
+pub(crate) fn is_ready(ctx: &AppContext) -> bool {
+    ctx.is_ready()
+}
+
+pub(crate) fn get_info(ctx: &AppContext) -> ServerInfoDto {
+    let mut features = Vec::new();
+    if cfg!(feature = "grpc-server") {
+        features.push("grpc-server".to_string());
+    }
+
+    let indexing_in_progress = ctx
+        .ain_env
+        .collections_map
+        .iter_collections()
+        .any(|entry| entry.value().is_indexing.load(Ordering::Relaxed));
+
+    ServerInfoDto {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        features,
+        uptime_seconds: ctx.started_at.elapsed().as_secs(),
+        data_path: get_data_path().display().to_string(),
+        loaded_collections: ctx.collection_cache_manager.get_loaded_collections(),
+        indexing_in_progress,
+        ready: is_ready(ctx),
+    }
+}
@@ -0,0 +1,21 @@
+use super::service;
+use crate::app_context::AppContext;
+use actix_web::{web, HttpResponse};
+
+/// Metrics in the Prometheus text exposition format, scrapers authenticate
+/// with an API key of a user allowed to list all the collections
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Not allowed to list the collections")
+    )
+)]
+pub(crate) async fn get_metrics(ctx: web::Data<AppContext>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(service::render_metrics(&ctx))
+}
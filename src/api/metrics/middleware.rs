@@ -0,0 +1,69 @@
+use std::future::{ready, Ready};
+use std::time::Instant;
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::models::metrics::metrics;
+
+// Requests that don't match any route are recorded under this label, so
+// that scanners can't create an unbounded number of series
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Records the latency of every request in the per route histogram of the
+/// metrics
+pub(crate) struct RequestMetricsMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestMetricsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddlewareService { service }))
+    }
+}
+
+pub(crate) struct RequestMetricsMiddlewareService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        // The route is resolved before calling the service, as the request
+        // is not returned when the service fails
+        let method = req.method().to_string();
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            metrics().observe_http_request(&method, &route, start.elapsed());
+            res
+        })
+    }
+}
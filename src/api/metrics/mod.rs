@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use actix_web::{web, Scope};
+
+use crate::api::auth::{
+    authentication_middleware::AuthenticationMiddleware,
+    authorization_middleware::AuthorizationMiddleware,
+};
+use crate::models::{rbac::Permission, types::AppEnv};
+
+pub mod controller;
+pub(crate) mod middleware;
+mod service;
+
+// The metrics are labelled with the collection names, so reading them
+// requires the global permission to list the collections
+pub(crate) fn metrics_module(ain_env: Arc<AppEnv>) -> Scope {
+    web::scope("/metrics")
+        .wrap(AuthenticationMiddleware(ain_env))
+        .route(
+            "",
+            web::get()
+                .to(controller::get_metrics)
+                .wrap(AuthorizationMiddleware(Permission::ListCollections)),
+        )
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::app_context::AppContext;
+use crate::indexes::hnsw::HNSWIndex;
+use crate::indexes::inverted::InvertedIndex;
+use crate::indexes::tf_idf::TFIDFIndex;
+use crate::indexes::IndexOps;
+use crate::models::collection::Collection;
+use crate::models::metrics::{metrics, MetricsWriter};
+
+pub(crate) fn render_metrics(ctx: &AppContext) -> String {
+    let mut writer = MetricsWriter::default();
+    metrics().write(&mut writer);
+
+    writer.header(
+        "cosdata_loaded_collections",
+        "Number of collections loaded in the collection cache",
+        "gauge",
+    );
+    writer.sample(
+        "cosdata_loaded_collections",
+        &[],
+        ctx.collection_cache_manager.get_loaded_collections().len(),
+    );
+
+    let collections: Vec<_> = ctx
+        .ain_env
+        .collections_map
+        .iter_collections()
+        .map(|entry| entry.value().clone())
+        .collect();
+
+    writer.header(
+        "cosdata_wal_size_bytes",
+        "Total size of the write-ahead log files of the collection",
+        "gauge",
+    );
+    for collection in &collections {
+        writer.sample(
+            "cosdata_wal_size_bytes",
+            &[("collection", &collection.meta.name)],
+            wal_size(&collection.get_path()),
+        );
+    }
+
+    write_indexing_metrics(&mut writer, &collections);
+    write_cache_metrics(&mut writer, &collections);
+
+    writer.finish()
+}
+
+// Sum of the sizes of the `<version>.wal` files of the implicit and the
+// committed explicit transactions which are not indexed yet
+fn wal_size(collection_path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(collection_path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "wal"))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn write_indexing_metrics(writer: &mut MetricsWriter, collections: &[Arc<Collection>]) {
+    let statuses: Vec<_> = collections
+        .iter()
+        .filter_map(|collection| {
+            let status = collection.indexing_status().ok()?;
+            Some((collection, status.status_summary))
+        })
+        .collect();
+
+    writer.header(
+        "cosdata_indexing_in_progress",
+        "Whether the collection is indexing a transaction",
+        "gauge",
+    );
+    for collection in collections {
+        writer.sample(
+            "cosdata_indexing_in_progress",
+            &[("collection", &collection.meta.name)],
+            collection.is_indexing.load(Ordering::Relaxed) as u8,
+        );
+    }
+
+    writer.header(
+        "cosdata_indexing_records_total",
+        "Number of records indexed by explicit transactions",
+        "counter",
+    );
+    for (collection, summary) in &statuses {
+        writer.sample(
+            "cosdata_indexing_records_total",
+            &[("collection", &collection.meta.name)],
+            summary.total_records_indexed_completed,
+        );
+    }
+
+    writer.header(
+        "cosdata_indexing_throughput_records_per_second",
+        "Average indexing throughput of the started explicit transactions",
+        "gauge",
+    );
+    for (collection, summary) in &statuses {
+        // the average is undefined until a transaction starts indexing
+        if summary.in_progress_transactions + summary.completed_transactions == 0 {
+            continue;
+        }
+        writer.sample(
+            "cosdata_indexing_throughput_records_per_second",
+            &[("collection", &collection.meta.name)],
+            summary.average_rate_per_second_completed,
+        );
+    }
+}
+
+fn write_cache_metrics(writer: &mut MetricsWriter, collections: &[Arc<Collection>]) {
+    let hnsw_indexes: Vec<_> = collections
+        .iter()
+        .filter_map(|collection| {
+            let index = collection.hnsw_index.read().clone()?;
+            Some((collection, index))
+        })
+        .collect();
+
+    writer.header(
+        "cosdata_hnsw_cache_hits_total",
+        "Number of lookups served from the HNSW node cache",
+        "counter",
+    );
+    for (collection, index) in &hnsw_indexes {
+        writer.sample(
+            "cosdata_hnsw_cache_hits_total",
+            &[("collection", &collection.meta.name)],
+            index.cache.registry.hits(),
+        );
+    }
+
+    writer.header(
+        "cosdata_hnsw_cache_misses_total",
+        "Number of lookups that loaded the node into the HNSW node cache",
+        "counter",
+    );
+    for (collection, index) in &hnsw_indexes {
+        writer.sample(
+            "cosdata_hnsw_cache_misses_total",
+            &[("collection", &collection.meta.name)],
+            index.cache.registry.misses(),
+        );
+    }
+
+    writer.header(
+        "cosdata_buffer_dirty_regions",
+        "Number of buffered file regions with writes that are not flushed yet",
+        "gauge",
+    );
+    for (collection, index) in &hnsw_indexes {
+        writer.sample(
+            "cosdata_buffer_dirty_regions",
+            &[
+                ("collection", &collection.meta.name),
+                ("index_type", HNSWIndex::INDEX_TYPE),
+            ],
+            index.cache.dirty_regions_count(),
+        );
+    }
+    for collection in collections {
+        if let Some(index) = collection.inverted_index.read().clone() {
+            writer.sample(
+                "cosdata_buffer_dirty_regions",
+                &[
+                    ("collection", &collection.meta.name),
+                    ("index_type", InvertedIndex::INDEX_TYPE),
+                ],
+                index.root.cache.dirty_regions_count(),
+            );
+        }
+        if let Some(index) = collection.tf_idf_index.read().clone() {
+            writer.sample(
+                "cosdata_buffer_dirty_regions",
+                &[
+                    ("collection", &collection.meta.name),
+                    ("index_type", TFIDFIndex::INDEX_TYPE),
+                ],
+                index.root.cache.dirty_regions_count(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wal_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1.wal"), [0u8; 10]).unwrap();
+        fs::write(dir.path().join("2.wal"), [0u8; 5]).unwrap();
+        fs::write(dir.path().join("nodes.ptr"), [0u8; 100]).unwrap();
+
+        assert_eq!(15, wal_size(dir.path()));
+        assert_eq!(0, wal_size(&dir.path().join("missing")));
+    }
+}
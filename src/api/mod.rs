@@ -1,4 +1,6 @@
 pub(crate) mod auth;
 pub(crate) mod docs;
+pub(crate) mod health;
+pub(crate) mod metrics;
 pub(crate) mod openapi;
 pub(crate) mod vectordb;
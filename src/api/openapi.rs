@@ -24,13 +24,42 @@ fn api_info() -> openapi::Info {
 #[derive(OpenApi)]
 #[openapi(
     paths(
-        crate::api::auth::controller::create_session
+        crate::api::auth::controller::create_session,
+        crate::api::auth::controller::refresh_session,
+        crate::api::auth::controller::revoke_session,
+        crate::api::auth::controller::create_role,
+        crate::api::auth::controller::list_roles,
+        crate::api::auth::controller::get_role,
+        crate::api::auth::controller::delete_role,
+        crate::api::auth::controller::create_user,
+        crate::api::auth::controller::list_users,
+        crate::api::auth::controller::get_user,
+        crate::api::auth::controller::delete_user,
+        crate::api::auth::controller::disable_user,
+        crate::api::auth::controller::enable_user,
+        crate::api::auth::controller::revoke_user_sessions,
+        crate::api::auth::controller::change_password,
+        crate::api::auth::controller::create_api_key,
+        crate::api::auth::controller::list_api_keys,
+        crate::api::auth::controller::get_api_key,
+        crate::api::auth::controller::revoke_api_key
     ),
     components(
         schemas(
             crate::api::auth::dtos::CreateSessionDTO,
             crate::api::auth::dtos::Session,
-            crate::api::auth::dtos::Claims
+            crate::api::auth::dtos::RefreshSessionDto,
+            crate::api::auth::dtos::RevokeSessionDto,
+            crate::api::auth::dtos::Claims,
+            crate::api::auth::dtos::CreateRoleDto,
+            crate::api::auth::dtos::RoleDto,
+            crate::api::auth::dtos::CreateUserDto,
+            crate::api::auth::dtos::ChangePasswordDto,
+            crate::api::auth::dtos::UserDto,
+            crate::api::auth::dtos::CreateApiKeyDto,
+            crate::api::auth::dtos::ApiKeyDto,
+            crate::api::auth::dtos::CreateApiKeyResponseDto,
+            crate::models::rbac::Permission
         )
     ),
     tags(
@@ -40,6 +69,27 @@ fn api_info() -> openapi::Info {
 )]
 pub struct AuthApiDoc;
 
+/// API documentation for collection access control endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::api::vectordb::access::controller::list_collection_access,
+        crate::api::vectordb::access::controller::get_user_roles,
+        crate::api::vectordb::access::controller::assign_role,
+        crate::api::vectordb::access::controller::revoke_role
+    ),
+    components(
+        schemas(
+            crate::api::vectordb::access::dtos::UserAccessDto
+        )
+    ),
+    tags(
+        (name = "access", description = "Collection access control endpoints")
+    ),
+    modifiers(&AccessApiDoc)
+)]
+pub struct AccessApiDoc;
+
 /// API documentation for collection management endpoints
 #[derive(OpenApi)]
 #[openapi(
@@ -65,6 +115,7 @@ pub struct AuthApiDoc;
             crate::api::vectordb::collections::dtos::ConditionOp,
             crate::models::collection::CollectionConfig,
             crate::models::collection::DenseVectorOptions,
+            crate::models::collection::DenseVectorField,
             crate::models::collection::SparseVectorOptions,
             crate::models::collection::TFIDFOptions,
             CollectionIndexingStatusResponse
@@ -98,6 +149,7 @@ pub struct CollectionsApiDoc;
             crate::api::vectordb::indexes::dtos::ValuesRange,
             crate::api::vectordb::indexes::dtos::DenseIndexQuantizationDto,
             crate::api::vectordb::indexes::dtos::HNSWHyperParamsDto,
+            crate::indexes::hnsw::types::QueryPrecision,
             crate::api::vectordb::indexes::dtos::DenseIndexParamsDto,
             crate::models::schema_traits::DistanceMetricSchema,
             crate::api::vectordb::indexes::dtos::IndexResponseDto,
@@ -106,6 +158,10 @@ pub struct CollectionsApiDoc;
             crate::api::vectordb::indexes::dtos::DenseIndexInfo,
             crate::api::vectordb::indexes::dtos::SparseIndexInfo,
             crate::api::vectordb::indexes::dtos::TfIdfIndexInfo,
+            crate::indexes::tf_idf::analyzer::TextAnalyzerConfig,
+            crate::indexes::tf_idf::analyzer::TokenizerConfig,
+            crate::indexes::tf_idf::analyzer::StopwordsConfig,
+            crate::indexes::tf_idf::analyzer::StemmerLanguage,
             crate::api::vectordb::indexes::dtos::QuantizationInfo,
             crate::api::vectordb::indexes::dtos::RangeInfo,
             crate::api::vectordb::indexes::dtos::HnswParamsInfo
@@ -124,24 +180,42 @@ pub struct IndexesApiDoc;
     paths(
         crate::api::vectordb::search::controller::dense_search,
         crate::api::vectordb::search::controller::batch_dense_search,
+        crate::api::vectordb::search::controller::multi_vector_search,
         crate::api::vectordb::search::controller::sparse_search,
         crate::api::vectordb::search::controller::batch_sparse_search,
         crate::api::vectordb::search::controller::hybrid_search,
         crate::api::vectordb::search::controller::tf_idf_search,
-        crate::api::vectordb::search::controller::batch_tf_idf_search
+        crate::api::vectordb::search::controller::batch_tf_idf_search,
+        crate::api::vectordb::search::controller::evaluate_dense_index,
+        crate::api::vectordb::search::controller::evaluate_sparse_index,
+        crate::api::vectordb::search::controller::evaluate_tf_idf_index
     ),
     components(
         schemas(
             crate::api::vectordb::search::dtos::DenseSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchDenseSearchRequestDto,
+            crate::api::vectordb::search::dtos::MultiVectorSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchDenseSearchRequestQueryDto,
             crate::api::vectordb::search::dtos::SparseSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchSparseSearchRequestDto,
             crate::api::vectordb::search::dtos::HybridSearchRequestDto,
             crate::api::vectordb::search::dtos::HybridSearchQuery,
+            crate::api::vectordb::search::dtos::FusionMethod,
+            crate::api::vectordb::search::dtos::RetrieverOptions,
             crate::api::vectordb::search::dtos::FindSimilarTFIDFDocumentDto,
             crate::api::vectordb::search::dtos::BatchSearchTFIDFDocumentsDto,
+            crate::api::vectordb::search::dtos::EvaluateDenseIndexDto,
+            crate::api::vectordb::search::dtos::EvaluateSparseIndexDto,
+            crate::api::vectordb::search::dtos::EvaluateTFIDFIndexDto,
+            crate::api::vectordb::search::dtos::LatencyPercentilesDto,
+            crate::api::vectordb::search::dtos::RecallReportDto,
+            crate::api::vectordb::search::dtos::DenseEvaluationDto,
+            crate::api::vectordb::search::dtos::ThresholdRecallDto,
+            crate::api::vectordb::search::dtos::QuantizationRecallDto,
+            crate::api::vectordb::search::dtos::SparseEvaluationDto,
+            crate::api::vectordb::search::dtos::TFIDFEvaluationDto,
             crate::api::vectordb::search::dtos::SearchResultItemDto,
+            crate::api::vectordb::search::highlight::HighlightOptions,
             crate::api::vectordb::search::dtos::SearchResponseDto,
             crate::api::vectordb::search::dtos::BatchSearchResponseDto
         )
@@ -249,6 +323,28 @@ pub struct StreamingApiDoc;
 #[openapi(
     paths(
         crate::api::auth::controller::create_session,
+        crate::api::auth::controller::refresh_session,
+        crate::api::auth::controller::revoke_session,
+        crate::api::auth::controller::create_role,
+        crate::api::auth::controller::list_roles,
+        crate::api::auth::controller::get_role,
+        crate::api::auth::controller::delete_role,
+        crate::api::auth::controller::create_user,
+        crate::api::auth::controller::list_users,
+        crate::api::auth::controller::get_user,
+        crate::api::auth::controller::delete_user,
+        crate::api::auth::controller::disable_user,
+        crate::api::auth::controller::enable_user,
+        crate::api::auth::controller::revoke_user_sessions,
+        crate::api::auth::controller::change_password,
+        crate::api::auth::controller::create_api_key,
+        crate::api::auth::controller::list_api_keys,
+        crate::api::auth::controller::get_api_key,
+        crate::api::auth::controller::revoke_api_key,
+        crate::api::vectordb::access::controller::list_collection_access,
+        crate::api::vectordb::access::controller::get_user_roles,
+        crate::api::vectordb::access::controller::assign_role,
+        crate::api::vectordb::access::controller::revoke_role,
         crate::api::vectordb::collections::controller::create_collection,
         crate::api::vectordb::collections::controller::get_collections,
         crate::api::vectordb::collections::controller::get_collection_by_id,
@@ -264,11 +360,15 @@ pub struct StreamingApiDoc;
         crate::api::vectordb::indexes::controller::delete_index,
         crate::api::vectordb::search::controller::dense_search,
         crate::api::vectordb::search::controller::batch_dense_search,
+        crate::api::vectordb::search::controller::multi_vector_search,
         crate::api::vectordb::search::controller::sparse_search,
         crate::api::vectordb::search::controller::batch_sparse_search,
         crate::api::vectordb::search::controller::hybrid_search,
         crate::api::vectordb::search::controller::tf_idf_search,
         crate::api::vectordb::search::controller::batch_tf_idf_search,
+        crate::api::vectordb::search::controller::evaluate_dense_index,
+        crate::api::vectordb::search::controller::evaluate_sparse_index,
+        crate::api::vectordb::search::controller::evaluate_tf_idf_index,
         crate::api::vectordb::vectors::controller::query_vectors,
         crate::api::vectordb::vectors::controller::get_vector_by_id,
         crate::api::vectordb::vectors::controller::check_vector_existence,
@@ -284,13 +384,29 @@ pub struct StreamingApiDoc;
         crate::api::vectordb::transactions::controller::abort_transaction,
         crate::api::vectordb::transactions::controller::upsert,
         crate::api::vectordb::streaming::controller::upsert,
-        crate::api::vectordb::streaming::controller::delete_vector_by_id
+        crate::api::vectordb::streaming::controller::delete_vector_by_id,
+        crate::api::metrics::controller::get_metrics,
+        crate::api::health::controller::health,
+        crate::api::health::controller::ready,
+        crate::api::health::controller::get_info
     ),
     components(
         schemas(
             crate::api::auth::dtos::CreateSessionDTO,
             crate::api::auth::dtos::Session,
+            crate::api::auth::dtos::RefreshSessionDto,
+            crate::api::auth::dtos::RevokeSessionDto,
             crate::api::auth::dtos::Claims,
+            crate::api::auth::dtos::CreateRoleDto,
+            crate::api::auth::dtos::RoleDto,
+            crate::api::auth::dtos::CreateUserDto,
+            crate::api::auth::dtos::ChangePasswordDto,
+            crate::api::auth::dtos::UserDto,
+            crate::api::auth::dtos::CreateApiKeyDto,
+            crate::api::auth::dtos::ApiKeyDto,
+            crate::api::auth::dtos::CreateApiKeyResponseDto,
+            crate::models::rbac::Permission,
+            crate::api::vectordb::access::dtos::UserAccessDto,
             crate::api::vectordb::collections::dtos::CreateCollectionDto,
             crate::api::vectordb::collections::dtos::CreateCollectionDtoResponse,
             crate::api::vectordb::collections::dtos::GetCollectionsDto,
@@ -301,6 +417,7 @@ pub struct StreamingApiDoc;
             crate::api::vectordb::collections::dtos::ConditionOp,
             crate::models::collection::CollectionConfig,
             crate::models::collection::DenseVectorOptions,
+            crate::models::collection::DenseVectorField,
             crate::models::collection::SparseVectorOptions,
             crate::models::collection::TFIDFOptions,
             CollectionIndexingStatusResponse,
@@ -313,6 +430,7 @@ pub struct StreamingApiDoc;
             crate::api::vectordb::indexes::dtos::ValuesRange,
             crate::api::vectordb::indexes::dtos::DenseIndexQuantizationDto,
             crate::api::vectordb::indexes::dtos::HNSWHyperParamsDto,
+            crate::indexes::hnsw::types::QueryPrecision,
             crate::api::vectordb::indexes::dtos::DenseIndexParamsDto,
             crate::models::schema_traits::DistanceMetricSchema,
             crate::api::vectordb::indexes::dtos::IndexResponseDto,
@@ -321,19 +439,37 @@ pub struct StreamingApiDoc;
             crate::api::vectordb::indexes::dtos::DenseIndexInfo,
             crate::api::vectordb::indexes::dtos::SparseIndexInfo,
             crate::api::vectordb::indexes::dtos::TfIdfIndexInfo,
+            crate::indexes::tf_idf::analyzer::TextAnalyzerConfig,
+            crate::indexes::tf_idf::analyzer::TokenizerConfig,
+            crate::indexes::tf_idf::analyzer::StopwordsConfig,
+            crate::indexes::tf_idf::analyzer::StemmerLanguage,
             crate::api::vectordb::indexes::dtos::QuantizationInfo,
             crate::api::vectordb::indexes::dtos::RangeInfo,
             crate::api::vectordb::indexes::dtos::HnswParamsInfo,
             crate::api::vectordb::search::dtos::DenseSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchDenseSearchRequestDto,
+            crate::api::vectordb::search::dtos::MultiVectorSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchDenseSearchRequestQueryDto,
             crate::api::vectordb::search::dtos::SparseSearchRequestDto,
             crate::api::vectordb::search::dtos::BatchSparseSearchRequestDto,
             crate::api::vectordb::search::dtos::HybridSearchRequestDto,
             crate::api::vectordb::search::dtos::HybridSearchQuery,
+            crate::api::vectordb::search::dtos::FusionMethod,
+            crate::api::vectordb::search::dtos::RetrieverOptions,
             crate::api::vectordb::search::dtos::FindSimilarTFIDFDocumentDto,
             crate::api::vectordb::search::dtos::BatchSearchTFIDFDocumentsDto,
+            crate::api::vectordb::search::dtos::EvaluateDenseIndexDto,
+            crate::api::vectordb::search::dtos::EvaluateSparseIndexDto,
+            crate::api::vectordb::search::dtos::EvaluateTFIDFIndexDto,
+            crate::api::vectordb::search::dtos::LatencyPercentilesDto,
+            crate::api::vectordb::search::dtos::RecallReportDto,
+            crate::api::vectordb::search::dtos::DenseEvaluationDto,
+            crate::api::vectordb::search::dtos::ThresholdRecallDto,
+            crate::api::vectordb::search::dtos::QuantizationRecallDto,
+            crate::api::vectordb::search::dtos::SparseEvaluationDto,
+            crate::api::vectordb::search::dtos::TFIDFEvaluationDto,
             crate::api::vectordb::search::dtos::SearchResultItemDto,
+            crate::api::vectordb::search::highlight::HighlightOptions,
             crate::api::vectordb::search::dtos::SearchResponseDto,
             crate::api::vectordb::search::dtos::BatchSearchResponseDto,
             crate::api::vectordb::vectors::dtos::VectorsQueryDto,
@@ -345,18 +481,24 @@ pub struct StreamingApiDoc;
             crate::api::vectordb::transactions::dtos::CreateTransactionResponseDto,
             crate::api::vectordb::transactions::dtos::UpsertDto,
             crate::models::collection_transaction::TransactionStatus,
-            crate::models::collection_transaction::ProcessingStats
+            crate::models::collection_transaction::ProcessingStats,
+            crate::api::health::dtos::HealthResponseDto,
+            crate::api::health::dtos::ReadinessResponseDto,
+            crate::api::health::dtos::ServerInfoDto
         )
     ),
     tags(
         (name = "auth", description = "Authentication endpoints"),
+        (name = "access", description = "Collection access control endpoints"),
         (name = "collections", description = "Collection management endpoints"),
         (name = "indexes", description = "Index management endpoints"),
         (name = "search", description = "Vector search endpoints"),
         (name = "vectors", description = "Vector management endpoints"),
         (name = "versions", description = "Version management endpoints"),
         (name = "transactions", description = "Transaction management endpoints"),
-        (name = "streaming", description = "Streaming endpoints")
+        (name = "streaming", description = "Streaming endpoints"),
+        (name = "metrics", description = "Monitoring endpoints"),
+        (name = "health", description = "Health and server information endpoints")
     ),
     modifiers(&CombinedApiDoc)
 )]
@@ -368,6 +510,12 @@ impl utoipa::Modify for AuthApiDoc {
     }
 }
 
+impl utoipa::Modify for AccessApiDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.info = api_info();
+    }
+}
+
 impl utoipa::Modify for CollectionsApiDoc {
     fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
         openapi.info = api_info();
@@ -0,0 +1,108 @@
+use actix_web::{web, HttpResponse, Result};
+
+use crate::app_context::AppContext;
+
+use super::{dtos::UserAccessDto, service};
+
+/// List access for a collection
+///
+/// Returns the roles granted to each user on the collection. Use `*` as the
+/// collection identifier for roles granted on all collections.
+#[utoipa::path(
+    get,
+    path = "/vectordb/collections/{collection_id}/access",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier, or `*`")
+    ),
+    responses(
+        (status = 200, description = "Access assignments of the collection", body = Vec<UserAccessDto>),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Collection not found")
+    ),
+    tag = "access"
+)]
+pub(crate) async fn list_collection_access(
+    collection_id: web::Path<String>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let access = service::list_collection_access(ctx.into_inner(), &collection_id).await?;
+    Ok(HttpResponse::Ok().json(access))
+}
+
+/// Get user roles on a collection
+#[utoipa::path(
+    get,
+    path = "/vectordb/collections/{collection_id}/access/users/{username}/roles",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier, or `*`"),
+        ("username" = String, Path, description = "Name of the user")
+    ),
+    responses(
+        (status = 200, description = "Roles of the user on the collection", body = UserAccessDto),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Collection or user not found")
+    ),
+    tag = "access"
+)]
+pub(crate) async fn get_user_roles(
+    path: web::Path<(String, String)>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let (collection_id, username) = path.into_inner();
+    let access = service::get_user_roles(ctx.into_inner(), &collection_id, &username).await?;
+    Ok(HttpResponse::Ok().json(access))
+}
+
+/// Assign role to user
+///
+/// Grants the role to the user on the collection.
+#[utoipa::path(
+    post,
+    path = "/vectordb/collections/{collection_id}/access/users/{username}/roles/{role}",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier, or `*`"),
+        ("username" = String, Path, description = "Name of the user"),
+        ("role" = String, Path, description = "Name of the role")
+    ),
+    responses(
+        (status = 200, description = "Role assigned successfully", body = UserAccessDto),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Collection, user or role not found")
+    ),
+    tag = "access"
+)]
+pub(crate) async fn assign_role(
+    path: web::Path<(String, String, String)>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let (collection_id, username, role) = path.into_inner();
+    let access = service::assign_role(ctx.into_inner(), &collection_id, &username, &role).await?;
+    Ok(HttpResponse::Ok().json(access))
+}
+
+/// Remove role from user
+///
+/// Revokes the role from the user on the collection.
+#[utoipa::path(
+    delete,
+    path = "/vectordb/collections/{collection_id}/access/users/{username}/roles/{role}",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier, or `*`"),
+        ("username" = String, Path, description = "Name of the user"),
+        ("role" = String, Path, description = "Name of the role")
+    ),
+    responses(
+        (status = 204, description = "Role removed successfully"),
+        (status = 403, description = "Permission denied"),
+        (status = 404, description = "Role assignment not found")
+    ),
+    tag = "access"
+)]
+pub(crate) async fn revoke_role(
+    path: web::Path<(String, String, String)>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse> {
+    let (collection_id, username, role) = path.into_inner();
+    service::revoke_role(ctx.into_inner(), &collection_id, &username, &role).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
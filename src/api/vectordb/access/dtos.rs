@@ -0,0 +1,22 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::rbac::RoleAssignment;
+
+/// Roles granted to a user on a collection
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct UserAccessDto {
+    /// Name of the user
+    pub username: String,
+    /// Roles granted to the user on the collection
+    pub roles: Vec<String>,
+}
+
+impl From<RoleAssignment> for UserAccessDto {
+    fn from(assignment: RoleAssignment) -> Self {
+        Self {
+            username: assignment.username,
+            roles: assignment.roles,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use actix_web::{web, Scope};
+
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
+
+pub(crate) mod controller;
+pub(crate) mod dtos;
+mod service;
+
+pub(crate) fn access_module() -> Scope {
+    web::scope("/collections/{collection_id}/access")
+        .route(
+            "",
+            web::get()
+                .to(controller::list_collection_access)
+                .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+        )
+        .route(
+            "/users/{username}/roles",
+            web::get()
+                .to(controller::get_user_roles)
+                .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+        )
+        .route(
+            "/users/{username}/roles/{role}",
+            web::post()
+                .to(controller::assign_role)
+                .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+        )
+        .route(
+            "/users/{username}/roles/{role}",
+            web::delete()
+                .to(controller::revoke_role)
+                .wrap(AuthorizationMiddleware(Permission::ManagePermissions)),
+        )
+}
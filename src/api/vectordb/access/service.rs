@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use crate::{api::auth::error::AuthError, app_context::AppContext, models::rbac::GLOBAL_SCOPE};
+
+use super::dtos::UserAccessDto;
+
+/// role assignments can be made on existing collections, or globally
+fn check_collection_exists(ctx: &AppContext, collection_id: &str) -> Result<(), AuthError> {
+    if collection_id == GLOBAL_SCOPE
+        || ctx
+            .ain_env
+            .collections_map
+            .get_collection(collection_id)
+            .is_some()
+    {
+        Ok(())
+    } else {
+        Err(AuthError::NotFound(format!(
+            "collection '{}'",
+            collection_id
+        )))
+    }
+}
+
+fn check_user_exists(ctx: &AppContext, username: &str) -> Result<(), AuthError> {
+    ctx.ain_env
+        .users_map
+        .get_user(username)
+        .map(|_| ())
+        .ok_or_else(|| AuthError::NotFound(format!("user '{}'", username)))
+}
+
+pub(crate) async fn list_collection_access(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+) -> Result<Vec<UserAccessDto>, AuthError> {
+    check_collection_exists(&ctx, collection_id)?;
+    Ok(ctx
+        .ain_env
+        .rbac
+        .list_collection_access(collection_id)
+        .into_iter()
+        .map(UserAccessDto::from)
+        .collect())
+}
+
+pub(crate) async fn get_user_roles(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    username: &str,
+) -> Result<UserAccessDto, AuthError> {
+    check_collection_exists(&ctx, collection_id)?;
+    check_user_exists(&ctx, username)?;
+    Ok(UserAccessDto {
+        username: username.to_string(),
+        roles: ctx.ain_env.rbac.get_user_roles(collection_id, username),
+    })
+}
+
+pub(crate) async fn assign_role(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    username: &str,
+    role: &str,
+) -> Result<UserAccessDto, AuthError> {
+    check_collection_exists(&ctx, collection_id)?;
+    check_user_exists(&ctx, username)?;
+    ctx.ain_env
+        .rbac
+        .assign_role(collection_id, username, role)?;
+    Ok(UserAccessDto {
+        username: username.to_string(),
+        roles: ctx.ain_env.rbac.get_user_roles(collection_id, username),
+    })
+}
+
+pub(crate) async fn revoke_role(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    username: &str,
+    role: &str,
+) -> Result<(), AuthError> {
+    check_collection_exists(&ctx, collection_id)?;
+    if !ctx
+        .ain_env
+        .rbac
+        .revoke_role(collection_id, username, role)?
+    {
+        return Err(AuthError::NotFound(format!(
+            "role '{}' of user '{}' on '{}'",
+            role, username, collection_id
+        )));
+    }
+    Ok(())
+}
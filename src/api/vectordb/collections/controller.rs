@@ -1,6 +1,6 @@
 use actix_web::{web, HttpResponse, Result};
 
-use crate::app_context::AppContext;
+use crate::{api::auth::dtos::Claims, app_context::AppContext};
 
 use super::{
     dtos::{
@@ -30,16 +30,18 @@ use crate::api::openapi::CollectionIndexingStatusResponse;
 pub(crate) async fn create_collection(
     web::Json(create_collection_dto): web::Json<CreateCollectionDto>,
     ctx: web::Data<AppContext>,
+    claims: Claims,
 ) -> Result<HttpResponse> {
     let create_collection_response_dto =
-        service::create_collection(ctx.into_inner(), create_collection_dto).await?;
+        service::create_collection(ctx.into_inner(), create_collection_dto, &claims.username)
+            .await?;
 
     Ok(HttpResponse::Created().json(create_collection_response_dto))
 }
 
 /// Get all collections
 ///
-/// Returns a list of all collections.
+/// Returns a list of all collections the user is allowed to list.
 #[utoipa::path(
     get,
     path = "/vectordb/collections",
@@ -55,8 +57,10 @@ pub(crate) async fn create_collection(
 pub(crate) async fn get_collections(
     web::Query(get_collections_dto): web::Query<GetCollectionsDto>,
     ctx: web::Data<AppContext>,
+    claims: Claims,
 ) -> Result<HttpResponse> {
-    let collections = service::get_collections(ctx.into_inner(), get_collections_dto).await?;
+    let collections =
+        service::get_collections(ctx.into_inner(), get_collections_dto, &claims).await?;
     Ok(HttpResponse::Ok().json(collections))
 }
 
@@ -1,33 +1,64 @@
 use actix_web::{web, Scope};
+
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::api::health::middleware::RecoveryGateMiddleware;
+use crate::models::rbac::Permission;
+
 pub(crate) mod controller;
 pub(crate) mod dtos;
 mod error;
 mod repo;
 pub(crate) mod service;
 
+// the routes creating, deleting, loading and unloading collections wait
+// for the restart recovery, the ones reading them don't
 pub(crate) fn collections_module() -> Scope {
     web::scope("/collections")
-        .route("", web::post().to(controller::create_collection))
+        .route(
+            "",
+            web::post()
+                .to(controller::create_collection)
+                .wrap(AuthorizationMiddleware(Permission::CreateCollection))
+                .wrap(RecoveryGateMiddleware),
+        )
         .route("", web::get().to(controller::get_collections))
-        .route("/loaded", web::get().to(controller::get_loaded_collections))
+        .route(
+            "/loaded",
+            web::get()
+                .to(controller::get_loaded_collections)
+                .wrap(AuthorizationMiddleware(Permission::ListCollections)),
+        )
         .route(
             "/{collection_id}",
-            web::get().to(controller::get_collection_by_id),
+            web::get()
+                .to(controller::get_collection_by_id)
+                .wrap(AuthorizationMiddleware(Permission::ListCollections)),
         )
         .route(
             "/{collection_id}/indexing_status",
-            web::get().to(controller::get_collection_indexing_status),
+            web::get()
+                .to(controller::get_collection_indexing_status)
+                .wrap(AuthorizationMiddleware(Permission::ListCollections)),
         )
         .route(
             "/{collection_id}",
-            web::delete().to(controller::delete_collection_by_id),
+            web::delete()
+                .to(controller::delete_collection_by_id)
+                .wrap(AuthorizationMiddleware(Permission::DeleteCollection))
+                .wrap(RecoveryGateMiddleware),
         )
         .route(
             "/{collection_id}/load",
-            web::post().to(controller::load_collection),
+            web::post()
+                .to(controller::load_collection)
+                .wrap(AuthorizationMiddleware(Permission::UpdateCollection))
+                .wrap(RecoveryGateMiddleware),
         )
         .route(
             "/{collection_id}/unload",
-            web::post().to(controller::unload_collection),
+            web::post()
+                .to(controller::unload_collection)
+                .wrap(AuthorizationMiddleware(Permission::UpdateCollection))
+                .wrap(RecoveryGateMiddleware),
         )
 }
@@ -65,7 +65,10 @@ pub(crate) async fn create_collection(
         vcs,
         &ctx,
     )
-    .map_err(CollectionsError::WaCustomError)?;
+    .map_err(|err| match err {
+        WaCustomError::InvalidData(msg) => CollectionsError::FailedToCreateCollection(msg),
+        err => CollectionsError::WaCustomError(err),
+    })?;
 
     // adding the created collection into the in-memory map
     ctx.ain_env
@@ -157,5 +160,11 @@ pub(crate) async fn delete_collection_by_name(
         .remove_collection(name)
         .map_err(CollectionsError::WaCustomError)?;
 
+    // revoking all the roles granted on the collection
+    ctx.ain_env
+        .rbac
+        .remove_collection(name)
+        .map_err(CollectionsError::WaCustomError)?;
+
     Ok(collection)
 }
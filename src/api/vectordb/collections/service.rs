@@ -1,8 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
+    api::auth::{authorization_middleware::is_authorized, dtos::Claims},
     app_context::AppContext,
-    models::collection::{Collection, CollectionIndexingStatus},
+    models::{
+        collection::{Collection, CollectionIndexingStatus},
+        rbac::{Permission, ADMIN_ROLE, GLOBAL_SCOPE},
+    },
 };
 
 use super::{
@@ -14,11 +18,25 @@ use super::{
     repo,
 };
 
+/// creates a collection and grants its creator the admin role on it
 pub(crate) async fn create_collection(
     ctx: Arc<AppContext>,
     create_collection_dto: CreateCollectionDto,
+    username: &str,
 ) -> Result<CreateCollectionDtoResponse, CollectionsError> {
+    // the creator gets the admin role on the collection, which on the
+    // global scope would make them an admin of all collections
+    if create_collection_dto.name == GLOBAL_SCOPE {
+        return Err(CollectionsError::FailedToCreateCollection(format!(
+            "collection name '{}' is reserved",
+            GLOBAL_SCOPE
+        )));
+    }
     let collection = repo::create_collection(ctx.clone(), create_collection_dto).await?;
+    ctx.ain_env
+        .rbac
+        .assign_role(&collection.meta.name, username, ADMIN_ROLE)
+        .map_err(CollectionsError::WaCustomError)?;
 
     Ok(CreateCollectionDtoResponse {
         id: collection.meta.name.clone(),
@@ -27,12 +45,19 @@ pub(crate) async fn create_collection(
     })
 }
 
+/// gets the collections which the user has `list_collections` permission on
 pub(crate) async fn get_collections(
     ctx: Arc<AppContext>,
     get_collections_dto: GetCollectionsDto,
+    claims: &Claims,
 ) -> Result<Vec<GetCollectionsResponseDto>, CollectionsError> {
-    let collections = repo::get_collections(ctx, get_collections_dto).await?;
-    Ok(collections)
+    let collections = repo::get_collections(ctx.clone(), get_collections_dto).await?;
+    Ok(collections
+        .into_iter()
+        .filter(|collection| {
+            is_authorized(&ctx, claims, &collection.name, Permission::ListCollections)
+        })
+        .collect())
 }
 
 /// gets a collection with vector counts by its id
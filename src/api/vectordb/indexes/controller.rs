@@ -2,7 +2,9 @@ use actix_web::{web, HttpResponse, Result};
 
 use crate::app_context::AppContext;
 
-use super::dtos::{CreateTFIDFIndexDto, IndexDetailsDto, IndexResponseDto, IndexType};
+use super::dtos::{
+    CreateTFIDFIndexDto, DeleteIndexQueryDto, IndexDetailsDto, IndexResponseDto, IndexType,
+};
 use super::error::IndexesError;
 use super::{
     dtos::{CreateDenseIndexDto, CreateSparseIndexDto},
@@ -146,7 +148,8 @@ pub(crate) async fn get_index(
     path = "/vectordb/collections/{collection_id}/indexes/{index_type}",
     params(
         ("collection_id" = String, Path, description = "Collection identifier"),
-        ("index_type" = IndexType, Path, description = "Type of index to delete (dense, sparse or tf_idf)")
+        ("index_type" = IndexType, Path, description = "Type of index to delete (dense, sparse or tf_idf)"),
+        ("field" = Option<String>, Query, description = "Named dense vector field whose index is deleted")
     ),
     responses(
         (status = 204, description = "Index successfully deleted"),
@@ -157,9 +160,10 @@ pub(crate) async fn get_index(
 )]
 pub(crate) async fn delete_index(
     path: web::Path<(String, IndexType)>,
+    web::Query(query): web::Query<DeleteIndexQueryDto>,
     ctx: web::Data<AppContext>,
 ) -> Result<HttpResponse, IndexesError> {
     let (collection_id, index_type) = path.into_inner();
-    service::delete_index(collection_id, index_type, ctx.into_inner()).await?;
+    service::delete_index(collection_id, index_type, query.field, ctx.into_inner()).await?;
     Ok(HttpResponse::NoContent().finish())
 }
@@ -2,8 +2,13 @@ use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::ToSchema;
 
 use crate::{
-    config_loader::Config, indexes::hnsw::types::HNSWHyperParams,
-    models::schema_traits::DistanceMetricSchema, quantization::StorageType,
+    config_loader::Config,
+    indexes::{
+        hnsw::types::{HNSWHyperParams, QueryPrecision},
+        tf_idf::analyzer::TextAnalyzerConfig,
+    },
+    models::schema_traits::DistanceMetricSchema,
+    quantization::StorageType,
 };
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -14,6 +19,13 @@ pub enum IndexType {
     TfIdf,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct DeleteIndexQueryDto {
+    /// Named dense vector field whose index is deleted, only for dense
+    /// indexes
+    pub field: Option<String>,
+}
+
 #[derive(Debug, Default, ToSchema)]
 pub enum SparseIndexQuantization {
     #[default]
@@ -50,6 +62,10 @@ pub enum IndexInfo {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DenseIndexInfo {
     pub name: String,
+    /// Named dense vector field indexed, absent for the default dense
+    /// vectors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
     pub algorithm: String,
     pub distance_metric: String,
     pub quantization: QuantizationInfo,
@@ -77,6 +93,10 @@ pub struct HnswParamsInfo {
     pub neighbors_count: usize,
     pub level_0_neighbors_count: usize,
     pub num_layers: u8,
+    #[serde(default)]
+    pub oversampling: f32,
+    #[serde(default)]
+    pub query_precision: QueryPrecision,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -93,6 +113,13 @@ pub struct TfIdfIndexInfo {
     pub algorithm: String,
     pub k1: f32,
     pub b: f32,
+    pub analyzer: TextAnalyzerConfig,
+    pub store_positions: bool,
+    #[serde(default)]
+    pub store_terms: bool,
+    /// Named text fields indexed so far
+    #[serde(default)]
+    pub fields: Vec<String>,
 }
 
 impl<'de> Deserialize<'de> for SparseIndexQuantization {
@@ -130,6 +157,8 @@ impl SparseIndexQuantization {
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum DataType {
+    /// 1-bit sign quantization, traversed with popcount distances and
+    /// re-scored with the full precision raw vectors
     Binary,
     Quaternay,
     Octal,
@@ -158,12 +187,18 @@ pub enum DenseIndexQuantizationDto {
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct HNSWHyperParamsDto {
-    ef_construction: Option<u32>, // Size of the dynamic candidate list during index construction
-    ef_search: Option<u32>,       // Size of the dynamic candidate list during search
-    num_layers: Option<u8>,       // Number of layers in the hierarchical graph
-    max_cache_size: Option<usize>, // Maximum number of elements in the cache
-    level_0_neighbors_count: Option<usize>,
-    neighbors_count: Option<usize>,
+    pub ef_construction: Option<u32>, // Size of the dynamic candidate list during index construction
+    pub ef_search: Option<u32>,       // Size of the dynamic candidate list during search
+    pub num_layers: Option<u8>,       // Number of layers in the hierarchical graph
+    pub max_cache_size: Option<usize>, // Maximum number of elements in the cache
+    pub level_0_neighbors_count: Option<usize>,
+    pub neighbors_count: Option<usize>,
+    /// Factor applied to `top_k` to get the number of candidates re-scored
+    /// with the full precision raw vectors, at least 1
+    pub oversampling: Option<f32>,
+    /// Precision the queries are scored in, `f32` and `f16` are only
+    /// supported by the `u8` and sub-byte storage types
+    pub query_precision: Option<QueryPrecision>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -178,6 +213,10 @@ pub(crate) struct CreateDenseIndexDto {
     pub distance_metric_type: DistanceMetricSchema,
     pub quantization: DenseIndexQuantizationDto,
     pub index: DenseIndexParamsDto,
+    /// Named dense vector field of the collection to index, the default
+    /// dense vectors are indexed if absent
+    #[serde(default)]
+    pub field: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -193,6 +232,15 @@ pub(crate) struct CreateTFIDFIndexDto {
     pub sample_threshold: usize,
     pub k1: f32,
     pub b: f32,
+    #[serde(default)]
+    pub analyzer: TextAnalyzerConfig,
+    /// Stores the positions of the terms, required for phrase queries
+    #[serde(default)]
+    pub store_positions: bool,
+    /// Stores the dictionary of the terms, required for prefix and fuzzy
+    /// queries
+    #[serde(default)]
+    pub store_terms: bool,
 }
 
 impl HNSWHyperParamsDto {
@@ -223,6 +271,14 @@ impl HNSWHyperParamsDto {
             default.neighbors_count = neighbors_count;
         }
 
+        if let Some(oversampling) = self.oversampling {
+            default.oversampling = oversampling;
+        }
+
+        if let Some(query_precision) = self.query_precision {
+            default.query_precision = query_precision;
+        }
+
         default
     }
 }
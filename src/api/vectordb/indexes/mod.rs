@@ -1,17 +1,45 @@
 use actix_web::{web, Scope};
+
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
 use controller::{create_dense_index, create_sparse_index, create_tf_idf_index, delete_index};
 
 pub(crate) mod controller;
 pub(crate) mod dtos;
-mod error;
+pub(crate) mod error;
 mod repo;
-mod service;
+pub(crate) mod service;
 
 pub(crate) fn indexes_module() -> Scope {
     web::scope("/collections/{collection_id}/indexes")
-        .route("", web::get().to(controller::get_index))
-        .route("/dense", web::post().to(create_dense_index))
-        .route("/sparse", web::post().to(create_sparse_index))
-        .route("/tf-idf", web::post().to(create_tf_idf_index))
-        .route("/{index_type}", web::delete().to(delete_index))
+        .route(
+            "",
+            web::get()
+                .to(controller::get_index)
+                .wrap(AuthorizationMiddleware(Permission::ListIndex)),
+        )
+        .route(
+            "/dense",
+            web::post()
+                .to(create_dense_index)
+                .wrap(AuthorizationMiddleware(Permission::CreateIndex)),
+        )
+        .route(
+            "/sparse",
+            web::post()
+                .to(create_sparse_index)
+                .wrap(AuthorizationMiddleware(Permission::CreateIndex)),
+        )
+        .route(
+            "/tf-idf",
+            web::post()
+                .to(create_tf_idf_index)
+                .wrap(AuthorizationMiddleware(Permission::CreateIndex)),
+        )
+        .route(
+            "/{index_type}",
+            web::delete()
+                .to(delete_index)
+                .wrap(AuthorizationMiddleware(Permission::DeleteIndex)),
+        )
 }
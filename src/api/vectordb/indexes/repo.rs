@@ -8,6 +8,10 @@ use crate::{
         init_tf_idf_index_for_collection,
     },
     app_context::AppContext,
+    indexes::{
+        hnsw::{types::QueryPrecision, HNSWIndex},
+        tf_idf::analyzer::TextAnalyzerConfig,
+    },
     models::types::{DistanceMetric, QuantizationMetric},
     quantization::StorageType,
 };
@@ -24,6 +28,7 @@ pub(crate) async fn create_dense_index(
     distance_metric: DistanceMetric,
     quantization: DenseIndexQuantizationDto,
     index_params: DenseIndexParamsDto,
+    field: Option<String>,
 ) -> Result<(), IndexesError> {
     let collection = ctx
         .ain_env
@@ -31,9 +36,21 @@ pub(crate) async fn create_dense_index(
         .get_collection(&collection_name)
         .ok_or(IndexesError::CollectionNotFound)?;
 
+    if let Some(field) = &field {
+        if collection.dense_dimension(Some(field)).is_none() {
+            return Err(IndexesError::NotFound(format!(
+                "Dense vector field '{}' not found in collection '{}'",
+                field, collection_name
+            )));
+        }
+    }
+
     // Check if index already exists BEFORE initializing
-    if collection.get_hnsw_index().is_some() {
-        return Err(IndexesError::IndexAlreadyExists("dense".to_string()));
+    if collection.get_dense_index(field.as_deref()).is_some() {
+        return Err(IndexesError::IndexAlreadyExists(match &field {
+            Some(field) => format!("dense ({})", field),
+            None => "dense".to_string(),
+        }));
     }
 
     let (quantization_metric, storage_type, range, sample_threshold, is_configured) =
@@ -55,6 +72,28 @@ pub(crate) async fn create_dense_index(
         };
     let DenseIndexParamsDto::Hnsw(hnsw_params_dto) = index_params;
     let hnsw_params = hnsw_params_dto.into_params(&ctx.config);
+    if !hnsw_params.oversampling.is_finite() || hnsw_params.oversampling < 1.0 {
+        return Err(IndexesError::FailedToCreateIndex(format!(
+            "oversampling must be at least 1, got {}",
+            hnsw_params.oversampling
+        )));
+    }
+    if hnsw_params.query_precision != QueryPrecision::Quantized {
+        if !matches!(
+            storage_type,
+            StorageType::UnsignedByte | StorageType::SubByte(1..=3)
+        ) {
+            return Err(IndexesError::FailedToCreateIndex(format!(
+                "query precision {:?} requires u8 or sub-byte storage, got {:?}",
+                hnsw_params.query_precision, storage_type
+            )));
+        }
+        if matches!(distance_metric, DistanceMetric::Hamming) {
+            return Err(IndexesError::FailedToCreateIndex(
+                "hamming distance doesn't support unquantized queries".to_string(),
+            ));
+        }
+    }
     init_hnsw_index_for_collection(
         ctx,
         collection,
@@ -65,6 +104,7 @@ pub(crate) async fn create_dense_index(
         storage_type,
         sample_threshold,
         is_configured,
+        field,
     )
     .await
     .map_err(|e| IndexesError::FailedToCreateIndex(e.to_string()))?;
@@ -103,6 +143,7 @@ pub(crate) async fn create_sparse_index(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn create_tf_idf_index(
     ctx: Arc<AppContext>,
     collection_name: String,
@@ -110,6 +151,9 @@ pub(crate) async fn create_tf_idf_index(
     sample_threshold: usize,
     k1: f32,
     b: f32,
+    analyzer: TextAnalyzerConfig,
+    store_positions: bool,
+    store_terms: bool,
 ) -> Result<(), IndexesError> {
     let collection = ctx
         .ain_env
@@ -117,13 +161,30 @@ pub(crate) async fn create_tf_idf_index(
         .get_collection(&collection_name)
         .ok_or(IndexesError::CollectionNotFound)?;
 
-    if collection.get_tf_idf_index().is_some() {
+    // the indexes in a format that can't be read aren't loaded, but have to
+    // be dropped before they're recreated
+    if collection.get_tf_idf_index().is_some()
+        || collection.get_path().join("tf_idf_index").exists()
+    {
         return Err(IndexesError::IndexAlreadyExists("tf_idf".to_string()));
     }
 
-    init_tf_idf_index_for_collection(ctx, &collection, sample_threshold, k1, b)
-        .await
-        .map_err(|e| IndexesError::FailedToCreateIndex(e.to_string()))?;
+    analyzer
+        .validate()
+        .map_err(|e| IndexesError::FailedToCreateIndex(format!("Invalid analyzer: {}", e)))?;
+
+    init_tf_idf_index_for_collection(
+        ctx,
+        &collection,
+        sample_threshold,
+        k1,
+        b,
+        analyzer,
+        store_positions,
+        store_terms,
+    )
+    .await
+    .map_err(|e| IndexesError::FailedToCreateIndex(e.to_string()))?;
 
     Ok(())
 }
@@ -142,7 +203,7 @@ pub(crate) async fn get_index(
 
     let mut indexes_array = Vec::new();
 
-    if let Some(hnsw) = collection.get_hnsw_index() {
+    for hnsw in collection.dense_indexes() {
         let distance_metric = *hnsw.distance_metric.read().unwrap();
         let values_range = *hnsw.values_range.read().unwrap();
         let hnsw_params = hnsw.hnsw_params.read().unwrap();
@@ -152,6 +213,7 @@ pub(crate) async fn get_index(
         indexes_array.push(serde_json::json!({
             "type": "dense",
             "name": collection_name,
+            "field": hnsw.field,
             "algorithm": "HNSW",
             "distance_metric": format!("{:?}", distance_metric),
             "quantization": {
@@ -165,6 +227,8 @@ pub(crate) async fn get_index(
                 "neighbors_count": hnsw_params.neighbors_count,
                 "level_0_neighbors_count": hnsw_params.level_0_neighbors_count,
                 "num_layers": hnsw_params.num_layers,
+                "oversampling": hnsw_params.oversampling,
+                "query_precision": hnsw_params.query_precision,
             }
         }));
     }
@@ -180,6 +244,20 @@ pub(crate) async fn get_index(
         }));
     }
 
+    if let Some(tf_idf) = collection.get_tf_idf_index() {
+        indexes_array.push(serde_json::json!({
+            "type": "tf_idf",
+            "name": collection_name,
+            "algorithm": "BM25",
+            "k1": tf_idf.k1,
+            "b": tf_idf.b,
+            "analyzer": tf_idf.analyzer.config(),
+            "store_positions": tf_idf.store_positions,
+            "store_terms": tf_idf.term_dictionary.is_some(),
+            "fields": tf_idf.field_names(),
+        }));
+    }
+
     Ok(serde_json::json!({
         "collection_name": collection_name,
         "indexes": indexes_array
@@ -190,6 +268,7 @@ pub(crate) async fn delete_index(
     ctx: Arc<AppContext>,
     collection_name: String,
     index_type: IndexType,
+    field: Option<String>,
 ) -> Result<(), IndexesError> {
     let collection = ctx
         .ain_env
@@ -201,6 +280,47 @@ pub(crate) async fn delete_index(
 
     let collection_path: PathBuf = collection.get_path().to_path_buf();
 
+    if let Some(field) = field {
+        let IndexType::Dense = index_type else {
+            return Err(IndexesError::NotFound(format!(
+                "Only dense indexes have named fields, found field '{}'",
+                field
+            )));
+        };
+        if collection.get_dense_field_index(&field).is_none() {
+            return Err(IndexesError::NotFound(format!(
+                "Dense index does not exist for field '{}' of collection '{}'",
+                field, collection_name
+            )));
+        }
+        ctx.ain_env
+            .collections_map
+            .remove_dense_field_index(&collection_name, &field)
+            .map_err(|e| {
+                IndexesError::FailedToDeleteIndex(format!(
+                    "Failed to remove dense index of field '{}' from map: {}",
+                    field, e
+                ))
+            })?;
+
+        let index_path = HNSWIndex::index_path(&collection_path, Some(&field));
+        if index_path.exists() {
+            fs::remove_dir_all(&index_path).map_err(|e| {
+                IndexesError::FailedToDeleteIndex(format!(
+                    "Failed to remove dense index directory '{}': {}",
+                    index_path.display(),
+                    e
+                ))
+            })?;
+        }
+        log::info!(
+            "Dense index of field '{}' removed successfully for collection '{}'",
+            field,
+            collection_name
+        );
+        return Ok(());
+    }
+
     match index_type {
         IndexType::Dense => {
             if collection.get_hnsw_index().is_none() {
@@ -293,7 +413,10 @@ pub(crate) async fn delete_index(
             );
         }
         IndexType::TfIdf => {
-            if collection.get_tf_idf_index().is_none() {
+            let index_path = collection_path.join("tf_idf_index");
+            // the indexes in a format that can't be read aren't loaded, but
+            // can still be dropped
+            if collection.get_tf_idf_index().is_none() && !index_path.exists() {
                 return Err(IndexesError::NotFound(format!(
                     "TF-IDF index does not exist for collection '{}'",
                     collection_name
@@ -309,7 +432,6 @@ pub(crate) async fn delete_index(
                     ))
                 })?;
 
-            let index_path = collection_path.join("tf_idf_index");
             if index_path.exists() {
                 log::info!(
                     "Attempting to remove TF-IDF index directory: {:?}",
@@ -22,6 +22,7 @@ pub(crate) async fn create_dense_index(
         create_index_dto.distance_metric_type.into(),
         create_index_dto.quantization,
         create_index_dto.index,
+        create_index_dto.field,
     )
     .await
 }
@@ -53,6 +54,9 @@ pub(crate) async fn create_tf_idf_index(
         create_index_dto.sample_threshold,
         create_index_dto.k1,
         create_index_dto.b,
+        create_index_dto.analyzer,
+        create_index_dto.store_positions,
+        create_index_dto.store_terms,
     )
     .await
 }
@@ -71,7 +75,8 @@ pub(crate) async fn get_index(
 pub(crate) async fn delete_index(
     collection_id: String,
     index_type: IndexType,
+    field: Option<String>,
     ctx: Arc<AppContext>,
 ) -> Result<(), IndexesError> {
-    repo::delete_index(ctx, collection_id, index_type).await
+    repo::delete_index(ctx, collection_id, index_type, field).await
 }
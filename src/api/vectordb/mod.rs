@@ -1,3 +1,4 @@
+pub(crate) mod access;
 pub(crate) mod collections;
 pub(crate) mod search;
 pub(crate) mod vectors;
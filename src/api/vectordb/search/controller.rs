@@ -5,8 +5,10 @@ use crate::models::collection_cache::CollectionCacheExt;
 
 use super::dtos::{
     BatchDenseSearchRequestDto, BatchSearchResponseDto, BatchSearchTFIDFDocumentsDto,
-    BatchSparseSearchRequestDto, DenseSearchRequestDto, FindSimilarTFIDFDocumentDto,
-    HybridSearchRequestDto, SearchResponseDto, SparseSearchRequestDto,
+    BatchSparseSearchRequestDto, DenseEvaluationDto, DenseSearchRequestDto, EvaluateDenseIndexDto,
+    EvaluateSparseIndexDto, EvaluateTFIDFIndexDto, FindSimilarTFIDFDocumentDto,
+    HybridSearchRequestDto, MultiVectorSearchRequestDto, SearchResponseDto, SparseEvaluationDto,
+    SparseSearchRequestDto, TFIDFEvaluationDto,
 };
 use super::error::SearchError;
 
@@ -79,6 +81,40 @@ pub(crate) async fn batch_dense_search(
     Ok(HttpResponse::Ok().json(results))
 }
 
+/// Search using multi-vector embeddings
+///
+/// Performs a late-interaction search of a multi-vector collection, ranking the embeddings by
+/// the MaxSim of their token vectors against the query vectors.
+#[utoipa::path(
+    post,
+    path = "/vectordb/collections/{collection_id}/search/multi-vector",
+    tag = "search",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier")
+    ),
+    request_body = MultiVectorSearchRequestDto,
+    responses(
+        (status = 200, description = "Search successfully completed", body = SearchResponseDto),
+        (status = 404, description = "Collection not found", body = String),
+        (status = 400, description = "Invalid filter or other request error", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub(crate) async fn multi_vector_search(
+    path: web::Path<String>,
+    web::Json(body): web::Json<MultiVectorSearchRequestDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse, SearchError> {
+    let collection_id = path.into_inner();
+
+    // Update cache usage
+    ctx.update_collection_for_query(&collection_id)
+        .map_err(|e| SearchError::InternalServerError(format!("Cache update error: {}", e)))?;
+
+    let results = service::multi_vector_search(ctx.into_inner(), &collection_id, body).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
 /// Search using sparse vector embeddings
 ///
 /// Performs a similarity search using sparse vector embeddings.
@@ -233,3 +269,98 @@ pub(crate) async fn batch_tf_idf_search(
     let results = service::batch_tf_idf_search(ctx.into_inner(), &collection_id, body).await?;
     Ok(HttpResponse::Ok().json(results))
 }
+
+/// Evaluate the recall of the dense index
+///
+/// Compares the results of the HNSW index with an exact scan of the dense vectors, for
+/// sampled stored vectors or the given queries, without and with each of the given filters.
+#[utoipa::path(
+    post,
+    path = "/vectordb/collections/{collection_id}/search/evaluate-dense",
+    tag = "search",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier")
+    ),
+    request_body = EvaluateDenseIndexDto,
+    responses(
+        (status = 200, description = "Evaluation successfully completed", body = DenseEvaluationDto),
+        (status = 404, description = "Collection not found", body = String),
+        (status = 400, description = "Invalid request error", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub(crate) async fn evaluate_dense_index(
+    path: web::Path<String>,
+    web::Json(body): web::Json<EvaluateDenseIndexDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse, SearchError> {
+    let collection_id = path.into_inner();
+    ctx.update_collection_for_query(&collection_id)
+        .map_err(|e| SearchError::InternalServerError(format!("Cache update error: {}", e)))?;
+
+    let results = service::evaluate_dense_index(ctx.into_inner(), &collection_id, body).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Evaluate the recall of the sparse index
+///
+/// Compares the results of the sparse index with an exact scan of the sparse vectors for each
+/// early termination threshold, and simulates the ranking with each quantization.
+#[utoipa::path(
+    post,
+    path = "/vectordb/collections/{collection_id}/search/evaluate-sparse",
+    tag = "search",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier")
+    ),
+    request_body = EvaluateSparseIndexDto,
+    responses(
+        (status = 200, description = "Evaluation successfully completed", body = SparseEvaluationDto),
+        (status = 404, description = "Collection not found", body = String),
+        (status = 400, description = "Invalid request error", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub(crate) async fn evaluate_sparse_index(
+    path: web::Path<String>,
+    web::Json(body): web::Json<EvaluateSparseIndexDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse, SearchError> {
+    let collection_id = path.into_inner();
+    ctx.update_collection_for_query(&collection_id)
+        .map_err(|e| SearchError::InternalServerError(format!("Cache update error: {}", e)))?;
+
+    let results = service::evaluate_sparse_index(ctx.into_inner(), &collection_id, body).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Evaluate the recall of the TF-IDF index
+///
+/// Compares the results of plain text queries with scoring all the matching documents.
+#[utoipa::path(
+    post,
+    path = "/vectordb/collections/{collection_id}/search/evaluate-tf-idf",
+    tag = "search",
+    params(
+        ("collection_id" = String, Path, description = "Collection identifier")
+    ),
+    request_body = EvaluateTFIDFIndexDto,
+    responses(
+        (status = 200, description = "Evaluation successfully completed", body = TFIDFEvaluationDto),
+        (status = 404, description = "Collection not found", body = String),
+        (status = 400, description = "Invalid request error", body = String),
+        (status = 500, description = "Internal server error", body = String)
+    )
+)]
+pub(crate) async fn evaluate_tf_idf_index(
+    path: web::Path<String>,
+    web::Json(body): web::Json<EvaluateTFIDFIndexDto>,
+    ctx: web::Data<AppContext>,
+) -> Result<HttpResponse, SearchError> {
+    let collection_id = path.into_inner();
+    ctx.update_collection_for_query(&collection_id)
+        .map_err(|e| SearchError::InternalServerError(format!("Cache update error: {}", e)))?;
+
+    let results = service::evaluate_tf_idf_index(ctx.into_inner(), &collection_id, body).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
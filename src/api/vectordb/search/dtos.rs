@@ -1,16 +1,23 @@
+use crate::api::vectordb::indexes::dtos::SparseIndexQuantization;
+use crate::api::vectordb::search::highlight::HighlightOptions;
 use crate::metadata::query_filtering::Filter;
 use crate::models::types::VectorId;
 use crate::{indexes::inverted::types::SparsePair, models::types::DocumentId};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-fn default_top_k() -> usize {
+pub(crate) fn default_top_k() -> usize {
     10
 }
 
-fn default_fusion_constant_k() -> f32 {
+pub(crate) fn default_fusion_constant_k() -> f32 {
     60.0
 }
 
+pub(crate) fn default_retriever_weight() -> f32 {
+    1.0
+}
+
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
 pub(crate) struct DenseSearchRequestDto {
     pub query_vector: Vec<f32>,
@@ -19,6 +26,19 @@ pub(crate) struct DenseSearchRequestDto {
     pub filter: Option<Filter>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Overrides the `ef_search` of the index for this search
+    pub ef_search: Option<u32>,
+    /// Maximum number of candidates kept per level by a filtered search,
+    /// defaults to 100
+    pub filtered_candidates_limit: Option<usize>,
+    /// Scans the dense vectors of all the embeddings for exact results
+    /// instead of searching the index. Slow, but gives the ground truth the
+    /// recall of the index can be measured against.
+    #[serde(default)]
+    pub exact: bool,
+    /// Named dense vector field searched, the default dense vectors are
+    /// searched if absent
+    pub field: Option<String>,
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
@@ -34,6 +54,43 @@ pub(crate) struct BatchDenseSearchRequestDto {
     pub top_k: Option<usize>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Overrides the `ef_search` of the index for this search
+    pub ef_search: Option<u32>,
+    /// Maximum number of candidates kept per level by a filtered search,
+    /// defaults to 100
+    pub filtered_candidates_limit: Option<usize>,
+    /// Scans the dense vectors of all the embeddings for exact results
+    /// instead of searching the index. Slow, but gives the ground truth the
+    /// recall of the index can be measured against.
+    #[serde(default)]
+    pub exact: bool,
+    /// Named dense vector field searched, the default dense vectors are
+    /// searched if absent
+    pub field: Option<String>,
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub(crate) struct MultiVectorSearchRequestDto {
+    /// Query token vectors, each one of the dimension of the collection
+    pub query_vectors: Vec<Vec<f32>>,
+    pub top_k: Option<usize>,
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<Filter>,
+    #[serde(default)]
+    pub return_raw_text: bool,
+    /// Number of token vectors fetched from the index for each query vector,
+    /// defaults to 32. The embeddings they belong to are re-ranked with
+    /// MaxSim.
+    pub candidates_per_vector: Option<usize>,
+    /// Overrides the `ef_search` of the index for this search
+    pub ef_search: Option<u32>,
+    /// Maximum number of candidates kept per level by a filtered search,
+    /// defaults to 100
+    pub filtered_candidates_limit: Option<usize>,
+    /// Re-ranks all the multi-vector embeddings instead of the candidates
+    /// found in the index
+    #[serde(default)]
+    pub exact: bool,
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
@@ -44,6 +101,8 @@ pub(crate) struct SparseSearchRequestDto {
     pub early_terminate_threshold: Option<f32>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Returns the fragments of the texts matching `highlight.query`
+    pub highlight: Option<HighlightOptions>,
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
@@ -54,6 +113,8 @@ pub(crate) struct BatchSparseSearchRequestDto {
     pub early_terminate_threshold: Option<f32>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Returns the fragments of the texts matching `highlight.query`
+    pub highlight: Option<HighlightOptions>,
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
@@ -62,15 +123,75 @@ pub(crate) struct HybridSearchRequestDto {
     pub query: HybridSearchQuery,
     #[serde(default = "default_top_k")]
     pub top_k: usize,
+    /// Only used by reciprocal rank fusion
     #[serde(default = "default_fusion_constant_k")]
     pub fusion_constant_k: f32,
     #[serde(default)]
+    pub fusion_method: FusionMethod,
+    /// Options of the dense retriever, if the query has a dense part
+    #[serde(default)]
+    pub dense: RetrieverOptions,
+    /// Options of the sparse retriever, if the query has a sparse part
+    #[serde(default)]
+    pub sparse: RetrieverOptions,
+    /// Options of the TF-IDF retriever, if the query has a text part
+    #[serde(default)]
+    pub tf_idf: RetrieverOptions,
+    #[serde(default)]
     pub return_raw_text: bool,
+    /// Returns the fragments of the texts matching the text query, or
+    /// `highlight.query` if the search has no text query
+    pub highlight: Option<HighlightOptions>,
+    /// Weights of the text fields searched by the text query, see
+    /// `FindSimilarTFIDFDocumentDto::field_weights`
+    pub field_weights: Option<HashMap<String, f32>>,
+}
+
+/// How the scores of the retrievers of a hybrid search are combined
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FusionMethod {
+    /// Reciprocal rank fusion, `1 / (rank + fusion_constant_k)`, ignores the
+    /// scores and only uses the ranks
+    #[default]
+    Rrf,
+    /// Scores rescaled to `[0, 1]` over the candidates of each retriever
+    MinMax,
+    /// Scores standardized to zero mean and unit variance over the candidates
+    /// of each retriever
+    ZScore,
+}
+
+#[derive(Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub(crate) struct RetrieverOptions {
+    /// Weight of the retriever in the fused scores
+    #[serde(default = "default_retriever_weight")]
+    pub weight: f32,
+    /// Number of candidates fetched from the retriever, defaults to three
+    /// times the `top_k` of the search
+    pub top_k: Option<usize>,
+}
+
+impl Default for RetrieverOptions {
+    fn default() -> Self {
+        Self {
+            weight: default_retriever_weight(),
+            top_k: None,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
 #[serde(untagged)]
 pub(crate) enum HybridSearchQuery {
+    // must come first, the variants are tried in order
+    DenseSparseAndTFIDF {
+        query_vector: Vec<f32>,
+        #[schema(value_type = Vec<String>)]
+        query_terms: Vec<SparsePair>,
+        query_text: String,
+        sparse_early_terminate_threshold: Option<f32>,
+    },
     DenseAndSparse {
         query_vector: Vec<f32>,
         #[schema(value_type = Vec<String>)]
@@ -97,6 +218,8 @@ pub(crate) struct SearchResultItemDto {
     pub document_id: Option<DocumentId>,
     pub score: f32,
     pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Debug, utoipa::ToSchema)]
@@ -115,10 +238,22 @@ pub(crate) struct BatchSearchResponseDto {
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
 pub(crate) struct FindSimilarTFIDFDocumentDto {
+    /// Supports required (`+term`) and excluded (`-term`) terms, `AND` /
+    /// `OR` / `NOT`, parenthesized groups, boosts (`term^2`), quoted
+    /// phrases with an optional slop (`"a b"~3`), and prefix (`vect*`) and
+    /// fuzzy (`databse~1`) terms, and terms restricted to a text field
+    /// (`title:rust`)
     pub query: String,
     pub top_k: Option<usize>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Returns the fragments of the texts matching the query
+    pub highlight: Option<HighlightOptions>,
+    /// Weights of the text fields (`text` for the text of the vectors)
+    /// searched by the terms without a field, the fields without a weight
+    /// aren't searched by them. All the fields have a weight of 1 by
+    /// default.
+    pub field_weights: Option<HashMap<String, f32>>,
 }
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
@@ -127,4 +262,119 @@ pub(crate) struct BatchSearchTFIDFDocumentsDto {
     pub top_k: Option<usize>,
     #[serde(default)]
     pub return_raw_text: bool,
+    /// Returns the fragments of the texts matching each query
+    pub highlight: Option<HighlightOptions>,
+    /// See `FindSimilarTFIDFDocumentDto::field_weights`
+    pub field_weights: Option<HashMap<String, f32>>,
+}
+
+pub(crate) fn default_evaluation_sample_size() -> usize {
+    100
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub(crate) struct EvaluateDenseIndexDto {
+    /// Queries to evaluate, `sample_size` stored vectors are sampled as
+    /// queries if not given, at most 1000 of either
+    pub queries: Option<Vec<Vec<f32>>>,
+    #[serde(default = "default_evaluation_sample_size")]
+    pub sample_size: usize,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// Overrides the `ef_search` of the index, see `DenseSearchRequestDto`
+    pub ef_search: Option<u32>,
+    /// Named dense vector field whose index is evaluated, see
+    /// `DenseSearchRequestDto`
+    pub field: Option<String>,
+    /// Filters the recall is also measured with, each on its own
+    #[schema(value_type = Vec<String>)]
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub(crate) struct EvaluateSparseIndexDto {
+    /// Queries to evaluate, `sample_size` stored vectors are sampled as
+    /// queries if not given, at most 1000 of either
+    #[schema(value_type = Option<Vec<Vec<String>>>)]
+    pub queries: Option<Vec<Vec<SparsePair>>>,
+    #[serde(default = "default_evaluation_sample_size")]
+    pub sample_size: usize,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// Thresholds the recall of the index is measured with, defaults to the
+    /// `early_terminate_threshold` of the server
+    #[serde(default)]
+    pub early_terminate_thresholds: Vec<f32>,
+    /// Quantizations whose effect on the ranking is simulated over the
+    /// stored vectors, defaults to all of them
+    #[serde(default)]
+    pub quantizations: Vec<SparseIndexQuantization>,
+}
+
+#[derive(Deserialize, Debug, utoipa::ToSchema)]
+pub(crate) struct EvaluateTFIDFIndexDto {
+    /// Queries to evaluate, at most 1000
+    pub queries: Vec<String>,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+}
+
+/// Latencies of the searches of an evaluation, in milliseconds
+#[derive(Serialize, Debug, Default, PartialEq, utoipa::ToSchema)]
+pub(crate) struct LatencyPercentilesDto {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct RecallReportDto {
+    /// Mean fraction of the exact `top_k` results found by the index
+    pub recall: f32,
+    pub latency: LatencyPercentilesDto,
+    /// Latencies of the exact searches the results are compared with
+    pub exact_latency: LatencyPercentilesDto,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct DenseEvaluationDto {
+    pub queries: usize,
+    pub top_k: usize,
+    pub recall: RecallReportDto,
+    /// In the order of the filters of the request
+    pub filters: Vec<RecallReportDto>,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct ThresholdRecallDto {
+    pub early_terminate_threshold: f32,
+    pub recall: RecallReportDto,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct QuantizationRecallDto {
+    /// Number of quantized values, as in `CreateSparseIndexDto`
+    pub quantization: u16,
+    /// Mean fraction of the exact `top_k` results ranked in the `top_k` by
+    /// the quantized values
+    pub recall: f32,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct SparseEvaluationDto {
+    pub queries: usize,
+    pub top_k: usize,
+    /// Quantization of the evaluated index
+    pub quantization: u16,
+    pub thresholds: Vec<ThresholdRecallDto>,
+    pub quantizations: Vec<QuantizationRecallDto>,
+}
+
+#[derive(Serialize, Debug, utoipa::ToSchema)]
+pub(crate) struct TFIDFEvaluationDto {
+    pub queries: usize,
+    pub top_k: usize,
+    pub recall: RecallReportDto,
 }
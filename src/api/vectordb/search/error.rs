@@ -13,7 +13,6 @@ pub(crate) enum SearchError {
     InvalidFilter(String),
     InternalServerError(String),
     WaCustom(WaCustomError),
-    #[allow(dead_code)]
     InvalidInput(String),
 }
 
@@ -60,6 +59,7 @@ impl From<WaCustomError> for SearchError {
         match err {
             WaCustomError::NotFound(msg) => SearchError::CollectionNotFound(msg),
             WaCustomError::MetadataError(e) => SearchError::InvalidFilter(e.to_string()),
+            WaCustomError::InvalidData(msg) => SearchError::InvalidInput(msg),
             e => SearchError::WaCustom(e),
         }
     }
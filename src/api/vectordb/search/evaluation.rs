@@ -0,0 +1,601 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::web;
+use rand::seq::SliceRandom;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::dtos::{
+    DenseEvaluationDto, EvaluateDenseIndexDto, EvaluateSparseIndexDto, EvaluateTFIDFIndexDto,
+    LatencyPercentilesDto, QuantizationRecallDto, RecallReportDto, SparseEvaluationDto,
+    TFIDFEvaluationDto, ThresholdRecallDto,
+};
+use super::error::SearchError;
+use super::repo::get_dense_index;
+use crate::api::vectordb::indexes::dtos::SparseIndexQuantization;
+use crate::app_context::AppContext;
+use crate::indexes::hnsw::{DenseSearchInput, DenseSearchOptions};
+use crate::indexes::inverted::types::SparsePair;
+use crate::indexes::inverted::{SparseSearchInput, SparseSearchOptions};
+use crate::indexes::tf_idf::{TFIDFSearchInput, TFIDFSearchOptions};
+use crate::indexes::{IndexOps, SearchResult};
+use crate::metadata::query_filtering::{filter_encoded_dimensions, Filter};
+use crate::models::collection::Collection;
+use crate::models::inverted_index::quantize_value;
+use crate::models::types::VectorId;
+
+/// Measures the recall of the HNSW index against an exact scan of the raw
+/// dense vectors, without and with each of the filters
+pub(crate) async fn evaluate_dense_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateDenseIndexDto,
+) -> Result<DenseEvaluationDto, SearchError> {
+    let collection_id = collection_id.to_string();
+    run_blocking(move || evaluate_dense_index_blocking(&ctx, &collection_id, request)).await
+}
+
+fn evaluate_dense_index_blocking(
+    ctx: &AppContext,
+    collection_id: &str,
+    request: EvaluateDenseIndexDto,
+) -> Result<DenseEvaluationDto, SearchError> {
+    let collection = get_collection(ctx, collection_id)?;
+    let hnsw_index = get_dense_index(&collection, collection_id, request.field.as_deref())?;
+    validate_top_k(request.top_k)?;
+
+    if !request.filters.is_empty() {
+        let schema = collection.meta.metadata_schema.as_ref().ok_or_else(|| {
+            SearchError::InvalidFilter("The collection doesn't support metadata filtering".into())
+        })?;
+        for filter in &request.filters {
+            filter_encoded_dimensions(schema, filter)
+                .map_err(|e| SearchError::InvalidFilter(e.to_string()))?;
+        }
+    }
+
+    let queries = match request.queries {
+        Some(queries) => queries,
+        None => {
+            validate_sample_size(request.sample_size)?;
+            sample(
+                collection
+                    .raw_embeddings()
+                    .filter_map(|(_, raw_emb)| hnsw_index.raw_vector(raw_emb))
+                    .collect(),
+                request.sample_size,
+            )
+        }
+    };
+    validate_queries(queries.len())?;
+
+    let search = |query: &[f32], filter: Option<&Filter>, exact: bool| {
+        timed(|| {
+            hnsw_index.search(
+                &collection,
+                DenseSearchInput(query.to_vec(), filter.cloned()),
+                &DenseSearchOptions {
+                    top_k: Some(request.top_k),
+                    ef_search: request.ef_search,
+                    filtered_candidates_limit: None,
+                    exact,
+                },
+                &ctx.config,
+                false,
+            )
+        })
+    };
+    let evaluate = |filter: Option<&Filter>| {
+        let mut recalls = RecallAccumulator::default();
+        let mut exact_latencies = Vec::with_capacity(queries.len());
+        for query in &queries {
+            let (results, latency) = search(query, filter, false)?;
+            let (exact_results, exact_latency) = search(query, filter, true)?;
+            recalls.add(&results, &exact_results, latency);
+            exact_latencies.push(exact_latency);
+        }
+        Ok::<_, SearchError>(recalls.report(&exact_latencies))
+    };
+
+    Ok(DenseEvaluationDto {
+        queries: queries.len(),
+        top_k: request.top_k,
+        recall: evaluate(None)?,
+        filters: request
+            .filters
+            .iter()
+            .map(|filter| evaluate(Some(filter)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Measures the recall of the sparse index against an exact scan of the raw
+/// sparse vectors with each of the early termination thresholds, and the
+/// recall of the rankings by the values quantized with each of the
+/// quantizations
+pub(crate) async fn evaluate_sparse_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateSparseIndexDto,
+) -> Result<SparseEvaluationDto, SearchError> {
+    let collection_id = collection_id.to_string();
+    run_blocking(move || evaluate_sparse_index_blocking(&ctx, &collection_id, request)).await
+}
+
+fn evaluate_sparse_index_blocking(
+    ctx: &AppContext,
+    collection_id: &str,
+    request: EvaluateSparseIndexDto,
+) -> Result<SparseEvaluationDto, SearchError> {
+    let collection = get_collection(ctx, collection_id)?;
+    let inverted_index = collection.get_inverted_index().ok_or_else(|| {
+        SearchError::IndexNotFound(format!("Sparse index for collection '{}'", collection_id))
+    })?;
+    validate_top_k(request.top_k)?;
+
+    let thresholds = if request.early_terminate_thresholds.is_empty() {
+        vec![ctx.config.search.early_terminate_threshold]
+    } else {
+        request.early_terminate_thresholds
+    };
+    if let Some(threshold) = thresholds
+        .iter()
+        .find(|threshold| !(0.0..=1.0).contains(*threshold))
+    {
+        return Err(SearchError::InvalidInput(format!(
+            "early_terminate_threshold must be between 0 and 1, got {}",
+            threshold
+        )));
+    }
+    let quantizations_bits: Vec<u8> = if request.quantizations.is_empty() {
+        vec![
+            SparseIndexQuantization::B16,
+            SparseIndexQuantization::B32,
+            SparseIndexQuantization::B64,
+            SparseIndexQuantization::B128,
+            SparseIndexQuantization::B256,
+        ]
+    } else {
+        request.quantizations
+    }
+    .into_iter()
+    .map(SparseIndexQuantization::into_bits)
+    .collect();
+
+    let stored: Vec<(&VectorId, &[SparsePair])> = collection
+        .raw_embeddings()
+        .filter_map(|(_, raw_emb)| Some((&raw_emb.id, raw_emb.sparse_values.as_deref()?)))
+        .collect();
+    let queries = match request.queries {
+        Some(queries) => queries,
+        None => {
+            validate_sample_size(request.sample_size)?;
+            sample(
+                stored.iter().map(|(_, pairs)| *pairs).collect(),
+                request.sample_size,
+            )
+        }
+    };
+    validate_queries(queries.len())?;
+
+    let values_upper_bound = *inverted_index.values_upper_bound.read().unwrap();
+    let reranking_factor = if ctx.config.rerank_sparse_with_raw_values {
+        ctx.config.sparse_raw_values_reranking_factor
+    } else {
+        1
+    };
+
+    let mut threshold_recalls: Vec<RecallAccumulator> =
+        thresholds.iter().map(|_| Default::default()).collect();
+    let mut quantization_recalls =
+        vec![Vec::with_capacity(queries.len()); quantizations_bits.len()];
+    let mut exact_latencies = Vec::with_capacity(queries.len());
+
+    for query in &queries {
+        let (exact_results, exact_latency) =
+            timed(|| Ok::<_, SearchError>(sparse_top_k(&stored, query, request.top_k, None)))?;
+        exact_latencies.push(exact_latency);
+
+        for (threshold, recalls) in thresholds.iter().zip(&mut threshold_recalls) {
+            let (results, latency) = timed(|| {
+                inverted_index.search(
+                    &collection,
+                    SparseSearchInput(query.clone()),
+                    &SparseSearchOptions {
+                        top_k: Some(request.top_k),
+                        early_terminate_threshold: Some(*threshold),
+                    },
+                    &ctx.config,
+                    false,
+                )
+            })?;
+            recalls.add(&results, &exact_results, latency);
+        }
+
+        for (bits, recalls) in quantizations_bits.iter().zip(&mut quantization_recalls) {
+            let results = sparse_top_k(
+                &stored,
+                query,
+                request.top_k,
+                Some(Quantized {
+                    bits: *bits,
+                    values_upper_bound,
+                    reranking_factor,
+                }),
+            );
+            recalls.push(recall(&results, &exact_results));
+        }
+    }
+
+    Ok(SparseEvaluationDto {
+        queries: queries.len(),
+        top_k: request.top_k,
+        quantization: 1 << inverted_index.root.root.quantization_bits,
+        thresholds: thresholds
+            .into_iter()
+            .zip(threshold_recalls)
+            .map(|(threshold, recalls)| ThresholdRecallDto {
+                early_terminate_threshold: threshold,
+                recall: recalls.report(&exact_latencies),
+            })
+            .collect(),
+        quantizations: quantizations_bits
+            .into_iter()
+            .zip(quantization_recalls)
+            .map(|(bits, recalls)| QuantizationRecallDto {
+                quantization: 1 << bits,
+                recall: mean(&recalls),
+            })
+            .collect(),
+    })
+}
+
+/// Measures the recall of the plain text search of the TF-IDF index
+/// against scoring all the matching documents
+///
+/// The postings of the TF-IDF index aren't quantized and its searches
+/// aren't terminated early, the plain text search only loses the
+/// documents sharing one of its buckets with a better document.
+pub(crate) async fn evaluate_tf_idf_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateTFIDFIndexDto,
+) -> Result<TFIDFEvaluationDto, SearchError> {
+    let collection_id = collection_id.to_string();
+    run_blocking(move || evaluate_tf_idf_index_blocking(&ctx, &collection_id, request)).await
+}
+
+fn evaluate_tf_idf_index_blocking(
+    ctx: &AppContext,
+    collection_id: &str,
+    request: EvaluateTFIDFIndexDto,
+) -> Result<TFIDFEvaluationDto, SearchError> {
+    let collection = get_collection(ctx, collection_id)?;
+    let tf_idf_index = collection.get_tf_idf_index().ok_or_else(|| {
+        SearchError::IndexNotFound(format!("TF-IDF index for collection '{}'", collection_id))
+    })?;
+    validate_top_k(request.top_k)?;
+    validate_queries(request.queries.len())?;
+
+    let search = |query: &str, exact: bool| {
+        timed(|| {
+            tf_idf_index.search(
+                &collection,
+                TFIDFSearchInput(query.to_string()),
+                &TFIDFSearchOptions {
+                    top_k: Some(request.top_k),
+                    field_weights: None,
+                    exact,
+                },
+                &ctx.config,
+                false,
+            )
+        })
+    };
+
+    let mut recalls = RecallAccumulator::default();
+    let mut exact_latencies = Vec::with_capacity(request.queries.len());
+    for query in &request.queries {
+        let (results, latency) = search(query, false)?;
+        let (exact_results, exact_latency) = search(query, true)?;
+        recalls.add(&results, &exact_results, latency);
+        exact_latencies.push(exact_latency);
+    }
+
+    Ok(TFIDFEvaluationDto {
+        queries: request.queries.len(),
+        top_k: request.top_k,
+        recall: recalls.report(&exact_latencies),
+    })
+}
+
+/// Upper bound of the queries of an evaluation, each of them is searched
+/// exactly by scanning all the stored vectors
+const MAX_EVALUATION_QUERIES: usize = 1000;
+
+fn get_collection(ctx: &AppContext, collection_id: &str) -> Result<Arc<Collection>, SearchError> {
+    ctx.ain_env
+        .collections_map
+        .get_collection(collection_id)
+        .ok_or_else(|| SearchError::CollectionNotFound(collection_id.to_string()))
+}
+
+fn validate_top_k(top_k: usize) -> Result<(), SearchError> {
+    if top_k == 0 {
+        return Err(SearchError::InvalidInput(
+            "top_k must be greater than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_sample_size(sample_size: usize) -> Result<(), SearchError> {
+    if sample_size > MAX_EVALUATION_QUERIES {
+        return Err(SearchError::InvalidInput(format!(
+            "sample_size must be at most {}, got {}",
+            MAX_EVALUATION_QUERIES, sample_size
+        )));
+    }
+    Ok(())
+}
+
+fn validate_queries(count: usize) -> Result<(), SearchError> {
+    if count == 0 {
+        return Err(SearchError::InvalidInput(
+            "No queries to evaluate, the collection has no vectors to sample".to_string(),
+        ));
+    }
+    if count > MAX_EVALUATION_QUERIES {
+        return Err(SearchError::InvalidInput(format!(
+            "At most {} queries can be evaluated, got {}",
+            MAX_EVALUATION_QUERIES, count
+        )));
+    }
+    Ok(())
+}
+
+// Evaluations scan all the stored vectors for each query, they are run on
+// the blocking thread pool to keep the workers serving requests
+async fn run_blocking<T: Send + 'static>(
+    evaluate: impl FnOnce() -> Result<T, SearchError> + Send + 'static,
+) -> Result<T, SearchError> {
+    web::block(evaluate)
+        .await
+        .map_err(|e| SearchError::InternalServerError(e.to_string()))?
+}
+
+// Up to `sample_size` of the stored values, picked at random
+fn sample<T: ToOwned + ?Sized>(stored: Vec<&T>, sample_size: usize) -> Vec<T::Owned> {
+    stored
+        .choose_multiple(&mut rand::thread_rng(), sample_size)
+        .map(|value| (*value).to_owned())
+        .collect()
+}
+
+fn timed<T, E>(search: impl FnOnce() -> Result<T, E>) -> Result<(T, Duration), SearchError>
+where
+    SearchError: From<E>,
+{
+    let start = Instant::now();
+    let results = search()?;
+    Ok((results, start.elapsed()))
+}
+
+/// Recalls and latencies of the searches of the index
+#[derive(Default)]
+struct RecallAccumulator {
+    recalls: Vec<f32>,
+    latencies: Vec<Duration>,
+}
+
+impl RecallAccumulator {
+    fn add(&mut self, results: &[SearchResult], exact_results: &[SearchResult], latency: Duration) {
+        self.recalls.push(recall(results, exact_results));
+        self.latencies.push(latency);
+    }
+
+    fn report(&self, exact_latencies: &[Duration]) -> RecallReportDto {
+        RecallReportDto {
+            recall: mean(&self.recalls),
+            latency: latency_percentiles(&self.latencies),
+            exact_latency: latency_percentiles(exact_latencies),
+        }
+    }
+}
+
+/// Fraction of the exact results found in the results
+fn recall(results: &[SearchResult], exact_results: &[SearchResult]) -> f32 {
+    if exact_results.is_empty() {
+        return 1.0;
+    }
+    let found: FxHashSet<&VectorId> = results.iter().map(|result| &result.0).collect();
+    let found_count = exact_results
+        .iter()
+        .filter(|result| found.contains(&result.0))
+        .count();
+    found_count as f32 / exact_results.len() as f32
+}
+
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+/// Nearest-rank percentiles of the latencies
+fn latency_percentiles(latencies: &[Duration]) -> LatencyPercentilesDto {
+    let mut latencies = latencies.to_vec();
+    latencies.sort_unstable();
+    let Some(max) = latencies.last() else {
+        return LatencyPercentilesDto::default();
+    };
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * latencies.len() as f64).ceil() as usize;
+        latencies[rank.clamp(1, latencies.len()) - 1].as_secs_f64() * 1000.0
+    };
+    LatencyPercentilesDto {
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p99: percentile(99.0),
+        max: max.as_secs_f64() * 1000.0,
+    }
+}
+
+/// Quantization a sparse index search is simulated with
+#[derive(Clone, Copy)]
+struct Quantized {
+    bits: u8,
+    values_upper_bound: f32,
+    /// The `top_k * reranking_factor` best vectors by their quantized dot
+    /// products are reranked by their raw dot products
+    reranking_factor: usize,
+}
+
+/// The `top_k` stored vectors sharing a dimension with the query, ranked by
+/// their raw dot products with it, or by their quantized ones first if
+/// `quantized` is given
+fn sparse_top_k(
+    stored: &[(&VectorId, &[SparsePair])],
+    query: &[SparsePair],
+    top_k: usize,
+    quantized: Option<Quantized>,
+) -> Vec<SearchResult> {
+    let query: FxHashMap<u32, f32> = query.iter().map(|pair| (pair.0, pair.1)).collect();
+    let quantize = |value: f32, quantized: &Quantized| {
+        quantize_value(value, quantized.bits, quantized.values_upper_bound) as u32
+    };
+
+    let mut scored: Vec<_> = stored
+        .iter()
+        .filter_map(|(id, pairs)| {
+            let mut matched = false;
+            let mut dot_product = 0.0;
+            let mut quantized_dot_product = 0;
+            for pair in *pairs {
+                let Some(query_value) = query.get(&pair.0) else {
+                    continue;
+                };
+                matched = true;
+                dot_product += query_value * pair.1;
+                if let Some(quantized) = &quantized {
+                    quantized_dot_product +=
+                        quantize(*query_value, quantized) * quantize(pair.1, quantized);
+                }
+            }
+            matched.then_some((*id, dot_product, quantized_dot_product))
+        })
+        .collect();
+
+    if let Some(quantized) = quantized {
+        scored.sort_by(|a, b| b.2.cmp(&a.2));
+        scored.truncate(top_k * quantized.reranking_factor);
+    }
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+    scored
+        .into_iter()
+        .map(|(id, dot_product, _)| (id.clone(), None, dot_product, None))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(ids: &[&str]) -> Vec<SearchResult> {
+        ids.iter()
+            .map(|id| (VectorId::from(id.to_string()), None, 0.0, None))
+            .collect()
+    }
+
+    #[test]
+    fn test_recall() {
+        let exact_results = results(&["a", "b", "c", "d"]);
+        assert_eq!(
+            recall(&results(&["b", "a", "x", "d"]), &exact_results),
+            0.75
+        );
+        assert_eq!(recall(&results(&[]), &exact_results), 0.0);
+        assert_eq!(recall(&results(&["a"]), &results(&[])), 1.0);
+    }
+
+    #[test]
+    fn test_validate_queries() {
+        assert!(validate_queries(0).is_err());
+        assert!(validate_queries(MAX_EVALUATION_QUERIES).is_ok());
+        assert!(validate_queries(MAX_EVALUATION_QUERIES + 1).is_err());
+        assert!(validate_sample_size(MAX_EVALUATION_QUERIES).is_ok());
+        assert!(validate_sample_size(MAX_EVALUATION_QUERIES + 1).is_err());
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let latencies: Vec<_> = (1..=100).rev().map(Duration::from_millis).collect();
+        let percentiles = latency_percentiles(&latencies);
+        assert!((percentiles.p50 - 50.0).abs() < 1e-9);
+        assert!((percentiles.p90 - 90.0).abs() < 1e-9);
+        assert!((percentiles.p99 - 99.0).abs() < 1e-9);
+        assert!((percentiles.max - 100.0).abs() < 1e-9);
+
+        assert_eq!(latency_percentiles(&[]), LatencyPercentilesDto::default());
+    }
+
+    #[test]
+    fn test_quantized_sparse_top_k() {
+        let ids: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| VectorId::from(id.to_string()))
+            .collect();
+        let vectors = [
+            vec![SparsePair(0, 0.50)],
+            vec![SparsePair(0, 0.52)],
+            vec![SparsePair(0, 0.90)],
+            vec![SparsePair(1, 1.0)],
+        ];
+        let stored: Vec<_> = ids
+            .iter()
+            .zip(&vectors)
+            .map(|(id, pairs)| (id, pairs.as_slice()))
+            .collect();
+        let query = [SparsePair(0, 1.0)];
+        let ids = |results: Vec<SearchResult>| -> Vec<String> {
+            results
+                .into_iter()
+                .map(|result| result.0.to_string())
+                .collect()
+        };
+
+        // "d" shares no dimension with the query
+        assert_eq!(
+            ids(sparse_top_k(&stored, &query, 10, None)),
+            ["c", "b", "a"]
+        );
+
+        // 0.50 and 0.52 quantize to the same value with 4 bits, the tie is
+        // broken in favour of "a" by the stable sort, but not with 8 bits
+        let quantized = |bits| Quantized {
+            bits,
+            values_upper_bound: 1.0,
+            reranking_factor: 1,
+        };
+        assert_eq!(
+            ids(sparse_top_k(&stored, &query, 2, Some(quantized(4)))),
+            ["c", "a"]
+        );
+        assert_eq!(
+            ids(sparse_top_k(&stored, &query, 2, Some(quantized(8)))),
+            ["c", "b"]
+        );
+
+        // reranking the candidates with the raw values recovers "b"
+        let reranked = Quantized {
+            reranking_factor: 2,
+            ..quantized(4)
+        };
+        assert_eq!(
+            ids(sparse_top_k(&stored, &query, 2, Some(reranked))),
+            ["c", "b"]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use rustc_hash::FxHashMap;
+
+use super::dtos::FusionMethod;
+use crate::indexes::SearchResult;
+use crate::models::types::{DocumentId, VectorId};
+
+/// Results of a retriever of a hybrid search, best first, with the weight of
+/// the retriever in the fused scores
+pub(crate) struct RetrieverResults {
+    pub results: Vec<SearchResult>,
+    pub weight: f32,
+}
+
+/// Fuses the results of the retrievers into the `top_k` best results
+///
+/// The fused score of a result is the weighted sum of its scores in the
+/// results of each retriever, as computed by the fusion method. Results
+/// missing from the candidates of a retriever get nothing from it.
+pub(crate) fn fuse_results(
+    retrievers: Vec<RetrieverResults>,
+    method: FusionMethod,
+    fusion_constant_k: f32,
+    top_k: usize,
+) -> Vec<SearchResult> {
+    let mut final_scores: FxHashMap<VectorId, (f32, Option<DocumentId>, Option<String>)> =
+        FxHashMap::default();
+
+    for RetrieverResults { results, weight } in retrievers {
+        let scores = fusion_scores(&results, method, fusion_constant_k);
+        for ((vector_id, document_id, _score, text), score) in results.into_iter().zip(scores) {
+            let entry = final_scores
+                .entry(vector_id)
+                .or_insert((0.0, document_id, None));
+            entry.0 += weight * score;
+            if entry.2.is_none() {
+                entry.2 = text;
+            }
+        }
+    }
+
+    let mut final_results: Vec<SearchResult> = final_scores
+        .into_iter()
+        .map(|(id, (score, document_id, text))| (id, document_id, score, text))
+        .collect();
+
+    if final_results.len() > top_k {
+        final_results.select_nth_unstable_by(top_k, |a, b| b.2.total_cmp(&a.2));
+    }
+    final_results.sort_unstable_by(|a, b| b.2.total_cmp(&a.2));
+    final_results.truncate(top_k);
+    final_results
+}
+
+// Scores of the results of a retriever, before weighting
+fn fusion_scores(results: &[SearchResult], method: FusionMethod, k: f32) -> Vec<f32> {
+    let scores = results.iter().map(|(_, _, score, _)| *score);
+    match method {
+        FusionMethod::Rrf => (0..results.len())
+            .map(|rank| 1.0 / (rank as f32 + k + f32::EPSILON))
+            .collect(),
+        FusionMethod::MinMax => {
+            let (min, max) = scores
+                .clone()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), score| {
+                    (min.min(score), max.max(score))
+                });
+            let range = max - min;
+            // all the candidates are as good as each other
+            scores
+                .map(|score| {
+                    if range > 0.0 {
+                        (score - min) / range
+                    } else {
+                        1.0
+                    }
+                })
+                .collect()
+        }
+        FusionMethod::ZScore => {
+            let count = results.len() as f32;
+            let mean = scores.clone().sum::<f32>() / count;
+            let variance = scores
+                .clone()
+                .map(|score| (score - mean) * (score - mean))
+                .sum::<f32>()
+                / count;
+            let std_dev = variance.sqrt();
+            scores
+                .map(|score| {
+                    if std_dev > 0.0 {
+                        (score - mean) / std_dev
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(scores: &[(&str, f32)]) -> Vec<SearchResult> {
+        scores
+            .iter()
+            .map(|(id, score)| (VectorId::from(id.to_string()), None, *score, None))
+            .collect()
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<String> {
+        results.iter().map(|result| result.0.to_string()).collect()
+    }
+
+    #[test]
+    fn test_min_max_fusion() {
+        let retrievers = vec![
+            RetrieverResults {
+                results: results(&[("a", 0.9), ("b", 0.5), ("c", 0.1)]),
+                weight: 1.0,
+            },
+            RetrieverResults {
+                results: results(&[("c", 40.0), ("b", 30.0), ("d", 20.0)]),
+                weight: 0.5,
+            },
+        ];
+        let fused = fuse_results(retrievers, FusionMethod::MinMax, 60.0, 3);
+
+        // a: 1.0, b: 0.5 + 0.5 * 0.5, c: 0.0 + 0.5 * 1.0, d: 0.0
+        assert_eq!(vec!["a", "b", "c"], ids(&fused));
+        assert!((fused[1].2 - 0.75).abs() < 1e-6);
+        assert!((fused[2].2 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_z_score_fusion() {
+        let retrievers = vec![
+            RetrieverResults {
+                results: results(&[("a", 3.0), ("b", 2.0), ("c", 1.0)]),
+                weight: 1.0,
+            },
+            RetrieverResults {
+                results: results(&[("c", 300.0), ("a", 200.0), ("b", 100.0)]),
+                weight: 3.0,
+            },
+            // a single candidate has no deviation
+            RetrieverResults {
+                results: results(&[("b", 5.0)]),
+                weight: 1.0,
+            },
+        ];
+        let fused = fuse_results(retrievers, FusionMethod::ZScore, 60.0, 10);
+
+        assert_eq!(vec!["c", "a", "b"], ids(&fused));
+        assert!(fused[1].2 > 0.0 && fused[2].2 < 0.0);
+    }
+
+    #[test]
+    fn test_weighted_rrf() {
+        let retrievers = vec![
+            RetrieverResults {
+                results: results(&[("a", 0.9), ("b", 0.8)]),
+                weight: 1.0,
+            },
+            RetrieverResults {
+                results: results(&[("b", 10.0), ("a", 1.0)]),
+                weight: 3.0,
+            },
+        ];
+        let fused = fuse_results(retrievers, FusionMethod::Rrf, 60.0, 1);
+
+        assert_eq!(vec!["b"], ids(&fused));
+    }
+}
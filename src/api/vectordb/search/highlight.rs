@@ -0,0 +1,356 @@
+use std::ops::Range;
+
+use rustc_hash::FxHashSet;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::indexes::tf_idf::{
+    analyzer::{AnalyzedToken, TextAnalyzer},
+    dictionary::edit_distance,
+    query::{Clause, QueryNode, TextQuery},
+    DEFAULT_FIELD,
+};
+use crate::models::sparse_ann_query::Occur;
+
+/// Options of the highlighted fragments returned with the search results
+#[derive(Debug, Clone, PartialEq, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct HighlightOptions {
+    /// Text whose terms are highlighted, in the syntax of the TF-IDF
+    /// queries. Defaults to the text query of the search, and is required
+    /// by searches without one.
+    pub query: Option<String>,
+    /// Maximum length of a fragment in characters, a fragment always
+    /// contains at least one token
+    pub fragment_size: usize,
+    /// Maximum number of fragments per result
+    pub number_of_fragments: usize,
+    pub pre_tag: String,
+    pub post_tag: String,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        Self {
+            query: None,
+            fragment_size: 150,
+            number_of_fragments: 3,
+            pre_tag: "<em>".to_string(),
+            post_tag: "</em>".to_string(),
+        }
+    }
+}
+
+/// Finds the best-matching fragments of texts and marks the terms of a
+/// query in them
+///
+/// The texts are analyzed like the documents of the TF-IDF index, so a
+/// query term matches all the words it was stemmed or normalized from.
+pub struct Highlighter<'a> {
+    analyzer: &'a TextAnalyzer,
+    options: &'a HighlightOptions,
+    terms: QueryTerms,
+}
+
+// Terms of the query which can match a document
+#[derive(Default)]
+struct QueryTerms {
+    terms: FxHashSet<String>,
+    prefixes: Vec<String>,
+    // terms with their maximum number of edits
+    fuzzy: Vec<(String, u32)>,
+}
+
+impl QueryTerms {
+    fn matches(&self, term: &str) -> bool {
+        self.terms.contains(term)
+            || self.prefixes.iter().any(|prefix| term.starts_with(prefix))
+            || self
+                .fuzzy
+                .iter()
+                .any(|(fuzzy, max_edits)| edit_distance(fuzzy, term, *max_edits).is_some())
+    }
+}
+
+// A run of consecutive tokens of the text, with its byte range
+struct Fragment {
+    tokens: Range<usize>,
+    text: Range<usize>,
+    distinct_matches: usize,
+    matches: usize,
+}
+
+impl<'a> Highlighter<'a> {
+    pub fn new(
+        analyzer: &'a TextAnalyzer,
+        query: &str,
+        options: &'a HighlightOptions,
+    ) -> Result<Self, String> {
+        if options.fragment_size == 0 {
+            return Err("fragment_size must be at least 1".to_string());
+        }
+        if options.number_of_fragments == 0 {
+            return Err("number_of_fragments must be at least 1".to_string());
+        }
+        let query = TextQuery::parse(query)?;
+        let mut terms = QueryTerms::default();
+        collect_terms(analyzer, &query.clauses, &mut terms);
+        Ok(Self {
+            analyzer,
+            options,
+            terms,
+        })
+    }
+
+    /// Returns the fragments of the text with the most distinct query terms,
+    /// best first. Texts without any query term have no fragments.
+    pub fn highlight(&self, text: &str) -> Vec<String> {
+        let tokens = self.analyzer.analyze_tokens(text);
+        let matched: Vec<bool> = tokens
+            .iter()
+            .map(|token| self.terms.matches(&token.term))
+            .collect();
+        if !matched.contains(&true) {
+            return Vec::new();
+        }
+
+        let mut fragments: Vec<_> = self
+            .split_fragments(text, &tokens)
+            .into_iter()
+            .filter_map(|mut fragment| {
+                let mut distinct = FxHashSet::default();
+                for i in fragment.tokens.clone().filter(|&i| matched[i]) {
+                    distinct.insert(&tokens[i].term);
+                    fragment.matches += 1;
+                }
+                fragment.distinct_matches = distinct.len();
+                (fragment.matches > 0).then_some(fragment)
+            })
+            .collect();
+        // the sort is stable, so equally good fragments stay in text order
+        fragments.sort_by(|a, b| {
+            b.distinct_matches
+                .cmp(&a.distinct_matches)
+                .then(b.matches.cmp(&a.matches))
+        });
+
+        fragments
+            .into_iter()
+            .take(self.options.number_of_fragments)
+            .map(|fragment| self.render(text, &tokens, &matched, &fragment))
+            .collect()
+    }
+
+    // Splits the text into consecutive fragments, each one ending at the end
+    // of its last token
+    fn split_fragments(&self, text: &str, tokens: &[AnalyzedToken]) -> Vec<Fragment> {
+        let mut fragments = Vec::new();
+        let mut start = 0;
+        let mut text_start = 0;
+        while start < tokens.len() {
+            let mut end = start + 1;
+            while end < tokens.len()
+                && text[text_start..tokens[end].offsets.end].chars().count()
+                    <= self.options.fragment_size
+            {
+                end += 1;
+            }
+            let text_end = if end == tokens.len() {
+                text.len()
+            } else {
+                tokens[end - 1].offsets.end.max(text_start)
+            };
+            fragments.push(Fragment {
+                tokens: start..end,
+                text: text_start..text_end,
+                distinct_matches: 0,
+                matches: 0,
+            });
+            start = end;
+            text_start = text_end;
+        }
+        fragments
+    }
+
+    fn render(
+        &self,
+        text: &str,
+        tokens: &[AnalyzedToken],
+        matched: &[bool],
+        fragment: &Fragment,
+    ) -> String {
+        // the tokens of n-gram tokenizers overlap, so the overlapping
+        // matches are merged into a single highlight
+        let mut highlights: Vec<Range<usize>> = Vec::new();
+        for i in fragment.tokens.clone().filter(|&i| matched[i]) {
+            let offsets = &tokens[i].offsets;
+            let start = offsets.start.max(fragment.text.start);
+            match highlights.last_mut() {
+                Some(last) if start < last.end => last.end = last.end.max(offsets.end),
+                _ => highlights.push(start..offsets.end),
+            }
+        }
+
+        // the separators left over from the previous fragment are skipped
+        let first_token = tokens[fragment.tokens.start]
+            .offsets
+            .start
+            .max(fragment.text.start);
+        let start = text[fragment.text.start..first_token]
+            .find(|c: char| !(c.is_whitespace() || c.is_ascii_punctuation()))
+            .map_or(first_token, |i| fragment.text.start + i);
+        let end = start + text[start..fragment.text.end].trim_end().len();
+
+        let mut rendered = String::new();
+        let mut cursor = start;
+        for highlight in highlights {
+            rendered.push_str(&text[cursor..highlight.start]);
+            rendered.push_str(&self.options.pre_tag);
+            rendered.push_str(&text[highlight.clone()]);
+            rendered.push_str(&self.options.post_tag);
+            cursor = highlight.end;
+        }
+        rendered.push_str(&text[cursor..end]);
+        rendered
+    }
+}
+
+// Collects the terms of the clauses which can match, the excluded ones
+// never appear in the results
+fn collect_terms(analyzer: &TextAnalyzer, clauses: &[Clause], terms: &mut QueryTerms) {
+    for clause in clauses
+        .iter()
+        .filter(|clause| clause.occur != Occur::MustNot)
+    {
+        collect_node_terms(analyzer, &clause.node, terms);
+    }
+}
+
+fn collect_node_terms(analyzer: &TextAnalyzer, node: &QueryNode, terms: &mut QueryTerms) {
+    match node {
+        QueryNode::Term { text, .. } | QueryNode::Phrase { text, .. } => {
+            terms.terms.extend(analyzer.analyze(text))
+        }
+        QueryNode::Prefix { text, .. } => {
+            let prefix = analyzer.normalize_token(text);
+            if !prefix.is_empty() {
+                terms.prefixes.push(prefix);
+            }
+        }
+        QueryNode::Fuzzy {
+            text, max_edits, ..
+        } => terms.fuzzy.extend(
+            analyzer
+                .analyze(text)
+                .into_iter()
+                .map(|term| (term, *max_edits)),
+        ),
+        QueryNode::Group { clauses, .. } => collect_terms(analyzer, clauses, terms),
+        // only the `text` of the vectors is highlighted
+        QueryNode::Field { name, node } => {
+            if name == DEFAULT_FIELD {
+                collect_node_terms(analyzer, node, terms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexes::tf_idf::analyzer::{TextAnalyzerConfig, TokenizerConfig};
+
+    fn highlight(
+        analyzer: &TextAnalyzer,
+        query: &str,
+        options: &HighlightOptions,
+        text: &str,
+    ) -> Vec<String> {
+        Highlighter::new(analyzer, query, options)
+            .unwrap()
+            .highlight(text)
+    }
+
+    #[test]
+    fn test_highlight_whole_text() {
+        let analyzer = TextAnalyzer::default();
+        let options = HighlightOptions::default();
+
+        assert_eq!(
+            vec!["The <em>Databases</em> of <em>vectors</em> are fast".to_string()],
+            highlight(
+                &analyzer,
+                "vector database -fast",
+                &options,
+                "The Databases of vectors are fast"
+            )
+        );
+        assert!(highlight(&analyzer, "rust", &options, "The vector database").is_empty());
+        assert_eq!(
+            vec!["<em>Vectors</em> in a <em>databse</em>".to_string()],
+            highlight(
+                &analyzer,
+                "vect* database~1",
+                &options,
+                "Vectors in a databse"
+            )
+        );
+    }
+
+    #[test]
+    fn test_only_text_field_is_highlighted() {
+        let analyzer = TextAnalyzer::default();
+        let options = HighlightOptions::default();
+
+        assert_eq!(
+            vec!["<em>rust</em> and go".to_string()],
+            highlight(&analyzer, "text:rust title:go", &options, "rust and go")
+        );
+    }
+
+    #[test]
+    fn test_best_fragments_first() {
+        let analyzer = TextAnalyzer::default();
+        let options = HighlightOptions {
+            fragment_size: 20,
+            number_of_fragments: 2,
+            pre_tag: "[".to_string(),
+            post_tag: "]".to_string(),
+            ..Default::default()
+        };
+        let text = "rust is fast. nothing to see here. a rust vector index. vector again";
+
+        assert_eq!(
+            vec![
+                "here. a [rust] [vector]".to_string(),
+                "[rust] is fast".to_string()
+            ],
+            highlight(&analyzer, "rust vector", &options, text)
+        );
+    }
+
+    #[test]
+    fn test_overlapping_ngrams() {
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            tokenizer: TokenizerConfig::CjkNgram { ngram_size: 2 },
+            ..Default::default()
+        });
+        let options = HighlightOptions::default();
+
+        assert_eq!(
+            vec!["<em>東京都</em>に住む".to_string()],
+            highlight(&analyzer, "東京都", &options, "東京都に住む")
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let analyzer = TextAnalyzer::default();
+        let options = HighlightOptions {
+            number_of_fragments: 0,
+            ..Default::default()
+        };
+        assert!(Highlighter::new(&analyzer, "rust", &options).is_err());
+        assert!(Highlighter::new(&analyzer, "(rust", &HighlightOptions::default()).is_err());
+    }
+}
@@ -1,22 +1,88 @@
 use actix_web::{web, Scope};
+
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
 use controller::{
-    batch_dense_search, batch_sparse_search, batch_tf_idf_search, dense_search, hybrid_search,
-    sparse_search, tf_idf_search,
+    batch_dense_search, batch_sparse_search, batch_tf_idf_search, dense_search,
+    evaluate_dense_index, evaluate_sparse_index, evaluate_tf_idf_index, hybrid_search,
+    multi_vector_search, sparse_search, tf_idf_search,
 };
 
 pub mod controller;
 pub(crate) mod dtos;
 pub(crate) mod error;
+mod evaluation;
+mod fusion;
+pub(crate) mod highlight;
 pub(crate) mod repo;
-mod service;
+pub(crate) mod service;
 
 pub(crate) fn search_module() -> Scope {
     web::scope("/collections/{collection_id}/search")
-        .route("/dense", web::post().to(dense_search))
-        .route("/batch-dense", web::post().to(batch_dense_search))
-        .route("/sparse", web::post().to(sparse_search))
-        .route("/batch-sparse", web::post().to(batch_sparse_search))
-        .route("/tf-idf", web::post().to(tf_idf_search))
-        .route("/batch-tf-idf", web::post().to(batch_tf_idf_search))
-        .route("/hybrid", web::post().to(hybrid_search))
+        .route(
+            "/dense",
+            web::post()
+                .to(dense_search)
+                .wrap(AuthorizationMiddleware(Permission::QueryDenseVectors)),
+        )
+        .route(
+            "/batch-dense",
+            web::post()
+                .to(batch_dense_search)
+                .wrap(AuthorizationMiddleware(Permission::QueryDenseVectors)),
+        )
+        .route(
+            "/multi-vector",
+            web::post()
+                .to(multi_vector_search)
+                .wrap(AuthorizationMiddleware(Permission::QueryDenseVectors)),
+        )
+        .route(
+            "/sparse",
+            web::post()
+                .to(sparse_search)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
+        .route(
+            "/batch-sparse",
+            web::post()
+                .to(batch_sparse_search)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
+        .route(
+            "/tf-idf",
+            web::post()
+                .to(tf_idf_search)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
+        .route(
+            "/batch-tf-idf",
+            web::post()
+                .to(batch_tf_idf_search)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
+        .route(
+            "/hybrid",
+            web::post()
+                .to(hybrid_search)
+                .wrap(AuthorizationMiddleware(Permission::QueryHybridVectors)),
+        )
+        .route(
+            "/evaluate-dense",
+            web::post()
+                .to(evaluate_dense_index)
+                .wrap(AuthorizationMiddleware(Permission::QueryDenseVectors)),
+        )
+        .route(
+            "/evaluate-sparse",
+            web::post()
+                .to(evaluate_sparse_index)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
+        .route(
+            "/evaluate-tf-idf",
+            web::post()
+                .to(evaluate_tf_idf_index)
+                .wrap(AuthorizationMiddleware(Permission::QuerySparseVectors)),
+        )
 }
@@ -1,15 +1,23 @@
 use std::sync::Arc;
 
-use rustc_hash::FxHashMap;
-
 use super::dtos;
 use super::error::SearchError;
+use super::fusion::{fuse_results, RetrieverResults};
+use super::highlight::{HighlightOptions, Highlighter};
 use crate::app_context::AppContext;
-use crate::indexes::hnsw::{DenseSearchInput, DenseSearchOptions};
+use crate::indexes::hnsw::{
+    DenseSearchInput, DenseSearchOptions, HNSWIndex, MultiVectorSearchInput,
+    MultiVectorSearchOptions,
+};
 use crate::indexes::inverted::{SparseSearchInput, SparseSearchOptions};
+use crate::indexes::tf_idf::analyzer::TextAnalyzer;
 use crate::indexes::tf_idf::{TFIDFSearchInput, TFIDFSearchOptions};
 use crate::indexes::{IndexOps, SearchResult};
-use crate::models::types::{DocumentId, VectorId};
+use crate::models::collection::Collection;
+
+/// Search result with the highlighted fragments of its text, if they were
+/// requested
+pub(crate) type HighlightedSearchResult = (SearchResult, Option<Vec<String>>);
 
 pub(crate) async fn dense_search(
     ctx: Arc<AppContext>,
@@ -22,9 +30,7 @@ pub(crate) async fn dense_search(
         .get_collection(collection_id)
         .ok_or_else(|| SearchError::CollectionNotFound(collection_id.to_string()))?;
 
-    let hnsw_index = collection.get_hnsw_index().ok_or_else(|| {
-        SearchError::IndexNotFound(format!("HNSW index for collection '{}'", collection_id))
-    })?;
+    let hnsw_index = get_dense_index(&collection, collection_id, request.field.as_deref())?;
 
     let warning = collection.is_indexing().then(|| {
         "Embeddings are currently being indexed; some results may be temporarily unavailable."
@@ -38,6 +44,9 @@ pub(crate) async fn dense_search(
                 DenseSearchInput(request.query_vector, request.filter),
                 &DenseSearchOptions {
                     top_k: request.top_k,
+                    ef_search: request.ef_search,
+                    filtered_candidates_limit: request.filtered_candidates_limit,
+                    exact: request.exact,
                 },
                 &ctx.config,
                 request.return_raw_text,
@@ -58,9 +67,7 @@ pub(crate) async fn batch_dense_search(
         .get_collection(collection_id)
         .ok_or_else(|| SearchError::CollectionNotFound(collection_id.to_string()))?;
 
-    let hnsw_index = collection.get_hnsw_index().ok_or_else(|| {
-        SearchError::IndexNotFound(format!("HNSW index for collection '{}'", collection_id))
-    })?;
+    let hnsw_index = get_dense_index(&collection, collection_id, request.field.as_deref())?;
 
     let warning = collection.is_indexing().then(|| {
         "Embeddings are currently being indexed; some results may be temporarily unavailable."
@@ -78,6 +85,9 @@ pub(crate) async fn batch_dense_search(
                     .collect(),
                 &DenseSearchOptions {
                     top_k: request.top_k,
+                    ef_search: request.ef_search,
+                    filtered_candidates_limit: request.filtered_candidates_limit,
+                    exact: request.exact,
                 },
                 &ctx.config,
                 request.return_raw_text,
@@ -87,11 +97,57 @@ pub(crate) async fn batch_dense_search(
     ))
 }
 
+pub(crate) async fn multi_vector_search(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: dtos::MultiVectorSearchRequestDto,
+) -> Result<(Vec<SearchResult>, Option<String>), SearchError> {
+    let collection = ctx
+        .ain_env
+        .collections_map
+        .get_collection(collection_id)
+        .ok_or_else(|| SearchError::CollectionNotFound(collection_id.to_string()))?;
+
+    if !collection.meta.dense_vector.multi_vector {
+        return Err(SearchError::InvalidInput(format!(
+            "Collection '{}' doesn't store multi-vector embeddings",
+            collection_id
+        )));
+    }
+
+    // the token vectors are indexed in the default dense index
+    let hnsw_index = collection.get_hnsw_index().ok_or_else(|| {
+        SearchError::IndexNotFound(format!("HNSW index for collection '{}'", collection_id))
+    })?;
+
+    let warning = collection.is_indexing().then(|| {
+        "Embeddings are currently being indexed; some results may be temporarily unavailable."
+            .to_string()
+    });
+
+    Ok((
+        hnsw_index.multi_vector_search(
+            &collection,
+            MultiVectorSearchInput(request.query_vectors, request.filter),
+            &MultiVectorSearchOptions {
+                top_k: request.top_k,
+                candidates_per_vector: request.candidates_per_vector,
+                ef_search: request.ef_search,
+                filtered_candidates_limit: request.filtered_candidates_limit,
+                exact: request.exact,
+            },
+            &ctx.config,
+            request.return_raw_text,
+        )?,
+        warning,
+    ))
+}
+
 pub(crate) async fn sparse_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
     request: dtos::SparseSearchRequestDto,
-) -> Result<(Vec<SearchResult>, Option<String>), SearchError> {
+) -> Result<(Vec<HighlightedSearchResult>, Option<String>), SearchError> {
     let collection = ctx
         .ain_env
         .collections_map
@@ -107,19 +163,27 @@ pub(crate) async fn sparse_search(
             .to_string()
     });
 
+    let results = inverted_index
+        .search(
+            &collection,
+            SparseSearchInput(request.query_terms),
+            &SparseSearchOptions {
+                top_k: request.top_k,
+                early_terminate_threshold: request.early_terminate_threshold,
+            },
+            &ctx.config,
+            request.return_raw_text || request.highlight.is_some(),
+        )
+        .map_err(SearchError::WaCustom)?;
+
     Ok((
-        inverted_index
-            .search(
-                &collection,
-                SparseSearchInput(request.query_terms),
-                &SparseSearchOptions {
-                    top_k: request.top_k,
-                    early_terminate_threshold: request.early_terminate_threshold,
-                },
-                &ctx.config,
-                request.return_raw_text,
-            )
-            .map_err(SearchError::WaCustom)?,
+        highlight_results(
+            &collection,
+            results,
+            request.highlight.as_ref(),
+            None,
+            request.return_raw_text,
+        )?,
         warning,
     ))
 }
@@ -128,7 +192,7 @@ pub(crate) async fn batch_sparse_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
     request: dtos::BatchSparseSearchRequestDto,
-) -> Result<(Vec<Vec<SearchResult>>, Option<String>), SearchError> {
+) -> Result<(Vec<Vec<HighlightedSearchResult>>, Option<String>), SearchError> {
     let collection = ctx
         .ain_env
         .collections_map
@@ -144,23 +208,36 @@ pub(crate) async fn batch_sparse_search(
             .to_string()
     });
 
+    let results_list = inverted_index
+        .batch_search(
+            &collection,
+            request
+                .query_terms_list
+                .into_iter()
+                .map(SparseSearchInput)
+                .collect(),
+            &SparseSearchOptions {
+                top_k: request.top_k,
+                early_terminate_threshold: request.early_terminate_threshold,
+            },
+            &ctx.config,
+            request.return_raw_text || request.highlight.is_some(),
+        )
+        .map_err(SearchError::WaCustom)?;
+
     Ok((
-        inverted_index
-            .batch_search(
-                &collection,
-                request
-                    .query_terms_list
-                    .into_iter()
-                    .map(SparseSearchInput)
-                    .collect(),
-                &SparseSearchOptions {
-                    top_k: request.top_k,
-                    early_terminate_threshold: request.early_terminate_threshold,
-                },
-                &ctx.config,
-                request.return_raw_text,
-            )
-            .map_err(SearchError::WaCustom)?,
+        results_list
+            .into_iter()
+            .map(|results| {
+                highlight_results(
+                    &collection,
+                    results,
+                    request.highlight.as_ref(),
+                    None,
+                    request.return_raw_text,
+                )
+            })
+            .collect::<Result<_, _>>()?,
         warning,
     ))
 }
@@ -169,182 +246,176 @@ pub(crate) async fn hybrid_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
     request: dtos::HybridSearchRequestDto,
-) -> Result<(Vec<SearchResult>, Option<String>), SearchError> {
+) -> Result<(Vec<HighlightedSearchResult>, Option<String>), SearchError> {
     let collection = ctx
         .ain_env
         .collections_map
         .get_collection(collection_id)
         .ok_or_else(|| SearchError::CollectionNotFound(collection_id.to_string()))?;
 
-    let results_pair = match request.query {
+    if request.top_k == 0 {
+        return Err(SearchError::InvalidInput(
+            "top_k must be greater than 0".to_string(),
+        ));
+    }
+    for (name, options) in [
+        ("dense", &request.dense),
+        ("sparse", &request.sparse),
+        ("tf_idf", &request.tf_idf),
+    ] {
+        if !options.weight.is_finite() || options.weight < 0.0 {
+            return Err(SearchError::InvalidInput(format!(
+                "Weight of the {} retriever must be a non-negative number, got {}",
+                name, options.weight
+            )));
+        }
+        if options.top_k == Some(0) {
+            return Err(SearchError::InvalidInput(format!(
+                "top_k of the {} retriever must be greater than 0",
+                name
+            )));
+        }
+    }
+    if request.fusion_method == dtos::FusionMethod::Rrf && request.fusion_constant_k < 0.0 {
+        log::warn!(
+            "RRF fusion_constant_k ({}) is non-positive.",
+            request.fusion_constant_k
+        );
+    }
+
+    let (dense_query, sparse_query, text_query) = match request.query {
+        dtos::HybridSearchQuery::DenseSparseAndTFIDF {
+            query_vector,
+            query_terms,
+            query_text,
+            sparse_early_terminate_threshold,
+        } => (
+            Some(query_vector),
+            Some((query_terms, sparse_early_terminate_threshold)),
+            Some(query_text),
+        ),
         dtos::HybridSearchQuery::DenseAndSparse {
             query_vector,
             query_terms,
             sparse_early_terminate_threshold,
-        } => {
-            let hnsw_index = collection.get_hnsw_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!("HNSW index for collection '{}'", collection_id))
-            })?;
-            let inverted_index = collection.get_inverted_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!(
-                    "Sparse index for collection '{}'",
-                    collection_id
-                ))
-            })?;
-
-            let dense_results = hnsw_index
-                .search(
-                    &collection,
-                    DenseSearchInput(query_vector, None),
-                    &DenseSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-            let sparse_results = inverted_index
-                .search(
-                    &collection,
-                    SparseSearchInput(query_terms),
-                    &SparseSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                        early_terminate_threshold: sparse_early_terminate_threshold,
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-
-            (dense_results, sparse_results)
-        }
+        } => (
+            Some(query_vector),
+            Some((query_terms, sparse_early_terminate_threshold)),
+            None,
+        ),
         dtos::HybridSearchQuery::DenseAndTFIDF {
             query_vector,
             query_text,
-        } => {
-            let hnsw_index = collection.get_hnsw_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!("HNSW index for collection '{}'", collection_id))
-            })?;
-            let tf_idf_index = collection.get_tf_idf_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!(
-                    "TF-IDF index for collection '{}'",
-                    collection_id
-                ))
-            })?;
-
-            let dense_results = hnsw_index
-                .search(
-                    &collection,
-                    DenseSearchInput(query_vector, None),
-                    &DenseSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-            let tf_idf_results = tf_idf_index
-                .search(
-                    &collection,
-                    TFIDFSearchInput(query_text),
-                    &TFIDFSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-
-            (dense_results, tf_idf_results)
-        }
+        } => (Some(query_vector), None, Some(query_text)),
         dtos::HybridSearchQuery::SparseAndTFIDF {
             query_terms,
             query_text,
             sparse_early_terminate_threshold,
-        } => {
-            let inverted_index = collection.get_inverted_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!(
-                    "Sparse index for collection '{}'",
-                    collection_id
-                ))
-            })?;
-            let tf_idf_index = collection.get_tf_idf_index().ok_or_else(|| {
-                SearchError::IndexNotFound(format!(
-                    "TF-IDF index for collection '{}'",
-                    collection_id
-                ))
-            })?;
-
-            let sparse_results = inverted_index
-                .search(
-                    &collection,
-                    SparseSearchInput(query_terms),
-                    &SparseSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                        early_terminate_threshold: sparse_early_terminate_threshold,
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-            let tf_idf_results = tf_idf_index
-                .search(
-                    &collection,
-                    TFIDFSearchInput(query_text),
-                    &TFIDFSearchOptions {
-                        top_k: Some(request.top_k * 3),
-                    },
-                    &ctx.config,
-                    request.return_raw_text,
-                )
-                .map_err(SearchError::WaCustom)?;
-
-            (sparse_results, tf_idf_results)
-        }
+        } => (
+            None,
+            Some((query_terms, sparse_early_terminate_threshold)),
+            Some(query_text),
+        ),
     };
+    let return_text = request.return_raw_text || request.highlight.is_some();
+    let candidates = |options: &dtos::RetrieverOptions| options.top_k.unwrap_or(request.top_k * 3);
 
-    let warning = collection.is_indexing().then(|| {
-        "Embeddings are currently being indexed; some results may be temporarily unavailable."
-            .to_string()
-    });
+    let mut retrievers = Vec::with_capacity(3);
 
-    let mut final_scores: FxHashMap<VectorId, (f32, Option<DocumentId>, Option<String>)> =
-        FxHashMap::default();
-    let k = request.fusion_constant_k;
-    if k < 0.0 {
-        log::warn!("RRF fusion_constant_k ({}) is non-positive.", k);
+    if let Some(query_vector) = dense_query {
+        let hnsw_index = get_dense_index(&collection, collection_id, None)?;
+        let results = hnsw_index
+            .search(
+                &collection,
+                DenseSearchInput(query_vector, None),
+                &DenseSearchOptions {
+                    top_k: Some(candidates(&request.dense)),
+                    ..Default::default()
+                },
+                &ctx.config,
+                return_text,
+            )
+            .map_err(SearchError::WaCustom)?;
+        retrievers.push(RetrieverResults {
+            results,
+            weight: request.dense.weight,
+        });
     }
 
-    for (rank, (vector_id, document_id, _score, text)) in results_pair.0.into_iter().enumerate() {
-        let score = 1.0 / (rank as f32 + k + f32::EPSILON);
-        final_scores.insert(vector_id, (score, document_id, text));
+    if let Some((query_terms, early_terminate_threshold)) = sparse_query {
+        let inverted_index = collection.get_inverted_index().ok_or_else(|| {
+            SearchError::IndexNotFound(format!("Sparse index for collection '{}'", collection_id))
+        })?;
+        let results = inverted_index
+            .search(
+                &collection,
+                SparseSearchInput(query_terms),
+                &SparseSearchOptions {
+                    top_k: Some(candidates(&request.sparse)),
+                    early_terminate_threshold,
+                },
+                &ctx.config,
+                return_text,
+            )
+            .map_err(SearchError::WaCustom)?;
+        retrievers.push(RetrieverResults {
+            results,
+            weight: request.sparse.weight,
+        });
     }
 
-    for (rank, (vector_id, document_id, _score, text)) in results_pair.1.into_iter().enumerate() {
-        let score = 1.0 / (rank as f32 + k + f32::EPSILON);
-        final_scores
-            .entry(vector_id)
-            .or_insert((0.0, document_id, text))
-            .0 += score;
+    if let Some(query_text) = &text_query {
+        let tf_idf_index = collection.get_tf_idf_index().ok_or_else(|| {
+            SearchError::IndexNotFound(format!("TF-IDF index for collection '{}'", collection_id))
+        })?;
+        let results = tf_idf_index
+            .search(
+                &collection,
+                TFIDFSearchInput(query_text.clone()),
+                &TFIDFSearchOptions {
+                    top_k: Some(candidates(&request.tf_idf)),
+                    field_weights: request.field_weights.clone(),
+                    exact: false,
+                },
+                &ctx.config,
+                return_text,
+            )
+            .map_err(SearchError::from)?;
+        retrievers.push(RetrieverResults {
+            results,
+            weight: request.tf_idf.weight,
+        });
     }
 
-    let mut final_results: Vec<(VectorId, Option<DocumentId>, f32, Option<String>)> = final_scores
-        .into_iter()
-        .map(|(id, (score, document_id, text))| (id, document_id, score, text))
-        .collect();
+    let warning = collection.is_indexing().then(|| {
+        "Embeddings are currently being indexed; some results may be temporarily unavailable."
+            .to_string()
+    });
 
-    if final_results.len() > request.top_k {
-        final_results.select_nth_unstable_by(request.top_k, |a, b| b.2.total_cmp(&a.2));
-    }
-    final_results.sort_unstable_by(|a, b| b.2.total_cmp(&a.2));
-    Ok((final_results, warning))
+    let final_results = fuse_results(
+        retrievers,
+        request.fusion_method,
+        request.fusion_constant_k,
+        request.top_k,
+    );
+
+    Ok((
+        highlight_results(
+            &collection,
+            final_results,
+            request.highlight.as_ref(),
+            text_query.as_deref(),
+            request.return_raw_text,
+        )?,
+        warning,
+    ))
 }
 
 pub(crate) async fn tf_idf_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
     request: dtos::FindSimilarTFIDFDocumentDto,
-) -> Result<(Vec<SearchResult>, Option<String>), SearchError> {
+) -> Result<(Vec<HighlightedSearchResult>, Option<String>), SearchError> {
     let collection = ctx
         .ain_env
         .collections_map
@@ -360,18 +431,28 @@ pub(crate) async fn tf_idf_search(
             .to_string()
     });
 
+    let results = tf_idf_index
+        .search(
+            &collection,
+            TFIDFSearchInput(request.query.clone()),
+            &TFIDFSearchOptions {
+                top_k: request.top_k,
+                field_weights: request.field_weights.clone(),
+                exact: false,
+            },
+            &ctx.config,
+            request.return_raw_text || request.highlight.is_some(),
+        )
+        .map_err(SearchError::from)?;
+
     Ok((
-        tf_idf_index
-            .search(
-                &collection,
-                TFIDFSearchInput(request.query),
-                &TFIDFSearchOptions {
-                    top_k: request.top_k,
-                },
-                &ctx.config,
-                request.return_raw_text,
-            )
-            .map_err(SearchError::WaCustom)?,
+        highlight_results(
+            &collection,
+            results,
+            request.highlight.as_ref(),
+            Some(request.query.as_str()),
+            request.return_raw_text,
+        )?,
         warning,
     ))
 }
@@ -380,7 +461,7 @@ pub(crate) async fn batch_tf_idf_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
     request: dtos::BatchSearchTFIDFDocumentsDto,
-) -> Result<(Vec<Vec<SearchResult>>, Option<String>), SearchError> {
+) -> Result<(Vec<Vec<HighlightedSearchResult>>, Option<String>), SearchError> {
     let collection = ctx
         .ain_env
         .collections_map
@@ -396,18 +477,110 @@ pub(crate) async fn batch_tf_idf_search(
             .to_string()
     });
 
+    let results_list = tf_idf_index
+        .batch_search(
+            &collection,
+            request
+                .queries
+                .iter()
+                .cloned()
+                .map(TFIDFSearchInput)
+                .collect(),
+            &TFIDFSearchOptions {
+                top_k: request.top_k,
+                field_weights: request.field_weights.clone(),
+                exact: false,
+            },
+            &ctx.config,
+            request.return_raw_text || request.highlight.is_some(),
+        )
+        .map_err(SearchError::from)?;
+
     Ok((
-        tf_idf_index
-            .batch_search(
-                &collection,
-                request.queries.into_iter().map(TFIDFSearchInput).collect(),
-                &TFIDFSearchOptions {
-                    top_k: request.top_k,
-                },
-                &ctx.config,
-                request.return_raw_text,
-            )
-            .map_err(SearchError::WaCustom)?,
+        results_list
+            .into_iter()
+            .zip(&request.queries)
+            .map(|(results, query)| {
+                highlight_results(
+                    &collection,
+                    results,
+                    request.highlight.as_ref(),
+                    Some(query.as_str()),
+                    request.return_raw_text,
+                )
+            })
+            .collect::<Result<_, _>>()?,
         warning,
     ))
 }
+
+/// Returns the HNSW index of a named dense field of the collection, or its
+/// default dense index if no field is given
+///
+/// The default dense index of a multi-vector collection indexes token
+/// vectors, which are only searched by `multi_vector_search`.
+pub(crate) fn get_dense_index(
+    collection: &Collection,
+    collection_id: &str,
+    field: Option<&str>,
+) -> Result<Arc<HNSWIndex>, SearchError> {
+    if field.is_none() && collection.meta.dense_vector.multi_vector {
+        return Err(SearchError::InvalidInput(format!(
+            "Collection '{}' stores multi-vector embeddings, use a multi-vector search",
+            collection_id
+        )));
+    }
+    collection.get_dense_index(field).ok_or_else(|| {
+        SearchError::IndexNotFound(match field {
+            Some(field) => format!(
+                "HNSW index for field '{}' of collection '{}'",
+                field, collection_id
+            ),
+            None => format!("HNSW index for collection '{}'", collection_id),
+        })
+    })
+}
+
+// Highlights the texts of the results with the analyzer of the TF-IDF index
+// of the collection, the texts themselves are only kept if they were
+// requested too
+fn highlight_results(
+    collection: &Collection,
+    results: Vec<SearchResult>,
+    highlight: Option<&HighlightOptions>,
+    text_query: Option<&str>,
+    return_raw_text: bool,
+) -> Result<Vec<HighlightedSearchResult>, SearchError> {
+    let Some(options) = highlight else {
+        return Ok(results.into_iter().map(|result| (result, None)).collect());
+    };
+    let query = options.query.as_deref().or(text_query).ok_or_else(|| {
+        SearchError::InvalidInput(
+            "`highlight.query` is required by searches without a text query".to_string(),
+        )
+    })?;
+
+    let tf_idf_index = collection.get_tf_idf_index();
+    let default_analyzer;
+    let analyzer = match &tf_idf_index {
+        Some(index) => &index.analyzer,
+        None => {
+            default_analyzer = TextAnalyzer::default();
+            &default_analyzer
+        }
+    };
+    let highlighter =
+        Highlighter::new(analyzer, query, options).map_err(SearchError::InvalidInput)?;
+
+    Ok(results
+        .into_iter()
+        .map(|(id, document_id, score, text)| {
+            let highlights = text
+                .as_deref()
+                .map(|text| highlighter.highlight(text))
+                .unwrap_or_default();
+            let text = text.filter(|_| return_raw_text);
+            ((id, document_id, score, text), Some(highlights))
+        })
+        .collect())
+}
@@ -3,11 +3,26 @@ use std::sync::Arc;
 
 use super::dtos::{
     BatchDenseSearchRequestDto, BatchSearchResponseDto, BatchSearchTFIDFDocumentsDto,
-    BatchSparseSearchRequestDto, DenseSearchRequestDto, FindSimilarTFIDFDocumentDto,
-    HybridSearchRequestDto, SearchResponseDto, SearchResultItemDto, SparseSearchRequestDto,
+    BatchSparseSearchRequestDto, DenseEvaluationDto, DenseSearchRequestDto, EvaluateDenseIndexDto,
+    EvaluateSparseIndexDto, EvaluateTFIDFIndexDto, FindSimilarTFIDFDocumentDto,
+    HybridSearchRequestDto, MultiVectorSearchRequestDto, SearchResponseDto, SearchResultItemDto,
+    SparseEvaluationDto, SparseSearchRequestDto, TFIDFEvaluationDto,
 };
 use super::error::SearchError;
-use super::repo;
+use super::evaluation;
+use super::repo::{self, HighlightedSearchResult};
+
+fn result_item(
+    ((id, document_id, score, text), highlights): HighlightedSearchResult,
+) -> SearchResultItemDto {
+    SearchResultItemDto {
+        id,
+        document_id,
+        score,
+        text,
+        highlights,
+    }
+}
 
 pub(crate) async fn dense_search(
     ctx: Arc<AppContext>,
@@ -24,6 +39,7 @@ pub(crate) async fn dense_search(
                 document_id,
                 score,
                 text,
+                highlights: None,
             })
             .collect(),
         warning,
@@ -48,6 +64,7 @@ pub(crate) async fn batch_dense_search(
                         document_id,
                         score,
                         text,
+                        highlights: None,
                     })
                     .collect(),
                 warning: None,
@@ -57,12 +74,12 @@ pub(crate) async fn batch_dense_search(
     })
 }
 
-pub(crate) async fn sparse_search(
+pub(crate) async fn multi_vector_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
-    request: SparseSearchRequestDto,
+    request: MultiVectorSearchRequestDto,
 ) -> Result<SearchResponseDto, SearchError> {
-    let (results, warning) = repo::sparse_search(ctx, collection_id, request).await?;
+    let (results, warning) = repo::multi_vector_search(ctx, collection_id, request).await?;
 
     Ok(SearchResponseDto {
         results: results
@@ -72,12 +89,26 @@ pub(crate) async fn sparse_search(
                 document_id,
                 score,
                 text,
+                highlights: None,
             })
             .collect(),
         warning,
     })
 }
 
+pub(crate) async fn sparse_search(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: SparseSearchRequestDto,
+) -> Result<SearchResponseDto, SearchError> {
+    let (results, warning) = repo::sparse_search(ctx, collection_id, request).await?;
+
+    Ok(SearchResponseDto {
+        results: results.into_iter().map(result_item).collect(),
+        warning,
+    })
+}
+
 pub(crate) async fn batch_sparse_search(
     ctx: Arc<AppContext>,
     collection_id: &str,
@@ -89,15 +120,7 @@ pub(crate) async fn batch_sparse_search(
         responses: results_list
             .into_iter()
             .map(|results| SearchResponseDto {
-                results: results
-                    .into_iter()
-                    .map(|(id, document_id, score, text)| SearchResultItemDto {
-                        id,
-                        document_id,
-                        score,
-                        text,
-                    })
-                    .collect(),
+                results: results.into_iter().map(result_item).collect(),
                 warning: None,
             })
             .collect(),
@@ -113,15 +136,7 @@ pub(crate) async fn hybrid_search(
     let (results, warning) = repo::hybrid_search(ctx, collection_id, request).await?;
 
     Ok(SearchResponseDto {
-        results: results
-            .into_iter()
-            .map(|(id, document_id, score, text)| SearchResultItemDto {
-                id,
-                document_id,
-                score,
-                text,
-            })
-            .collect(),
+        results: results.into_iter().map(result_item).collect(),
         warning,
     })
 }
@@ -134,15 +149,7 @@ pub(crate) async fn tf_idf_search(
     let (results, warning) = repo::tf_idf_search(ctx, collection_id, request).await?;
 
     Ok(SearchResponseDto {
-        results: results
-            .into_iter()
-            .map(|(id, document_id, score, text)| SearchResultItemDto {
-                id,
-                document_id,
-                score,
-                text,
-            })
-            .collect(),
+        results: results.into_iter().map(result_item).collect(),
         warning,
     })
 }
@@ -158,18 +165,34 @@ pub(crate) async fn batch_tf_idf_search(
         responses: results_list
             .into_iter()
             .map(|results| SearchResponseDto {
-                results: results
-                    .into_iter()
-                    .map(|(id, document_id, score, text)| SearchResultItemDto {
-                        id,
-                        document_id,
-                        score,
-                        text,
-                    })
-                    .collect(),
+                results: results.into_iter().map(result_item).collect(),
                 warning: None,
             })
             .collect(),
         warning,
     })
 }
+
+pub(crate) async fn evaluate_dense_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateDenseIndexDto,
+) -> Result<DenseEvaluationDto, SearchError> {
+    evaluation::evaluate_dense_index(ctx, collection_id, request).await
+}
+
+pub(crate) async fn evaluate_sparse_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateSparseIndexDto,
+) -> Result<SparseEvaluationDto, SearchError> {
+    evaluation::evaluate_sparse_index(ctx, collection_id, request).await
+}
+
+pub(crate) async fn evaluate_tf_idf_index(
+    ctx: Arc<AppContext>,
+    collection_id: &str,
+    request: EvaluateTFIDFIndexDto,
+) -> Result<TFIDFEvaluationDto, SearchError> {
+    evaluation::evaluate_tf_idf_index(ctx, collection_id, request).await
+}
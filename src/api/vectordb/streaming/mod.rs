@@ -1,14 +1,24 @@
 pub mod controller;
 mod repo;
-mod service;
+pub(crate) mod service;
 
 use actix_web::{web, Scope};
 
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
+
 pub(crate) fn streaming_module() -> Scope {
     web::scope("/collections/{collection_id}/streaming")
-        .route("/upsert", web::post().to(controller::upsert))
+        .route(
+            "/upsert",
+            web::post()
+                .to(controller::upsert)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
+        )
         .route(
             "/vectors/{vector_id}",
-            web::delete().to(controller::delete_vector_by_id),
+            web::delete()
+                .to(controller::delete_vector_by_id)
+                .wrap(AuthorizationMiddleware(Permission::DeleteVectors)),
         )
 }
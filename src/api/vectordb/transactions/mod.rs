@@ -1,36 +1,56 @@
 pub mod controller;
 pub mod dtos;
-pub(super) mod error;
+pub(crate) mod error;
 mod repo;
-mod service;
+pub(crate) mod service;
 
 use actix_web::{web, Scope};
 
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
+
 pub(crate) fn transactions_module() -> Scope {
     web::scope("/collections/{collection_id}/transactions")
-        .route("", web::post().to(controller::create_transaction))
+        .route(
+            "",
+            web::post()
+                .to(controller::create_transaction)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
+        )
         .route(
             "/{transaction_id}/commit",
-            web::post().to(controller::commit_transaction),
+            web::post()
+                .to(controller::commit_transaction)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
         )
         .route(
             "/{transaction_id}/status",
-            web::get().to(controller::get_transaction_status),
+            web::get()
+                .to(controller::get_transaction_status)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
         )
         .route(
             "/{transaction_id}/vectors",
-            web::post().to(controller::create_vector_in_transaction),
+            web::post()
+                .to(controller::create_vector_in_transaction)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
         )
         .route(
             "/{transaction_id}/upsert",
-            web::post().to(controller::upsert),
+            web::post()
+                .to(controller::upsert)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
         )
         .route(
             "/{transaction_id}/vectors/{vector_id}",
-            web::delete().to(controller::delete_vector_by_id),
+            web::delete()
+                .to(controller::delete_vector_by_id)
+                .wrap(AuthorizationMiddleware(Permission::DeleteVectors)),
         )
         .route(
             "/{transaction_id}/abort",
-            web::post().to(controller::abort_transaction),
+            web::post()
+                .to(controller::abort_transaction)
+                .wrap(AuthorizationMiddleware(Permission::UpsertVectors)),
         )
 }
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
 use crate::{
     metadata::MetadataFields,
@@ -31,6 +31,15 @@ pub(crate) struct CreateVectorDto {
     #[schema(value_type = Object, nullable = true)]
     pub sparse_values: Option<Vec<SparsePair>>,
     pub text: Option<String>,
+    /// Named text fields, e.g. `{"title": "...", "body": "..."}`, searchable
+    /// separately with `field:term` queries
+    pub text_fields: Option<HashMap<String, String>>,
+    /// Vectors of the named dense fields of the collection, e.g.
+    /// `{"title_emb": [...], "image_emb": [...]}`
+    pub dense_fields: Option<HashMap<String, Vec<f32>>>,
+    /// Token vectors of the embedding in a multi-vector collection, e.g.
+    /// `[[0.1, 0.2, 0.3], [0.3, 0.2, 0.1]]`
+    pub multi_vector_values: Option<Vec<Vec<f32>>>,
 }
 
 impl From<CreateVectorDto> for RawVectorEmbedding {
@@ -42,6 +51,9 @@ impl From<CreateVectorDto> for RawVectorEmbedding {
             metadata: dto.metadata,
             sparse_values: dto.sparse_values,
             text: dto.text,
+            text_fields: dto.text_fields,
+            dense_fields: dto.dense_fields,
+            multi_vector_values: dto.multi_vector_values,
         }
     }
 }
@@ -55,6 +67,9 @@ impl From<RawVectorEmbedding> for CreateVectorDto {
             metadata: emb.metadata,
             sparse_values: emb.sparse_values,
             text: emb.text,
+            text_fields: emb.text_fields,
+            dense_fields: emb.dense_fields,
+            multi_vector_values: emb.multi_vector_values,
         }
     }
 }
@@ -72,7 +87,7 @@ impl<'de> Deserialize<'de> for CreateVectorDto {
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                 write!(
                     formatter,
-                    "a vector with dense_values (+metadata), sparse (indices + values), or text"
+                    "a vector with dense_values, dense_fields or multi_vector_values (+metadata), sparse (indices + values), text or text_fields"
                 )
             }
 
@@ -86,6 +101,9 @@ impl<'de> Deserialize<'de> for CreateVectorDto {
                 let mut metadata = None;
                 let mut sparse_values_raw: Option<(Vec<u32>, Vec<f32>)> = None;
                 let mut text = None;
+                let mut text_fields = None;
+                let mut dense_fields = None;
+                let mut multi_vector_values = None;
 
                 while let Some(key) = map.next_key::<String>()? {
                     match key.as_str() {
@@ -135,6 +153,24 @@ impl<'de> Deserialize<'de> for CreateVectorDto {
                             }
                             text = Some(map.next_value()?);
                         }
+                        "text_fields" => {
+                            if text_fields.is_some() {
+                                return Err(de::Error::duplicate_field("text_fields"));
+                            }
+                            text_fields = map.next_value()?;
+                        }
+                        "dense_fields" => {
+                            if dense_fields.is_some() {
+                                return Err(de::Error::duplicate_field("dense_fields"));
+                            }
+                            dense_fields = map.next_value()?;
+                        }
+                        "multi_vector_values" => {
+                            if multi_vector_values.is_some() {
+                                return Err(de::Error::duplicate_field("multi_vector_values"));
+                            }
+                            multi_vector_values = map.next_value()?;
+                        }
                         _ => {
                             return Err(de::Error::unknown_field(
                                 &key,
@@ -146,6 +182,9 @@ impl<'de> Deserialize<'de> for CreateVectorDto {
                                     "sparse_values",
                                     "sparse_indices",
                                     "text",
+                                    "text_fields",
+                                    "dense_fields",
+                                    "multi_vector_values",
                                 ],
                             ));
                         }
@@ -179,6 +218,9 @@ impl<'de> Deserialize<'de> for CreateVectorDto {
                     metadata,
                     sparse_values,
                     text,
+                    text_fields,
+                    dense_fields,
+                    multi_vector_values,
                 })
             }
         }
@@ -1,5 +1,8 @@
 use actix_web::{web, Scope};
 
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
+
 pub mod controller;
 pub(crate) mod dtos;
 pub(crate) mod error;
@@ -8,14 +11,28 @@ mod service;
 
 pub(crate) fn vectors_module() -> Scope {
     web::scope("/collections/{collection_id}/vectors")
-        .route("", web::get().to(controller::query_vectors))
-        .route("/{vector_id}", web::get().to(controller::get_vector_by_id))
+        .route(
+            "",
+            web::get()
+                .to(controller::query_vectors)
+                .wrap(AuthorizationMiddleware(Permission::ListVectors)),
+        )
+        .route(
+            "/{vector_id}",
+            web::get()
+                .to(controller::get_vector_by_id)
+                .wrap(AuthorizationMiddleware(Permission::ListVectors)),
+        )
         .route(
             "/{vector_id}",
-            web::head().to(controller::check_vector_existence),
+            web::head()
+                .to(controller::check_vector_existence)
+                .wrap(AuthorizationMiddleware(Permission::CheckVectorExistence)),
         )
         .route(
             "/{vector_id}/neighbors",
-            web::get().to(controller::fetch_vector_neighbors),
+            web::get()
+                .to(controller::fetch_vector_neighbors)
+                .wrap(AuthorizationMiddleware(Permission::ListVectors)),
         )
 }
@@ -1,5 +1,8 @@
 use actix_web::{web, Scope};
 
+use crate::api::auth::authorization_middleware::AuthorizationMiddleware;
+use crate::models::rbac::Permission;
+
 pub mod controller;
 pub(crate) mod dtos;
 mod error;
@@ -7,7 +10,17 @@ mod service;
 
 pub(crate) fn version_module() -> Scope {
     web::scope("/collections/{collection_id}/versions")
-        .route("", web::get().to(controller::list_versions))
-        .route("/current", web::get().to(controller::get_current_version))
+        .route(
+            "",
+            web::get()
+                .to(controller::list_versions)
+                .wrap(AuthorizationMiddleware(Permission::ListVersions)),
+        )
+        .route(
+            "/current",
+            web::get()
+                .to(controller::get_current_version)
+                .wrap(AuthorizationMiddleware(Permission::GetCurrentVersion)),
+        )
     // .route("/current", web::put().to(controller::set_current_version))
 }
@@ -3,7 +3,7 @@ use crate::indexes::hnsw::offset_counter::{HNSWIndexFileOffsetCounter, IndexFile
 use crate::indexes::hnsw::types::HNSWHyperParams;
 use crate::indexes::hnsw::{DenseInputEmbedding, HNSWIndex};
 use crate::indexes::inverted::InvertedIndex;
-use crate::indexes::tf_idf::TFIDFIndex;
+use crate::indexes::tf_idf::{analyzer::TextAnalyzerConfig, TFIDFIndex};
 use crate::indexes::IndexOps;
 use crate::metadata::{pseudo_level_probs, pseudo_node_vector, pseudo_root_id};
 use crate::models::buffered_io::{BufferManagerFactory, FilelessBufferManager};
@@ -21,7 +21,8 @@ use std::fs;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
-/// creates a dense index for a collection
+/// creates a dense index for a collection, or for one of its named dense
+/// fields
 #[allow(clippy::too_many_arguments)]
 pub async fn init_hnsw_index_for_collection(
     ctx: Arc<AppContext>,
@@ -33,10 +34,19 @@ pub async fn init_hnsw_index_for_collection(
     storage_type: StorageType,
     sample_threshold: usize,
     is_configured: bool,
+    field: Option<String>,
 ) -> Result<Arc<HNSWIndex>, WaCustomError> {
     let collection_name = &collection.meta.name;
+    let dimension = collection
+        .dense_dimension(field.as_deref())
+        .ok_or_else(|| {
+            WaCustomError::InvalidData(format!(
+                "Dense vector field not found: {}",
+                field.as_deref().unwrap_or_default()
+            ))
+        })?;
     let collection_path: Arc<Path> = collection.get_path();
-    let index_path = collection_path.join("dense_hnsw");
+    let index_path = HNSWIndex::index_path(&collection_path, field.as_deref());
     // ensuring that the index has a separate directory created inside the collection directory
     fs::create_dir_all(&index_path).map_err(|e| WaCustomError::FsError(e.to_string()))?;
 
@@ -77,7 +87,7 @@ pub async fn init_hnsw_index_for_collection(
         distance_metric.clone(),
     );
     if let Some(values_range) = values_range {
-        store_values_range(&lmdb, values_range).map_err(|e| {
+        store_values_range(&lmdb, field.as_deref(), values_range).map_err(|e| {
             WaCustomError::DatabaseError(format!("Failed to store values range to LMDB: {}", e))
         })?;
     }
@@ -91,7 +101,7 @@ pub async fn init_hnsw_index_for_collection(
     let root = create_root_node(
         &quantization_metric,
         storage_type,
-        collection.meta.dense_vector.dimension,
+        dimension,
         &cache.prop_file,
         *collection.current_version.read(),
         &offset_counter,
@@ -140,8 +150,7 @@ pub async fn init_hnsw_index_for_collection(
     // pseudo nodes can be created through the index's methods
     let pseudo_root = match &collection.meta.metadata_schema {
         Some(metadata_schema) => {
-            let num_dims = collection.meta.dense_vector.dimension;
-            let pseudo_vals = pseudo_node_vector(num_dims);
+            let pseudo_vals = pseudo_node_vector(dimension);
             let pseudo_root_id = pseudo_root_id();
             let node = create_pseudo_root_node(
                 &quantization_metric,
@@ -166,7 +175,7 @@ pub async fn init_hnsw_index_for_collection(
         root,
         pseudo_root,
         lp,
-        collection.meta.dense_vector.dimension,
+        dimension,
         quantization_metric,
         distance_metric,
         storage_type,
@@ -181,6 +190,7 @@ pub async fn init_hnsw_index_for_collection(
             .as_ref()
             .map_or(1, |schema| schema.max_num_replicas()),
         offset_counter,
+        field,
     ));
 
     ctx.ain_env
@@ -191,8 +201,7 @@ pub async fn init_hnsw_index_for_collection(
     // nodes to ensure that the query vectors with metadata dimensions
     // are reachable from the root node.
     if collection.meta.metadata_schema.is_some() {
-        let num_dims = collection.meta.dense_vector.dimension;
-        let pseudo_vals = pseudo_node_vector(num_dims);
+        let pseudo_vals = pseudo_node_vector(dimension);
         // base id for nonroot pseudo nodes is 1 more than the pseudo node
         let pseudo_vec = DenseInputEmbedding(pseudo_root_id(), pseudo_vals, None, true);
         let version_number = *collection.current_version.read();
@@ -237,12 +246,16 @@ pub async fn init_inverted_index_for_collection(
 }
 
 /// creates an inverted index for a collection
+#[allow(clippy::too_many_arguments)]
 pub async fn init_tf_idf_index_for_collection(
     ctx: Arc<AppContext>,
     collection: &Collection,
     sample_threshold: usize,
     k1: f32,
     b: f32,
+    analyzer: TextAnalyzerConfig,
+    store_positions: bool,
+    store_terms: bool,
 ) -> Result<Arc<TFIDFIndex>, WaCustomError> {
     let collection_path: Arc<Path> = collection.get_path();
     let index_path = collection_path.join("tf_idf_index");
@@ -253,6 +266,9 @@ pub async fn init_tf_idf_index_for_collection(
         sample_threshold,
         k1,
         b,
+        analyzer,
+        store_positions,
+        store_terms,
     )?);
 
     ctx.ain_env
@@ -1,4 +1,6 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use crate::args::CosdataArgs;
 use crate::config_loader::Config;
@@ -14,6 +16,13 @@ pub struct AppContext {
     pub threadpool: Arc<ThreadPool>,
     pub ain_env: Arc<AppEnv>,
     pub collection_cache_manager: Arc<CollectionCacheManager>,
+    pub started_at: Instant,
+    // Set once the restart recovery has indexed the versions left
+    // unindexed by the last shutdown
+    pub ready: Arc<AtomicBool>,
+    // Cause of the failure of the restart recovery, the node stays
+    // unready if set
+    pub recovery_error: Arc<RwLock<Option<String>>>,
 }
 
 impl AppContext {
@@ -38,13 +47,71 @@ impl AppContext {
             ain_env.clone(),
         ));
 
+        let ready = Arc::new(AtomicBool::new(false));
+        let recovery_error = Arc::new(RwLock::new(None));
+        spawn_restart_recovery(
+            ain_env.clone(),
+            config.clone(),
+            threadpool.clone(),
+            ready.clone(),
+            recovery_error.clone(),
+        );
+
         Ok(Self {
             config,
             ain_env,
             threadpool,
             collection_cache_manager,
+            started_at: Instant::now(),
+            ready,
+            recovery_error,
         })
     }
+
+    /// Whether the restart recovery has completed, the requests reading or
+    /// writing the vectors are rejected until then
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    /// Why the node isn't ready, `None` once the restart recovery has
+    /// completed
+    pub fn not_ready_reason(&self) -> Option<String> {
+        if self.is_ready() {
+            return None;
+        }
+        Some(match self.recovery_error.read().unwrap().as_deref() {
+            Some(error) => format!("The restart recovery failed: {}", error),
+            None => "The restart recovery is still running".to_string(),
+        })
+    }
+}
+
+// Runs the restart recovery in the background, so that the servers can
+// start and report their readiness in the meantime. If the recovery fails,
+// the node never becomes ready and reports the failure as the reason.
+fn spawn_restart_recovery(
+    ain_env: Arc<AppEnv>,
+    config: Arc<Config>,
+    threadpool: Arc<ThreadPool>,
+    ready: Arc<AtomicBool>,
+    recovery_error: Arc<RwLock<Option<String>>>,
+) {
+    std::thread::Builder::new()
+        .name("restart-recovery".to_string())
+        .spawn(move || {
+            match ain_env
+                .collections_map
+                .recover_unindexed_versions(&config, &threadpool)
+            {
+                Ok(()) => ready.store(true, Ordering::SeqCst),
+                Err(e) => {
+                    log::error!("Restart recovery failed: {}", e);
+                    *recovery_error.write().unwrap() = Some(e.to_string());
+                }
+            }
+        })
+        .expect("Failed to spawn the restart recovery thread");
 }
 
 use crate::models::collection_cache::CollectionCacheExt;
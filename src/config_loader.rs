@@ -10,6 +10,8 @@ pub struct Config {
     #[serde(default)]
     pub thread_pool: ThreadPool,
     pub server: Server,
+    #[serde(default)]
+    pub grpc: Grpc,
     pub hnsw: Hnsw,
     pub indexing: Indexing,
     pub search: Search,
@@ -156,6 +158,39 @@ impl Server {
     }
 }
 
+// The gRPC server shares the TLS settings of `Server`, only its bind
+// address is configured separately
+#[derive(Deserialize, Clone)]
+pub struct Grpc {
+    #[serde(default = "default_grpc_host")]
+    pub host: Host,
+    #[serde(default = "default_grpc_port")]
+    pub port: Port,
+}
+
+fn default_grpc_host() -> Host {
+    Host::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST))
+}
+
+fn default_grpc_port() -> Port {
+    Port(50051)
+}
+
+impl Default for Grpc {
+    fn default() -> Self {
+        Self {
+            host: default_grpc_host(),
+            port: default_grpc_port(),
+        }
+    }
+}
+
+impl Grpc {
+    pub fn listen_address(&self) -> HostPort {
+        HostPort(&self.host, &self.port)
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Hnsw {
     pub default_neighbors_count: usize,
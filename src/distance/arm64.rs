@@ -0,0 +1,237 @@
+use std::arch::aarch64::*;
+
+use super::asymmetric::CodeSums;
+
+#[target_feature(enable = "neon")]
+pub unsafe fn squared_euclidean_u8_neon(a: &[u8], b: &[u8]) -> u64 {
+    let len = a.len().min(b.len());
+
+    const CHUNK_SIZE: usize = 16;
+
+    let mut sum = vdupq_n_u64(0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        // Absolute differences, whose squares fit in u16
+        let diff = vabdq_u8(vld1q_u8(a.as_ptr().add(i)), vld1q_u8(b.as_ptr().add(i)));
+        let squares_low = vmull_u8(vget_low_u8(diff), vget_low_u8(diff));
+        let squares_high = vmull_u8(vget_high_u8(diff), vget_high_u8(diff));
+
+        // Pairwise sums widened to 64-bit accumulators
+        let squares = vaddq_u32(vpaddlq_u16(squares_low), vpaddlq_u16(squares_high));
+        sum = vpadalq_u32(sum, squares);
+
+        i += CHUNK_SIZE;
+    }
+
+    let mut result = vaddvq_u64(sum);
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        let diff = x as i64 - y as i64;
+        result += (diff * diff) as u64;
+    }
+    result
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn squared_euclidean_f32_neon(a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
+
+    const CHUNK_SIZE: usize = 4;
+
+    let mut sum = vdupq_n_f32(0.0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        let diff = vsubq_f32(vld1q_f32(a.as_ptr().add(i)), vld1q_f32(b.as_ptr().add(i)));
+        sum = vfmaq_f32(sum, diff, diff);
+        i += CHUNK_SIZE;
+    }
+
+    let mut result = vaddvq_f32(sum);
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        let diff = x - y;
+        result += diff * diff;
+    }
+    result
+}
+
+// Counts the differing bits of two u8 vectors
+#[target_feature(enable = "neon")]
+pub unsafe fn hamming_u8_neon(a: &[u8], b: &[u8]) -> u32 {
+    let len = a.len().min(b.len());
+
+    const CHUNK_SIZE: usize = 16;
+
+    let mut sum = vdupq_n_u32(0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        let diff = veorq_u8(vld1q_u8(a.as_ptr().add(i)), vld1q_u8(b.as_ptr().add(i)));
+        sum = vpadalq_u16(sum, vpaddlq_u8(vcntq_u8(diff)));
+        i += CHUNK_SIZE;
+    }
+
+    let mut result = vaddvq_u32(sum);
+    for (x, y) in a[i..len].iter().zip(&b[i..len]) {
+        result += (x ^ y).count_ones();
+    }
+    result
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn hamming_f32_neon(a: &[f32], b: &[f32]) -> u32 {
+    let len = a.len().min(b.len());
+
+    const CHUNK_SIZE: usize = 4;
+
+    let mut sum = vdupq_n_u32(0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        // NaNs never compare equal, like with `!=`
+        let equal = vceqq_f32(vld1q_f32(a.as_ptr().add(i)), vld1q_f32(b.as_ptr().add(i)));
+        sum = vaddq_u32(sum, vshrq_n_u32::<31>(vmvnq_u32(equal)));
+        i += CHUNK_SIZE;
+    }
+
+    let mut result = vaddvq_u32(sum);
+    for (x, y) in a[i..len].iter().zip(&b[i..len]) {
+        result += (x != y) as u32;
+    }
+    result
+}
+
+// Counts the positions whose bits differ in any of the bit planes of
+// two sub-byte quantized vectors
+#[target_feature(enable = "neon")]
+pub unsafe fn hamming_bit_planes_neon<T: AsRef<[u8]>>(x_vec: &[T], y_vec: &[T]) -> u32 {
+    let len = x_vec
+        .iter()
+        .chain(y_vec)
+        .map(|plane| plane.as_ref().len())
+        .min()
+        .unwrap_or(0);
+
+    const CHUNK_SIZE: usize = 16;
+
+    let mut sum = vdupq_n_u32(0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        let mut diff = vdupq_n_u8(0);
+        for (x, y) in x_vec.iter().zip(y_vec) {
+            let x = vld1q_u8(x.as_ref().as_ptr().add(i));
+            let y = vld1q_u8(y.as_ref().as_ptr().add(i));
+            diff = vorrq_u8(diff, veorq_u8(x, y));
+        }
+        sum = vpadalq_u16(sum, vpaddlq_u8(vcntq_u8(diff)));
+        i += CHUNK_SIZE;
+    }
+
+    let mut result = vaddvq_u32(sum);
+    for j in i..len {
+        let diff = x_vec
+            .iter()
+            .zip(y_vec)
+            .fold(0u8, |diff, (x, y)| diff | (x.as_ref()[j] ^ y.as_ref()[j]));
+        result += diff.count_ones();
+    }
+    result
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn code_sums_u8_f32_neon(query: &[f32], codes: &[u8]) -> CodeSums {
+    let len = query.len().min(codes.len());
+
+    const CHUNK_SIZE: usize = 8;
+
+    let mut query_dot_codes = vdupq_n_f32(0.0);
+    let mut query_sum = vdupq_n_f32(0.0);
+    let mut codes_sum = vdupq_n_f32(0.0);
+    let mut codes_sqr_sum = vdupq_n_f32(0.0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        // Widens 8 codes to single precision floats
+        let n = vmovl_u8(vld1_u8(codes.as_ptr().add(i)));
+        let n_low = vcvtq_f32_u32(vmovl_u16(vget_low_u16(n)));
+        let n_high = vcvtq_f32_u32(vmovl_u16(vget_high_u16(n)));
+        let q_low = vld1q_f32(query.as_ptr().add(i));
+        let q_high = vld1q_f32(query.as_ptr().add(i + 4));
+
+        query_dot_codes = vfmaq_f32(vfmaq_f32(query_dot_codes, q_low, n_low), q_high, n_high);
+        query_sum = vaddq_f32(query_sum, vaddq_f32(q_low, q_high));
+        codes_sum = vaddq_f32(codes_sum, vaddq_f32(n_low, n_high));
+        codes_sqr_sum = vfmaq_f32(vfmaq_f32(codes_sqr_sum, n_low, n_low), n_high, n_high);
+
+        i += CHUNK_SIZE;
+    }
+
+    let mut sums = CodeSums {
+        query_dot_codes: vaddvq_f32(query_dot_codes),
+        query_sum: vaddvq_f32(query_sum),
+        codes_sum: vaddvq_f32(codes_sum),
+        codes_sqr_sum: vaddvq_f32(codes_sqr_sum),
+    };
+    for (&q, &n) in query[i..len].iter().zip(&codes[i..len]) {
+        let n = n as f32;
+        sums.query_dot_codes += q * n;
+        sums.query_sum += q;
+        sums.codes_sum += n;
+        sums.codes_sqr_sum += n * n;
+    }
+    sums
+}
+
+// Sums the query values whose bit is set for every bit plane into
+// `masked_sums` and returns the sum of all the query values
+#[target_feature(enable = "neon")]
+pub unsafe fn masked_query_sums_f32_neon(
+    query: &[f32],
+    planes: &[Vec<u8>],
+    masked_sums: &mut [f32; 8],
+) -> f32 {
+    let len = query.len();
+
+    const CHUNK_SIZE: usize = 8;
+
+    // Bit of every lane in a byte of a bit plane
+    let lane_bits_low = vld1q_u32([1u32, 2, 4, 8].as_ptr());
+    let lane_bits_high = vld1q_u32([16u32, 32, 64, 128].as_ptr());
+    let mut masked = [vdupq_n_f32(0.0); 8];
+    let mut query_sum = vdupq_n_f32(0.0);
+    let mut i = 0;
+
+    while i + CHUNK_SIZE <= len {
+        let q_low = vreinterpretq_u32_f32(vld1q_f32(query.as_ptr().add(i)));
+        let q_high = vreinterpretq_u32_f32(vld1q_f32(query.as_ptr().add(i + 4)));
+        query_sum = vaddq_f32(
+            query_sum,
+            vaddq_f32(vreinterpretq_f32_u32(q_low), vreinterpretq_f32_u32(q_high)),
+        );
+        for (plane, acc) in planes.iter().zip(masked.iter_mut()) {
+            let byte = vdupq_n_u32(plane[i / 8] as u32);
+            let low = vandq_u32(q_low, vtstq_u32(byte, lane_bits_low));
+            let high = vandq_u32(q_high, vtstq_u32(byte, lane_bits_high));
+            *acc = vaddq_f32(
+                *acc,
+                vaddq_f32(vreinterpretq_f32_u32(low), vreinterpretq_f32_u32(high)),
+            );
+        }
+        i += CHUNK_SIZE;
+    }
+
+    for (sum, acc) in masked_sums.iter_mut().zip(masked) {
+        *sum = vaddvq_f32(acc);
+    }
+    let mut result = vaddvq_f32(query_sum);
+    for (j, &q) in query.iter().enumerate().skip(i) {
+        result += q;
+        for (plane, sum) in planes.iter().zip(masked_sums.iter_mut()) {
+            if (plane[j / 8] >> (j % 8)) & 1 == 1 {
+                *sum += q;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,419 @@
+use half::f16;
+
+#[cfg(target_arch = "aarch64")]
+use super::arm64;
+#[cfg(target_arch = "x86_64")]
+use super::x86_64;
+use super::DistanceError;
+use crate::storage::Storage;
+
+// Asymmetric distances score a query kept in full or half precision
+// directly against the codes of `UnsignedByte` and `SubByte` stored
+// vectors. Every code `n` is de-quantized on the fly to the value `c +
+// s * n`, the center of the interval it was quantized from, which keeps
+// the precision of the query that would be lost by quantizing it like
+// the stored vectors.
+//
+// The kernels only compute sums over the codes, which are combined
+// with the de-quantization parameters afterwards.
+
+/// Sums over the dimensions of a query and the codes of a stored vector
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CodeSums {
+    pub query_dot_codes: f32,
+    pub query_sum: f32,
+    pub codes_sum: f32,
+    pub codes_sqr_sum: f32,
+}
+
+/// Dot product of a query with a de-quantized stored vector and their
+/// squared magnitudes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsymmetricSums {
+    pub dot_product: f32,
+    pub query_mag_sqr: f32,
+    pub stored_mag_sqr: f32,
+}
+
+// Query values of an asymmetric distance computation
+#[derive(Clone, Copy)]
+enum QueryValues<'a> {
+    F32(&'a [f32]),
+    F16(&'a [f16]),
+}
+
+impl QueryValues<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::F32(values) => values.len(),
+            Self::F16(values) => values.len(),
+        }
+    }
+}
+
+/// Computes the sums needed by the asymmetric distances if `query` is a
+/// full or half precision query and `stored` a `UnsignedByte` or
+/// `SubByte` stored vector, `None` otherwise.
+///
+/// `values_range` is the range the `UnsignedByte` vectors were
+/// quantized with. Sub-byte vectors are always quantized over `[-1,
+/// 1]`.
+pub fn asymmetric_sums(
+    query: &Storage,
+    stored: &Storage,
+    values_range: Option<(f32, f32)>,
+) -> Result<Option<AsymmetricSums>, DistanceError> {
+    let (query_values, query_mag) = match query {
+        Storage::FullPrecisionFP { mag, vec } => (QueryValues::F32(vec), *mag),
+        Storage::HalfPrecisionFP { mag, quant_vec } => (QueryValues::F16(quant_vec), *mag),
+        _ => return Ok(None),
+    };
+
+    let (sums, offset, scale) = match stored {
+        Storage::UnsignedByte { quant_vec, .. } => {
+            if quant_vec.len() != query_values.len() {
+                return Err(DistanceError::StorageMismatch);
+            }
+            let (min, max) = values_range.ok_or(DistanceError::CalculationError)?;
+            let scale = (max - min) / 255.0;
+            (
+                code_sums_u8(query_values, quant_vec),
+                min + 0.5 * scale,
+                scale,
+            )
+        }
+        Storage::SubByte {
+            quant_vec,
+            resolution,
+            ..
+        } => {
+            if *resolution == 0 || *resolution > 8 || quant_vec.len() != *resolution as usize {
+                return Err(DistanceError::CalculationError);
+            }
+            if quant_vec
+                .iter()
+                .any(|plane| plane.len() != query_values.len().div_ceil(8))
+            {
+                return Err(DistanceError::StorageMismatch);
+            }
+            let scale = 2.0 / (1u32 << resolution) as f32;
+            (
+                code_sums_subbyte(query_values, quant_vec),
+                -1.0 + 0.5 * scale,
+                scale,
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    let dim = query_values.len() as f32;
+    Ok(Some(AsymmetricSums {
+        dot_product: offset * sums.query_sum + scale * sums.query_dot_codes,
+        query_mag_sqr: query_mag * query_mag,
+        stored_mag_sqr: dim * offset * offset
+            + 2.0 * offset * scale * sums.codes_sum
+            + scale * scale * sums.codes_sqr_sum,
+    }))
+}
+
+fn code_sums_u8(query: QueryValues, codes: &[u8]) -> CodeSums {
+    match query {
+        QueryValues::F32(query) => code_sums_u8_f32(query, codes),
+        QueryValues::F16(query) => code_sums_u8_f16(query, codes),
+    }
+}
+
+// Sums over the levels of the values of a sub-byte vector, where bit
+// plane `b` of `r` planes holds the bits of weight `2^(r - 1 - b)`
+fn code_sums_subbyte(query: QueryValues, planes: &[Vec<u8>]) -> CodeSums {
+    let resolution = planes.len();
+    let weight = |plane: usize| (1u32 << (resolution - 1 - plane)) as f32;
+
+    let mut masked_sums = [0f32; 8];
+    let query_sum = match query {
+        QueryValues::F32(query) => masked_query_sums_f32(query, planes, &mut masked_sums),
+        QueryValues::F16(query) => masked_query_sums_f16(query, planes, &mut masked_sums),
+    };
+
+    let mut sums = CodeSums {
+        query_sum,
+        ..Default::default()
+    };
+    for (b, (plane_b, masked_sum)) in planes.iter().zip(masked_sums).enumerate() {
+        sums.query_dot_codes += weight(b) * masked_sum;
+        for (c, plane_c) in planes.iter().enumerate() {
+            let count = plane_b
+                .iter()
+                .zip(plane_c)
+                .map(|(&x, &y)| (x & y).count_ones())
+                .sum::<u32>() as f32;
+            if b == c {
+                sums.codes_sum += weight(b) * count;
+            }
+            sums.codes_sqr_sum += weight(b) * weight(c) * count;
+        }
+    }
+    sums
+}
+
+fn code_sums_u8_scalar<T: Copy + Into<f32>>(query: &[T], codes: &[u8]) -> CodeSums {
+    query
+        .iter()
+        .zip(codes)
+        .fold(CodeSums::default(), |mut sums, (&q, &n)| {
+            let (q, n) = (q.into(), n as f32);
+            sums.query_dot_codes += q * n;
+            sums.query_sum += q;
+            sums.codes_sum += n;
+            sums.codes_sqr_sum += n * n;
+            sums
+        })
+}
+
+// Sums the query values whose bit is set for every bit plane into
+// `masked_sums` and returns the sum of all the query values
+fn masked_query_sums_scalar<T: Copy + Into<f32>>(
+    query: &[T],
+    planes: &[Vec<u8>],
+    masked_sums: &mut [f32; 8],
+) -> f32 {
+    let mut query_sum = 0.0;
+    for (i, &q) in query.iter().enumerate() {
+        let q = q.into();
+        query_sum += q;
+        for (plane, sum) in planes.iter().zip(masked_sums.iter_mut()) {
+            if (plane[i / 8] >> (i % 8)) & 1 == 1 {
+                *sum += q;
+            }
+        }
+    }
+    query_sum
+}
+
+fn code_sums_u8_f32(query: &[f32], codes: &[u8]) -> CodeSums {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return unsafe { x86_64::code_sums_u8_f32_avx2(query, codes) };
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return unsafe { arm64::code_sums_u8_f32_neon(query, codes) };
+        }
+    }
+
+    code_sums_u8_scalar(query, codes)
+}
+
+fn code_sums_u8_f16(query: &[f16], codes: &[u8]) -> CodeSums {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("fma")
+            && is_x86_feature_detected!("f16c")
+        {
+            return unsafe { x86_64::code_sums_u8_f16_f16c(query, codes) };
+        }
+    }
+
+    code_sums_u8_scalar(query, codes)
+}
+
+fn masked_query_sums_f32(query: &[f32], planes: &[Vec<u8>], masked_sums: &mut [f32; 8]) -> f32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return unsafe { x86_64::masked_query_sums_f32_avx2(query, planes, masked_sums) };
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return unsafe { arm64::masked_query_sums_f32_neon(query, planes, masked_sums) };
+        }
+    }
+
+    masked_query_sums_scalar(query, planes, masked_sums)
+}
+
+fn masked_query_sums_f16(query: &[f16], planes: &[Vec<u8>], masked_sums: &mut [f32; 8]) -> f32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("fma")
+            && is_x86_feature_detected!("f16c")
+        {
+            return unsafe { x86_64::masked_query_sums_f16_f16c(query, planes, masked_sums) };
+        }
+    }
+
+    masked_query_sums_scalar(query, planes, masked_sums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::quantize_to_u8_bits;
+    use quickcheck_macros::quickcheck;
+    use rand::Rng;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-3 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    fn full_precision(vec: &[f32]) -> Storage {
+        Storage::FullPrecisionFP {
+            mag: vec.iter().map(|x| x * x).sum::<f32>().sqrt(),
+            vec: vec.to_vec(),
+        }
+    }
+
+    fn half_precision(vec: &[f32]) -> Storage {
+        let quant_vec: Vec<f16> = vec.iter().map(|&x| f16::from_f32(x)).collect();
+        Storage::HalfPrecisionFP {
+            mag: quant_vec
+                .iter()
+                .map(|&x| f32::from(x) * f32::from(x))
+                .sum::<f32>()
+                .sqrt(),
+            quant_vec,
+        }
+    }
+
+    // Sums of a query and an explicitly de-quantized stored vector
+    fn reference_sums(query: &[f32], stored: &[f32]) -> AsymmetricSums {
+        AsymmetricSums {
+            dot_product: query.iter().zip(stored).map(|(q, s)| q * s).sum(),
+            query_mag_sqr: query.iter().map(|q| q * q).sum(),
+            stored_mag_sqr: stored.iter().map(|s| s * s).sum(),
+        }
+    }
+
+    fn sums_approx_eq(actual: AsymmetricSums, expected: AsymmetricSums) -> bool {
+        // The dot product is compared relative to the magnitudes, as it
+        // cancels out for orthogonal vectors
+        let mags = (expected.query_mag_sqr * expected.stored_mag_sqr).sqrt();
+        (actual.dot_product - expected.dot_product).abs() <= 1e-4 * mags.max(1.0)
+            && approx_eq(actual.query_mag_sqr, expected.query_mag_sqr)
+            && approx_eq(actual.stored_mag_sqr, expected.stored_mag_sqr)
+    }
+
+    fn check_u8(query: &[f32], codes: &[u8], range: (f32, f32)) -> bool {
+        let scale = (range.1 - range.0) / 255.0;
+        let stored = Storage::UnsignedByte {
+            mag: 0.0,
+            quant_vec: codes.to_vec(),
+        };
+        let dequantized: Vec<f32> = codes
+            .iter()
+            .map(|&n| range.0 + (n as f32 + 0.5) * scale)
+            .collect();
+
+        let half_query: Vec<f32> = query.iter().map(|&x| f16::from_f32(x).to_f32()).collect();
+        [
+            (full_precision(query), query.to_vec()),
+            (half_precision(query), half_query),
+        ]
+        .iter()
+        .all(|(query_storage, query)| {
+            let actual = asymmetric_sums(query_storage, &stored, Some(range))
+                .unwrap()
+                .unwrap();
+            sums_approx_eq(actual, reference_sums(query, &dequantized))
+        })
+    }
+
+    fn check_subbyte(query: &[f32], values: &[f32], resolution: u8) -> bool {
+        let planes = quantize_to_u8_bits(values, resolution);
+        let scale = 2.0 / (1u32 << resolution) as f32;
+        // Bit plane 0 holds the most significant bit of the levels
+        let dequantized: Vec<f32> = (0..values.len())
+            .map(|i| {
+                let level = planes.iter().fold(0u32, |level, plane| {
+                    (level << 1) | ((plane[i / 8] >> (i % 8)) & 1) as u32
+                });
+                -1.0 + (level as f32 + 0.5) * scale
+            })
+            .collect();
+        let stored = Storage::SubByte {
+            mag: 0.0,
+            quant_vec: planes,
+            resolution,
+        };
+
+        let half_query: Vec<f32> = query.iter().map(|&x| f16::from_f32(x).to_f32()).collect();
+        [
+            (full_precision(query), query.to_vec()),
+            (half_precision(query), half_query),
+        ]
+        .iter()
+        .all(|(query_storage, query)| {
+            let actual = asymmetric_sums(query_storage, &stored, None)
+                .unwrap()
+                .unwrap();
+            sums_approx_eq(actual, reference_sums(query, &dequantized))
+        })
+    }
+
+    #[quickcheck]
+    fn prop_asymmetric_u8_matches_dequantized(values: Vec<(i16, u8)>) -> bool {
+        let (query, codes): (Vec<f32>, Vec<u8>) = values
+            .into_iter()
+            .map(|(q, n)| (q as f32 / 32768.0, n))
+            .unzip();
+        check_u8(&query, &codes, (-1.0, 1.0)) && check_u8(&query, &codes, (-0.25, 3.5))
+    }
+
+    #[quickcheck]
+    fn prop_asymmetric_subbyte_matches_dequantized(
+        values: Vec<(i16, i16)>,
+        resolution: u8,
+    ) -> bool {
+        let resolution = resolution % 3 + 1;
+        let (query, stored): (Vec<f32>, Vec<f32>) = values
+            .into_iter()
+            .map(|(q, s)| (q as f32 / 32768.0, s as f32 / 32768.0))
+            .unzip();
+        check_subbyte(&query, &stored, resolution)
+    }
+
+    #[test]
+    fn test_asymmetric_long_vectors() {
+        let mut rng = rand::thread_rng();
+        for size in [255, 256, 257, 1000, 1536] {
+            let query: Vec<f32> = (0..size).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let codes: Vec<u8> = (0..size).map(|_| rng.gen()).collect();
+            assert!(check_u8(&query, &codes, (-1.0, 1.0)), "size {}", size);
+
+            let values: Vec<f32> = (0..size).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            for resolution in 1..=3 {
+                assert!(
+                    check_subbyte(&query, &values, resolution),
+                    "size {} resolution {}",
+                    size,
+                    resolution
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_asymmetric_only_for_unquantized_queries() {
+        let stored = Storage::UnsignedByte {
+            mag: 0.0,
+            quant_vec: vec![1, 2, 3],
+        };
+        let query = full_precision(&[0.1, 0.2, 0.3]);
+        assert!(asymmetric_sums(&stored, &stored, Some((0.0, 1.0)))
+            .unwrap()
+            .is_none());
+        assert!(asymmetric_sums(&query, &query, None).unwrap().is_none());
+        // The range the stored vectors were quantized with is required
+        assert!(asymmetric_sums(&query, &stored, None).is_err());
+        assert!(asymmetric_sums(&full_precision(&[0.1]), &stored, Some((0.0, 1.0))).is_err());
+    }
+}
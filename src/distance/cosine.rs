@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::{DistanceError, DistanceFunction};
+use super::{
+    asymmetric::asymmetric_sums, hamming::hamming_distance_binary, DistanceError, DistanceFunction,
+};
 use crate::{
     models::{
         dot_product::{
@@ -19,12 +21,12 @@ impl DistanceFunction for CosineDistance {
     type Item = Self;
     fn calculate(
         &self,
-        _x: &VectorData,
-        _y: &VectorData,
-        _is_indexing: bool,
+        x: &VectorData,
+        y: &VectorData,
+        is_indexing: bool,
     ) -> Result<Self::Item, DistanceError> {
-        // TODO: Implement cosine distance
-        unimplemented!("Cosine distance is not implemented yet");
+        let CosineSimilarity(similarity) = CosineSimilarity(0.0).calculate(x, y, is_indexing)?;
+        Ok(CosineDistance(1.0 - similarity))
     }
 }
 
@@ -70,7 +72,7 @@ impl DistanceFunction for CosineSimilarity {
                 unreachable!()
             }
             (ReplicaNodeKind::Base, ReplicaNodeKind::Base) => {
-                cosine_similarity(x.quantized_vec, y.quantized_vec, None)?
+                cosine_similarity(x.quantized_vec, y.quantized_vec, None, x.values_range)?
             }
             (ReplicaNodeKind::Metadata, ReplicaNodeKind::Metadata) => {
                 // Safe use of unwrap as metadata nodes will
@@ -82,7 +84,7 @@ impl DistanceFunction for CosineSimilarity {
                 // quantized vector values.
                 let mdims_cosim = cosine_similarity_mdims(x_metadata, y_metadata)?;
                 if mdims_cosim.0 > 0.99 {
-                    cosine_similarity(x.quantized_vec, y.quantized_vec, None)?
+                    cosine_similarity(x.quantized_vec, y.quantized_vec, None, x.values_range)?
                 } else {
                     CosineSimilarity(-1.0)
                 }
@@ -105,7 +107,15 @@ fn cosine_similarity(
     x_quantized: &Storage,
     y_quantized: &Storage,
     m_dot_product: Option<(f32, f32, f32)>,
+    values_range: Option<(f32, f32)>,
 ) -> Result<CosineSimilarity, DistanceError> {
+    if let Some(sums) = asymmetric_sums(x_quantized, y_quantized, values_range)? {
+        return cosine_similarity_from_dot_product(
+            sums.dot_product,
+            sums.query_mag_sqr.sqrt(),
+            sums.stored_mag_sqr.sqrt(),
+        );
+    }
     match (x_quantized, y_quantized) {
         (
             Storage::UnsignedByte {
@@ -145,6 +155,9 @@ fn cosine_similarity(
             if x_res != y_res {
                 return Err(DistanceError::StorageMismatch);
             }
+            if *x_res == 1 && m_dot_product.is_none() {
+                return Ok(binary_cosine_similarity(&x_vec[0], &y_vec[0]));
+            }
             let dot_product = match *x_res {
                 1 => dot_product_binary(x_vec, y_vec, *x_res),
                 2 => dot_product_quaternary(x_vec, y_vec, *x_res),
@@ -234,6 +247,19 @@ fn cosine_similarity_from_dot_product(
     }
 }
 
+// Calculates the cosine similarity of the sign vectors of two 1-bit
+// quantized vectors from their hamming distance, `1 - 2h/n`. The zero
+// padding bits match in both vectors, so the bias is the same for all
+// pairs of equal dimension and doesn't change the ordering.
+fn binary_cosine_similarity(x: &[u8], y: &[u8]) -> CosineSimilarity {
+    let bits = (x.len() * 8) as f32;
+    if bits == 0.0 {
+        return CosineSimilarity(0.0);
+    }
+    let distance = hamming_distance_binary(x, y) as f32;
+    CosineSimilarity(1.0 - 2.0 * distance / bits)
+}
+
 // Calculates cosine similarity for metadata dimensions only
 //
 // Returns `DistanceError` if either maginitudes are equal to 0. This
@@ -1,6 +1,7 @@
-use super::{DistanceError, DistanceFunction};
+use super::{asymmetric::asymmetric_sums, DistanceError, DistanceFunction};
 use crate::models::dot_product::{
-    dot_product_binary, dot_product_f16, dot_product_octal, dot_product_quaternary, dot_product_u8,
+    dot_product_binary, dot_product_f16, dot_product_f32, dot_product_octal,
+    dot_product_quaternary, dot_product_u8,
 };
 use crate::models::types::VectorData;
 use crate::storage::Storage;
@@ -17,6 +18,9 @@ impl DistanceFunction for DotProductDistance {
         y: &VectorData,
         _is_indexing: bool,
     ) -> Result<Self::Item, DistanceError> {
+        if let Some(sums) = asymmetric_sums(x.quantized_vec, y.quantized_vec, x.values_range)? {
+            return Ok(DotProductDistance(sums.dot_product));
+        }
         match (x.quantized_vec, y.quantized_vec) {
             (
                 Storage::UnsignedByte {
@@ -59,6 +63,10 @@ impl DistanceFunction for DotProductDistance {
                 };
                 Ok(DotProductDistance(dot_product))
             }
+            (
+                Storage::FullPrecisionFP { vec: vec_x, .. },
+                Storage::FullPrecisionFP { vec: vec_y, .. },
+            ) => Ok(DotProductDistance(dot_product_f32(vec_x, vec_y))),
             _ => Err(DistanceError::StorageMismatch),
         }
     }
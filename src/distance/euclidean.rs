@@ -3,6 +3,12 @@ use crate::{models::types::VectorData, storage::Storage};
 use half::f16;
 use serde::{Deserialize, Serialize};
 
+#[cfg(target_arch = "aarch64")]
+use super::arm64;
+#[cfg(target_arch = "x86_64")]
+use super::x86_64;
+use super::{asymmetric::asymmetric_sums, hamming::hamming_distance_binary};
+
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize, PartialOrd)]
 pub struct EuclideanDistance(pub f32);
 
@@ -14,6 +20,10 @@ impl DistanceFunction for EuclideanDistance {
         y: &VectorData,
         _is_indexing: bool,
     ) -> Result<Self::Item, DistanceError> {
+        if let Some(sums) = asymmetric_sums(x.quantized_vec, y.quantized_vec, x.values_range)? {
+            let squared = sums.query_mag_sqr + sums.stored_mag_sqr - 2.0 * sums.dot_product;
+            return Ok(EuclideanDistance(squared.max(0.0).sqrt()));
+        }
         match (x.quantized_vec, y.quantized_vec) {
             (
                 Storage::UnsignedByte {
@@ -31,36 +41,272 @@ impl DistanceFunction for EuclideanDistance {
                     quant_vec: vec_y, ..
                 },
             ) => Ok(euclidean_distance_f16(vec_x, vec_y)),
-            (Storage::SubByte { .. }, Storage::SubByte { .. }) => {
-                // TODO: Implement euclidean distance for SubByte storage
-                unimplemented!("Euclidean distance for SubByte is not implemented yet");
+            (
+                Storage::SubByte {
+                    quant_vec: vec_x,
+                    resolution: res_x,
+                    ..
+                },
+                Storage::SubByte {
+                    quant_vec: vec_y,
+                    resolution: res_y,
+                    ..
+                },
+            ) => {
+                if res_x != res_y {
+                    return Err(DistanceError::StorageMismatch);
+                }
+                euclidean_distance_subbyte(vec_x, vec_y, *res_x)
             }
+            (
+                Storage::FullPrecisionFP { vec: vec_x, .. },
+                Storage::FullPrecisionFP { vec: vec_y, .. },
+            ) => Ok(euclidean_distance_f32(vec_x, vec_y)),
             _ => Err(DistanceError::StorageMismatch),
         }
     }
 }
+
+fn squared_euclidean_u8_scalar(x: &[u8], y: &[u8]) -> u64 {
+    x.iter()
+        .zip(y.iter())
+        .map(|(&a, &b)| {
+            let diff = (a as i64) - (b as i64);
+            (diff * diff) as u64
+        })
+        .sum()
+}
+
+fn squared_euclidean_f16_scalar(x: &[f16], y: &[f16]) -> f32 {
+    x.iter()
+        .zip(y.iter())
+        .map(|(&a, &b)| {
+            let diff = f32::from(a) - f32::from(b);
+            diff * diff
+        })
+        .sum()
+}
+
+fn squared_euclidean_f32_scalar(x: &[f32], y: &[f32]) -> f32 {
+    x.iter()
+        .zip(y.iter())
+        .map(|(&a, &b)| {
+            let diff = a - b;
+            diff * diff
+        })
+        .sum()
+}
+
 pub fn euclidean_distance_u8(x: &[u8], y: &[u8]) -> EuclideanDistance {
-    EuclideanDistance(
-        x.iter()
-            .zip(y.iter())
-            .map(|(&a, &b)| {
-                let diff = (a as i16) - (b as i16);
-                (diff * diff) as f32
-            })
-            .sum::<f32>()
-            .sqrt(),
-    )
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            let squared = unsafe { x86_64::squared_euclidean_u8_avx2(x, y) };
+            return EuclideanDistance((squared as f32).sqrt());
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            let squared = unsafe { arm64::squared_euclidean_u8_neon(x, y) };
+            return EuclideanDistance((squared as f32).sqrt());
+        }
+    }
+
+    EuclideanDistance((squared_euclidean_u8_scalar(x, y) as f32).sqrt())
 }
 
 pub fn euclidean_distance_f16(x: &[f16], y: &[f16]) -> EuclideanDistance {
-    EuclideanDistance(
-        x.iter()
-            .zip(y.iter())
-            .map(|(&a, &b)| {
-                let diff = f32::from(a) - f32::from(b);
-                diff * diff
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("fma")
+            && is_x86_feature_detected!("f16c")
+        {
+            let squared = unsafe { x86_64::squared_euclidean_f16_f16c(x, y) };
+            return EuclideanDistance(squared.sqrt());
+        }
+    }
+
+    EuclideanDistance(squared_euclidean_f16_scalar(x, y).sqrt())
+}
+
+pub fn euclidean_distance_f32(x: &[f32], y: &[f32]) -> EuclideanDistance {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            let squared = unsafe { x86_64::squared_euclidean_f32_avx2(x, y) };
+            return EuclideanDistance(squared.sqrt());
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            let squared = unsafe { arm64::squared_euclidean_f32_neon(x, y) };
+            return EuclideanDistance(squared.sqrt());
+        }
+    }
+
+    EuclideanDistance(squared_euclidean_f32_scalar(x, y).sqrt())
+}
+
+// Calculates the euclidean distance between the quantized levels of two
+// sub-byte vectors. For 1-bit vectors the squared distance is the
+// number of differing bits, otherwise it's expanded to `x.x + y.y -
+// 2x.y` over the bit-plane dot products.
+//
+// Returns `DistanceError` for resolutions other than 1, 2 and 3.
+pub fn euclidean_distance_subbyte(
+    x: &[Vec<u8>],
+    y: &[Vec<u8>],
+    resolution: u8,
+) -> Result<EuclideanDistance, DistanceError> {
+    match resolution {
+        1 => {
+            let squared = hamming_distance_binary(&x[0], &y[0]) as f32;
+            Ok(EuclideanDistance(squared.sqrt()))
+        }
+        2 | 3 => {
+            let squared = subbyte_dot_product(x, x) + subbyte_dot_product(y, y)
+                - 2 * subbyte_dot_product(x, y);
+            Ok(EuclideanDistance((squared as f32).sqrt()))
+        }
+        _ => Err(DistanceError::CalculationError),
+    }
+}
+
+// Calculates the dot product of the quantized levels of two sub-byte
+// vectors from the popcounts of each pair of bit planes. The planes are
+// packed most significant first by `quantize_to_u8_bits`, so plane `i`
+// of `n` weighs `2^(n - 1 - i)`.
+fn subbyte_dot_product(x: &[Vec<u8>], y: &[Vec<u8>]) -> i64 {
+    let mut dot_product = 0;
+    for (i, x_plane) in x.iter().enumerate() {
+        for (j, y_plane) in y.iter().enumerate() {
+            let count: u32 = x_plane
+                .iter()
+                .zip(y_plane)
+                .map(|(&a, &b)| (a & b).count_ones())
+                .sum();
+            let shift = (x.len() - 1 - i) + (y.len() - 1 - j);
+            dot_product += (count as i64) << shift;
+        }
+    }
+    dot_product
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::quantize_to_u8_bits;
+    use quickcheck_macros::quickcheck;
+    use rand::Rng;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-4 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    // Quantized levels of the values of a vector packed with
+    // `quantize_to_u8_bits`, most significant plane first
+    fn subbyte_levels(planes: &[Vec<u8>], len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                planes.iter().fold(0u8, |level, plane| {
+                    (level << 1) | ((plane[i / 8] >> (i % 8)) & 1)
+                })
             })
-            .sum::<f32>()
-            .sqrt(),
-    )
+            .collect()
+    }
+
+    fn subbyte_reference(x: &[f32], y: &[f32], resolution: u8) -> (f32, f32) {
+        let x_planes = quantize_to_u8_bits(x, resolution);
+        let y_planes = quantize_to_u8_bits(y, resolution);
+        let x_levels = subbyte_levels(&x_planes, x.len());
+        let y_levels = subbyte_levels(&y_planes, y.len());
+        let expected = (squared_euclidean_u8_scalar(&x_levels, &y_levels) as f32).sqrt();
+        let actual = euclidean_distance_subbyte(&x_planes, &y_planes, resolution)
+            .unwrap()
+            .0;
+        (actual, expected)
+    }
+
+    #[quickcheck]
+    fn prop_euclidean_u8_matches_scalar(pairs: Vec<(u8, u8)>) -> bool {
+        let (x, y): (Vec<u8>, Vec<u8>) = pairs.into_iter().unzip();
+        let expected = (squared_euclidean_u8_scalar(&x, &y) as f32).sqrt();
+        euclidean_distance_u8(&x, &y).0 == expected
+    }
+
+    #[quickcheck]
+    fn prop_euclidean_f16_matches_scalar(pairs: Vec<(i16, i16)>) -> bool {
+        let (x, y): (Vec<f16>, Vec<f16>) = pairs
+            .into_iter()
+            .map(|(a, b)| {
+                (
+                    f16::from_f32(a as f32 / 256.0),
+                    f16::from_f32(b as f32 / 256.0),
+                )
+            })
+            .unzip();
+        let expected = squared_euclidean_f16_scalar(&x, &y).sqrt();
+        approx_eq(euclidean_distance_f16(&x, &y).0, expected)
+    }
+
+    #[quickcheck]
+    fn prop_euclidean_f32_matches_scalar(pairs: Vec<(i16, i16)>) -> bool {
+        let (x, y): (Vec<f32>, Vec<f32>) = pairs
+            .into_iter()
+            .map(|(a, b)| (a as f32 / 1024.0, b as f32 / 1024.0))
+            .unzip();
+        let expected = squared_euclidean_f32_scalar(&x, &y).sqrt();
+        approx_eq(euclidean_distance_f32(&x, &y).0, expected)
+    }
+
+    #[quickcheck]
+    fn prop_euclidean_subbyte_matches_levels(pairs: Vec<(i16, i16)>, resolution: u8) -> bool {
+        let resolution = resolution % 3 + 1;
+        let (x, y): (Vec<f32>, Vec<f32>) = pairs
+            .into_iter()
+            .map(|(a, b)| (a as f32 / 32768.0, b as f32 / 32768.0))
+            .unzip();
+        let (actual, expected) = subbyte_reference(&x, &y, resolution);
+        approx_eq(actual, expected)
+    }
+
+    #[test]
+    fn test_euclidean_long_vectors() {
+        let mut rng = rand::thread_rng();
+        for size in [255, 256, 257, 1000, 1536] {
+            let x_u8: Vec<u8> = (0..size).map(|_| rng.gen()).collect();
+            let y_u8: Vec<u8> = (0..size).map(|_| rng.gen()).collect();
+            let expected = (squared_euclidean_u8_scalar(&x_u8, &y_u8) as f32).sqrt();
+            assert_eq!(euclidean_distance_u8(&x_u8, &y_u8).0, expected);
+
+            let x: Vec<f32> = (0..size).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let y: Vec<f32> = (0..size).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let expected = squared_euclidean_f32_scalar(&x, &y).sqrt();
+            assert!(approx_eq(euclidean_distance_f32(&x, &y).0, expected));
+
+            let x_f16: Vec<f16> = x.iter().map(|&v| f16::from_f32(v)).collect();
+            let y_f16: Vec<f16> = y.iter().map(|&v| f16::from_f32(v)).collect();
+            let expected = squared_euclidean_f16_scalar(&x_f16, &y_f16).sqrt();
+            assert!(approx_eq(
+                euclidean_distance_f16(&x_f16, &y_f16).0,
+                expected
+            ));
+
+            for resolution in 1..=3 {
+                let (actual, expected) = subbyte_reference(&x, &y, resolution);
+                assert!(approx_eq(actual, expected), "resolution {}", resolution);
+            }
+        }
+    }
+
+    #[test]
+    fn test_euclidean_unsupported_resolution() {
+        let planes = vec![vec![0u8; 4]; 4];
+        assert!(euclidean_distance_subbyte(&planes, &planes, 4).is_err());
+    }
 }
@@ -1,17 +1,24 @@
 use half::f16;
 use serde::{Deserialize, Serialize};
 
+#[cfg(target_arch = "aarch64")]
+use super::arm64;
+#[cfg(target_arch = "x86_64")]
+use super::x86_64;
 use super::{DistanceError, DistanceFunction};
 use crate::{models::types::VectorData, storage::Storage};
 
+// The hamming distance is the number of differing bits of u8 vectors and
+// the number of dimensions whose values differ for the other storage
+// types. Floats are compared by value (so `0.0` and `-0.0` are equal and
+// NaNs differ from everything) and sub-byte values differ if any of their
+// bits differ.
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize, PartialOrd)]
 pub struct HammingDistance(pub f32);
 
 impl DistanceFunction for HammingDistance {
     type Item = Self;
 
-    // Implementation here
-    #[allow(unused_variables)]
     fn calculate(
         &self,
         x: &VectorData,
@@ -52,64 +59,262 @@ impl DistanceFunction for HammingDistance {
                     quant_vec: vec_y, ..
                 },
             ) => Ok(hamming_distance_f16(vec_x, vec_y)),
+            (
+                Storage::FullPrecisionFP { vec: vec_x, .. },
+                Storage::FullPrecisionFP { vec: vec_y, .. },
+            ) => Ok(hamming_distance_f32(vec_x, vec_y)),
             _ => Err(DistanceError::StorageMismatch),
         }
     }
 }
 
+fn hamming_values_scalar<T: PartialEq>(x: &[T], y: &[T]) -> u32 {
+    x.iter().zip(y.iter()).filter(|(a, b)| a != b).count() as u32
+}
+
+fn hamming_u8_scalar(x: &[u8], y: &[u8]) -> u32 {
+    x.iter()
+        .zip(y.iter())
+        .map(|(&a, &b)| (a ^ b).count_ones())
+        .sum()
+}
+
+fn hamming_bit_planes_scalar<T: AsRef<[u8]>>(x: &[T], y: &[T]) -> u32 {
+    let len = x
+        .iter()
+        .chain(y)
+        .map(|plane| plane.as_ref().len())
+        .min()
+        .unwrap_or(0);
+    (0..len)
+        .map(|i| {
+            x.iter()
+                .zip(y)
+                .fold(0u8, |diff, (x, y)| diff | (x.as_ref()[i] ^ y.as_ref()[i]))
+                .count_ones()
+        })
+        .sum()
+}
+
+fn hamming_bit_planes<T: AsRef<[u8]>>(x: &[T], y: &[T]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { x86_64::hamming_bit_planes_avx2(x, y) };
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return unsafe { arm64::hamming_bit_planes_neon(x, y) };
+        }
+    }
+
+    hamming_bit_planes_scalar(x, y)
+}
+
 pub fn hamming_distance_u8(x: &[u8], y: &[u8]) -> HammingDistance {
     if x.len() != y.len() {
         return HammingDistance(f32::INFINITY);
     }
 
-    let distance = x
-        .iter()
-        .zip(y.iter())
-        .map(|(&a, &b)| (a ^ b).count_ones() as f32)
-        .sum::<f32>();
-    HammingDistance(distance)
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return HammingDistance(unsafe { x86_64::hamming_u8_avx2(x, y) } as f32);
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return HammingDistance(unsafe { arm64::hamming_u8_neon(x, y) } as f32);
+        }
+    }
+
+    HammingDistance(hamming_u8_scalar(x, y) as f32)
+}
+
+// Number of differing bits of two 1-bit quantized vectors
+pub fn hamming_distance_binary(x: &[u8], y: &[u8]) -> u32 {
+    hamming_bit_planes(&[x], &[y])
 }
 
 pub fn hamming_distance_subbyte(x: &[Vec<u8>], y: &[Vec<u8>], resolution: u8) -> HammingDistance {
-    if x.len() != y.len() || resolution == 0 || resolution > 8 {
+    if x.len() != y.len() || x.len() != resolution as usize {
+        return HammingDistance(f32::INFINITY);
+    }
+    if x.iter().zip(y).any(|(x, y)| x.len() != y.len()) {
         return HammingDistance(f32::INFINITY);
     }
 
-    let mask = (1u8 << resolution) - 1;
-    let mut total_distance = 0f32;
+    HammingDistance(hamming_bit_planes(x, y) as f32)
+}
 
-    for (vec_x, vec_y) in x.iter().zip(y.iter()) {
-        if vec_x.len() != vec_y.len() {
-            return HammingDistance(f32::INFINITY);
-        }
+pub fn hamming_distance_f16(x: &[f16], y: &[f16]) -> HammingDistance {
+    if x.len() != y.len() {
+        return HammingDistance(f32::INFINITY);
+    }
 
-        for (&byte_x, &byte_y) in vec_x.iter().zip(vec_y.iter()) {
-            let bits_per_byte = 8 / resolution;
-            for i in 0..bits_per_byte {
-                let shift = i * resolution;
-                let val_x = (byte_x >> shift) & mask;
-                let val_y = (byte_y >> shift) & mask;
-                total_distance += (val_x ^ val_y).count_ones() as f32;
-            }
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx") && is_x86_feature_detected!("f16c") {
+            return HammingDistance(unsafe { x86_64::hamming_f16_f16c(x, y) } as f32);
         }
     }
 
-    HammingDistance(total_distance)
+    HammingDistance(hamming_values_scalar(x, y) as f32)
 }
 
-pub fn hamming_distance_f16(x: &[f16], y: &[f16]) -> HammingDistance {
+pub fn hamming_distance_f32(x: &[f32], y: &[f32]) -> HammingDistance {
     if x.len() != y.len() {
         return HammingDistance(f32::INFINITY);
     }
 
-    let distance = x
-        .iter()
-        .zip(y.iter())
-        .map(|(&a, &b)| {
-            let bits_a = a.to_bits();
-            let bits_b = b.to_bits();
-            (bits_a ^ bits_b).count_ones() as f32
-        })
-        .sum::<f32>();
-    HammingDistance(distance)
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx") {
+            return HammingDistance(unsafe { x86_64::hamming_f32_avx(x, y) } as f32);
+        }
+    }
+
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return HammingDistance(unsafe { arm64::hamming_f32_neon(x, y) } as f32);
+        }
+    }
+
+    HammingDistance(hamming_values_scalar(x, y) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::quantize_to_u8_bits;
+    use quickcheck_macros::quickcheck;
+    use rand::Rng;
+
+    // Quantized levels of the values of a vector packed with
+    // `quantize_to_u8_bits`, most significant plane first
+    fn subbyte_levels(planes: &[Vec<u8>], len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                planes.iter().fold(0u8, |level, plane| {
+                    (level << 1) | ((plane[i / 8] >> (i % 8)) & 1)
+                })
+            })
+            .collect()
+    }
+
+    fn subbyte_reference(x: &[f32], y: &[f32], resolution: u8) -> (f32, f32) {
+        let x_planes = quantize_to_u8_bits(x, resolution);
+        let y_planes = quantize_to_u8_bits(y, resolution);
+        let expected = hamming_values_scalar(
+            &subbyte_levels(&x_planes, x.len()),
+            &subbyte_levels(&y_planes, y.len()),
+        ) as f32;
+        let actual = hamming_distance_subbyte(&x_planes, &y_planes, resolution).0;
+        (actual, expected)
+    }
+
+    #[quickcheck]
+    fn prop_hamming_u8_matches_scalar(pairs: Vec<(u8, u8)>) -> bool {
+        let (x, y): (Vec<u8>, Vec<u8>) = pairs.into_iter().unzip();
+        hamming_distance_u8(&x, &y).0 == hamming_u8_scalar(&x, &y) as f32
+    }
+
+    #[quickcheck]
+    fn prop_hamming_f16_matches_scalar(pairs: Vec<(f32, f32)>) -> bool {
+        let (x, y): (Vec<f16>, Vec<f16>) = pairs
+            .into_iter()
+            .map(|(a, b)| (f16::from_f32(a), f16::from_f32(b)))
+            .unzip();
+        hamming_distance_f16(&x, &y).0 == hamming_values_scalar(&x, &y) as f32
+    }
+
+    #[quickcheck]
+    fn prop_hamming_f32_matches_scalar(pairs: Vec<(f32, f32)>) -> bool {
+        let (x, y): (Vec<f32>, Vec<f32>) = pairs.into_iter().unzip();
+        hamming_distance_f32(&x, &y).0 == hamming_values_scalar(&x, &y) as f32
+    }
+
+    #[quickcheck]
+    fn prop_hamming_subbyte_matches_levels(pairs: Vec<(i8, i8)>, resolution: u8) -> bool {
+        let resolution = resolution % 3 + 1;
+        let (x, y): (Vec<f32>, Vec<f32>) = pairs
+            .into_iter()
+            .map(|(a, b)| (a as f32 / 128.0, b as f32 / 128.0))
+            .unzip();
+        let (actual, expected) = subbyte_reference(&x, &y, resolution);
+        actual == expected
+    }
+
+    #[test]
+    fn test_hamming_long_vectors() {
+        let mut rng = rand::thread_rng();
+        for size in [255, 256, 257, 1000, 2048] {
+            let x_u8: Vec<u8> = (0..size).map(|_| rng.gen_range(0..4)).collect();
+            let y_u8: Vec<u8> = (0..size).map(|_| rng.gen_range(0..4)).collect();
+            assert_eq!(
+                hamming_distance_u8(&x_u8, &y_u8).0,
+                hamming_u8_scalar(&x_u8, &y_u8) as f32
+            );
+
+            let x: Vec<f32> = x_u8.iter().map(|&v| v as f32 - 1.5).collect();
+            let y: Vec<f32> = y_u8.iter().map(|&v| v as f32 - 1.5).collect();
+            assert_eq!(
+                hamming_distance_f32(&x, &y).0,
+                hamming_values_scalar(&x, &y) as f32
+            );
+
+            let x_f16: Vec<f16> = x.iter().map(|&v| f16::from_f32(v)).collect();
+            let y_f16: Vec<f16> = y.iter().map(|&v| f16::from_f32(v)).collect();
+            assert_eq!(
+                hamming_distance_f16(&x_f16, &y_f16).0,
+                hamming_values_scalar(&x_f16, &y_f16) as f32
+            );
+
+            for resolution in 1..=3 {
+                let (actual, expected) = subbyte_reference(&x, &y, resolution);
+                assert_eq!(actual, expected, "resolution {}", resolution);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hamming_distance_binary_matches_subbyte() {
+        let x: Vec<f32> = (0..1000)
+            .map(|i| ((i * 7) % 13) as f32 / 6.5 - 1.0)
+            .collect();
+        let y: Vec<f32> = (0..1000)
+            .map(|i| ((i * 5) % 11) as f32 / 5.5 - 1.0)
+            .collect();
+        let x_bits = quantize_to_u8_bits(&x, 1);
+        let y_bits = quantize_to_u8_bits(&y, 1);
+
+        let expected = hamming_distance_subbyte(&x_bits, &y_bits, 1).0;
+        assert_eq!(
+            hamming_distance_binary(&x_bits[0], &y_bits[0]) as f32,
+            expected
+        );
+        assert_eq!(hamming_distance_binary(&x_bits[0], &x_bits[0]), 0);
+    }
+
+    #[test]
+    fn test_hamming_u8_counts_bits() {
+        // 0b0011 and 0b0101 differ in 2 bits, 0xff and 0x00 in 8
+        let x = [3u8, 0xff, 7];
+        let y = [5u8, 0x00, 7];
+        assert_eq!(hamming_distance_u8(&x, &y).0, 10.0);
+        assert_eq!(hamming_u8_scalar(&x, &y), 10);
+    }
+
+    #[test]
+    fn test_hamming_compares_float_values() {
+        let x = [0.0f32, f32::NAN, 1.0];
+        let y = [-0.0f32, f32::NAN, 1.0];
+        assert_eq!(hamming_distance_f32(&x, &y).0, 1.0);
+    }
 }
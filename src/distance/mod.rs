@@ -1,8 +1,15 @@
+pub mod asymmetric;
 pub mod cosine;
 pub mod dotproduct;
 pub mod euclidean;
 pub mod hamming;
 
+#[cfg(target_arch = "aarch64")]
+mod arm64;
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+
 use crate::models::types::VectorData;
 
 pub trait DistanceFunction: std::fmt::Debug + Send + Sync {
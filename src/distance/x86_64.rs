@@ -0,0 +1,316 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::arch::x86_64::*;
+
+use half::f16;
+
+use super::asymmetric::CodeSums;
+use crate::models::dot_product::x86_64::count_ones_simd_avx2_256i;
+
+#[target_feature(enable = "avx2")]
+unsafe fn sum_u64x4(x: __m256i) -> u64 {
+    _mm256_extract_epi64(x, 0) as u64
+        + _mm256_extract_epi64(x, 1) as u64
+        + _mm256_extract_epi64(x, 2) as u64
+        + _mm256_extract_epi64(x, 3) as u64
+}
+
+#[target_feature(enable = "avx")]
+unsafe fn sum_f32x8(x: __m256) -> f32 {
+    let mut lanes = [0f32; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), x);
+    lanes.iter().sum()
+}
+
+#[target_feature(enable = "avx2")]
+pub unsafe fn squared_euclidean_u8_avx2(a: &[u8], b: &[u8]) -> u64 {
+    let len = a.len().min(b.len());
+    let zero = _mm256_setzero_si256();
+    let mut acc = _mm256_setzero_si256();
+
+    let mut i = 0;
+    while i + 32 <= len {
+        let va = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+        let vb = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+
+        // Differences of the values widened to 16-bit integers
+        let diff_lo = _mm256_sub_epi16(
+            _mm256_unpacklo_epi8(va, zero),
+            _mm256_unpacklo_epi8(vb, zero),
+        );
+        let diff_hi = _mm256_sub_epi16(
+            _mm256_unpackhi_epi8(va, zero),
+            _mm256_unpackhi_epi8(vb, zero),
+        );
+
+        // Sums of squares in 32-bit lanes, widened to 64-bit lanes so
+        // that long vectors can't overflow the accumulator
+        let squares = _mm256_add_epi32(
+            _mm256_madd_epi16(diff_lo, diff_lo),
+            _mm256_madd_epi16(diff_hi, diff_hi),
+        );
+        acc = _mm256_add_epi64(acc, _mm256_unpacklo_epi32(squares, zero));
+        acc = _mm256_add_epi64(acc, _mm256_unpackhi_epi32(squares, zero));
+
+        i += 32;
+    }
+
+    let mut result = sum_u64x4(acc);
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        let diff = x as i64 - y as i64;
+        result += (diff * diff) as u64;
+    }
+    result
+}
+
+#[target_feature(enable = "avx2", enable = "fma")]
+pub unsafe fn squared_euclidean_f32_avx2(a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
+    let mut acc = _mm256_setzero_ps();
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let diff = _mm256_sub_ps(
+            _mm256_loadu_ps(a.as_ptr().add(i)),
+            _mm256_loadu_ps(b.as_ptr().add(i)),
+        );
+        acc = _mm256_fmadd_ps(diff, diff, acc);
+        i += 8;
+    }
+
+    let mut result = sum_f32x8(acc);
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        let diff = x - y;
+        result += diff * diff;
+    }
+    result
+}
+
+// Loads 8 half precision floats converted to single precision
+#[target_feature(enable = "avx", enable = "f16c")]
+unsafe fn load_f16x8(ptr: *const f16) -> __m256 {
+    _mm256_cvtph_ps(_mm_loadu_si128(ptr as *const __m128i))
+}
+
+#[target_feature(enable = "avx2", enable = "fma", enable = "f16c")]
+pub unsafe fn squared_euclidean_f16_f16c(a: &[f16], b: &[f16]) -> f32 {
+    let len = a.len().min(b.len());
+    let mut acc = _mm256_setzero_ps();
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let diff = _mm256_sub_ps(load_f16x8(a.as_ptr().add(i)), load_f16x8(b.as_ptr().add(i)));
+        acc = _mm256_fmadd_ps(diff, diff, acc);
+        i += 8;
+    }
+
+    let mut result = sum_f32x8(acc);
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        let diff = f32::from(x) - f32::from(y);
+        result += diff * diff;
+    }
+    result
+}
+
+// Counts the differing bits of two u8 vectors
+#[target_feature(enable = "avx2")]
+pub unsafe fn hamming_u8_avx2(a: &[u8], b: &[u8]) -> u32 {
+    let len = a.len().min(b.len());
+    let mut result = 0u64;
+
+    let mut i = 0;
+    while i + 32 <= len {
+        let va = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+        let vb = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+        result += count_ones_simd_avx2_256i(_mm256_xor_si256(va, vb));
+        i += 32;
+    }
+
+    for (x, y) in a[i..len].iter().zip(&b[i..len]) {
+        result += (x ^ y).count_ones() as u64;
+    }
+    result as u32
+}
+
+#[target_feature(enable = "avx")]
+pub unsafe fn hamming_f32_avx(a: &[f32], b: &[f32]) -> u32 {
+    let len = a.len().min(b.len());
+    let mut result = 0u32;
+
+    let mut i = 0;
+    while i + 8 <= len {
+        // Unordered comparison, so NaNs differ from every value like
+        // with `!=`
+        let not_equal = _mm256_cmp_ps::<_CMP_NEQ_UQ>(
+            _mm256_loadu_ps(a.as_ptr().add(i)),
+            _mm256_loadu_ps(b.as_ptr().add(i)),
+        );
+        result += (_mm256_movemask_ps(not_equal) as u32).count_ones();
+        i += 8;
+    }
+
+    for (x, y) in a[i..len].iter().zip(&b[i..len]) {
+        result += (x != y) as u32;
+    }
+    result
+}
+
+#[target_feature(enable = "avx", enable = "f16c")]
+pub unsafe fn hamming_f16_f16c(a: &[f16], b: &[f16]) -> u32 {
+    let len = a.len().min(b.len());
+    let mut result = 0u32;
+
+    let mut i = 0;
+    while i + 8 <= len {
+        let not_equal = _mm256_cmp_ps::<_CMP_NEQ_UQ>(
+            load_f16x8(a.as_ptr().add(i)),
+            load_f16x8(b.as_ptr().add(i)),
+        );
+        result += (_mm256_movemask_ps(not_equal) as u32).count_ones();
+        i += 8;
+    }
+
+    for (x, y) in a[i..len].iter().zip(&b[i..len]) {
+        result += (x != y) as u32;
+    }
+    result
+}
+
+// Counts the positions whose bits differ in any of the bit planes of
+// two sub-byte quantized vectors
+#[target_feature(enable = "avx2")]
+pub unsafe fn hamming_bit_planes_avx2<T: AsRef<[u8]>>(x_vec: &[T], y_vec: &[T]) -> u32 {
+    let len = x_vec
+        .iter()
+        .chain(y_vec)
+        .map(|plane| plane.as_ref().len())
+        .min()
+        .unwrap_or(0);
+    let mut result = 0u64;
+
+    let mut i = 0;
+    while i + 32 <= len {
+        let mut diff = _mm256_setzero_si256();
+        for (x, y) in x_vec.iter().zip(y_vec) {
+            let x = _mm256_loadu_si256(x.as_ref().as_ptr().add(i) as *const __m256i);
+            let y = _mm256_loadu_si256(y.as_ref().as_ptr().add(i) as *const __m256i);
+            diff = _mm256_or_si256(diff, _mm256_xor_si256(x, y));
+        }
+        result += count_ones_simd_avx2_256i(diff);
+        i += 32;
+    }
+
+    for j in i..len {
+        let diff = x_vec
+            .iter()
+            .zip(y_vec)
+            .fold(0u8, |diff, (x, y)| diff | (x.as_ref()[j] ^ y.as_ref()[j]));
+        result += diff.count_ones() as u64;
+    }
+    result as u32
+}
+
+// Generates the asymmetric kernels of a query type, `$load` loads 8
+// query values as single precision floats
+macro_rules! asymmetric_kernels {
+    ($query_type:ty, $load:ident, $code_sums:ident, $masked_sums:ident, $features:literal) => {
+        #[target_feature(enable = $features)]
+        pub unsafe fn $code_sums(query: &[$query_type], codes: &[u8]) -> CodeSums {
+            let len = query.len().min(codes.len());
+            let mut query_dot_codes = _mm256_setzero_ps();
+            let mut query_sum = _mm256_setzero_ps();
+            let mut codes_sum = _mm256_setzero_ps();
+            let mut codes_sqr_sum = _mm256_setzero_ps();
+
+            let mut i = 0;
+            while i + 8 <= len {
+                let q = $load(query.as_ptr().add(i));
+                // Widens 8 codes to single precision floats
+                let n = _mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(_mm_loadl_epi64(
+                    codes.as_ptr().add(i) as *const __m128i,
+                )));
+                query_dot_codes = _mm256_fmadd_ps(q, n, query_dot_codes);
+                query_sum = _mm256_add_ps(query_sum, q);
+                codes_sum = _mm256_add_ps(codes_sum, n);
+                codes_sqr_sum = _mm256_fmadd_ps(n, n, codes_sqr_sum);
+                i += 8;
+            }
+
+            let mut sums = CodeSums {
+                query_dot_codes: sum_f32x8(query_dot_codes),
+                query_sum: sum_f32x8(query_sum),
+                codes_sum: sum_f32x8(codes_sum),
+                codes_sqr_sum: sum_f32x8(codes_sqr_sum),
+            };
+            for (&q, &n) in query[i..len].iter().zip(&codes[i..len]) {
+                let (q, n) = (f32::from(q), n as f32);
+                sums.query_dot_codes += q * n;
+                sums.query_sum += q;
+                sums.codes_sum += n;
+                sums.codes_sqr_sum += n * n;
+            }
+            sums
+        }
+
+        #[target_feature(enable = $features)]
+        pub unsafe fn $masked_sums(
+            query: &[$query_type],
+            planes: &[Vec<u8>],
+            masked_sums: &mut [f32; 8],
+        ) -> f32 {
+            let len = query.len();
+            // Bit of every lane in a byte of a bit plane
+            let lane_bits = _mm256_setr_epi32(1, 2, 4, 8, 16, 32, 64, 128);
+            let mut masked = [_mm256_setzero_ps(); 8];
+            let mut query_sum = _mm256_setzero_ps();
+
+            let mut i = 0;
+            while i + 8 <= len {
+                let q = $load(query.as_ptr().add(i));
+                query_sum = _mm256_add_ps(query_sum, q);
+                for (plane, acc) in planes.iter().zip(masked.iter_mut()) {
+                    let byte = _mm256_set1_epi32(plane[i / 8] as i32);
+                    let mask = _mm256_cmpeq_epi32(_mm256_and_si256(byte, lane_bits), lane_bits);
+                    *acc = _mm256_add_ps(*acc, _mm256_and_ps(q, _mm256_castsi256_ps(mask)));
+                }
+                i += 8;
+            }
+
+            for (sum, acc) in masked_sums.iter_mut().zip(masked) {
+                *sum = sum_f32x8(acc);
+            }
+            let mut result = sum_f32x8(query_sum);
+            for (j, &q) in query.iter().enumerate().skip(i) {
+                let q = f32::from(q);
+                result += q;
+                for (plane, sum) in planes.iter().zip(masked_sums.iter_mut()) {
+                    if (plane[j / 8] >> (j % 8)) & 1 == 1 {
+                        *sum += q;
+                    }
+                }
+            }
+            result
+        }
+    };
+}
+
+#[target_feature(enable = "avx")]
+unsafe fn load_f32x8(ptr: *const f32) -> __m256 {
+    _mm256_loadu_ps(ptr)
+}
+
+asymmetric_kernels!(
+    f32,
+    load_f32x8,
+    code_sums_u8_f32_avx2,
+    masked_query_sums_f32_avx2,
+    "avx2,fma"
+);
+
+asymmetric_kernels!(
+    f16,
+    load_f16x8,
+    code_sums_u8_f16_f16c,
+    masked_query_sums_f16_f16c,
+    "avx2,fma,f16c"
+);
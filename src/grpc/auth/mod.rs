@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use tonic::{service::Interceptor, Request, Response, Status};
+
+use crate::api::auth::{
+    authentication_middleware::validate_access_token, authorization_middleware, dtos::Claims,
+    error::AuthError,
+};
+use crate::app_context::AppContext;
+use crate::models::rbac::Permission;
+
+/// Rejects requests without a valid session token or API key, same as the
+/// authentication middleware of the HTTP API. The resolved `Claims` are put
+/// into the request extensions.
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    pub context: Arc<AppContext>,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let claims = authenticate(&self.context, &request)?;
+        request.extensions_mut().insert(claims);
+        Ok(request)
+    }
+}
+
+/// Resolves the session of the bearer token in the `authorization`
+/// metadata of the request, unless already done by `AuthInterceptor`
+pub fn authenticate<T>(context: &AppContext, request: &Request<T>) -> Result<Claims, AuthError> {
+    if let Some(claims) = request.extensions().get::<Claims>() {
+        return Ok(claims.clone());
+    }
+    let auth_header = request
+        .metadata()
+        .get("authorization")
+        .ok_or(AuthError::InvalidToken)?
+        .to_str()
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    let mut header = auth_header.split_whitespace();
+    let (_, access_token) = (header.next(), header.next().ok_or(AuthError::InvalidToken)?);
+    validate_access_token(access_token, &context.ain_env)
+}
+
+/// Authenticates the request and checks the permission of its user on the
+/// collection
+pub fn authorize<T>(
+    context: &AppContext,
+    request: &Request<T>,
+    collection: &str,
+    permission: Permission,
+) -> Result<Claims, AuthError> {
+    let claims = authenticate(context, request)?;
+    authorization_middleware::authorize(context, &claims, collection, permission)?;
+    Ok(claims)
+}
+
+crate::cfg_grpc! {
+    use crate::api::auth::{
+        dtos::{CreateSessionDTO, RefreshSessionDto, RevokeSessionDto, Session},
+        service,
+    };
+    use super::proto::{
+        auth_service_server::AuthService, Claims as ProtoClaims, CreateSessionRequest,
+        CreateSessionResponse, RefreshSessionRequest, RevokeSessionRequest,
+    };
+
+    pub struct AuthServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    impl AuthServiceImpl {
+        fn session_response(&self, session: Session) -> Result<CreateSessionResponse, AuthError> {
+            let claims = validate_access_token(&session.access_token, &self.context.ain_env)?;
+            Ok(CreateSessionResponse {
+                access_token: session.access_token,
+                created_at: session.created_at,
+                expires_at: session.expires_at,
+                claims: Some(ProtoClaims {
+                    exp: claims.exp,
+                    iat: claims.iat,
+                    username: claims.username,
+                }),
+                refresh_token: session.refresh_token,
+                refresh_expires_at: session.refresh_expires_at,
+            })
+        }
+    }
+
+    // Not behind `AuthInterceptor`, these are how clients get a token in the
+    // first place
+    #[tonic::async_trait]
+    impl AuthService for AuthServiceImpl {
+        async fn create_session(
+            &self,
+            request: Request<CreateSessionRequest>,
+        ) -> Result<Response<CreateSessionResponse>, Status> {
+            let req = request.into_inner();
+            let session = service::create_session(
+                CreateSessionDTO {
+                    username: req.username,
+                    password: req.password,
+                },
+                self.context.clone(),
+            )
+            .await?;
+            Ok(Response::new(self.session_response(session)?))
+        }
+
+        async fn refresh_session(
+            &self,
+            request: Request<RefreshSessionRequest>,
+        ) -> Result<Response<CreateSessionResponse>, Status> {
+            let session = service::refresh_session(
+                RefreshSessionDto {
+                    refresh_token: request.into_inner().refresh_token,
+                },
+                self.context.clone(),
+            )
+            .await?;
+            Ok(Response::new(self.session_response(session)?))
+        }
+
+        async fn revoke_session(
+            &self,
+            request: Request<RevokeSessionRequest>,
+        ) -> Result<Response<()>, Status> {
+            service::revoke_session(
+                RevokeSessionDto {
+                    token: request.into_inner().token,
+                },
+                self.context.clone(),
+            )
+            .await?;
+            Ok(Response::new(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,101 @@
+use tonic::service::Interceptor;
+use tonic::{Code, Request};
+
+use crate::api::auth::dtos::Claims;
+use crate::grpc::auth::{AuthInterceptor, AuthServiceImpl};
+use crate::grpc::proto::auth_service_server::AuthService;
+use crate::grpc::proto::{CreateSessionRequest, RefreshSessionRequest, RevokeSessionRequest};
+use crate::grpc::test_utils::{test_context, TEST_ADMIN_KEY};
+use crate::models::types::ADMIN_USERNAME;
+
+fn bearer_request(token: &str) -> Request<()> {
+    let mut request = Request::new(());
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", token).parse().unwrap(),
+    );
+    request
+}
+
+#[tokio::test]
+async fn test_session_lifecycle() {
+    let context = test_context();
+    let service = AuthServiceImpl {
+        context: context.clone(),
+    };
+    let mut interceptor = AuthInterceptor { context };
+
+    let session = service
+        .create_session(Request::new(CreateSessionRequest {
+            username: ADMIN_USERNAME.to_string(),
+            password: TEST_ADMIN_KEY.to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(session.claims.unwrap().username, ADMIN_USERNAME);
+
+    let request = interceptor
+        .call(bearer_request(&session.access_token))
+        .unwrap();
+    assert_eq!(
+        request.extensions().get::<Claims>().unwrap().username,
+        ADMIN_USERNAME
+    );
+
+    let refreshed = service
+        .refresh_session(Request::new(RefreshSessionRequest {
+            refresh_token: session.refresh_token.clone(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_ne!(refreshed.access_token, session.access_token);
+
+    // refreshing revokes the previous tokens
+    let status = interceptor
+        .call(bearer_request(&session.access_token))
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let status = service
+        .refresh_session(Request::new(RefreshSessionRequest {
+            refresh_token: session.refresh_token,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    service
+        .revoke_session(Request::new(RevokeSessionRequest {
+            token: refreshed.access_token.clone(),
+        }))
+        .await
+        .unwrap();
+    let status = interceptor
+        .call(bearer_request(&refreshed.access_token))
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_invalid_credentials() {
+    let context = test_context();
+    let service = AuthServiceImpl {
+        context: context.clone(),
+    };
+    let mut interceptor = AuthInterceptor { context };
+
+    let status = service
+        .create_session(Request::new(CreateSessionRequest {
+            username: ADMIN_USERNAME.to_string(),
+            password: "wrong-password".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = interceptor.call(Request::new(())).unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let status = interceptor.call(bearer_request("not-a-token")).unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
@@ -1,17 +1,22 @@
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
+use crate::api::auth::authorization_middleware::is_authorized;
 use crate::app_context::AppContext;
 use crate::metadata::schema::MetadataSchema;
 use crate::models::collection::{
-    Collection, CollectionConfig, DenseVectorOptions, SparseVectorOptions, TFIDFOptions,
+    Collection, CollectionConfig, DenseVectorField, DenseVectorOptions, SparseVectorOptions,
+    TFIDFOptions,
 };
 use crate::models::common::WaCustomError;
 use crate::models::meta_persist::update_current_version;
+use crate::models::rbac::{Permission, ADMIN_ROLE, GLOBAL_SCOPE};
 use crate::models::types::MetaDb;
 use crate::models::versioning::VersionControl;
 
 crate::cfg_grpc! {
+    use super::auth::{authenticate, authorize};
+    use super::server::check_ready;
     use super::proto::{
         collections_service_server::CollectionsService, Collection as ProtoCollection,
         CreateCollectionRequest, CreateCollectionResponse, DeleteCollectionRequest,
@@ -28,7 +33,24 @@ crate::cfg_grpc! {
             &self,
             request: Request<CreateCollectionRequest>,
         ) -> Result<Response<CreateCollectionResponse>, Status> {
+            let claims = authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::CreateCollection,
+            )?;
+            // creating and deleting collections waits for the restart
+            // recovery, like the calls of the gated services
+            check_ready(&self.context)?;
             let req = request.into_inner();
+            // the creator gets the admin role on the collection, which on
+            // the global scope would make them an admin of all collections
+            if req.name == GLOBAL_SCOPE {
+                return Err(Status::invalid_argument(format!(
+                    "Collection name '{}' is reserved",
+                    GLOBAL_SCOPE
+                )));
+            }
 
             // Create options from request
             let dense_vector = DenseVectorOptions {
@@ -37,6 +59,16 @@ crate::cfg_grpc! {
                     .as_ref()
                     .map_or(0, |d| d.dimension as usize),
                 enabled: req.dense_vector.as_ref().is_some_and(|d| d.enabled),
+                fields: req.dense_vector.as_ref().map_or_else(Vec::new, |d| {
+                    d.fields
+                        .iter()
+                        .map(|field| DenseVectorField {
+                            name: field.name.clone(),
+                            dimension: field.dimension as usize,
+                        })
+                        .collect()
+                }),
+                multi_vector: req.dense_vector.as_ref().is_some_and(|d| d.multi_vector),
             };
 
             let sparse_vector = SparseVectorOptions {
@@ -101,6 +133,11 @@ crate::cfg_grpc! {
                 .flush()
                 .map_err(Status::from)?;
             update_current_version(&collection.lmdb, hash).map_err(Status::from)?;
+            self.context
+                .ain_env
+                .rbac
+                .assign_role(&collection.meta.name, &claims.username, ADMIN_ROLE)
+                .map_err(Status::from)?;
 
             Ok(Response::new(CreateCollectionResponse {
                 id: collection.meta.name.clone(),
@@ -111,13 +148,22 @@ crate::cfg_grpc! {
 
         async fn get_collections(
             &self,
-            _request: Request<GetCollectionsRequest>,
+            request: Request<GetCollectionsRequest>,
         ) -> Result<Response<GetCollectionsResponse>, Status> {
+            let claims = authenticate(&self.context, &request)?;
             let collections = self
                 .context
                 .ain_env
                 .collections_map
                 .iter_collections()
+                .filter(|entry| {
+                    is_authorized(
+                        &self.context,
+                        &claims,
+                        entry.key(),
+                        Permission::ListCollections,
+                    )
+                })
                 .map(|entry| ProtoCollection {
                     name: entry.key().clone(),
                     description: entry.value().meta.description.clone(),
@@ -131,6 +177,12 @@ crate::cfg_grpc! {
             &self,
             request: Request<GetCollectionRequest>,
         ) -> Result<Response<ProtoCollection>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().id,
+                Permission::ListCollections,
+            )?;
             let collection = self
                 .context
                 .ain_env
@@ -148,6 +200,13 @@ crate::cfg_grpc! {
             &self,
             request: Request<DeleteCollectionRequest>,
         ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().id,
+                Permission::DeleteCollection,
+            )?;
+            check_ready(&self.context)?;
             let collection_id = request.into_inner().id;
 
             self.context
@@ -160,8 +219,16 @@ crate::cfg_grpc! {
                     }
                     _ => Status::internal(format!("Failed to delete collection: {}", e)),
                 })?;
+            self.context
+                .ain_env
+                .rbac
+                .remove_collection(&collection_id)
+                .map_err(Status::from)?;
 
             Ok(Response::new(()))
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,86 @@
+use tonic::{Code, Request};
+
+use crate::api::auth::dtos::Claims;
+use crate::grpc::collections::CollectionsServiceImpl;
+use crate::grpc::proto::collections_service_server::CollectionsService;
+use crate::grpc::proto::{CollectionConfig, CreateCollectionRequest, DenseVectorOptions};
+use crate::grpc::test_utils::test_context;
+use crate::models::rbac::{Permission, Role, ADMIN_ROLE, GLOBAL_SCOPE};
+
+fn create_collection_request(name: &str) -> CreateCollectionRequest {
+    CreateCollectionRequest {
+        name: name.to_string(),
+        description: None,
+        dense_vector: Some(DenseVectorOptions {
+            dimension: 4,
+            enabled: true,
+            fields: Vec::new(),
+            multi_vector: false,
+        }),
+        sparse_vector: None,
+        tf_idf_options: None,
+        metadata_schema: None,
+        config: Some(CollectionConfig {
+            max_vectors: None,
+            replication_factor: None,
+        }),
+        store_raw_text: Some(false),
+    }
+}
+
+fn user_request<T>(username: &str, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(Claims {
+        exp: u64::MAX,
+        iat: 0,
+        username: username.to_string(),
+        api_key: None,
+    });
+    request
+}
+
+#[tokio::test]
+async fn test_create_collection_rejects_global_scope() {
+    let context = test_context();
+    let rbac = &context.ain_env.rbac;
+    rbac.create_role(Role {
+        name: "grpc_collections_creator".to_string(),
+        description: None,
+        permissions: vec![Permission::CreateCollection],
+    })
+    .unwrap();
+    rbac.assign_role(GLOBAL_SCOPE, "grpc_creator", "grpc_collections_creator")
+        .unwrap();
+    let service = CollectionsServiceImpl {
+        context: context.clone(),
+    };
+
+    let status = service
+        .create_collection(user_request(
+            "grpc_creator",
+            create_collection_request(GLOBAL_SCOPE),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(!rbac
+        .get_user_roles(GLOBAL_SCOPE, "grpc_creator")
+        .iter()
+        .any(|role| role == ADMIN_ROLE));
+    assert!(!rbac.has_permission("grpc_creator", "other", Permission::DeleteCollection));
+
+    // the creators of other collections only get the admin role on them
+    service
+        .create_collection(user_request(
+            "grpc_creator",
+            create_collection_request("grpc_collections_created"),
+        ))
+        .await
+        .unwrap();
+    assert!(rbac.has_permission(
+        "grpc_creator",
+        "grpc_collections_created",
+        Permission::DeleteCollection
+    ));
+    assert!(!rbac.has_permission("grpc_creator", "other", Permission::DeleteCollection));
+}
@@ -1,3 +1,7 @@
+use crate::api::auth::error::AuthError;
+use crate::api::vectordb::indexes::error::IndexesError;
+use crate::api::vectordb::search::error::SearchError;
+use crate::api::vectordb::transactions::error::TransactionError;
 use crate::models::common::WaCustomError;
 use tonic::Status;
 
@@ -5,6 +9,7 @@ impl From<WaCustomError> for Status {
     fn from(error: WaCustomError) -> Self {
         match error {
             WaCustomError::InvalidParams => Status::invalid_argument(error.to_string()),
+            WaCustomError::InvalidData(msg) => Status::invalid_argument(msg),
             WaCustomError::NotFound(msg) => Status::not_found(msg),
             WaCustomError::FsError(msg) => Status::internal(format!("Filesystem error: {}", msg)),
             WaCustomError::DatabaseError(msg) => {
@@ -20,3 +25,71 @@ impl From<WaCustomError> for Status {
         }
     }
 }
+
+impl From<AuthError> for Status {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::WrongCredentials | AuthError::InvalidRequest(_) => {
+                Status::invalid_argument(error.to_string())
+            }
+            AuthError::InvalidToken => Status::unauthenticated(error.to_string()),
+            AuthError::Forbidden => Status::permission_denied(error.to_string()),
+            AuthError::NotFound(msg) => Status::not_found(msg),
+            AuthError::FailedToExtractTokenFromRequest | AuthError::ServerError(_) => {
+                Status::internal(error.to_string())
+            }
+        }
+    }
+}
+
+impl From<IndexesError> for Status {
+    fn from(error: IndexesError) -> Self {
+        match error {
+            IndexesError::NotFound(_) | IndexesError::CollectionNotFound => {
+                Status::not_found(error.to_string())
+            }
+            IndexesError::IndexAlreadyExists(_) => Status::already_exists(error.to_string()),
+            IndexesError::FailedToCreateIndex(_) | IndexesError::InvalidIndexType(_) => {
+                Status::invalid_argument(error.to_string())
+            }
+            IndexesError::FailedToGetAppEnv
+            | IndexesError::FailedToDeleteIndex(_)
+            | IndexesError::WaCustom(_) => Status::internal(error.to_string()),
+        }
+    }
+}
+
+impl From<TransactionError> for Status {
+    fn from(error: TransactionError) -> Self {
+        match error {
+            TransactionError::NotFound
+            | TransactionError::CollectionNotFound
+            | TransactionError::IndexNotFound => Status::not_found(error.to_string()),
+            TransactionError::OnGoingTransaction => Status::failed_precondition(error.to_string()),
+            TransactionError::NotImplemented => Status::unimplemented(error.to_string()),
+            TransactionError::FailedToGetAppEnv => Status::internal(error.to_string()),
+            TransactionError::FailedToGetTransactionStatus(_)
+            | TransactionError::FailedToCreateTransaction(_)
+            | TransactionError::FailedToCommitTransaction(_)
+            | TransactionError::FailedToCreateVector(_)
+            | TransactionError::FailedToDeleteVector(_) => {
+                Status::invalid_argument(error.to_string())
+            }
+        }
+    }
+}
+
+impl From<SearchError> for Status {
+    fn from(error: SearchError) -> Self {
+        match error {
+            SearchError::CollectionNotFound(_) => Status::not_found(error.to_string()),
+            SearchError::IndexNotFound(_) => Status::failed_precondition(error.to_string()),
+            SearchError::InvalidFilter(_) | SearchError::InvalidInput(_) => {
+                Status::invalid_argument(error.to_string())
+            }
+            SearchError::InternalServerError(_) | SearchError::WaCustom(_) => {
+                Status::internal(error.to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use serde::de::{value::Error as DeError, IntoDeserializer};
+use serde::Deserialize;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::api::vectordb::indexes::dtos::{
+    CreateDenseIndexDto, CreateSparseIndexDto, CreateTFIDFIndexDto, DataType as DataTypeDto,
+    DenseIndexParamsDto, DenseIndexQuantizationDto, HNSWHyperParamsDto, IndexInfo,
+    IndexType as IndexTypeDto, SparseIndexQuantization, ValuesRange as ValuesRangeDto,
+};
+use crate::api::vectordb::indexes::error::IndexesError;
+use crate::api::vectordb::indexes::service;
+use crate::app_context::AppContext;
+use crate::indexes::hnsw::types::QueryPrecision;
+use crate::indexes::tf_idf::analyzer::{
+    StemmerLanguage, StopwordsConfig, TextAnalyzerConfig, TokenizerConfig, DEFAULT_NGRAM_SIZE,
+};
+use crate::models::rbac::Permission;
+use crate::models::schema_traits::DistanceMetricSchema;
+
+crate::cfg_grpc! {
+    use super::auth::authorize;
+    use super::proto::{
+        create_dense_index_request::Quantization,
+        hnsw_params::QueryPrecision as ProtoQueryPrecision,
+        index_details::Details,
+        indexes_service_server::IndexesService,
+        text_analyzer::{Stemmer, Stopwords, Tokenizer},
+        CreateDenseIndexRequest, CreateSparseIndexRequest, CreateTfIdfIndexRequest, DataType,
+        DeleteIndexRequest, DenseIndexDetails, GetIndexesRequest, GetIndexesResponse, HnswParams,
+        IndexDetails, IndexType, SparseIndexDetails, TextAnalyzer, TfIdfIndexDetails, ValuesRange,
+    };
+
+    pub struct IndexesServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    impl From<DataType> for DataTypeDto {
+        fn from(data_type: DataType) -> Self {
+            match data_type {
+                DataType::Binary => Self::Binary,
+                DataType::Quaternary => Self::Quaternay,
+                DataType::Octal => Self::Octal,
+                DataType::U8 => Self::U8,
+                DataType::F16 => Self::F16,
+                DataType::F32 => Self::F32,
+            }
+        }
+    }
+
+    impl From<IndexType> for IndexTypeDto {
+        fn from(index_type: IndexType) -> Self {
+            match index_type {
+                IndexType::Dense => Self::Dense,
+                IndexType::Sparse => Self::Sparse,
+                IndexType::Tfidf => Self::TfIdf,
+            }
+        }
+    }
+
+    impl From<QueryPrecision> for ProtoQueryPrecision {
+        fn from(precision: QueryPrecision) -> Self {
+            match precision {
+                QueryPrecision::Quantized => Self::Quantized,
+                QueryPrecision::F32 => Self::F32,
+                QueryPrecision::F16 => Self::F16,
+            }
+        }
+    }
+
+    fn parse_hnsw_params(params: HnswParams) -> Result<HNSWHyperParamsDto, IndexesError> {
+        let query_precision = match params.query_precision.map(ProtoQueryPrecision::try_from) {
+            None => None,
+            Some(Ok(ProtoQueryPrecision::Quantized)) => Some(QueryPrecision::Quantized),
+            Some(Ok(ProtoQueryPrecision::F32)) => Some(QueryPrecision::F32),
+            Some(Ok(ProtoQueryPrecision::F16)) => Some(QueryPrecision::F16),
+            Some(Err(_)) => {
+                return Err(IndexesError::FailedToCreateIndex(
+                    "Invalid query precision".to_string(),
+                ))
+            }
+        };
+        Ok(HNSWHyperParamsDto {
+            ef_construction: params.ef_construction,
+            ef_search: params.ef_search,
+            num_layers: params.num_layers.map(|num_layers| num_layers as u8),
+            max_cache_size: params.max_cache_size.map(|size| size as usize),
+            level_0_neighbors_count: params.level_0_neighbors_count.map(|count| count as usize),
+            neighbors_count: params.neighbors_count.map(|count| count as usize),
+            oversampling: params.oversampling,
+            query_precision,
+        })
+    }
+
+    impl From<IndexInfo> for IndexDetails {
+        fn from(index: IndexInfo) -> Self {
+            match index {
+                IndexInfo::Dense(dense) => Self {
+                    name: dense.name,
+                    algorithm: dense.algorithm,
+                    details: Some(Details::Dense(DenseIndexDetails {
+                        distance_metric: dense.distance_metric,
+                        quantization_type: dense.quantization.quantization_type,
+                        storage: dense.quantization.storage,
+                        range: Some(ValuesRange {
+                            min: dense.quantization.range.min,
+                            max: dense.quantization.range.max,
+                        }),
+                        hnsw_params: Some(HnswParams {
+                            ef_construction: Some(dense.params.ef_construction),
+                            ef_search: Some(dense.params.ef_search),
+                            num_layers: Some(dense.params.num_layers as u32),
+                            max_cache_size: None,
+                            level_0_neighbors_count: Some(
+                                dense.params.level_0_neighbors_count as u32,
+                            ),
+                            neighbors_count: Some(dense.params.neighbors_count as u32),
+                            oversampling: Some(dense.params.oversampling),
+                            query_precision: Some(
+                                ProtoQueryPrecision::from(dense.params.query_precision).into(),
+                            ),
+                        }),
+                        field: dense.field,
+                    })),
+                },
+                IndexInfo::Sparse(sparse) => Self {
+                    name: sparse.name,
+                    algorithm: sparse.algorithm,
+                    details: Some(Details::Sparse(SparseIndexDetails {
+                        quantization_bits: sparse.quantization_bits as u32,
+                        values_upper_bound: sparse.values_upper_bound,
+                    })),
+                },
+                IndexInfo::TfIdf(tf_idf) => Self {
+                    name: tf_idf.name,
+                    algorithm: tf_idf.algorithm,
+                    details: Some(Details::TfIdf(TfIdfIndexDetails {
+                        k1: tf_idf.k1,
+                        b: tf_idf.b,
+                        analyzer: Some(tf_idf.analyzer.into()),
+                        store_positions: tf_idf.store_positions,
+                        store_terms: tf_idf.store_terms,
+                        fields: tf_idf.fields,
+                    })),
+                },
+            }
+        }
+    }
+
+    impl From<StemmerLanguage> for Stemmer {
+        fn from(stemmer: StemmerLanguage) -> Self {
+            match stemmer {
+                StemmerLanguage::None => Self::NoStemmer,
+                StemmerLanguage::English => Self::English,
+                StemmerLanguage::German => Self::German,
+                StemmerLanguage::Spanish => Self::Spanish,
+                StemmerLanguage::Italian => Self::Italian,
+            }
+        }
+    }
+
+    impl From<Stemmer> for StemmerLanguage {
+        fn from(stemmer: Stemmer) -> Self {
+            match stemmer {
+                Stemmer::NoStemmer => Self::None,
+                Stemmer::English => Self::English,
+                Stemmer::German => Self::German,
+                Stemmer::Spanish => Self::Spanish,
+                Stemmer::Italian => Self::Italian,
+            }
+        }
+    }
+
+    impl From<TextAnalyzerConfig> for TextAnalyzer {
+        fn from(config: TextAnalyzerConfig) -> Self {
+            let (tokenizer, ngram_size) = match config.tokenizer {
+                TokenizerConfig::Standard => (Tokenizer::Standard, None),
+                TokenizerConfig::Whitespace => (Tokenizer::Whitespace, None),
+                TokenizerConfig::CjkNgram { ngram_size } => {
+                    (Tokenizer::CjkNgram, Some(ngram_size as u32))
+                }
+            };
+            let (stopwords, custom_stopwords) = match config.stopwords {
+                StopwordsConfig::None => (Stopwords::NoStopwords, Vec::new()),
+                StopwordsConfig::English => (Stopwords::EnglishStopwords, Vec::new()),
+                StopwordsConfig::Custom(words) => (Stopwords::CustomStopwords, words),
+            };
+            Self {
+                tokenizer: Some(tokenizer.into()),
+                ngram_size,
+                lowercase: Some(config.lowercase),
+                ascii_folding: Some(config.ascii_folding),
+                stopwords: Some(stopwords.into()),
+                custom_stopwords,
+                stemmer: Some(Stemmer::from(config.stemmer).into()),
+                max_token_length: Some(config.max_token_length as u32),
+            }
+        }
+    }
+
+    fn parse_text_analyzer(analyzer: TextAnalyzer) -> Result<TextAnalyzerConfig, IndexesError> {
+        let invalid =
+            |field: &str| IndexesError::FailedToCreateIndex(format!("Invalid analyzer {}", field));
+        let default = TextAnalyzerConfig::default();
+
+        let tokenizer = match analyzer.tokenizer.map(Tokenizer::try_from) {
+            None => default.tokenizer,
+            Some(Ok(Tokenizer::Standard)) => TokenizerConfig::Standard,
+            Some(Ok(Tokenizer::Whitespace)) => TokenizerConfig::Whitespace,
+            Some(Ok(Tokenizer::CjkNgram)) => TokenizerConfig::CjkNgram {
+                ngram_size: analyzer
+                    .ngram_size
+                    .map_or(DEFAULT_NGRAM_SIZE, |size| size as usize),
+            },
+            Some(Err(_)) => return Err(invalid("tokenizer")),
+        };
+        let stopwords = match analyzer.stopwords.map(Stopwords::try_from) {
+            None => default.stopwords,
+            Some(Ok(Stopwords::EnglishStopwords)) => StopwordsConfig::English,
+            Some(Ok(Stopwords::NoStopwords)) => StopwordsConfig::None,
+            Some(Ok(Stopwords::CustomStopwords)) => {
+                StopwordsConfig::Custom(analyzer.custom_stopwords)
+            }
+            Some(Err(_)) => return Err(invalid("stopwords")),
+        };
+        let stemmer = match analyzer.stemmer.map(Stemmer::try_from) {
+            None => default.stemmer,
+            Some(Ok(stemmer)) => stemmer.into(),
+            Some(Err(_)) => return Err(invalid("stemmer")),
+        };
+
+        Ok(TextAnalyzerConfig {
+            tokenizer,
+            lowercase: analyzer.lowercase.unwrap_or(default.lowercase),
+            ascii_folding: analyzer.ascii_folding.unwrap_or(default.ascii_folding),
+            stopwords,
+            stemmer,
+            max_token_length: analyzer
+                .max_token_length
+                .map_or(default.max_token_length, |len| len as usize),
+        })
+    }
+
+    // Accepts the same values as the `distance_metric_type` field of the
+    // HTTP API, e.g. "cosine" or "dot_product"
+    fn parse_distance_metric(
+        distance_metric: &str,
+    ) -> Result<DistanceMetricSchema, IndexesError> {
+        DistanceMetricSchema::deserialize(distance_metric.into_deserializer()).map_err(
+            |e: DeError| {
+                IndexesError::FailedToCreateIndex(format!("Invalid distance metric: {}", e))
+            },
+        )
+    }
+
+    fn parse_sparse_quantization(
+        quantization: u32,
+    ) -> Result<SparseIndexQuantization, IndexesError> {
+        match quantization {
+            16 => Ok(SparseIndexQuantization::B16),
+            32 => Ok(SparseIndexQuantization::B32),
+            64 => Ok(SparseIndexQuantization::B64),
+            128 => Ok(SparseIndexQuantization::B128),
+            256 => Ok(SparseIndexQuantization::B256),
+            _ => Err(IndexesError::FailedToCreateIndex(format!(
+                "Invalid value for quantization: {}. Expected 16, 32, 64, 128 or 256.",
+                quantization
+            ))),
+        }
+    }
+
+    #[tonic::async_trait]
+    impl IndexesService for IndexesServiceImpl {
+        async fn create_dense_index(
+            &self,
+            request: Request<CreateDenseIndexRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::CreateIndex,
+            )?;
+            let req = request.into_inner();
+
+            let quantization = match req.quantization {
+                Some(Quantization::Auto(auto)) => DenseIndexQuantizationDto::Auto {
+                    sample_threshold: auto.sample_threshold as usize,
+                },
+                Some(Quantization::Scalar(scalar)) => {
+                    let data_type = DataType::try_from(scalar.data_type)
+                        .map_err(|_| Status::invalid_argument("Invalid data type"))?;
+                    let range = scalar
+                        .range
+                        .ok_or_else(|| Status::invalid_argument("Missing values range"))?;
+                    DenseIndexQuantizationDto::Scalar {
+                        data_type: data_type.into(),
+                        range: ValuesRangeDto {
+                            min: range.min,
+                            max: range.max,
+                        },
+                    }
+                }
+                None => return Err(Status::invalid_argument("Missing quantization")),
+            };
+
+            let create_index_dto = CreateDenseIndexDto {
+                name: req.name,
+                distance_metric_type: parse_distance_metric(&req.distance_metric_type)?,
+                quantization,
+                index: DenseIndexParamsDto::Hnsw(parse_hnsw_params(
+                    req.hnsw_params.unwrap_or_default(),
+                )?),
+                field: req.field,
+            };
+            service::create_dense_index(req.collection_id, create_index_dto, self.context.clone())
+                .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn create_sparse_index(
+            &self,
+            request: Request<CreateSparseIndexRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::CreateIndex,
+            )?;
+            let req = request.into_inner();
+
+            let create_index_dto = CreateSparseIndexDto {
+                name: req.name,
+                quantization: parse_sparse_quantization(req.quantization)?,
+                sample_threshold: req.sample_threshold as usize,
+            };
+            service::create_sparse_index(req.collection_id, create_index_dto, self.context.clone())
+                .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn create_tf_idf_index(
+            &self,
+            request: Request<CreateTfIdfIndexRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::CreateIndex,
+            )?;
+            let req = request.into_inner();
+
+            let create_index_dto = CreateTFIDFIndexDto {
+                name: req.name,
+                sample_threshold: req.sample_threshold as usize,
+                k1: req.k1,
+                b: req.b,
+                analyzer: req
+                    .analyzer
+                    .map(parse_text_analyzer)
+                    .transpose()?
+                    .unwrap_or_default(),
+                store_positions: req.store_positions,
+                store_terms: req.store_terms,
+            };
+            service::create_tf_idf_index(req.collection_id, create_index_dto, self.context.clone())
+                .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn get_indexes(
+            &self,
+            request: Request<GetIndexesRequest>,
+        ) -> Result<Response<GetIndexesResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::ListIndex,
+            )?;
+            let index_details =
+                service::get_index(request.into_inner().collection_id, self.context.clone())
+                    .await?;
+
+            Ok(Response::new(GetIndexesResponse {
+                collection_name: index_details.collection_name,
+                indexes: index_details
+                    .indexes
+                    .into_iter()
+                    .map(IndexDetails::from)
+                    .collect(),
+            }))
+        }
+
+        async fn delete_index(
+            &self,
+            request: Request<DeleteIndexRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::DeleteIndex,
+            )?;
+            let req = request.into_inner();
+            let index_type = IndexType::try_from(req.index_type)
+                .map_err(|_| Status::invalid_argument("Invalid index type"))?;
+
+            service::delete_index(
+                req.collection_id,
+                index_type.into(),
+                req.field,
+                self.context.clone(),
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,547 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tonic::Code;
+
+use crate::app_context::AppContext;
+use crate::grpc::indexes::IndexesServiceImpl;
+use crate::grpc::proto::create_dense_index_request::Quantization;
+use crate::grpc::proto::hnsw_params::QueryPrecision;
+use crate::grpc::proto::index_details::Details;
+use crate::grpc::proto::indexes_service_server::IndexesService;
+use crate::grpc::proto::search_service_server::SearchService;
+use crate::grpc::proto::text_analyzer::{Stemmer, Stopwords, Tokenizer};
+use crate::grpc::proto::{
+    AutoQuantization, CreateDenseIndexRequest, CreateSparseIndexRequest, CreateTfIdfIndexRequest,
+    DataType, DeleteIndexRequest, DenseSearchRequest, GetIndexesRequest, HnswParams, IndexType,
+    ScalarQuantization, SimilarVectorMatch, StreamUpsertRequest, TextAnalyzer, ValuesRange, Vector,
+};
+use crate::grpc::search::SearchServiceImpl;
+use crate::grpc::streaming::upsert_stream;
+use crate::grpc::test_utils::{admin_request, create_test_collection, test_context};
+use crate::indexes::hnsw::DenseSearchOptions;
+use crate::models::common::remove_duplicates_and_filter;
+
+fn dense_index_request(collection_id: &str, distance_metric: &str) -> CreateDenseIndexRequest {
+    CreateDenseIndexRequest {
+        collection_id: collection_id.to_string(),
+        name: "dense".to_string(),
+        distance_metric_type: distance_metric.to_string(),
+        quantization: Some(Quantization::Auto(AutoQuantization {
+            sample_threshold: 100,
+        })),
+        hnsw_params: Some(HnswParams {
+            ef_construction: Some(64),
+            ..Default::default()
+        }),
+        field: None,
+    }
+}
+
+#[tokio::test]
+async fn test_dense_index_lifecycle() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_dense", 4).await;
+    let service = IndexesServiceImpl {
+        context: context.clone(),
+    };
+
+    service
+        .create_dense_index(admin_request(dense_index_request(
+            "grpc_indexes_dense",
+            "cosine",
+        )))
+        .await
+        .unwrap();
+
+    let status = service
+        .create_dense_index(admin_request(dense_index_request(
+            "grpc_indexes_dense",
+            "cosine",
+        )))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let indexes = service
+        .get_indexes(admin_request(GetIndexesRequest {
+            collection_id: "grpc_indexes_dense".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(indexes.collection_name, "grpc_indexes_dense");
+    assert_eq!(indexes.indexes.len(), 1);
+    match indexes.indexes[0].details.as_ref().unwrap() {
+        Details::Dense(dense) => {
+            assert_eq!(
+                dense.hnsw_params.as_ref().unwrap().ef_construction,
+                Some(64)
+            );
+        }
+        _ => panic!("Expected dense index details"),
+    }
+
+    service
+        .delete_index(admin_request(DeleteIndexRequest {
+            collection_id: "grpc_indexes_dense".to_string(),
+            index_type: IndexType::Dense as i32,
+            field: None,
+        }))
+        .await
+        .unwrap();
+    let indexes = service
+        .get_indexes(admin_request(GetIndexesRequest {
+            collection_id: "grpc_indexes_dense".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(indexes.indexes.is_empty());
+}
+
+#[tokio::test]
+async fn test_binary_dense_index_oversampling() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_binary", 4).await;
+    let service = IndexesServiceImpl { context };
+
+    let mut request = dense_index_request("grpc_indexes_binary", "cosine");
+    request.quantization = Some(Quantization::Scalar(ScalarQuantization {
+        data_type: DataType::Binary as i32,
+        range: Some(ValuesRange {
+            min: -1.0,
+            max: 1.0,
+        }),
+    }));
+    request.hnsw_params = Some(HnswParams {
+        oversampling: Some(0.5),
+        ..Default::default()
+    });
+    let status = service
+        .create_dense_index(admin_request(request.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    request.hnsw_params = Some(HnswParams {
+        oversampling: Some(8.0),
+        ..Default::default()
+    });
+    service
+        .create_dense_index(admin_request(request))
+        .await
+        .unwrap();
+
+    let indexes = service
+        .get_indexes(admin_request(GetIndexesRequest {
+            collection_id: "grpc_indexes_binary".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    match indexes.indexes[0].details.as_ref().unwrap() {
+        Details::Dense(dense) => {
+            assert_eq!(dense.storage, "SubByte(1)");
+            assert_eq!(dense.hnsw_params.as_ref().unwrap().oversampling, Some(8.0));
+        }
+        _ => panic!("Expected dense index details"),
+    }
+}
+
+const TEST_QUERY: [f32; 4] = [0.3, 0.7, 0.2, 0.5];
+
+fn test_vector(i: usize) -> Vec<f32> {
+    (0..4)
+        .map(|d| ((i * 37 + d * 11) % 101) as f32 / 101.0)
+        .collect()
+}
+
+fn cosine_similarity(x: &[f32], y: &[f32]) -> f32 {
+    let dot_product: f32 = x.iter().zip(y).map(|(x, y)| x * y).sum();
+    let mag = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot_product / (mag(x) * mag(y))
+}
+
+async fn upsert_dense_vectors(
+    context: &Arc<AppContext>,
+    collection_id: &str,
+    vectors: Vec<(String, Vec<f32>)>,
+) {
+    let count = vectors.len() as u64;
+    let vectors = vectors
+        .into_iter()
+        .map(|(id, dense_values)| Vector {
+            id,
+            document_id: None,
+            dense_values,
+            sparse_values: Vec::new(),
+            text: None,
+            text_fields: HashMap::new(),
+            dense_fields: HashMap::new(),
+            multi_vector_values: Vec::new(),
+            metadata: HashMap::new(),
+        })
+        .collect();
+    let request = StreamUpsertRequest {
+        collection_id: collection_id.to_string(),
+        transaction_id: None,
+        vectors,
+    };
+    let claims = admin_request(()).extensions().get().cloned().unwrap();
+    let responses: Vec<_> = upsert_stream(
+        context.clone(),
+        claims,
+        futures_util::stream::iter(vec![Ok(request)]),
+    )
+    .collect()
+    .await;
+    assert_eq!(responses[0].as_ref().unwrap().vectors_upserted, count);
+}
+
+// The top 5 matches of `TEST_QUERY`
+async fn dense_search(
+    context: &Arc<AppContext>,
+    collection_id: &str,
+    exact: bool,
+) -> Vec<SimilarVectorMatch> {
+    let search_service = SearchServiceImpl {
+        context: context.clone(),
+    };
+    let request = DenseSearchRequest {
+        collection_id: collection_id.to_string(),
+        query_vector: TEST_QUERY.to_vec(),
+        top_k: Some(5),
+        filter: None,
+        return_raw_text: false,
+        ef_search: None,
+        filtered_candidates_limit: None,
+        exact,
+        field: None,
+    };
+    search_service
+        .dense_search(admin_request(request))
+        .await
+        .unwrap()
+        .into_inner()
+        .results
+        .unwrap()
+        .matches
+}
+
+#[tokio::test]
+async fn test_dense_index_query_precision() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_query_precision", 4).await;
+    let service = IndexesServiceImpl {
+        context: context.clone(),
+    };
+
+    // Unquantized queries can't be scored against hamming distances
+    let mut request = dense_index_request("grpc_indexes_query_precision", "hamming");
+    request.hnsw_params = Some(HnswParams {
+        query_precision: Some(QueryPrecision::F32.into()),
+        ..Default::default()
+    });
+    let status = service
+        .create_dense_index(admin_request(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let mut traversal_errors = Vec::new();
+    for precision in [
+        QueryPrecision::Quantized,
+        QueryPrecision::F32,
+        QueryPrecision::F16,
+    ] {
+        let collection_id = format!("grpc_indexes_query_precision_{:?}", precision);
+        create_test_collection(&context, &collection_id, 4).await;
+        let mut request = dense_index_request(&collection_id, "cosine");
+        request.quantization = Some(Quantization::Scalar(ScalarQuantization {
+            data_type: DataType::U8 as i32,
+            range: Some(ValuesRange { min: 0.0, max: 1.0 }),
+        }));
+        request.hnsw_params = Some(HnswParams {
+            query_precision: Some(precision.into()),
+            ..Default::default()
+        });
+        service
+            .create_dense_index(admin_request(request))
+            .await
+            .unwrap();
+
+        let indexes = service
+            .get_indexes(admin_request(GetIndexesRequest {
+                collection_id: collection_id.clone(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        match indexes.indexes[0].details.as_ref().unwrap() {
+            Details::Dense(dense) => {
+                assert_eq!(
+                    dense.hnsw_params.as_ref().unwrap().query_precision,
+                    Some(precision.into())
+                );
+            }
+            _ => panic!("Expected dense index details"),
+        }
+
+        let vectors = (0..32)
+            .map(|i| (format!("v{}", i), test_vector(i)))
+            .collect();
+        upsert_dense_vectors(&context, &collection_id, vectors).await;
+
+        // The results are re-scored with the raw vectors, so the index has
+        // to find the same nearest vectors as the exact search at every
+        // precision
+        let expected = dense_search(&context, &collection_id, true).await;
+        let actual = dense_search(&context, &collection_id, false).await;
+        assert_eq!(actual.len(), expected.len(), "{:?}", precision);
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert_eq!(actual.id, expected.id, "{:?}", precision);
+            assert!(
+                (actual.score - expected.score).abs() < 1e-5,
+                "{:?}: {} != {}",
+                precision,
+                actual.score,
+                expected.score
+            );
+        }
+
+        // The traversal scores the query in its precision against the
+        // quantized stored vectors, measure how far that is from the
+        // cosine similarities of the raw vectors
+        let collection = context
+            .ain_env
+            .collections_map
+            .get_collection(&collection_id)
+            .unwrap();
+        let hnsw_index = collection.get_hnsw_index().unwrap();
+        let query = TEST_QUERY;
+        let nodes = hnsw_index
+            .ann_search_nodes(
+                &collection,
+                &query,
+                None,
+                &DenseSearchOptions::default(),
+                &context.config,
+            )
+            .unwrap();
+        let distances = remove_duplicates_and_filter(&hnsw_index, nodes, None, &hnsw_index.cache);
+        assert!(distances.len() >= 5, "{:?}", precision);
+        let error = distances
+            .iter()
+            .map(|(internal_id, distance)| {
+                let raw_emb = collection.get_raw_emb_by_internal_id(internal_id).unwrap();
+                let raw_vector = hnsw_index.raw_vector(raw_emb).unwrap();
+                (distance.get_value() - cosine_similarity(&query, raw_vector)).abs()
+            })
+            .sum::<f32>()
+            / distances.len() as f32;
+        traversal_errors.push((precision, error));
+    }
+
+    // The quantized query adds its own rounding error, about as large as
+    // the stored vectors', to the one of the stored vectors
+    let traversal_error = |precision: QueryPrecision| {
+        traversal_errors
+            .iter()
+            .find(|(p, _)| *p == precision)
+            .unwrap()
+            .1
+    };
+    assert!(
+        traversal_error(QueryPrecision::F32) < traversal_error(QueryPrecision::Quantized),
+        "{:?}",
+        traversal_errors
+    );
+    assert!(
+        traversal_error(QueryPrecision::F16) < traversal_error(QueryPrecision::Quantized),
+        "{:?}",
+        traversal_errors
+    );
+}
+
+#[tokio::test]
+async fn test_dense_index_euclidean_search() {
+    let context = test_context();
+    let collection_id = "grpc_indexes_euclidean_search";
+    create_test_collection(&context, collection_id, 4).await;
+    let service = IndexesServiceImpl {
+        context: context.clone(),
+    };
+    let mut request = dense_index_request(collection_id, "euclidean");
+    request.quantization = Some(Quantization::Scalar(ScalarQuantization {
+        data_type: DataType::U8 as i32,
+        range: Some(ValuesRange { min: 0.0, max: 1.0 }),
+    }));
+    service
+        .create_dense_index(admin_request(request))
+        .await
+        .unwrap();
+
+    // "scaled" points the same way as the query, so it's the most similar
+    // vector by cosine similarity, but it's far from it
+    let mut vectors: Vec<_> = (0..32)
+        .map(|i| (format!("v{}", i), test_vector(i)))
+        .collect();
+    vectors.push((
+        "scaled".to_string(),
+        TEST_QUERY.iter().map(|x| x * 0.1).collect(),
+    ));
+    let mut nearest: Vec<_> = vectors
+        .iter()
+        .map(|(id, vector)| {
+            let distance = TEST_QUERY
+                .iter()
+                .zip(vector)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+                .sqrt();
+            (id.clone(), distance)
+        })
+        .collect();
+    nearest.sort_by(|a, b| a.1.total_cmp(&b.1));
+    nearest.truncate(5);
+    upsert_dense_vectors(&context, collection_id, vectors).await;
+
+    for exact in [true, false] {
+        let matches = dense_search(&context, collection_id, exact).await;
+        assert_eq!(
+            matches.iter().map(|m| m.id.clone()).collect::<HashSet<_>>(),
+            nearest
+                .iter()
+                .map(|(id, _)| id.clone())
+                .collect::<HashSet<_>>(),
+            "exact: {}",
+            exact
+        );
+        // the scores are the euclidean distances, nearest first
+        for (m, (_, distance)) in matches.iter().zip(&nearest) {
+            assert!(
+                (m.score - distance).abs() < 1e-5,
+                "exact: {}, {}: {} != {}",
+                exact,
+                m.id,
+                m.score,
+                distance
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_sparse_index_quantization() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_sparse", 4).await;
+    let service = IndexesServiceImpl { context };
+
+    let mut request = CreateSparseIndexRequest {
+        collection_id: "grpc_indexes_sparse".to_string(),
+        name: "sparse".to_string(),
+        quantization: 48,
+        sample_threshold: 100,
+    };
+    let status = service
+        .create_sparse_index(admin_request(request.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    request.quantization = 64;
+    service
+        .create_sparse_index(admin_request(request))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_tf_idf_index_analyzer() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_tf_idf", 4).await;
+    let service = IndexesServiceImpl { context };
+
+    let mut request = CreateTfIdfIndexRequest {
+        collection_id: "grpc_indexes_tf_idf".to_string(),
+        name: "tf_idf".to_string(),
+        sample_threshold: 100,
+        k1: 1.2,
+        b: 0.75,
+        analyzer: Some(TextAnalyzer {
+            tokenizer: Some(Tokenizer::CjkNgram as i32),
+            stopwords: Some(42),
+            ..Default::default()
+        }),
+        store_positions: true,
+        store_terms: true,
+    };
+    let status = service
+        .create_tf_idf_index(admin_request(request.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    request.analyzer.as_mut().unwrap().stopwords = Some(Stopwords::NoStopwords as i32);
+    service
+        .create_tf_idf_index(admin_request(request))
+        .await
+        .unwrap();
+
+    let indexes = service
+        .get_indexes(admin_request(GetIndexesRequest {
+            collection_id: "grpc_indexes_tf_idf".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    match indexes.indexes[0].details.as_ref().unwrap() {
+        Details::TfIdf(tf_idf) => {
+            let analyzer = tf_idf.analyzer.as_ref().unwrap();
+            assert_eq!(analyzer.tokenizer, Some(Tokenizer::CjkNgram as i32));
+            assert_eq!(analyzer.ngram_size, Some(2));
+            assert_eq!(analyzer.stopwords, Some(Stopwords::NoStopwords as i32));
+            // unset fields fall back to the defaults of the analyzer
+            assert_eq!(analyzer.stemmer, Some(Stemmer::English as i32));
+            assert_eq!(analyzer.lowercase, Some(true));
+            assert!(tf_idf.store_positions);
+            assert!(tf_idf.store_terms);
+        }
+        _ => panic!("Expected TF-IDF index details"),
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_index_requests() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_indexes_invalid", 4).await;
+    let service = IndexesServiceImpl { context };
+
+    let status = service
+        .create_dense_index(admin_request(dense_index_request(
+            "grpc_indexes_invalid",
+            "manhattan",
+        )))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = service
+        .get_indexes(admin_request(GetIndexesRequest {
+            collection_id: "grpc_indexes_missing".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    // requests which didn't go through the interceptor carry no claims
+    let status = service
+        .get_indexes(tonic::Request::new(GetIndexesRequest {
+            collection_id: "grpc_indexes_invalid".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
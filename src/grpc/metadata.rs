@@ -1,4 +1,5 @@
 use crate::grpc::proto;
+use crate::metadata::query_filtering::{Filter, Operator, Predicate};
 use crate::metadata::{schema, FieldValue};
 use std::collections::HashSet;
 
@@ -111,6 +112,45 @@ impl From<schema::MetadataSchema> for proto::MetadataSchema {
     }
 }
 
+// Filter conversions
+impl TryFrom<proto::Predicate> for Predicate {
+    type Error = String;
+    fn try_from(predicate: proto::Predicate) -> Result<Self, Self::Error> {
+        let operator = match proto::predicate::Operator::try_from(predicate.operator) {
+            Ok(proto::predicate::Operator::Equal) => Operator::Equal,
+            Ok(proto::predicate::Operator::NotEqual) => Operator::NotEqual,
+            Err(_) => return Err(format!("Invalid operator: {}", predicate.operator)),
+        };
+        Ok(Predicate {
+            field_name: predicate.field_name,
+            field_value: predicate
+                .field_value
+                .ok_or("Predicate must have a field value")?
+                .try_into()?,
+            operator,
+        })
+    }
+}
+
+impl TryFrom<proto::Filter> for Filter {
+    type Error = String;
+    fn try_from(filter: proto::Filter) -> Result<Self, Self::Error> {
+        let predicates = |predicates: proto::Predicates| {
+            predicates
+                .predicates
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<Predicate>, _>>()
+        };
+        match filter.filter {
+            Some(proto::filter::Filter::Is(predicate)) => Ok(Filter::Is(predicate.try_into()?)),
+            Some(proto::filter::Filter::And(and)) => Ok(Filter::And(predicates(and)?)),
+            Some(proto::filter::Filter::Or(or)) => Ok(Filter::Or(predicates(or)?)),
+            None => Err("Filter must have a condition".to_string()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +249,37 @@ mod tests {
         assert_eq!(converted_back.fields.len(), schema.fields.len());
         assert_eq!(converted_back.conditions.len(), schema.conditions.len());
     }
+
+    #[test]
+    fn test_filter_conversion() {
+        let predicate = |name: &str, value: i32| proto::Predicate {
+            field_name: name.to_string(),
+            field_value: Some(FieldValue::Int(value).into()),
+            operator: proto::predicate::Operator::NotEqual as i32,
+        };
+
+        let filter = proto::Filter {
+            filter: Some(proto::filter::Filter::And(proto::Predicates {
+                predicates: vec![predicate("age", 1), predicate("level", 2)],
+            })),
+        };
+        match Filter::try_from(filter).unwrap() {
+            Filter::And(predicates) => {
+                assert_eq!(predicates.len(), 2);
+                assert_eq!(predicates[1].field_name, "level");
+                assert_eq!(predicates[1].operator, Operator::NotEqual);
+                assert!(matches!(predicates[1].field_value, FieldValue::Int(2)));
+            }
+            _ => panic!("Expected an AND filter"),
+        }
+
+        // Test invalid filters
+        assert!(Filter::try_from(proto::Filter { filter: None }).is_err());
+        let mut invalid = predicate("age", 1);
+        invalid.operator = 7;
+        let filter = proto::Filter {
+            filter: Some(proto::filter::Filter::Is(invalid)),
+        };
+        assert!(Filter::try_from(filter).is_err());
+    }
 }
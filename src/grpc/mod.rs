@@ -1,7 +1,15 @@
+pub mod auth;
 pub mod collections;
 pub mod error;
+pub mod indexes;
 pub mod metadata;
+pub mod search;
 pub mod server;
+pub mod streaming;
+#[cfg(test)]
+pub mod test_utils;
+pub mod transactions;
+pub mod users;
 pub mod vectors;
 
 #[cfg(feature = "grpc-server")]
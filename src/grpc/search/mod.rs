@@ -0,0 +1,538 @@
+use futures_util::{stream, Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::api::vectordb::search::dtos::{
+    default_fusion_constant_k, default_top_k, BatchDenseSearchRequestDto,
+    BatchDenseSearchRequestQueryDto, BatchSearchResponseDto, BatchSearchTFIDFDocumentsDto,
+    BatchSparseSearchRequestDto, DenseSearchRequestDto, FusionMethod, HybridSearchQuery,
+    HybridSearchRequestDto, MultiVectorSearchRequestDto, RetrieverOptions, SearchResponseDto,
+    SearchResultItemDto,
+};
+use crate::api::vectordb::search::error::SearchError;
+use crate::api::vectordb::search::highlight::HighlightOptions;
+use crate::api::vectordb::search::service;
+use crate::app_context::AppContext;
+use crate::indexes::inverted::types::SparsePair;
+use crate::metadata::query_filtering::Filter;
+use crate::models::rbac::Permission;
+
+crate::cfg_grpc! {
+    use super::auth::authorize;
+    use super::proto::{
+        batch_search_request::Queries, hybrid_search_request::Query,
+        search_service_server::SearchService, BatchSearchRequest, BatchSearchResponse,
+        BatchSearchResult, DenseSearchRequest, FindSimilarVectorsResponse,
+        FusionMethod as ProtoFusionMethod, Highlight, HybridSearchRequest,
+        MultiVectorSearchRequest, RetrieverOptions as ProtoRetrieverOptions, SearchResults,
+        SimilarVectorMatch, SparsePair as ProtoSparsePair,
+    };
+
+    /// Number of queries of a streamed batch search which are searched
+    /// together before their results are sent back
+    const STREAM_CHUNK_SIZE: usize = 16;
+
+    pub struct SearchServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    pub type StreamBatchSearchStream =
+        Pin<Box<dyn Stream<Item = Result<BatchSearchResult, Status>> + Send>>;
+
+    impl From<SearchResultItemDto> for SimilarVectorMatch {
+        fn from(item: SearchResultItemDto) -> Self {
+            Self {
+                id: item.id.into(),
+                document_id: item.document_id.map(Into::into),
+                score: item.score,
+                text: item.text,
+                highlights: item.highlights.unwrap_or_default(),
+            }
+        }
+    }
+
+    impl From<SearchResponseDto> for FindSimilarVectorsResponse {
+        fn from(response: SearchResponseDto) -> Self {
+            Self {
+                results: Some(search_results(response.results)),
+                warning: response.warning,
+            }
+        }
+    }
+
+    fn search_results(results: Vec<SearchResultItemDto>) -> SearchResults {
+        SearchResults {
+            matches: results.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    fn sparse_pairs(pairs: Vec<ProtoSparsePair>) -> Vec<SparsePair> {
+        pairs
+            .into_iter()
+            .map(|pair| SparsePair(pair.index, pair.value))
+            .collect()
+    }
+
+    impl From<Highlight> for HighlightOptions {
+        fn from(highlight: Highlight) -> Self {
+            let defaults = Self::default();
+            Self {
+                query: highlight.query,
+                fragment_size: highlight
+                    .fragment_size
+                    .map_or(defaults.fragment_size, |size| size as usize),
+                number_of_fragments: highlight
+                    .number_of_fragments
+                    .map_or(defaults.number_of_fragments, |count| count as usize),
+                pre_tag: highlight.pre_tag.unwrap_or(defaults.pre_tag),
+                post_tag: highlight.post_tag.unwrap_or(defaults.post_tag),
+            }
+        }
+    }
+
+    impl From<ProtoRetrieverOptions> for RetrieverOptions {
+        fn from(options: ProtoRetrieverOptions) -> Self {
+            let defaults = Self::default();
+            Self {
+                weight: options.weight.unwrap_or(defaults.weight),
+                top_k: options.top_k.map(|top_k| top_k as usize),
+            }
+        }
+    }
+
+    fn parse_filter(filter: Option<super::proto::Filter>) -> Result<Option<Filter>, SearchError> {
+        filter
+            .map(Filter::try_from)
+            .transpose()
+            .map_err(SearchError::InvalidFilter)
+    }
+
+    // Queries of a batch search, so that they can be searched in chunks
+    enum BatchQueries {
+        Dense(Vec<BatchDenseSearchRequestQueryDto>),
+        Sparse(Vec<Vec<SparsePair>>),
+        TfIdf(Vec<String>),
+    }
+
+    impl BatchQueries {
+        fn parse(queries: Queries) -> Result<Self, SearchError> {
+            Ok(match queries {
+                Queries::Dense(dense) => Self::Dense(
+                    dense
+                        .queries
+                        .into_iter()
+                        .map(|query| {
+                            Ok(BatchDenseSearchRequestQueryDto {
+                                vector: query.vector,
+                                filter: parse_filter(query.filter)?,
+                            })
+                        })
+                        .collect::<Result<_, SearchError>>()?,
+                ),
+                Queries::Sparse(sparse) => Self::Sparse(
+                    sparse
+                        .queries
+                        .into_iter()
+                        .map(|query| sparse_pairs(query.values))
+                        .collect(),
+                ),
+                Queries::TfIdf(tf_idf) => Self::TfIdf(tf_idf.queries),
+            })
+        }
+
+        fn len(&self) -> usize {
+            match self {
+                Self::Dense(queries) => queries.len(),
+                Self::Sparse(queries) => queries.len(),
+                Self::TfIdf(queries) => queries.len(),
+            }
+        }
+
+        fn into_chunks(self, chunk_size: usize) -> Vec<Self> {
+            fn chunks<T>(mut queries: Vec<T>, chunk_size: usize) -> Vec<Vec<T>> {
+                let mut chunks = Vec::new();
+                while queries.len() > chunk_size {
+                    let rest = queries.split_off(chunk_size);
+                    chunks.push(queries);
+                    queries = rest;
+                }
+                chunks.push(queries);
+                chunks
+            }
+            match self {
+                Self::Dense(queries) => chunks(queries, chunk_size)
+                    .into_iter()
+                    .map(Self::Dense)
+                    .collect(),
+                Self::Sparse(queries) => chunks(queries, chunk_size)
+                    .into_iter()
+                    .map(Self::Sparse)
+                    .collect(),
+                Self::TfIdf(queries) => chunks(queries, chunk_size)
+                    .into_iter()
+                    .map(Self::TfIdf)
+                    .collect(),
+            }
+        }
+    }
+
+    struct BatchSearchOptions {
+        top_k: Option<usize>,
+        early_terminate_threshold: Option<f32>,
+        return_raw_text: bool,
+        highlight: Option<HighlightOptions>,
+        field_weights: Option<HashMap<String, f32>>,
+        ef_search: Option<u32>,
+        filtered_candidates_limit: Option<usize>,
+        exact: bool,
+        field: Option<String>,
+    }
+
+    fn batch_permission(request: &BatchSearchRequest) -> Option<Permission> {
+        match request.queries {
+            Some(Queries::Dense(_)) => Some(Permission::QueryDenseVectors),
+            Some(Queries::Sparse(_)) | Some(Queries::TfIdf(_)) => {
+                Some(Permission::QuerySparseVectors)
+            }
+            None => None,
+        }
+    }
+
+    fn parse_batch_request(
+        request: BatchSearchRequest,
+    ) -> Result<(BatchQueries, BatchSearchOptions), SearchError> {
+        let queries = request
+            .queries
+            .ok_or_else(|| SearchError::InvalidInput("Queries must be specified".to_string()))?;
+        Ok((
+            BatchQueries::parse(queries)?,
+            BatchSearchOptions {
+                top_k: request.top_k.map(|top_k| top_k as usize),
+                early_terminate_threshold: request.early_terminate_threshold,
+                return_raw_text: request.return_raw_text,
+                highlight: request.highlight.map(Into::into),
+                field_weights: Some(request.field_weights).filter(|weights| !weights.is_empty()),
+                ef_search: request.ef_search,
+                filtered_candidates_limit: request
+                    .filtered_candidates_limit
+                    .map(|limit| limit as usize),
+                exact: request.exact,
+                field: request.field,
+            },
+        ))
+    }
+
+    async fn batch_search(
+        context: Arc<AppContext>,
+        collection_id: &str,
+        queries: BatchQueries,
+        options: &BatchSearchOptions,
+    ) -> Result<BatchSearchResponseDto, SearchError> {
+        match queries {
+            BatchQueries::Dense(queries) => {
+                service::batch_dense_search(
+                    context,
+                    collection_id,
+                    BatchDenseSearchRequestDto {
+                        queries,
+                        top_k: options.top_k,
+                        return_raw_text: options.return_raw_text,
+                        ef_search: options.ef_search,
+                        filtered_candidates_limit: options.filtered_candidates_limit,
+                        exact: options.exact,
+                        field: options.field.clone(),
+                    },
+                )
+                .await
+            }
+            BatchQueries::Sparse(query_terms_list) => {
+                service::batch_sparse_search(
+                    context,
+                    collection_id,
+                    BatchSparseSearchRequestDto {
+                        query_terms_list,
+                        top_k: options.top_k,
+                        early_terminate_threshold: options.early_terminate_threshold,
+                        return_raw_text: options.return_raw_text,
+                        highlight: options.highlight.clone(),
+                    },
+                )
+                .await
+            }
+            BatchQueries::TfIdf(queries) => {
+                service::batch_tf_idf_search(
+                    context,
+                    collection_id,
+                    BatchSearchTFIDFDocumentsDto {
+                        queries,
+                        top_k: options.top_k,
+                        return_raw_text: options.return_raw_text,
+                        highlight: options.highlight.clone(),
+                        field_weights: options.field_weights.clone(),
+                    },
+                )
+                .await
+            }
+        }
+    }
+
+    struct BatchStreamState {
+        context: Arc<AppContext>,
+        collection_id: String,
+        options: BatchSearchOptions,
+        chunks: std::vec::IntoIter<BatchQueries>,
+        // index of the first query of the next chunk
+        offset: usize,
+    }
+
+    /// Searches the queries in chunks of `STREAM_CHUNK_SIZE`, yielding the
+    /// results of every query of a chunk once the chunk is searched. The
+    /// stream ends at the first error.
+    fn batch_search_stream(
+        context: Arc<AppContext>,
+        collection_id: String,
+        queries: BatchQueries,
+        options: BatchSearchOptions,
+    ) -> StreamBatchSearchStream {
+        let state = BatchStreamState {
+            context,
+            collection_id,
+            options,
+            chunks: queries.into_chunks(STREAM_CHUNK_SIZE).into_iter(),
+            offset: 0,
+        };
+
+        Box::pin(
+            stream::unfold(Some(state), |state| async move {
+                let mut state = state?;
+                let chunk = state.chunks.next()?;
+                let chunk_len = chunk.len();
+                let response = match batch_search(
+                    state.context.clone(),
+                    &state.collection_id,
+                    chunk,
+                    &state.options,
+                )
+                .await
+                {
+                    Ok(response) => response,
+                    Err(err) => return Some((vec![Err(Status::from(err))], None)),
+                };
+
+                let offset = state.offset;
+                let results: Vec<_> = response
+                    .responses
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, response)| BatchSearchResult {
+                        query_index: (offset + i) as u32,
+                        results: Some(search_results(response.results)),
+                        warning: response.warning,
+                    })
+                    .map(Ok)
+                    .collect();
+                state.offset += chunk_len;
+                Some((results, Some(state)))
+            })
+            .flat_map(stream::iter),
+        )
+    }
+
+    #[tonic::async_trait]
+    impl SearchService for SearchServiceImpl {
+        type StreamBatchSearchStream = StreamBatchSearchStream;
+
+        async fn dense_search(
+            &self,
+            request: Request<DenseSearchRequest>,
+        ) -> Result<Response<FindSimilarVectorsResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::QueryDenseVectors,
+            )?;
+            let req = request.into_inner();
+
+            let response = service::dense_search(
+                self.context.clone(),
+                &req.collection_id,
+                DenseSearchRequestDto {
+                    query_vector: req.query_vector,
+                    top_k: req.top_k.map(|top_k| top_k as usize),
+                    filter: parse_filter(req.filter)?,
+                    return_raw_text: req.return_raw_text,
+                    ef_search: req.ef_search,
+                    filtered_candidates_limit: req
+                        .filtered_candidates_limit
+                        .map(|limit| limit as usize),
+                    exact: req.exact,
+                    field: req.field,
+                },
+            )
+            .await?;
+
+            Ok(Response::new(response.into()))
+        }
+
+        async fn multi_vector_search(
+            &self,
+            request: Request<MultiVectorSearchRequest>,
+        ) -> Result<Response<FindSimilarVectorsResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::QueryDenseVectors,
+            )?;
+            let req = request.into_inner();
+
+            let response = service::multi_vector_search(
+                self.context.clone(),
+                &req.collection_id,
+                MultiVectorSearchRequestDto {
+                    query_vectors: req
+                        .query_vectors
+                        .into_iter()
+                        .map(|vector| vector.values)
+                        .collect(),
+                    top_k: req.top_k.map(|top_k| top_k as usize),
+                    filter: parse_filter(req.filter)?,
+                    return_raw_text: req.return_raw_text,
+                    candidates_per_vector: req
+                        .candidates_per_vector
+                        .map(|candidates| candidates as usize),
+                    ef_search: req.ef_search,
+                    filtered_candidates_limit: req
+                        .filtered_candidates_limit
+                        .map(|limit| limit as usize),
+                    exact: req.exact,
+                },
+            )
+            .await?;
+
+            Ok(Response::new(response.into()))
+        }
+
+        async fn batch_search(
+            &self,
+            request: Request<BatchSearchRequest>,
+        ) -> Result<Response<BatchSearchResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                batch_permission(request.get_ref())
+                    .ok_or_else(|| Status::invalid_argument("Queries must be specified"))?,
+            )?;
+            let req = request.into_inner();
+            let collection_id = req.collection_id.clone();
+            let (queries, options) = parse_batch_request(req)?;
+
+            let response =
+                batch_search(self.context.clone(), &collection_id, queries, &options).await?;
+
+            Ok(Response::new(BatchSearchResponse {
+                results: response
+                    .responses
+                    .into_iter()
+                    .map(|response| search_results(response.results))
+                    .collect(),
+                warning: response.warning,
+            }))
+        }
+
+        async fn stream_batch_search(
+            &self,
+            request: Request<BatchSearchRequest>,
+        ) -> Result<Response<Self::StreamBatchSearchStream>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                batch_permission(request.get_ref())
+                    .ok_or_else(|| Status::invalid_argument("Queries must be specified"))?,
+            )?;
+            let req = request.into_inner();
+            let collection_id = req.collection_id.clone();
+            let (queries, options) = parse_batch_request(req)?;
+
+            Ok(Response::new(batch_search_stream(
+                self.context.clone(),
+                collection_id,
+                queries,
+                options,
+            )))
+        }
+
+        async fn hybrid_search(
+            &self,
+            request: Request<HybridSearchRequest>,
+        ) -> Result<Response<FindSimilarVectorsResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::QueryHybridVectors,
+            )?;
+            let req = request.into_inner();
+
+            let query = match req.query {
+                Some(Query::DenseAndSparse(query)) => HybridSearchQuery::DenseAndSparse {
+                    query_vector: query.query_vector,
+                    query_terms: sparse_pairs(query.query_terms),
+                    sparse_early_terminate_threshold: query.sparse_early_terminate_threshold,
+                },
+                Some(Query::DenseAndTfIdf(query)) => HybridSearchQuery::DenseAndTFIDF {
+                    query_vector: query.query_vector,
+                    query_text: query.query_text,
+                },
+                Some(Query::SparseAndTfIdf(query)) => HybridSearchQuery::SparseAndTFIDF {
+                    query_terms: sparse_pairs(query.query_terms),
+                    query_text: query.query_text,
+                    sparse_early_terminate_threshold: query.sparse_early_terminate_threshold,
+                },
+                Some(Query::DenseSparseAndTfIdf(query)) => HybridSearchQuery::DenseSparseAndTFIDF {
+                    query_vector: query.query_vector,
+                    query_terms: sparse_pairs(query.query_terms),
+                    query_text: query.query_text,
+                    sparse_early_terminate_threshold: query.sparse_early_terminate_threshold,
+                },
+                None => return Err(Status::invalid_argument("Query must be specified")),
+            };
+            let fusion_method = match ProtoFusionMethod::try_from(req.fusion_method) {
+                Ok(ProtoFusionMethod::Rrf) => FusionMethod::Rrf,
+                Ok(ProtoFusionMethod::MinMax) => FusionMethod::MinMax,
+                Ok(ProtoFusionMethod::ZScore) => FusionMethod::ZScore,
+                Err(_) => return Err(Status::invalid_argument("Invalid fusion method")),
+            };
+
+            let response = service::hybrid_search(
+                self.context.clone(),
+                &req.collection_id,
+                HybridSearchRequestDto {
+                    query,
+                    top_k: req.top_k.map_or_else(default_top_k, |top_k| top_k as usize),
+                    fusion_constant_k: req
+                        .fusion_constant_k
+                        .unwrap_or_else(default_fusion_constant_k),
+                    fusion_method,
+                    dense: req.dense.map(Into::into).unwrap_or_default(),
+                    sparse: req.sparse.map(Into::into).unwrap_or_default(),
+                    tf_idf: req.tf_idf.map(Into::into).unwrap_or_default(),
+                    return_raw_text: req.return_raw_text,
+                    highlight: req.highlight.map(Into::into),
+                    field_weights: Some(req.field_weights).filter(|weights| !weights.is_empty()),
+                },
+            )
+            .await?;
+
+            Ok(Response::new(response.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
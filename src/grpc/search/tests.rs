@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+use tonic::Code;
+
+use crate::grpc::collections::CollectionsServiceImpl;
+use crate::grpc::indexes::IndexesServiceImpl;
+use crate::grpc::proto::collections_service_server::CollectionsService;
+use crate::grpc::proto::create_dense_index_request::Quantization;
+use crate::grpc::proto::indexes_service_server::IndexesService;
+use crate::grpc::proto::search_service_server::SearchService;
+use crate::grpc::proto::{
+    batch_search_request::Queries, hybrid_search_request::Query, BatchSearchRequest,
+    CollectionConfig, CreateCollectionRequest, CreateDenseIndexRequest, CreateSparseIndexRequest,
+    DataType, DenseAndSparseQuery, DenseQueries, DenseQuery, DenseSearchRequest, DenseValues,
+    DenseVectorOptions, Filter, FusionMethod, HnswParams, HybridSearchRequest,
+    MultiVectorSearchRequest, ScalarQuantization, SparsePair, StreamUpsertRequest, ValuesRange,
+    Vector,
+};
+use crate::grpc::search::SearchServiceImpl;
+use crate::grpc::streaming::upsert_stream;
+use crate::grpc::test_utils::{admin_request, create_test_collection, test_context};
+
+const DIMENSION: usize = 4;
+
+fn vector(i: usize) -> Vec<f32> {
+    (0..DIMENSION)
+        .map(|d| ((i * DIMENSION + d) % 7) as f32 / 7.0)
+        .collect()
+}
+
+// Creates a collection with dense and sparse indexes and 32 vectors
+async fn setup(collection_id: &str) -> SearchServiceImpl {
+    let context = test_context();
+    create_test_collection(&context, collection_id, DIMENSION as u32).await;
+    let indexes_service = IndexesServiceImpl {
+        context: context.clone(),
+    };
+    indexes_service
+        .create_dense_index(admin_request(CreateDenseIndexRequest {
+            collection_id: collection_id.to_string(),
+            name: "dense".to_string(),
+            distance_metric_type: "cosine".to_string(),
+            quantization: Some(Quantization::Scalar(ScalarQuantization {
+                data_type: DataType::F32 as i32,
+                range: Some(ValuesRange { min: 0.0, max: 1.0 }),
+            })),
+            hnsw_params: None,
+            field: None,
+        }))
+        .await
+        .unwrap();
+    indexes_service
+        .create_sparse_index(admin_request(CreateSparseIndexRequest {
+            collection_id: collection_id.to_string(),
+            name: "sparse".to_string(),
+            quantization: 64,
+            sample_threshold: 1,
+        }))
+        .await
+        .unwrap();
+
+    let vectors = (0..32)
+        .map(|i| Vector {
+            id: format!("v{}", i),
+            document_id: None,
+            dense_values: vector(i),
+            sparse_values: vec![SparsePair {
+                index: i as u32 % 4,
+                value: 1.0,
+            }],
+            text: None,
+            text_fields: HashMap::new(),
+            dense_fields: HashMap::new(),
+            multi_vector_values: Vec::new(),
+            metadata: HashMap::new(),
+        })
+        .collect();
+    let request = StreamUpsertRequest {
+        collection_id: collection_id.to_string(),
+        transaction_id: None,
+        vectors,
+    };
+    let claims = admin_request(()).extensions().get().cloned().unwrap();
+    let responses: Vec<_> = upsert_stream(
+        context.clone(),
+        claims,
+        futures_util::stream::iter(vec![Ok(request)]),
+    )
+    .collect()
+    .await;
+    assert_eq!(responses[0].as_ref().unwrap().vectors_upserted, 32);
+
+    SearchServiceImpl { context }
+}
+
+fn dense_batch_request(collection_id: &str, count: usize) -> BatchSearchRequest {
+    BatchSearchRequest {
+        collection_id: collection_id.to_string(),
+        queries: Some(Queries::Dense(DenseQueries {
+            queries: (0..count)
+                .map(|i| DenseQuery {
+                    vector: vector(i),
+                    filter: None,
+                })
+                .collect(),
+        })),
+        top_k: Some(3),
+        early_terminate_threshold: None,
+        return_raw_text: false,
+        highlight: None,
+        field_weights: HashMap::new(),
+        ef_search: None,
+        filtered_candidates_limit: None,
+        exact: false,
+        field: None,
+    }
+}
+
+#[tokio::test]
+async fn test_dense_and_hybrid_search() {
+    let service = setup("grpc_search_single").await;
+
+    let response = service
+        .dense_search(admin_request(DenseSearchRequest {
+            collection_id: "grpc_search_single".to_string(),
+            query_vector: vector(5),
+            top_k: Some(3),
+            filter: None,
+            return_raw_text: false,
+            ef_search: Some(16),
+            filtered_candidates_limit: None,
+            exact: false,
+            field: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let matches = response.results.unwrap().matches;
+    assert_eq!(matches.len(), 3);
+    assert!(matches[0].score >= matches[2].score);
+
+    // the vectors repeat every 7 vectors, the exact search finds the copies
+    // of the query
+    let response = service
+        .dense_search(admin_request(DenseSearchRequest {
+            collection_id: "grpc_search_single".to_string(),
+            query_vector: vector(5),
+            top_k: Some(3),
+            filter: None,
+            return_raw_text: false,
+            ef_search: None,
+            filtered_candidates_limit: None,
+            exact: true,
+            field: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let matches = response.results.unwrap().matches;
+    assert_eq!(matches.len(), 3);
+    for m in &matches {
+        assert!(["v5", "v12", "v19", "v26"].contains(&m.id.as_str()));
+        assert!(m.score > 0.999);
+    }
+
+    let response = service
+        .hybrid_search(admin_request(HybridSearchRequest {
+            collection_id: "grpc_search_single".to_string(),
+            query: Some(Query::DenseAndSparse(DenseAndSparseQuery {
+                query_vector: vector(5),
+                query_terms: vec![SparsePair {
+                    index: 1,
+                    value: 1.0,
+                }],
+                sparse_early_terminate_threshold: None,
+            })),
+            top_k: Some(4),
+            fusion_constant_k: None,
+            return_raw_text: false,
+            highlight: None,
+            field_weights: HashMap::new(),
+            fusion_method: FusionMethod::Rrf.into(),
+            dense: None,
+            sparse: None,
+            tf_idf: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.results.unwrap().matches.len(), 4);
+
+    // a filter without any condition
+    let status = service
+        .dense_search(admin_request(DenseSearchRequest {
+            collection_id: "grpc_search_single".to_string(),
+            query_vector: vector(5),
+            top_k: None,
+            filter: Some(Filter { filter: None }),
+            return_raw_text: false,
+            ef_search: None,
+            filtered_candidates_limit: None,
+            exact: false,
+            field: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_batch_search_stream() {
+    let service = setup("grpc_search_batch").await;
+
+    // more queries than fit in a single streamed chunk
+    let response = service
+        .batch_search(admin_request(dense_batch_request("grpc_search_batch", 20)))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.results.len(), 20);
+
+    let streamed: Vec<_> = service
+        .stream_batch_search(admin_request(dense_batch_request("grpc_search_batch", 20)))
+        .await
+        .unwrap()
+        .into_inner()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(streamed.len(), 20);
+    for (i, result) in streamed.into_iter().enumerate() {
+        assert_eq!(result.query_index, i as u32);
+        let ids: Vec<_> = result
+            .results
+            .unwrap()
+            .matches
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        let expected: Vec<_> = response.results[i]
+            .matches
+            .iter()
+            .map(|m| m.id.clone())
+            .collect();
+        assert_eq!(ids, expected);
+    }
+
+    let mut request = dense_batch_request("grpc_search_batch", 1);
+    request.queries = None;
+    let status = service
+        .batch_search(admin_request(request))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = service
+        .batch_search(admin_request(dense_batch_request("grpc_search_missing", 1)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_binary_dense_search_rescoring() {
+    let context = test_context();
+    create_test_collection(&context, "grpc_search_binary", DIMENSION as u32).await;
+    IndexesServiceImpl {
+        context: context.clone(),
+    }
+    .create_dense_index(admin_request(CreateDenseIndexRequest {
+        collection_id: "grpc_search_binary".to_string(),
+        name: "dense".to_string(),
+        distance_metric_type: "cosine".to_string(),
+        quantization: Some(Quantization::Scalar(ScalarQuantization {
+            data_type: DataType::Binary as i32,
+            range: Some(ValuesRange {
+                min: -1.0,
+                max: 1.0,
+            }),
+        })),
+        hnsw_params: Some(HnswParams {
+            oversampling: Some(8.0),
+            ..Default::default()
+        }),
+        field: None,
+    }))
+    .await
+    .unwrap();
+
+    // all the components are positive, so every vector has the same sign
+    // bits and the 1-bit distances tie, only the re-scoring with the raw
+    // vectors tells them apart
+    let values = |i: usize| vec![0.9, 0.1 + 0.1 * i as f32, 0.5, 0.8 - 0.1 * i as f32];
+    let vectors = (0..6)
+        .map(|i| Vector {
+            id: format!("v{}", i),
+            document_id: None,
+            dense_values: values(i),
+            sparse_values: Vec::new(),
+            text: None,
+            text_fields: HashMap::new(),
+            dense_fields: HashMap::new(),
+            multi_vector_values: Vec::new(),
+            metadata: HashMap::new(),
+        })
+        .collect();
+    let request = StreamUpsertRequest {
+        collection_id: "grpc_search_binary".to_string(),
+        transaction_id: None,
+        vectors,
+    };
+    let claims = admin_request(()).extensions().get().cloned().unwrap();
+    let responses: Vec<_> = upsert_stream(
+        context.clone(),
+        claims,
+        futures_util::stream::iter(vec![Ok(request)]),
+    )
+    .collect()
+    .await;
+    assert_eq!(responses[0].as_ref().unwrap().vectors_upserted, 6);
+
+    let service = SearchServiceImpl { context };
+    let response = service
+        .dense_search(admin_request(DenseSearchRequest {
+            collection_id: "grpc_search_binary".to_string(),
+            query_vector: values(4),
+            top_k: Some(3),
+            filter: None,
+            return_raw_text: false,
+            ef_search: None,
+            filtered_candidates_limit: None,
+            exact: false,
+            field: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let ids: Vec<_> = response
+        .results
+        .unwrap()
+        .matches
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    assert_eq!(ids, ["v4", "v5", "v3"]);
+}
+
+#[tokio::test]
+async fn test_multi_vector_search() {
+    let context = test_context();
+    let collections_service = CollectionsServiceImpl {
+        context: context.clone(),
+    };
+    collections_service
+        .create_collection(admin_request(CreateCollectionRequest {
+            name: "grpc_search_multi_vector".to_string(),
+            description: None,
+            dense_vector: Some(DenseVectorOptions {
+                dimension: DIMENSION as u32,
+                enabled: true,
+                fields: Vec::new(),
+                multi_vector: true,
+            }),
+            sparse_vector: None,
+            tf_idf_options: None,
+            metadata_schema: None,
+            config: Some(CollectionConfig {
+                max_vectors: None,
+                replication_factor: None,
+            }),
+            store_raw_text: Some(false),
+        }))
+        .await
+        .unwrap();
+    IndexesServiceImpl {
+        context: context.clone(),
+    }
+    .create_dense_index(admin_request(CreateDenseIndexRequest {
+        collection_id: "grpc_search_multi_vector".to_string(),
+        name: "dense".to_string(),
+        distance_metric_type: "cosine".to_string(),
+        quantization: Some(Quantization::Scalar(ScalarQuantization {
+            data_type: DataType::F32 as i32,
+            range: Some(ValuesRange { min: 0.0, max: 1.0 }),
+        })),
+        hnsw_params: None,
+        field: None,
+    }))
+    .await
+    .unwrap();
+
+    // each embedding holds the token vectors `i` and `i + 1`
+    let vectors = (0..6)
+        .map(|i| Vector {
+            id: format!("v{}", i),
+            document_id: None,
+            dense_values: Vec::new(),
+            sparse_values: Vec::new(),
+            text: None,
+            text_fields: HashMap::new(),
+            dense_fields: HashMap::new(),
+            multi_vector_values: vec![
+                DenseValues { values: vector(i) },
+                DenseValues {
+                    values: vector(i + 1),
+                },
+            ],
+            metadata: HashMap::new(),
+        })
+        .collect();
+    let request = StreamUpsertRequest {
+        collection_id: "grpc_search_multi_vector".to_string(),
+        transaction_id: None,
+        vectors,
+    };
+    let claims = admin_request(()).extensions().get().cloned().unwrap();
+    let responses: Vec<_> = upsert_stream(
+        context.clone(),
+        claims,
+        futures_util::stream::iter(vec![Ok(request)]),
+    )
+    .collect()
+    .await;
+    assert_eq!(responses[0].as_ref().unwrap().vectors_upserted, 6);
+
+    let service = SearchServiceImpl { context };
+    let response = service
+        .multi_vector_search(admin_request(MultiVectorSearchRequest {
+            collection_id: "grpc_search_multi_vector".to_string(),
+            query_vectors: vec![
+                DenseValues { values: vector(2) },
+                DenseValues { values: vector(3) },
+            ],
+            top_k: Some(1),
+            filter: None,
+            return_raw_text: false,
+            candidates_per_vector: None,
+            ef_search: None,
+            filtered_candidates_limit: None,
+            exact: true,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let matches = response.results.unwrap().matches;
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].id, "v2");
+
+    // the default dense index holds token vectors, not embeddings
+    let status = service
+        .dense_search(admin_request(DenseSearchRequest {
+            collection_id: "grpc_search_multi_vector".to_string(),
+            query_vector: vector(2),
+            top_k: Some(1),
+            filter: None,
+            return_raw_text: false,
+            ef_search: None,
+            filtered_candidates_limit: None,
+            exact: false,
+            field: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = service
+        .hybrid_search(admin_request(HybridSearchRequest {
+            collection_id: "grpc_search_multi_vector".to_string(),
+            query: Some(Query::DenseAndSparse(DenseAndSparseQuery {
+                query_vector: vector(2),
+                query_terms: vec![SparsePair {
+                    index: 1,
+                    value: 1.0,
+                }],
+                sparse_early_terminate_threshold: None,
+            })),
+            top_k: Some(1),
+            fusion_constant_k: None,
+            return_raw_text: false,
+            highlight: None,
+            field_weights: HashMap::new(),
+            fusion_method: FusionMethod::Rrf.into(),
+            dense: None,
+            sparse: None,
+            tf_idf: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
@@ -1,14 +1,27 @@
 use log::info;
+use std::net::ToSocketAddrs;
 use std::sync::Arc;
-use tonic::transport::Server;
+use tonic::service::Interceptor;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::{Request, Status};
 
+use super::auth::{AuthInterceptor, AuthServiceImpl};
 use super::collections::CollectionsServiceImpl;
+use super::indexes::IndexesServiceImpl;
 use super::proto::{
-    collections_service_server::CollectionsServiceServer,
-    vectors_service_server::VectorsServiceServer,
+    auth_service_server::AuthServiceServer, collections_service_server::CollectionsServiceServer,
+    indexes_service_server::IndexesServiceServer, search_service_server::SearchServiceServer,
+    streaming_service_server::StreamingServiceServer,
+    transactions_service_server::TransactionsServiceServer,
+    users_service_server::UsersServiceServer, vectors_service_server::VectorsServiceServer,
 };
+use super::search::SearchServiceImpl;
+use super::streaming::StreamingServiceImpl;
+use super::transactions::TransactionsServiceImpl;
+use super::users::UsersServiceImpl;
 use super::vectors::VectorsServiceImpl;
 use crate::app_context::AppContext;
+use crate::config_loader::{ServerMode, Ssl};
 use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
 
 fn reflection_service() -> ServerReflectionServer<impl ServerReflection> {
@@ -18,24 +31,135 @@ fn reflection_service() -> ServerReflectionServer<impl ServerReflection> {
         .unwrap()
 }
 
-pub async fn start_grpc_server(
+// Rejects the requests with `UNAVAILABLE` until the restart recovery
+// completes, then authenticates them with the inner interceptor
+#[derive(Clone)]
+struct RecoveryGateInterceptor<I> {
     context: Arc<AppContext>,
-    port: u16,
-) -> Result<(), Box<dyn std::error::Error>> {
-    const DEFAULT_HOST: &str = "[::1]";
-    let addr = format!("{}:{}", DEFAULT_HOST, port).parse()?;
+    inner: I,
+}
+
+impl<I: Interceptor> Interceptor for RecoveryGateInterceptor<I> {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        check_ready(&self.context)?;
+        self.inner.call(request)
+    }
+}
 
+// `UNAVAILABLE` until the restart recovery completes, for the services
+// only some of whose calls wait for it
+pub(crate) fn check_ready(context: &AppContext) -> Result<(), Status> {
+    match context.not_ready_reason() {
+        Some(reason) => Err(Status::unavailable(reason)),
+        None => Ok(()),
+    }
+}
+
+// Uses the same cert and key as the HTTP server
+fn load_tls_config(ssl_config: &Ssl) -> Result<ServerTlsConfig, Box<dyn std::error::Error>> {
+    // the HTTP server may have installed it already
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    let cert = std::fs::read(&ssl_config.cert_file).map_err(|e| {
+        format!(
+            "Failed to read certificate file {}: {}",
+            ssl_config.cert_file.display(),
+            e
+        )
+    })?;
+    let key = std::fs::read(&ssl_config.key_file).map_err(|e| {
+        format!(
+            "Failed to read key file {}: {}",
+            ssl_config.key_file.display(),
+            e
+        )
+    })?;
+    Ok(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+}
+
+pub async fn start_grpc_server(context: Arc<AppContext>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = context.config.clone();
+    let addr = config
+        .grpc
+        .listen_address()
+        .to_socket_addrs()?
+        .next()
+        .ok_or("Failed to resolve gRPC listen address")?;
+
+    let mut server = Server::builder();
+    if let ServerMode::Https = config.server.mode {
+        server = server.tls_config(load_tls_config(&config.server.ssl)?)?;
+    }
+
+    let auth_interceptor = AuthInterceptor {
+        context: context.clone(),
+    };
+    // searches and writes wait for the restart recovery
+    let gated_interceptor = RecoveryGateInterceptor {
+        context: context.clone(),
+        inner: auth_interceptor.clone(),
+    };
+    let auth_service = AuthServiceImpl {
+        context: context.clone(),
+    };
     let collections_service = CollectionsServiceImpl {
         context: context.clone(),
     };
+    let indexes_service = IndexesServiceImpl {
+        context: context.clone(),
+    };
+    let search_service = SearchServiceImpl {
+        context: context.clone(),
+    };
+    let streaming_service = StreamingServiceImpl {
+        context: context.clone(),
+    };
+    let transactions_service = TransactionsServiceImpl {
+        context: context.clone(),
+    };
+    let users_service = UsersServiceImpl {
+        context: context.clone(),
+    };
     let vectors_service = VectorsServiceImpl {
         context: context.clone(),
     };
 
-    info!("gRPC server listening on {}", addr);
-    Server::builder()
-        .add_service(CollectionsServiceServer::new(collections_service))
-        .add_service(VectorsServiceServer::new(vectors_service))
+    info!(
+        "gRPC server listening on {}://{}",
+        config.server.mode.protocol(),
+        addr
+    );
+    server
+        // the auth service hands out the tokens, so it can't require one
+        .add_service(AuthServiceServer::new(auth_service))
+        .add_service(CollectionsServiceServer::with_interceptor(
+            collections_service,
+            auth_interceptor.clone(),
+        ))
+        .add_service(IndexesServiceServer::with_interceptor(
+            indexes_service,
+            gated_interceptor.clone(),
+        ))
+        .add_service(SearchServiceServer::with_interceptor(
+            search_service,
+            gated_interceptor.clone(),
+        ))
+        .add_service(StreamingServiceServer::with_interceptor(
+            streaming_service,
+            gated_interceptor.clone(),
+        ))
+        .add_service(TransactionsServiceServer::with_interceptor(
+            transactions_service,
+            gated_interceptor.clone(),
+        ))
+        .add_service(UsersServiceServer::with_interceptor(
+            users_service,
+            auth_interceptor,
+        ))
+        .add_service(VectorsServiceServer::with_interceptor(
+            vectors_service,
+            gated_interceptor,
+        ))
         .add_service(reflection_service())
         .serve(addr)
         .await?;
@@ -0,0 +1,163 @@
+use futures_util::{stream, Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::api::auth::authorization_middleware::authorize;
+use crate::api::auth::dtos::Claims;
+use crate::api::vectordb::streaming::service as streaming_service;
+use crate::api::vectordb::transactions::service as transactions_service;
+use crate::api::vectordb::vectors::dtos::CreateVectorDto;
+use crate::app_context::AppContext;
+use crate::models::collection_cache::CollectionCacheExt;
+use crate::models::rbac::Permission;
+
+crate::cfg_grpc! {
+    use super::auth::authenticate;
+    use super::proto::{
+        streaming_service_server::StreamingService, StreamUpsertRequest, StreamUpsertResponse,
+    };
+
+    pub struct StreamingServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    pub type StreamUpsertStream =
+        Pin<Box<dyn Stream<Item = Result<StreamUpsertResponse, Status>> + Send>>;
+
+    // Collection and transaction of an upsert stream, taken from its first
+    // message
+    struct UpsertTarget {
+        collection_id: String,
+        transaction_id: Option<u32>,
+    }
+
+    struct UpsertState<S> {
+        context: Arc<AppContext>,
+        claims: Claims,
+        inbound: S,
+        target: Option<UpsertTarget>,
+        vectors_upserted: u64,
+    }
+
+    /// Upserts the vectors of the inbound stream in batches of
+    /// `upload_process_batch_size` vectors, yielding the running total after
+    /// every batch
+    ///
+    /// The inbound stream is only read while no batch is being indexed, so
+    /// a client sending faster than the vectors can be indexed is slowed
+    /// down by the flow control of the transport. The stream ends at the
+    /// first error.
+    pub fn upsert_stream<S>(
+        context: Arc<AppContext>,
+        claims: Claims,
+        inbound: S,
+    ) -> StreamUpsertStream
+    where
+        S: Stream<Item = Result<StreamUpsertRequest, Status>> + Send + Unpin + 'static,
+    {
+        let state = UpsertState {
+            context,
+            claims,
+            inbound,
+            target: None,
+            vectors_upserted: 0,
+        };
+
+        Box::pin(stream::unfold(Some(state), |state| async move {
+            let mut state = state?;
+            let batch_size = state.context.config.upload_process_batch_size.max(1);
+
+            let result = async {
+                let mut batch: Vec<CreateVectorDto> = Vec::new();
+                let mut is_last = false;
+                while batch.len() < batch_size {
+                    let Some(request) = state.inbound.next().await else {
+                        is_last = true;
+                        break;
+                    };
+                    let request = request?;
+                    if state.target.is_none() {
+                        authorize(
+                            &state.context,
+                            &state.claims,
+                            &request.collection_id,
+                            Permission::UpsertVectors,
+                        )?;
+                        state
+                            .context
+                            .update_collection_for_transaction(&request.collection_id)
+                            .map_err(|e| Status::internal(format!("Cache error: {}", e)))?;
+                        state.target = Some(UpsertTarget {
+                            collection_id: request.collection_id,
+                            transaction_id: request.transaction_id,
+                        });
+                    }
+                    for vector in request.vectors {
+                        batch.push(CreateVectorDto::try_from(vector)?);
+                    }
+                }
+
+                let Some(target) = &state.target else {
+                    return Ok(None);
+                };
+                if batch.is_empty() {
+                    return Ok(None);
+                }
+                let count = batch.len() as u64;
+                match target.transaction_id {
+                    Some(transaction_id) => {
+                        transactions_service::upsert_vectors(
+                            state.context.clone(),
+                            &target.collection_id,
+                            transaction_id.into(),
+                            batch,
+                        )
+                        .await?
+                    }
+                    None => {
+                        streaming_service::upsert_vectors(
+                            state.context.clone(),
+                            &target.collection_id,
+                            batch,
+                        )
+                        .await?
+                    }
+                }
+                state.vectors_upserted += count;
+                Ok::<_, Status>(Some(is_last))
+            }
+            .await;
+
+            let vectors_upserted = state.vectors_upserted;
+            match result {
+                Ok(Some(is_last)) => Some((
+                    Ok(StreamUpsertResponse { vectors_upserted }),
+                    (!is_last).then_some(state),
+                )),
+                Ok(None) => None,
+                Err(status) => Some((Err(status), None)),
+            }
+        }))
+    }
+
+    #[tonic::async_trait]
+    impl StreamingService for StreamingServiceImpl {
+        type StreamUpsertStream = StreamUpsertStream;
+
+        async fn stream_upsert(
+            &self,
+            request: Request<Streaming<StreamUpsertRequest>>,
+        ) -> Result<Response<Self::StreamUpsertStream>, Status> {
+            let claims = authenticate(&self.context, &request)?;
+            Ok(Response::new(upsert_stream(
+                self.context.clone(),
+                claims,
+                request.into_inner(),
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
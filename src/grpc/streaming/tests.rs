@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use futures_util::{stream, StreamExt};
+use tonic::{Code, Status};
+
+use crate::api::auth::dtos::Claims;
+use crate::grpc::indexes::IndexesServiceImpl;
+use crate::grpc::proto::create_dense_index_request::Quantization;
+use crate::grpc::proto::indexes_service_server::IndexesService;
+use crate::grpc::proto::{
+    AutoQuantization, CreateDenseIndexRequest, StreamUpsertRequest, StreamUpsertResponse, Vector,
+};
+use crate::grpc::streaming::upsert_stream;
+use crate::grpc::test_utils::{admin_request, create_test_collection, test_context};
+use crate::models::types::{VectorId, ADMIN_USERNAME};
+
+async fn setup(collection_id: &str) {
+    let context = test_context();
+    create_test_collection(&context, collection_id, 4).await;
+    IndexesServiceImpl { context }
+        .create_dense_index(admin_request(CreateDenseIndexRequest {
+            collection_id: collection_id.to_string(),
+            name: "dense".to_string(),
+            distance_metric_type: "cosine".to_string(),
+            quantization: Some(Quantization::Auto(AutoQuantization {
+                sample_threshold: 100,
+            })),
+            hnsw_params: None,
+            field: None,
+        }))
+        .await
+        .unwrap();
+}
+
+fn claims(username: &str) -> Claims {
+    Claims {
+        exp: u64::MAX,
+        iat: 0,
+        username: username.to_string(),
+        api_key: None,
+    }
+}
+
+fn upsert_request(collection_id: &str, ids: &[&str]) -> StreamUpsertRequest {
+    StreamUpsertRequest {
+        collection_id: collection_id.to_string(),
+        transaction_id: None,
+        vectors: ids
+            .iter()
+            .map(|id| Vector {
+                id: id.to_string(),
+                document_id: None,
+                dense_values: vec![0.1, 0.2, 0.3, 0.4],
+                sparse_values: Vec::new(),
+                text: None,
+                text_fields: HashMap::new(),
+                dense_fields: HashMap::new(),
+                multi_vector_values: Vec::new(),
+                metadata: HashMap::new(),
+            })
+            .collect(),
+    }
+}
+
+async fn run(
+    username: &str,
+    requests: Vec<Result<StreamUpsertRequest, Status>>,
+) -> Vec<Result<StreamUpsertResponse, Status>> {
+    upsert_stream(test_context(), claims(username), stream::iter(requests))
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn test_stream_upsert() {
+    setup("grpc_streaming_upsert").await;
+    let responses = run(
+        ADMIN_USERNAME,
+        vec![
+            Ok(upsert_request("grpc_streaming_upsert", &["v1", "v2"])),
+            Ok(upsert_request("", &["v3"])),
+        ],
+    )
+    .await;
+
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].as_ref().unwrap().vectors_upserted, 3);
+    let collection = test_context()
+        .ain_env
+        .collections_map
+        .get_collection("grpc_streaming_upsert")
+        .unwrap();
+    assert!(collection
+        .external_to_internal_map
+        .get_latest(&VectorId::from("v3".to_string()))
+        .is_some());
+
+    // nothing to upsert, nothing to acknowledge
+    assert!(run(ADMIN_USERNAME, Vec::new()).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_upsert_errors() {
+    setup("grpc_streaming_errors").await;
+
+    let responses = run(
+        "nobody",
+        vec![Ok(upsert_request("grpc_streaming_errors", &["v1"]))],
+    )
+    .await;
+    assert_eq!(
+        responses[0].as_ref().unwrap_err().code(),
+        Code::PermissionDenied
+    );
+
+    let mut request = upsert_request("grpc_streaming_errors", &["v1"]);
+    request.transaction_id = Some(42);
+    let responses = run(ADMIN_USERNAME, vec![Ok(request)]).await;
+    assert_eq!(responses[0].as_ref().unwrap_err().code(), Code::NotFound);
+
+    // the stream ends at the first error
+    let responses = run(
+        ADMIN_USERNAME,
+        vec![
+            Err(Status::cancelled("client went away")),
+            Ok(upsert_request("grpc_streaming_errors", &["v1"])),
+        ],
+    )
+    .await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].as_ref().unwrap_err().code(), Code::Cancelled);
+}
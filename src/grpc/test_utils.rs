@@ -0,0 +1,91 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tonic::Request;
+
+use crate::api::auth::dtos::Claims;
+use crate::app_context::AppContext;
+use crate::args::CosdataArgs;
+use crate::config_loader::Config;
+use crate::grpc::collections::CollectionsServiceImpl;
+use crate::grpc::proto::collections_service_server::CollectionsService;
+use crate::grpc::proto::{
+    CollectionConfig, CreateCollectionRequest, DenseVectorOptions, SparseVectorOptions,
+    TfidfOptions,
+};
+use crate::models::crypto::get_current_timestamp;
+use crate::models::types::ADMIN_USERNAME;
+
+pub const TEST_ADMIN_KEY: &str = "test-admin-key";
+
+// The LMDB environment can only be opened once per process, so all the
+// tests share one context backed by a temporary data directory
+pub fn test_context() -> Arc<AppContext> {
+    static CONTEXT: OnceLock<Arc<AppContext>> = OnceLock::new();
+    CONTEXT
+        .get_or_init(|| {
+            let data_dir = tempfile::tempdir().unwrap().into_path();
+            std::env::set_var("COSDATA_HOME", data_dir);
+            let config: Config =
+                toml::from_str(include_str!("../../config.toml")).expect("Invalid test config");
+            let args = CosdataArgs {
+                admin_key: TEST_ADMIN_KEY.to_string(),
+                skip_confirmation: true,
+                confirmed: true,
+            };
+            let context = Arc::new(AppContext::new(config, args).unwrap());
+            // creating and deleting collections wait for the restart
+            // recovery, which has nothing to recover in the empty directory
+            while let Some(reason) = context.not_ready_reason() {
+                assert!(
+                    context.recovery_error.read().unwrap().is_none(),
+                    "{}",
+                    reason
+                );
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            context
+        })
+        .clone()
+}
+
+/// Wraps the message in a request authenticated as the admin user, as if it
+/// had gone through `AuthInterceptor`
+pub fn admin_request<T>(message: T) -> Request<T> {
+    let now = get_current_timestamp();
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(Claims {
+        exp: now + 3600,
+        iat: now,
+        username: ADMIN_USERNAME.to_string(),
+        api_key: None,
+    });
+    request
+}
+
+/// Creates a collection with dense, sparse and TF-IDF vectors enabled
+pub async fn create_test_collection(context: &Arc<AppContext>, name: &str, dimension: u32) {
+    let collections_service = CollectionsServiceImpl {
+        context: context.clone(),
+    };
+    collections_service
+        .create_collection(admin_request(CreateCollectionRequest {
+            name: name.to_string(),
+            description: None,
+            dense_vector: Some(DenseVectorOptions {
+                dimension,
+                enabled: true,
+                fields: Vec::new(),
+                multi_vector: false,
+            }),
+            sparse_vector: Some(SparseVectorOptions { enabled: true }),
+            tf_idf_options: Some(TfidfOptions { enabled: true }),
+            metadata_schema: None,
+            config: Some(CollectionConfig {
+                max_vectors: None,
+                replication_factor: None,
+            }),
+            store_raw_text: Some(false),
+        }))
+        .await
+        .unwrap();
+}
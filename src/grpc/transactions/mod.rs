@@ -0,0 +1,209 @@
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::api::vectordb::transactions::service;
+use crate::api::vectordb::vectors::dtos::CreateVectorDto;
+use crate::app_context::AppContext;
+use crate::indexes::inverted::types::SparsePair;
+use crate::metadata::{FieldValue, MetadataFields};
+use crate::models::rbac::Permission;
+use crate::models::types::VectorId;
+
+crate::cfg_grpc! {
+    use super::auth::authorize;
+    use super::proto::{
+        transactions_service_server::TransactionsService, AbortTransactionRequest,
+        CommitTransactionRequest, CreateTransactionRequest, CreateTransactionResponse,
+        CreateVectorInTransactionRequest, DeleteVectorInTransactionRequest, UpsertVectorsRequest,
+        Vector,
+    };
+
+    pub struct TransactionsServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    // Empty repeated fields are treated as absent, since protobuf can't tell
+    // them apart
+    impl TryFrom<Vector> for CreateVectorDto {
+        type Error = Status;
+
+        fn try_from(vector: Vector) -> Result<Self, Self::Error> {
+            let metadata = vector
+                .metadata
+                .into_iter()
+                .map(|(name, value)| FieldValue::try_from(value).map(|value| (name, value)))
+                .collect::<Result<MetadataFields, _>>()
+                .map_err(|e| Status::invalid_argument(format!("Invalid metadata: {}", e)))?;
+            Ok(Self {
+                id: VectorId::from(vector.id),
+                document_id: vector.document_id.map(Into::into),
+                dense_values: Some(vector.dense_values).filter(|values| !values.is_empty()),
+                metadata: Some(metadata).filter(|metadata| !metadata.is_empty()),
+                sparse_values: Some(vector.sparse_values)
+                    .filter(|values| !values.is_empty())
+                    .map(|values| {
+                        values
+                            .into_iter()
+                            .map(|pair| SparsePair(pair.index, pair.value))
+                            .collect()
+                    }),
+                text: vector.text,
+                text_fields: Some(vector.text_fields).filter(|fields| !fields.is_empty()),
+                dense_fields: Some(vector.dense_fields)
+                    .filter(|fields| !fields.is_empty())
+                    .map(|fields| {
+                        fields
+                            .into_iter()
+                            .map(|(field, values)| (field, values.values))
+                            .collect()
+                    }),
+                multi_vector_values: Some(vector.multi_vector_values)
+                    .filter(|values| !values.is_empty())
+                    .map(|values| values.into_iter().map(|values| values.values).collect()),
+            })
+        }
+    }
+
+    #[tonic::async_trait]
+    impl TransactionsService for TransactionsServiceImpl {
+        async fn create_transaction(
+            &self,
+            request: Request<CreateTransactionRequest>,
+        ) -> Result<Response<CreateTransactionResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::UpsertVectors,
+            )?;
+            let req = request.into_inner();
+            let transaction =
+                service::create_transaction(self.context.clone(), &req.collection_id).await?;
+
+            Ok(Response::new(CreateTransactionResponse {
+                transaction_id: *transaction.transaction_id,
+                created_at: Some(prost_types::Timestamp {
+                    seconds: transaction.created_at.timestamp(),
+                    nanos: transaction.created_at.timestamp_subsec_nanos() as i32,
+                }),
+            }))
+        }
+
+        async fn commit_transaction(
+            &self,
+            request: Request<CommitTransactionRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::UpsertVectors,
+            )?;
+            let req = request.into_inner();
+            service::commit_transaction(
+                self.context.clone(),
+                &req.collection_id,
+                req.transaction_id.into(),
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn abort_transaction(
+            &self,
+            request: Request<AbortTransactionRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::UpsertVectors,
+            )?;
+            let req = request.into_inner();
+            service::abort_transaction(
+                self.context.clone(),
+                &req.collection_id,
+                req.transaction_id.into(),
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn create_vector_in_transaction(
+            &self,
+            request: Request<CreateVectorInTransactionRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::UpsertVectors,
+            )?;
+            let req = request.into_inner();
+            let vector = req
+                .vector
+                .ok_or_else(|| Status::invalid_argument("Missing vector"))?;
+            service::create_vector_in_transaction(
+                self.context.clone(),
+                &req.collection_id,
+                req.transaction_id.into(),
+                vector.try_into()?,
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn delete_vector_in_transaction(
+            &self,
+            request: Request<DeleteVectorInTransactionRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::DeleteVectors,
+            )?;
+            let req = request.into_inner();
+            service::delete_vector_by_id(
+                self.context.clone(),
+                &req.collection_id,
+                req.transaction_id.into(),
+                VectorId::from(req.vector_id),
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn upsert_vectors(
+            &self,
+            request: Request<UpsertVectorsRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::UpsertVectors,
+            )?;
+            let req = request.into_inner();
+            service::upsert_vectors(
+                self.context.clone(),
+                &req.collection_id,
+                req.transaction_id.into(),
+                req.vectors
+                    .into_iter()
+                    .map(CreateVectorDto::try_from)
+                    .collect::<Result<_, _>>()?,
+            )
+            .await?;
+
+            Ok(Response::new(()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
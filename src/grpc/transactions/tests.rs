@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use tonic::Code;
+
+use crate::api::vectordb::vectors::dtos::CreateVectorDto;
+use crate::grpc::collections::CollectionsServiceImpl;
+use crate::grpc::indexes::IndexesServiceImpl;
+use crate::grpc::proto::collections_service_server::CollectionsService;
+use crate::grpc::proto::create_dense_index_request::Quantization;
+use crate::grpc::proto::field_value::Value;
+use crate::grpc::proto::indexes_service_server::IndexesService;
+use crate::grpc::proto::transactions_service_server::TransactionsService;
+use crate::grpc::proto::{
+    AbortTransactionRequest, AutoQuantization, CollectionConfig, CommitTransactionRequest,
+    CreateCollectionRequest, CreateDenseIndexRequest, CreateTransactionRequest,
+    CreateVectorInTransactionRequest, DeleteVectorInTransactionRequest, DenseValues,
+    DenseVectorField, DenseVectorOptions, FieldValue, UpsertVectorsRequest, Vector,
+};
+use crate::grpc::test_utils::{admin_request, create_test_collection, test_context};
+use crate::grpc::transactions::TransactionsServiceImpl;
+use crate::metadata::FieldValue as MetadataFieldValue;
+
+async fn setup(collection_id: &str) -> TransactionsServiceImpl {
+    let context = test_context();
+    create_test_collection(&context, collection_id, 4).await;
+    IndexesServiceImpl {
+        context: context.clone(),
+    }
+    .create_dense_index(admin_request(CreateDenseIndexRequest {
+        collection_id: collection_id.to_string(),
+        name: "dense".to_string(),
+        distance_metric_type: "cosine".to_string(),
+        quantization: Some(Quantization::Auto(AutoQuantization {
+            sample_threshold: 100,
+        })),
+        hnsw_params: None,
+        field: None,
+    }))
+    .await
+    .unwrap();
+    TransactionsServiceImpl { context }
+}
+
+fn dense_vector(id: &str, values: [f32; 4]) -> Vector {
+    Vector {
+        id: id.to_string(),
+        document_id: None,
+        dense_values: values.to_vec(),
+        sparse_values: Vec::new(),
+        text: None,
+        text_fields: HashMap::new(),
+        dense_fields: HashMap::new(),
+        multi_vector_values: Vec::new(),
+        metadata: HashMap::new(),
+    }
+}
+
+async fn create_transaction(service: &TransactionsServiceImpl, collection_id: &str) -> u32 {
+    service
+        .create_transaction(admin_request(CreateTransactionRequest {
+            collection_id: collection_id.to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .transaction_id
+}
+
+#[tokio::test]
+async fn test_transaction_commit() {
+    let service = setup("grpc_transactions_commit").await;
+    let transaction_id = create_transaction(&service, "grpc_transactions_commit").await;
+
+    // only one transaction can be open at a time
+    let status = service
+        .create_transaction(admin_request(CreateTransactionRequest {
+            collection_id: "grpc_transactions_commit".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    service
+        .upsert_vectors(admin_request(UpsertVectorsRequest {
+            collection_id: "grpc_transactions_commit".to_string(),
+            transaction_id,
+            vectors: vec![
+                dense_vector("v1", [0.1, 0.2, 0.3, 0.4]),
+                dense_vector("v2", [0.4, 0.3, 0.2, 0.1]),
+            ],
+        }))
+        .await
+        .unwrap();
+    service
+        .create_vector_in_transaction(admin_request(CreateVectorInTransactionRequest {
+            collection_id: "grpc_transactions_commit".to_string(),
+            transaction_id,
+            vector: Some(dense_vector("v3", [0.2, 0.2, 0.2, 0.2])),
+        }))
+        .await
+        .unwrap();
+    service
+        .commit_transaction(admin_request(CommitTransactionRequest {
+            collection_id: "grpc_transactions_commit".to_string(),
+            transaction_id,
+        }))
+        .await
+        .unwrap();
+
+    // a committed transaction can't be used anymore
+    let status = service
+        .delete_vector_in_transaction(admin_request(DeleteVectorInTransactionRequest {
+            collection_id: "grpc_transactions_commit".to_string(),
+            transaction_id,
+            vector_id: "v1".to_string(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_transaction_abort() {
+    let service = setup("grpc_transactions_abort").await;
+    let transaction_id = create_transaction(&service, "grpc_transactions_abort").await;
+
+    let status = service
+        .create_vector_in_transaction(admin_request(CreateVectorInTransactionRequest {
+            collection_id: "grpc_transactions_abort".to_string(),
+            transaction_id,
+            vector: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    service
+        .abort_transaction(admin_request(AbortTransactionRequest {
+            collection_id: "grpc_transactions_abort".to_string(),
+            transaction_id,
+        }))
+        .await
+        .unwrap();
+
+    // the collection is free for a new transaction
+    create_transaction(&service, "grpc_transactions_abort").await;
+}
+
+fn create_collection_request(name: &str) -> CreateCollectionRequest {
+    CreateCollectionRequest {
+        name: name.to_string(),
+        description: None,
+        dense_vector: Some(DenseVectorOptions {
+            dimension: 4,
+            enabled: true,
+            fields: vec![DenseVectorField {
+                name: "image".to_string(),
+                dimension: 2,
+            }],
+            multi_vector: false,
+        }),
+        sparse_vector: None,
+        tf_idf_options: None,
+        metadata_schema: None,
+        config: Some(CollectionConfig {
+            max_vectors: None,
+            replication_factor: None,
+        }),
+        store_raw_text: Some(false),
+    }
+}
+
+#[tokio::test]
+async fn test_dense_field_dimension_without_index() {
+    let context = test_context();
+    let collections_service = CollectionsServiceImpl {
+        context: context.clone(),
+    };
+    // the dense indexes of the fields are persisted as `{collection}/{field}`
+    let status = collections_service
+        .create_collection(admin_request(create_collection_request(
+            "grpc_transactions/image",
+        )))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    collections_service
+        .create_collection(admin_request(create_collection_request(
+            "grpc_transactions_dense_field",
+        )))
+        .await
+        .unwrap();
+
+    let service = TransactionsServiceImpl { context };
+    let transaction_id = create_transaction(&service, "grpc_transactions_dense_field").await;
+    let mut vector = dense_vector("v1", [0.1, 0.2, 0.3, 0.4]);
+    vector.dense_fields.insert(
+        "image".to_string(),
+        DenseValues {
+            values: vec![0.1, 0.2, 0.3],
+        },
+    );
+    let status = service
+        .upsert_vectors(admin_request(UpsertVectorsRequest {
+            collection_id: "grpc_transactions_dense_field".to_string(),
+            transaction_id,
+            vectors: vec![vector.clone()],
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    vector.dense_fields.get_mut("image").unwrap().values.pop();
+    service
+        .upsert_vectors(admin_request(UpsertVectorsRequest {
+            collection_id: "grpc_transactions_dense_field".to_string(),
+            transaction_id,
+            vectors: vec![vector],
+        }))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_vector_metadata_conversion() {
+    let mut vector = dense_vector("v1", [0.1, 0.2, 0.3, 0.4]);
+    vector.metadata.insert(
+        "color".to_string(),
+        FieldValue {
+            value: Some(Value::StringValue("red".to_string())),
+        },
+    );
+    let dto = CreateVectorDto::try_from(vector.clone()).unwrap();
+    assert_eq!(
+        dto.metadata.unwrap().get("color"),
+        Some(&MetadataFieldValue::String("red".to_string()))
+    );
+
+    vector
+        .metadata
+        .insert("size".to_string(), FieldValue { value: None });
+    let status = CreateVectorDto::try_from(vector).unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let dto = CreateVectorDto::try_from(dense_vector("v2", [0.1, 0.2, 0.3, 0.4])).unwrap();
+    assert!(dto.metadata.is_none());
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::api::auth::dtos::{ChangePasswordDto, CreateUserDto, UserDto};
+use crate::api::auth::service;
+use crate::app_context::AppContext;
+use crate::models::rbac::{Permission, GLOBAL_SCOPE};
+
+crate::cfg_grpc! {
+    use super::auth::{authenticate, authorize};
+    use super::proto::{
+        users_service_server::UsersService, ChangePasswordRequest, CreateUserRequest,
+        DeleteUserRequest, GetUserRequest, ListUsersResponse, SetUserDisabledRequest,
+        User as ProtoUser,
+    };
+
+    pub struct UsersServiceImpl {
+        pub context: Arc<AppContext>,
+    }
+
+    impl From<UserDto> for ProtoUser {
+        fn from(user: UserDto) -> Self {
+            Self {
+                username: user.username,
+                disabled: user.disabled,
+                created_at: user.created_at,
+            }
+        }
+    }
+
+    #[tonic::async_trait]
+    impl UsersService for UsersServiceImpl {
+        async fn create_user(
+            &self,
+            request: Request<CreateUserRequest>,
+        ) -> Result<Response<ProtoUser>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::ManagePermissions,
+            )?;
+            let req = request.into_inner();
+            let user = service::create_user(
+                CreateUserDto {
+                    username: req.username,
+                    password: req.password,
+                },
+                self.context.clone(),
+            )
+            .await?;
+
+            Ok(Response::new(user.into()))
+        }
+
+        async fn list_users(
+            &self,
+            request: Request<()>,
+        ) -> Result<Response<ListUsersResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::ManagePermissions,
+            )?;
+            let users = service::list_users(self.context.clone()).await?;
+
+            Ok(Response::new(ListUsersResponse {
+                users: users.into_iter().map(ProtoUser::from).collect(),
+            }))
+        }
+
+        async fn get_user(
+            &self,
+            request: Request<GetUserRequest>,
+        ) -> Result<Response<ProtoUser>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::ManagePermissions,
+            )?;
+            let user =
+                service::get_user(&request.into_inner().username, self.context.clone()).await?;
+
+            Ok(Response::new(user.into()))
+        }
+
+        async fn delete_user(
+            &self,
+            request: Request<DeleteUserRequest>,
+        ) -> Result<Response<()>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::ManagePermissions,
+            )?;
+            service::delete_user(&request.into_inner().username, self.context.clone()).await?;
+
+            Ok(Response::new(()))
+        }
+
+        async fn set_user_disabled(
+            &self,
+            request: Request<SetUserDisabledRequest>,
+        ) -> Result<Response<ProtoUser>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                GLOBAL_SCOPE,
+                Permission::ManagePermissions,
+            )?;
+            let req = request.into_inner();
+            let user =
+                service::set_user_disabled(&req.username, req.disabled, self.context.clone())
+                    .await?;
+
+            Ok(Response::new(user.into()))
+        }
+
+        async fn change_password(
+            &self,
+            request: Request<ChangePasswordRequest>,
+        ) -> Result<Response<ProtoUser>, Status> {
+            // users can change their own password, the service checks the
+            // permission otherwise
+            let claims = authenticate(&self.context, &request)?;
+            let req = request.into_inner();
+            let user = service::change_password(
+                &req.username,
+                ChangePasswordDto {
+                    password: req.password,
+                },
+                &claims,
+                self.context.clone(),
+            )
+            .await?;
+
+            Ok(Response::new(user.into()))
+        }
+    }
+}
@@ -6,13 +6,16 @@ use crate::indexes::hnsw::{DenseSearchInput, DenseSearchOptions};
 use crate::indexes::inverted::{SparseSearchInput, SparseSearchOptions};
 use crate::indexes::tf_idf::{TFIDFSearchInput, TFIDFSearchOptions};
 use crate::indexes::IndexOps;
+use crate::metadata::query_filtering::Filter;
 use crate::models::common::WaCustomError;
+use crate::models::rbac::Permission;
 use crate::models::types::VectorId;
 use crate::{app_context::AppContext, indexes::inverted::types::SparsePair};
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
 crate::cfg_grpc! {
+    use super::auth::authorize;
     use super::proto::{
         vectors_service_server::VectorsService, FindSimilarVectorsRequest, FindSimilarVectorsResponse,
         GetVectorRequest, SimilarVectorMatch, Vector, VectorResponse,
@@ -29,6 +32,12 @@ crate::cfg_grpc! {
             &self,
             request: Request<GetVectorRequest>,
         ) -> Result<Response<VectorResponse>, Status> {
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                Permission::ListVectors,
+            )?;
             let req = request.into_inner();
 
             // Validate collection and vector type
@@ -65,6 +74,25 @@ crate::cfg_grpc! {
                         })
                         .collect(),
                     text: vector.text,
+                    text_fields: vector.text_fields.unwrap_or_default(),
+                    dense_fields: vector
+                        .dense_fields
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(field, values)| (field, super::proto::DenseValues { values }))
+                        .collect(),
+                    multi_vector_values: vector
+                        .multi_vector_values
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|values| super::proto::DenseValues { values })
+                        .collect(),
+                    metadata: vector
+                        .metadata
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(name, value)| (name, value.into()))
+                        .collect(),
                 }),
             }))
         }
@@ -74,6 +102,22 @@ crate::cfg_grpc! {
             &self,
             request: Request<FindSimilarVectorsRequest>,
         ) -> Result<Response<FindSimilarVectorsResponse>, Status> {
+            let permission = match request.get_ref().query {
+                Some(super::proto::find_similar_vectors_request::Query::Dense(_)) => {
+                    Permission::QueryDenseVectors
+                }
+                Some(super::proto::find_similar_vectors_request::Query::Sparse(_))
+                | Some(super::proto::find_similar_vectors_request::Query::TfIdf(_)) => {
+                    Permission::QuerySparseVectors
+                }
+                None => return Err(Status::invalid_argument("Query must be specified")),
+            };
+            authorize(
+                &self.context,
+                &request,
+                &request.get_ref().collection_id,
+                permission,
+            )?;
             let req = request.into_inner();
 
             // Validate collection exists
@@ -94,34 +138,60 @@ crate::cfg_grpc! {
             match req.query {
                 // Handle dense vector similarity search
                 Some(super::proto::find_similar_vectors_request::Query::Dense(dense)) => {
-                    if !collection.meta.dense_vector.enabled {
-                        return Err(Status::failed_precondition(
-                            "Dense vectors are not enabled for this collection",
-                        ));
-                    }
+                    let hnsw_index = match dense.field.as_deref() {
+                        Some(field) => {
+                            collection.get_dense_field_index(field).ok_or_else(|| {
+                                Status::failed_precondition(format!(
+                                    "Dense index not initialized for field '{}'",
+                                    field
+                                ))
+                            })?
+                        }
+                        None => {
+                            if !collection.meta.dense_vector.enabled {
+                                return Err(Status::failed_precondition(
+                                    "Dense vectors are not enabled for this collection",
+                                ));
+                            }
+                            // the default dense index of a multi-vector
+                            // collection holds token vectors
+                            if collection.meta.dense_vector.multi_vector {
+                                return Err(Status::invalid_argument(
+                                    "Collection stores multi-vector embeddings, use a multi-vector search",
+                                ));
+                            }
+                            collection.get_hnsw_index().ok_or_else(|| {
+                                Status::failed_precondition("Dense index not initialized")
+                            })?
+                        }
+                    };
 
-                    let hnsw_index = collection
-                        .get_hnsw_index()
-                        .ok_or_else(|| Status::failed_precondition("Dense index not initialized"))?;
+                    let filter = dense
+                        .filter
+                        .map(Filter::try_from)
+                        .transpose()
+                        .map_err(|e| {
+                            Status::invalid_argument(format!("Invalid metadata filter: {}", e))
+                        })?;
 
                     // Perform similarity search
                     let results = hnsw_index
                         .search(
                             &collection,
-                            DenseSearchInput(
-                                dense.vector,
-                                // @TODO: Support for metadata filtering to be
-                                // added for grpc endpoints
-                                None,
-                            ),
+                            DenseSearchInput(dense.vector, filter),
                             &DenseSearchOptions {
                                 top_k: dense.top_k.map(|top_k| top_k as usize),
+                                ..Default::default()
                             },
                             &self.context.config,
                             dense.return_raw_text.unwrap_or_default(),
                         )
                         .map_err(|e| match e {
                             WaCustomError::NotFound(msg) => Status::not_found(msg),
+                            WaCustomError::MetadataError(e) => Status::invalid_argument(format!(
+                                "Invalid metadata filter: {}",
+                                e
+                            )),
                             _ => Status::internal(format!("Failed to find similar vectors: {}", e)),
                         })?;
 
@@ -134,6 +204,7 @@ crate::cfg_grpc! {
                                     document_id: document_id.map(Into::into),
                                     score,
                                     text,
+                                    highlights: Vec::new(),
                                 })
                                 .collect(),
                         }),
@@ -181,6 +252,7 @@ crate::cfg_grpc! {
                                     document_id: document_id.map(Into::into),
                                     score,
                                     text,
+                                    highlights: Vec::new(),
                                 })
                                 .collect(),
                         }),
@@ -204,6 +276,9 @@ crate::cfg_grpc! {
                             TFIDFSearchInput(idf.query),
                             &TFIDFSearchOptions {
                                 top_k: idf.top_k.map(|top_k| top_k as usize),
+                                field_weights: Some(idf.field_weights)
+                                    .filter(|weights| !weights.is_empty()),
+                                exact: false,
                             },
                             &self.context.config,
                             idf.return_raw_text.unwrap_or_default(),
@@ -219,6 +294,7 @@ crate::cfg_grpc! {
                                     document_id: document_id.map(Into::into),
                                     score,
                                     text,
+                                    highlights: Vec::new(),
                                 })
                                 .collect(),
                         }),
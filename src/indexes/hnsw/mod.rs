@@ -1,7 +1,7 @@
 pub(crate) mod offset_counter;
 pub(crate) mod types;
 
-use super::{IndexOps, InternalSearchResult};
+use super::{IndexOps, InternalSearchResult, SearchResult};
 use crate::{
     config_loader::Config,
     metadata::{
@@ -10,22 +10,34 @@ use crate::{
     },
     models::{
         cache_loader::HNSWIndexCache,
-        collection::{Collection, RawVectorEmbedding},
-        common::{TSHashTable, WaCustomError},
+        collection::{token_internal_id, Collection, RawVectorEmbedding},
+        common::{remove_duplicates_and_filter, TSHashTable, WaCustomError},
         meta_persist::store_values_range,
+        metrics::metrics,
         prob_node::SharedLatestNode,
-        types::{DistanceMetric, FileOffset, HNSWLevel, InternalId, MetaDb, QuantizationMetric},
+        types::{
+            DistanceMetric, FileOffset, HNSWLevel, InternalId, MetaDb, MetricResult,
+            QuantizationMetric,
+        },
         versioning::VersionNumber,
     },
     quantization::{Quantization, StorageType},
-    vector_store::{ann_search, delete_embedding, finalize_ann_results, index_embeddings},
+    vector_store::{
+        ann_search, delete_embedding, exact_search, finalize_ann_results, index_embeddings,
+        rerank_multi_vector_candidates, DEFAULT_FILTERED_CANDIDATES_LIMIT,
+    },
 };
 use offset_counter::HNSWIndexFileOffsetCounter;
-use std::sync::{
-    atomic::{AtomicBool, AtomicUsize, Ordering},
-    Arc, RwLock,
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
 };
-use types::{HNSWHyperParams, QuantizedDenseVectorEmbedding};
+use types::{HNSWHyperParams, QuantizedDenseVectorEmbedding, QueryPrecision};
 
 pub struct DenseInputEmbedding(
     pub InternalId,
@@ -39,8 +51,57 @@ pub struct DenseInputEmbedding(
 
 pub struct DenseSearchInput(pub Vec<f32>, pub Option<Filter>);
 
+#[derive(Default)]
 pub struct DenseSearchOptions {
     pub top_k: Option<usize>,
+    /// Overrides the `ef_search` of the index for this search
+    pub ef_search: Option<u32>,
+    /// Maximum number of candidates kept per level by a filtered search,
+    /// defaults to `DEFAULT_FILTERED_CANDIDATES_LIMIT`
+    pub filtered_candidates_limit: Option<usize>,
+    /// Scans the raw dense vectors of all the embeddings instead of
+    /// searching the index, see `exact_search`
+    pub exact: bool,
+}
+
+impl DenseSearchOptions {
+    fn validate(&self) -> Result<(), WaCustomError> {
+        if self.ef_search == Some(0) {
+            return Err(WaCustomError::InvalidData(
+                "ef_search must be greater than 0".to_string(),
+            ));
+        }
+        if self.filtered_candidates_limit == Some(0) {
+            return Err(WaCustomError::InvalidData(
+                "filtered_candidates_limit must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Query vectors of a multi-vector search, with an optional filter
+pub struct MultiVectorSearchInput(pub Vec<Vec<f32>>, pub Option<Filter>);
+
+/// Default number of token vectors fetched from the index for each query
+/// vector of a multi-vector search
+pub const DEFAULT_CANDIDATES_PER_VECTOR: usize = 32;
+
+#[derive(Default)]
+pub struct MultiVectorSearchOptions {
+    pub top_k: Option<usize>,
+    /// Number of token vectors fetched from the index for each query
+    /// vector, defaults to `DEFAULT_CANDIDATES_PER_VECTOR`. The embeddings
+    /// they belong to are the candidates re-ranked with MaxSim.
+    pub candidates_per_vector: Option<usize>,
+    /// Overrides the `ef_search` of the index for this search
+    pub ef_search: Option<u32>,
+    /// Maximum number of candidates kept per level by a filtered search,
+    /// defaults to `DEFAULT_FILTERED_CANDIDATES_LIMIT`
+    pub filtered_candidates_limit: Option<usize>,
+    /// Re-ranks all the multi-vector embeddings instead of the candidates
+    /// found in the index
+    pub exact: bool,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -75,6 +136,9 @@ pub struct HNSWIndex {
     pub max_replica_per_node: u8,
     pub offset_counter: RwLock<HNSWIndexFileOffsetCounter>,
     pub versions_synchronization_map: TSHashTable<SharedLatestNode, ()>,
+    /// Named dense field of the collection indexed by this index, `None`
+    /// for the default dense vectors
+    pub field: Option<String>,
 }
 
 #[derive(Default)]
@@ -112,6 +176,7 @@ impl HNSWIndex {
         is_configured: bool,
         max_replica_per_node: u8,
         offset_counter: HNSWIndexFileOffsetCounter,
+        field: Option<String>,
     ) -> Self {
         Self {
             root_vec,
@@ -132,6 +197,34 @@ impl HNSWIndex {
             max_replica_per_node,
             offset_counter: RwLock::new(offset_counter),
             versions_synchronization_map: TSHashTable::new(16),
+            field,
+        }
+    }
+
+    /// Returns the directory of the dense index of a collection, or of the
+    /// index of one of its named dense fields
+    pub fn index_path(collection_path: &Path, field: Option<&str>) -> PathBuf {
+        match field {
+            Some(field) => collection_path.join("dense_fields").join(field),
+            None => collection_path.join("dense_hnsw"),
+        }
+    }
+
+    /// Returns the name the index is persisted under in the hnsw indexes
+    /// database
+    pub fn persisted_name(collection_name: &str, field: Option<&str>) -> String {
+        match field {
+            Some(field) => format!("{}/{}", collection_name, field),
+            None => collection_name.to_string(),
+        }
+    }
+
+    /// Returns the raw vector of the embedding indexed by this index, if
+    /// it has one
+    pub fn raw_vector<'a>(&self, raw_emb: &'a RawVectorEmbedding) -> Option<&'a [f32]> {
+        match &self.field {
+            Some(field) => raw_emb.dense_fields.as_ref()?.get(field).map(Vec::as_slice),
+            None => raw_emb.dense_values.as_deref(),
         }
     }
 
@@ -153,6 +246,176 @@ impl HNSWIndex {
         let node = unsafe { self.get_pseudo_root_vec().map(|node| &*node) };
         node.map(|n| n.file_offset)
     }
+
+    /// Whether the index stores 1-bit quantized vectors, traversed with
+    /// popcount distances
+    pub fn is_binary(&self) -> bool {
+        matches!(*self.storage_type.read().unwrap(), StorageType::SubByte(1))
+    }
+
+    /// Storage type the search queries are quantized to, the stored
+    /// vectors' unless the index scores unquantized queries
+    pub fn query_storage_type(&self) -> StorageType {
+        match self.hnsw_params.read().unwrap().query_precision {
+            QueryPrecision::Quantized => *self.storage_type.read().unwrap(),
+            QueryPrecision::F32 => StorageType::FullPrecisionFP,
+            QueryPrecision::F16 => StorageType::HalfPrecisionFP,
+        }
+    }
+
+    /// Number of quantized candidates re-scored with the full precision
+    /// raw vectors to return `top_k` results
+    pub fn num_rescored_candidates(&self, top_k: usize) -> usize {
+        let oversampling = self.hnsw_params.read().unwrap().oversampling;
+        ((top_k as f32 * oversampling).ceil() as usize).max(top_k)
+    }
+
+    /// Searches a multi-vector collection for the embeddings whose token
+    /// vectors best match the query vectors
+    ///
+    /// The nearest token vectors of each query vector are fetched from the
+    /// index, then the embeddings they belong to are re-ranked by the exact
+    /// MaxSim of their raw token vectors, so each embedding is returned at
+    /// most once.
+    pub fn multi_vector_search(
+        &self,
+        collection: &Collection,
+        query: MultiVectorSearchInput,
+        options: &MultiVectorSearchOptions,
+        config: &Config,
+        return_raw_text: bool,
+    ) -> Result<Vec<SearchResult>, WaCustomError> {
+        let start = Instant::now();
+        let MultiVectorSearchInput(query_vectors, filter) = query;
+        if query_vectors.is_empty() {
+            return Err(WaCustomError::InvalidData(
+                "Multi-vector query without query vectors".to_string(),
+            ));
+        }
+        if let Some(query_vector) = query_vectors.iter().find(|v| v.len() != self.dim) {
+            return Err(WaCustomError::InvalidData(format!(
+                "Expected dimension of query vectors to be {}, found {}",
+                self.dim,
+                query_vector.len()
+            )));
+        }
+        let candidates_per_vector = options
+            .candidates_per_vector
+            .unwrap_or(DEFAULT_CANDIDATES_PER_VECTOR);
+        if candidates_per_vector == 0 {
+            return Err(WaCustomError::InvalidData(
+                "candidates_per_vector must be greater than 0".to_string(),
+            ));
+        }
+        // the top k of the searches of the query vectors also sets the
+        // minimum number of candidates traversed in binary indexes
+        let dense_options = DenseSearchOptions {
+            top_k: Some(candidates_per_vector),
+            ef_search: options.ef_search,
+            filtered_candidates_limit: options.filtered_candidates_limit,
+            exact: options.exact,
+        };
+        dense_options.validate()?;
+
+        let candidates = if options.exact {
+            collection
+                .raw_embeddings()
+                .filter(|(_, raw_emb)| {
+                    filter
+                        .as_ref()
+                        .is_none_or(|filter| filter.matches(raw_emb.metadata.as_ref()))
+                })
+                .map(|(internal_id, _)| internal_id)
+                .collect::<HashSet<_>>()
+        } else {
+            let mut candidates = HashSet::new();
+            for query_vector in &query_vectors {
+                let results = self.ann_search_nodes(
+                    collection,
+                    query_vector,
+                    filter.as_ref(),
+                    &dense_options,
+                    config,
+                )?;
+                let token_ids =
+                    remove_duplicates_and_filter(self, results, None, &self.cache).into_iter();
+                for (token_id, _) in token_ids.take(candidates_per_vector) {
+                    if let Some(internal_id) = collection.get_internal_id_by_token_id(&token_id) {
+                        candidates.insert(internal_id);
+                    }
+                }
+            }
+            candidates
+        };
+
+        let results = rerank_multi_vector_candidates(
+            collection,
+            candidates,
+            &query_vectors,
+            options.top_k,
+            return_raw_text,
+        );
+        let results = self.remap_search_results(collection, results, return_raw_text);
+        metrics().observe_search("multi_vector", start.elapsed());
+        results
+    }
+
+    // searches the index for the nodes nearest to the query vector, with
+    // their metadata replicas
+    pub(crate) fn ann_search_nodes(
+        &self,
+        collection: &Collection,
+        query_vector: &[f32],
+        filter: Option<&Filter>,
+        options: &DenseSearchOptions,
+        config: &Config,
+    ) -> Result<Vec<(SharedLatestNode, MetricResult)>, WaCustomError> {
+        let id = InternalId::from(u32::MAX - 1);
+        let quantized_vec = self.quantization_metric.read().unwrap().quantize(
+            query_vector,
+            self.query_storage_type(),
+            *self.values_range.read().unwrap(),
+        )?;
+        let vec_emb = QuantizedDenseVectorEmbedding {
+            quantized_vec: Arc::new(quantized_vec),
+            hash_vec: id,
+        };
+
+        let mut hnsw_params = self.hnsw_params.read().unwrap().clone();
+        if let Some(ef_search) = options.ef_search {
+            hnsw_params.ef_search = ef_search;
+        }
+        // 1-bit distances are coarse, so the traversal keeps at least as
+        // many candidates as get re-scored
+        if let Some(top_k) = options.top_k.filter(|_| self.is_binary()) {
+            let num_candidates = self.num_rescored_candidates(top_k) as u32;
+            hnsw_params.ef_search = hnsw_params.ef_search.max(num_candidates);
+        }
+
+        let query_filter_dims = filter.map(|filter| {
+            let metadata_schema = collection.meta.metadata_schema.as_ref().unwrap();
+            filter_encoded_dimensions(metadata_schema, filter).unwrap()
+        });
+
+        let root_node = if filter.is_some() {
+            self.get_pseudo_root_vec().unwrap()
+        } else {
+            self.get_root_vec()
+        };
+
+        ann_search(
+            config,
+            self,
+            vec_emb,
+            query_filter_dims.as_ref(),
+            root_node,
+            HNSWLevel(hnsw_params.num_layers),
+            &hnsw_params,
+            options
+                .filtered_candidates_limit
+                .unwrap_or(DEFAULT_FILTERED_CANDIDATES_LIMIT),
+        )
+    }
 }
 
 impl IndexOps for HNSWIndex {
@@ -161,6 +424,8 @@ impl IndexOps for HNSWIndex {
     type SearchOptions = DenseSearchOptions;
     type Data = HNSWIndexData;
 
+    const INDEX_TYPE: &'static str = "dense";
+
     fn validate_embedding(&self, embedding: Self::IndexingInput) -> Result<(), WaCustomError> {
         // @TODO(vineet): Add validation for metadata fields (if
         // applicable)
@@ -192,7 +457,18 @@ impl IndexOps for HNSWIndex {
         version: VersionNumber,
         config: &Config,
     ) -> Result<(), WaCustomError> {
-        delete_embedding(config, self, version, id, raw_emb)
+        if let Some(raw_vec) = self.raw_vector(raw_emb) {
+            delete_embedding(config, self, version, id, raw_vec)?;
+        }
+        // the token vectors of multi-vector embeddings are only indexed in
+        // the default dense index
+        if self.field.is_none() {
+            for (i, values) in raw_emb.multi_vector_values.iter().flatten().enumerate() {
+                let token_id = token_internal_id(id, i, self.max_replica_per_node as usize);
+                delete_embedding(config, self, version, token_id, values)?;
+            }
+        }
+        Ok(())
     }
 
     fn sample_embedding(&self, embedding: &Self::IndexingInput) {
@@ -306,7 +582,7 @@ impl IndexOps for HNSWIndex {
         let range = (range_start, range_end);
         *self.values_range.write().unwrap() = range;
         self.is_configured.store(true, Ordering::Release);
-        store_values_range(lmdb, range)?;
+        store_values_range(lmdb, self.field.as_deref(), range)?;
         Ok(())
     }
 
@@ -355,40 +631,20 @@ impl IndexOps for HNSWIndex {
         config: &Config,
         return_raw_text: bool,
     ) -> Result<Vec<InternalSearchResult>, WaCustomError> {
-        let id = InternalId::from(u32::MAX - 1);
-        let quantized_vec = self.quantization_metric.read().unwrap().quantize(
-            &query.0,
-            *self.storage_type.read().unwrap(),
-            *self.values_range.read().unwrap(),
-        )?;
-        let vec_emb = QuantizedDenseVectorEmbedding {
-            quantized_vec: Arc::new(quantized_vec),
-            hash_vec: id,
-        };
-
-        let hnsw_params_guard = self.hnsw_params.read().unwrap();
-
-        let query_filter_dims = query.1.as_ref().map(|filter| {
-            let metadata_schema = collection.meta.metadata_schema.as_ref().unwrap();
-            filter_encoded_dimensions(metadata_schema, filter).unwrap()
-        });
-
-        let root_node = if query.1.is_some() {
-            self.get_pseudo_root_vec().unwrap()
-        } else {
-            self.get_root_vec()
-        };
+        options.validate()?;
+        if options.exact {
+            return exact_search(
+                collection,
+                self,
+                &query.0,
+                query.1.as_ref(),
+                options.top_k,
+                return_raw_text,
+            );
+        }
 
-        let results = ann_search(
-            config,
-            self,
-            vec_emb,
-            query_filter_dims.as_ref(),
-            root_node,
-            HNSWLevel(hnsw_params_guard.num_layers),
-            &hnsw_params_guard,
-        )?;
-        drop(hnsw_params_guard);
+        let results =
+            self.ann_search_nodes(collection, &query.0, query.1.as_ref(), options, config)?;
         finalize_ann_results(
             collection,
             self,
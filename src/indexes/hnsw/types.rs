@@ -1,11 +1,32 @@
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::{
     config_loader::Config, metadata::MetadataFields, models::types::InternalId, storage::Storage,
 };
 
+/// Factor applied to `top_k` to get the number of quantized candidates
+/// re-scored with the full precision raw vectors
+pub const DEFAULT_OVERSAMPLING: f32 = 5.0;
+
+fn default_oversampling() -> f32 {
+    DEFAULT_OVERSAMPLING
+}
+
+/// Precision the search queries are scored in against the quantized
+/// stored vectors. `F32` and `F16` keep the query unquantized and
+/// de-quantize the `UnsignedByte` and `SubByte` stored vectors on the fly
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryPrecision {
+    #[default]
+    Quantized,
+    F32,
+    F16,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HNSWHyperParams {
     pub num_layers: u8,
@@ -14,6 +35,10 @@ pub struct HNSWHyperParams {
     pub max_cache_size: usize,
     pub level_0_neighbors_count: usize,
     pub neighbors_count: usize,
+    #[serde(default = "default_oversampling")]
+    pub oversampling: f32,
+    #[serde(default)]
+    pub query_precision: QueryPrecision,
 }
 
 impl HNSWHyperParams {
@@ -25,6 +50,8 @@ impl HNSWHyperParams {
             max_cache_size: config.hnsw.default_max_cache_size,
             level_0_neighbors_count: config.hnsw.default_level_0_neighbors_count,
             neighbors_count: config.hnsw.default_neighbors_count,
+            oversampling: DEFAULT_OVERSAMPLING,
+            query_precision: QueryPrecision::Quantized,
         }
     }
 }
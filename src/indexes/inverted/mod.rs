@@ -114,6 +114,8 @@ impl IndexOps for InvertedIndex {
     type SearchOptions = SparseSearchOptions;
     type Data = InvertedIndexData;
 
+    const INDEX_TYPE: &'static str = "sparse";
+
     fn validate_embedding(&self, _embedding: Self::IndexingInput) -> Result<(), WaCustomError> {
         Ok(())
     }
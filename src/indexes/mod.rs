@@ -1,6 +1,6 @@
 use rayon::prelude::*;
 
-use std::{hash::Hasher, sync::RwLock};
+use std::{hash::Hasher, sync::RwLock, time::Instant};
 
 use lmdb::{Transaction, WriteFlags};
 use siphasher::sip::SipHasher24;
@@ -10,6 +10,7 @@ use crate::{
     models::{
         collection::{Collection, RawVectorEmbedding},
         common::WaCustomError,
+        metrics::metrics,
         types::{DocumentId, InternalId, MetaDb, VectorId},
         versioning::VersionNumber,
     },
@@ -35,6 +36,9 @@ pub trait IndexOps: Send + Sync {
     type SearchOptions: Send + Sync;
     type Data: serde::Serialize + serde::de::DeserializeOwned;
 
+    /// Name of the index type, used as a label of the search metrics
+    const INDEX_TYPE: &'static str;
+
     fn validate_embedding(&self, embedding: Self::IndexingInput) -> Result<(), WaCustomError>;
 
     fn run_upload(
@@ -253,8 +257,11 @@ pub trait IndexOps: Send + Sync {
         config: &Config,
         return_raw_text: bool,
     ) -> Result<Vec<SearchResult>, WaCustomError> {
+        let start = Instant::now();
         let results = self.search_internal(collection, query, options, config, return_raw_text)?;
-        self.remap_search_results(collection, results, return_raw_text)
+        let results = self.remap_search_results(collection, results, return_raw_text);
+        metrics().observe_search(Self::INDEX_TYPE, start.elapsed());
+        results
     }
 
     fn batch_search(
@@ -0,0 +1,627 @@
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use snowball_stemmer::Stemmer;
+use std::ops::Range;
+use utoipa::ToSchema;
+
+pub const DEFAULT_NGRAM_SIZE: usize = 2;
+
+const DEFAULT_STOPWORDS: [&str; 35] = [
+    "a", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
+    "not", "of", "on", "or", "s", "such", "t", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with", "www",
+];
+
+/// How the text is split into tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenizerConfig {
+    /// Runs of alphanumeric characters and underscores
+    Standard,
+    /// Runs of non-whitespace characters
+    Whitespace,
+    /// Like `standard`, but runs of CJK characters, which are not separated
+    /// by spaces, are split into overlapping character n-grams
+    CjkNgram {
+        #[serde(default = "default_ngram_size")]
+        ngram_size: usize,
+    },
+}
+
+fn default_ngram_size() -> usize {
+    DEFAULT_NGRAM_SIZE
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StopwordsConfig {
+    None,
+    /// A short list of very common English words
+    English,
+    Custom(Vec<String>),
+}
+
+/// Stemmers other than English are the "light" stemmers of J. Savoy, which
+/// only remove the common inflectional suffixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StemmerLanguage {
+    None,
+    English,
+    German,
+    Spanish,
+    Italian,
+}
+
+/// Configuration of the analysis pipeline of the TF-IDF index: tokens are
+/// produced by the tokenizer, normalized (lowercasing and ASCII folding),
+/// filtered (length and stopwords) and finally stemmed
+///
+/// The defaults match the behaviour of the index before the analyzer was
+/// configurable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct TextAnalyzerConfig {
+    pub tokenizer: TokenizerConfig,
+    pub lowercase: bool,
+    /// Replaces accented latin characters with their ASCII equivalent,
+    /// e.g. "é" with "e"
+    pub ascii_folding: bool,
+    pub stopwords: StopwordsConfig,
+    pub stemmer: StemmerLanguage,
+    /// Tokens longer than this (in bytes) are dropped
+    pub max_token_length: usize,
+}
+
+impl Default for TextAnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            tokenizer: TokenizerConfig::Standard,
+            lowercase: true,
+            ascii_folding: false,
+            stopwords: StopwordsConfig::English,
+            stemmer: StemmerLanguage::English,
+            max_token_length: 40,
+        }
+    }
+}
+
+impl TextAnalyzerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let TokenizerConfig::CjkNgram { ngram_size } = self.tokenizer {
+            if ngram_size == 0 {
+                return Err("ngram_size must be at least 1".to_string());
+            }
+        }
+        if self.max_token_length == 0 {
+            return Err("max_token_length must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A term of a text
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzedToken {
+    pub term: String,
+    pub position: u32,
+    /// Byte range of the token the term comes from
+    pub offsets: Range<usize>,
+}
+
+/// Turns texts into the terms of the TF-IDF index. The same analyzer must be
+/// used for the documents and the queries of an index.
+pub struct TextAnalyzer {
+    config: TextAnalyzerConfig,
+    // normalized the same way as the tokens
+    stopwords: FxHashSet<String>,
+}
+
+impl TextAnalyzer {
+    pub fn new(config: TextAnalyzerConfig) -> Self {
+        let mut this = Self {
+            config,
+            stopwords: FxHashSet::default(),
+        };
+        let stopwords: Vec<String> = match &this.config.stopwords {
+            StopwordsConfig::None => Vec::new(),
+            StopwordsConfig::English => DEFAULT_STOPWORDS.iter().map(|w| w.to_string()).collect(),
+            StopwordsConfig::Custom(words) => words.clone(),
+        };
+        this.stopwords = stopwords
+            .into_iter()
+            .map(|word| this.normalize(word))
+            .collect();
+        this
+    }
+
+    pub fn config(&self) -> &TextAnalyzerConfig {
+        &self.config
+    }
+
+    /// Returns the terms of the text, in order of appearance
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        self.analyze_with_positions(text)
+            .into_iter()
+            .map(|(_, term)| term)
+            .collect()
+    }
+
+    /// Returns the terms of the text with their positions. Every token of
+    /// the tokenizer takes a position, including the dropped ones, so that
+    /// removing a stopword doesn't make its neighbours adjacent.
+    pub fn analyze_with_positions(&self, text: &str) -> Vec<(u32, String)> {
+        self.analyze_tokens(text)
+            .into_iter()
+            .map(|token| (token.position, token.term))
+            .collect()
+    }
+
+    /// Same as [`Self::analyze_with_positions`], but also returns where each
+    /// term is in the text
+    pub fn analyze_tokens(&self, text: &str) -> Vec<AnalyzedToken> {
+        let stemmer = match self.config.stemmer {
+            StemmerLanguage::English => Some(Stemmer::create()),
+            _ => None,
+        };
+
+        let mut terms = Vec::new();
+        let mut next_position = 0;
+        self.for_each_token(text, |token| {
+            let position = next_position;
+            next_position += 1;
+            // tokens are always slices of the text
+            let start = token.as_ptr() as usize - text.as_ptr() as usize;
+            if token.len() > self.config.max_token_length {
+                return;
+            }
+            let term = self.normalize(token.to_string());
+            if self.stopwords.contains(&term) {
+                return;
+            }
+            let term = match self.config.stemmer {
+                StemmerLanguage::None => term,
+                StemmerLanguage::English => stemmer.as_ref().unwrap().stem(&term).to_string(),
+                StemmerLanguage::German => light_stem(&term, german_light_stem),
+                StemmerLanguage::Spanish => light_stem(&term, spanish_light_stem),
+                StemmerLanguage::Italian => light_stem(&term, italian_light_stem),
+            };
+            terms.push(AnalyzedToken {
+                term,
+                position,
+                offsets: start..start + token.len(),
+            });
+        });
+        terms
+    }
+
+    /// Lowercases and folds the token like the terms, without stemming it,
+    /// e.g. for matching prefixes of terms
+    pub fn normalize_token(&self, token: &str) -> String {
+        self.normalize(token.to_string())
+    }
+
+    fn normalize(&self, mut token: String) -> String {
+        if self.config.lowercase {
+            token = token.to_lowercase();
+        }
+        if self.config.ascii_folding {
+            token = fold_to_ascii(&token);
+        }
+        token
+    }
+
+    fn for_each_token<'a>(&self, text: &'a str, mut f: impl FnMut(&'a str)) {
+        match self.config.tokenizer {
+            TokenizerConfig::Standard => tokenize(text).into_iter().for_each(f),
+            TokenizerConfig::Whitespace => text.split_whitespace().for_each(f),
+            TokenizerConfig::CjkNgram { ngram_size } => {
+                for token in tokenize(text) {
+                    for (run, is_cjk_run) in split_cjk_runs(token) {
+                        if is_cjk_run {
+                            char_ngrams(run, ngram_size).into_iter().for_each(&mut f);
+                        } else {
+                            f(run);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for TextAnalyzer {
+    fn default() -> Self {
+        Self::new(TextAnalyzerConfig::default())
+    }
+}
+
+pub fn tokenize(text: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            if start.is_none() {
+                start = Some(i);
+            }
+        } else if let Some(s) = start {
+            result.push(&text[s..i]);
+            start = None;
+        }
+    }
+
+    if let Some(s) = start {
+        result.push(&text[s..]);
+    }
+
+    result
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{30A0}'..='\u{30FF}' // Katakana
+        | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+        | '\u{31F0}'..='\u{31FF}' // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2A6DF}' // CJK Unified Ideographs Extension B
+    )
+}
+
+// Splits the token into maximal runs of CJK and non-CJK characters
+fn split_cjk_runs(token: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+
+    for (i, c) in token.char_indices() {
+        let cjk = is_cjk(c);
+        match current {
+            Some(prev) if prev != cjk => {
+                runs.push((&token[start..i], prev));
+                start = i;
+            }
+            _ => {}
+        }
+        current = Some(cjk);
+    }
+
+    if let Some(cjk) = current {
+        runs.push((&token[start..], cjk));
+    }
+
+    runs
+}
+
+// Overlapping n-grams of the characters of the run, or the whole run if it
+// is shorter than `n`
+fn char_ngrams(run: &str, n: usize) -> Vec<&str> {
+    let boundaries: Vec<usize> = run
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(run.len()))
+        .collect();
+    let chars_count = boundaries.len() - 1;
+    if chars_count <= n {
+        return vec![run];
+    }
+    (0..=chars_count - n)
+        .map(|i| &run[boundaries[i]..boundaries[i + n]])
+        .collect()
+}
+
+fn fold_to_ascii(token: &str) -> String {
+    let mut folded = String::with_capacity(token.len());
+    for c in token.chars() {
+        match fold_char(c) {
+            Some(replacement) => folded.push_str(replacement),
+            None => folded.push(c),
+        }
+    }
+    folded
+}
+
+fn fold_char(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ď' | 'đ' | 'ð' => "d",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĥ' | 'ħ' => "h",
+        'Ĥ' | 'Ħ' => "H",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ĵ' => "j",
+        'Ĵ' => "J",
+        'ķ' => "k",
+        'Ķ' => "K",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'þ' => "th",
+        'Þ' => "TH",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ŵ' => "w",
+        'Ŵ' => "W",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ý' | 'Ÿ' | 'Ŷ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        _ => return None,
+    })
+}
+
+// Runs a light stemmer, which works on the characters of the term and
+// returns the length of the stem
+fn light_stem(term: &str, stem: fn(&mut [char]) -> usize) -> String {
+    let mut chars: Vec<char> = term.chars().collect();
+    let len = stem(&mut chars);
+    chars[..len].iter().collect()
+}
+
+// Replaces the accented vowels handled by the light stemmers
+fn normalize_vowels(s: &mut [char]) {
+    for c in s.iter_mut() {
+        *c = match *c {
+            'à' | 'á' | 'â' | 'ä' => 'a',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            c => c,
+        };
+    }
+}
+
+fn german_light_stem(s: &mut [char]) -> usize {
+    normalize_vowels(s);
+    let len = german_step1(s, s.len());
+    german_step2(s, len)
+}
+
+fn german_st_ending(c: char) -> bool {
+    matches!(c, 'b' | 'd' | 'f' | 'g' | 'h' | 'k' | 'l' | 'm' | 'n' | 't')
+}
+
+fn german_step1(s: &[char], len: usize) -> usize {
+    if len > 5 && s[len - 3] == 'e' && s[len - 2] == 'r' && s[len - 1] == 'n' {
+        return len - 3;
+    }
+    if len > 4 && s[len - 2] == 'e' && matches!(s[len - 1], 'm' | 'n' | 'r' | 's') {
+        return len - 2;
+    }
+    if len > 3 && s[len - 1] == 'e' {
+        return len - 1;
+    }
+    if len > 3 && s[len - 1] == 's' && german_st_ending(s[len - 2]) {
+        return len - 1;
+    }
+    len
+}
+
+fn german_step2(s: &[char], len: usize) -> usize {
+    if len > 5 && s[len - 3] == 'e' && s[len - 2] == 's' && s[len - 1] == 't' {
+        return len - 3;
+    }
+    if len > 4 && s[len - 2] == 'e' && matches!(s[len - 1], 'r' | 'n') {
+        return len - 2;
+    }
+    if len > 4 && s[len - 2] == 's' && s[len - 1] == 't' && german_st_ending(s[len - 3]) {
+        return len - 2;
+    }
+    len
+}
+
+fn spanish_light_stem(s: &mut [char]) -> usize {
+    let len = s.len();
+    if len < 5 {
+        return len;
+    }
+    normalize_vowels(s);
+    match s[len - 1] {
+        'o' | 'a' | 'e' => len - 1,
+        's' => {
+            if s[len - 2] == 'e' && s[len - 3] == 's' && s[len - 4] == 'e' {
+                return len - 2;
+            }
+            if s[len - 2] == 'e' && s[len - 3] == 'c' {
+                s[len - 3] = 'z';
+                return len - 2;
+            }
+            if matches!(s[len - 2], 'o' | 'a' | 'e') {
+                return len - 2;
+            }
+            len
+        }
+        _ => len,
+    }
+}
+
+fn italian_light_stem(s: &mut [char]) -> usize {
+    let len = s.len();
+    if len < 6 {
+        return len;
+    }
+    normalize_vowels(s);
+    match s[len - 1] {
+        'e' if matches!(s[len - 2], 'i' | 'h') => len - 2,
+        'i' if matches!(s[len - 2], 'h' | 'i') => len - 2,
+        'a' | 'o' if s[len - 2] == 'i' => len - 2,
+        'e' | 'i' | 'a' | 'o' => len - 1,
+        _ => len,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_analyzer_drops_stopwords_and_long_tokens() {
+        let long_token = "x".repeat(41);
+        let text = format!("The Vector and the {} database", long_token);
+        let terms = TextAnalyzer::default().analyze(&text);
+        assert_eq!(2, terms.len());
+        assert!(!terms.iter().any(|term| term == "the" || term == "and"));
+    }
+
+    #[test]
+    fn test_whitespace_tokenizer_and_custom_stopwords() {
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            tokenizer: TokenizerConfig::Whitespace,
+            stopwords: StopwordsConfig::Custom(vec!["Der".to_string(), "und".to_string()]),
+            stemmer: StemmerLanguage::None,
+            ..Default::default()
+        });
+        assert_eq!(
+            vec!["c++", "rust-lang"],
+            analyzer.analyze("der C++ und Rust-lang")
+        );
+    }
+
+    #[test]
+    fn test_ascii_folding() {
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            ascii_folding: true,
+            stopwords: StopwordsConfig::None,
+            stemmer: StemmerLanguage::None,
+            ..Default::default()
+        });
+        assert_eq!(
+            vec!["creme", "brulee", "strasse", "lodz"],
+            analyzer.analyze("Crème BRÛLÉE Straße Łódź")
+        );
+
+        // stopwords are folded too, so that they match folded tokens
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            ascii_folding: true,
+            stopwords: StopwordsConfig::Custom(vec!["él".to_string()]),
+            stemmer: StemmerLanguage::None,
+            ..Default::default()
+        });
+        assert_eq!(vec!["dijo"], analyzer.analyze("El dijo"));
+    }
+
+    #[test]
+    fn test_lowercasing_can_be_disabled() {
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            lowercase: false,
+            stopwords: StopwordsConfig::None,
+            stemmer: StemmerLanguage::None,
+            ..Default::default()
+        });
+        assert_eq!(vec!["Rust", "rust"], analyzer.analyze("Rust rust"));
+    }
+
+    #[test]
+    fn test_cjk_ngrams() {
+        let analyzer = TextAnalyzer::new(TextAnalyzerConfig {
+            tokenizer: TokenizerConfig::CjkNgram { ngram_size: 2 },
+            stopwords: StopwordsConfig::None,
+            stemmer: StemmerLanguage::None,
+            ..Default::default()
+        });
+        assert_eq!(
+            vec!["向量", "量数", "数据", "据库", "db", "库"],
+            analyzer.analyze("向量数据库db库")
+        );
+        assert_eq!(vec!["검색"], analyzer.analyze("검색"));
+    }
+
+    #[test]
+    fn test_light_stemmers() {
+        let stem = |stemmer, word: &str| {
+            TextAnalyzer::new(TextAnalyzerConfig {
+                stopwords: StopwordsConfig::None,
+                stemmer,
+                ..Default::default()
+            })
+            .analyze(word)
+            .remove(0)
+        };
+
+        assert_eq!(
+            stem(StemmerLanguage::German, "häuser"),
+            stem(StemmerLanguage::German, "hauses")
+        );
+        assert_eq!("gat", stem(StemmerLanguage::Spanish, "gatos"));
+        assert_eq!("luz", stem(StemmerLanguage::Spanish, "luces"));
+        assert_eq!("ragazz", stem(StemmerLanguage::Italian, "ragazzi"));
+        assert_eq!("ragazz", stem(StemmerLanguage::Italian, "ragazza"));
+    }
+
+    #[test]
+    fn test_positions_keep_gaps_of_dropped_tokens() {
+        let terms = TextAnalyzer::default().analyze_with_positions("vector in the database");
+        assert_eq!(
+            vec![(0, "vector".to_string()), (3, "databas".to_string())],
+            terms
+        );
+    }
+
+    #[test]
+    fn test_token_offsets() {
+        let text = "Über-fast vector databases";
+        let tokens = TextAnalyzer::default().analyze_tokens(text);
+        let offsets: Vec<_> = tokens
+            .iter()
+            .map(|token| &text[token.offsets.clone()])
+            .collect();
+        assert_eq!(vec!["Über", "fast", "vector", "databases"], offsets);
+        assert_eq!("databas", tokens[3].term);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(TextAnalyzerConfig::default().validate().is_ok());
+        let config = TextAnalyzerConfig {
+            tokenizer: TokenizerConfig::CjkNgram { ngram_size: 0 },
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_defaults_when_deserializing() {
+        let config: TextAnalyzerConfig = serde_json::from_str(
+            r#"{"tokenizer": {"type": "cjk_ngram"}, "stopwords": {"custom": ["x"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            TokenizerConfig::CjkNgram { ngram_size: 2 },
+            config.tokenizer
+        );
+        assert_eq!(
+            StopwordsConfig::Custom(vec!["x".to_string()]),
+            config.stopwords
+        );
+        assert!(config.lowercase);
+        assert_eq!(StemmerLanguage::English, config.stemmer);
+    }
+}
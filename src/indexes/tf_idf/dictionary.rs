@@ -0,0 +1,179 @@
+use std::{collections::BTreeSet, fs::OpenOptions, ops::Bound, path::Path, sync::RwLock};
+
+use crate::models::buffered_io::{BufIoError, BufferManager};
+
+/// Maximum number of index terms a prefix or fuzzy term expands into
+pub const MAX_EXPANSIONS: usize = 64;
+
+/// Sorted set of the terms of the TF-IDF index, which expands the prefix and
+/// fuzzy terms of the queries into the terms of the index
+///
+/// Terms are only ever added: a term whose documents were all deleted
+/// expands into a term without postings, which doesn't match any document.
+/// Each new term is appended to the `terms.dict` file of the index as its
+/// length (4 bytes) followed by its UTF-8 bytes.
+pub struct TermDictionary {
+    terms: RwLock<BTreeSet<String>>,
+    bufman: BufferManager,
+}
+
+impl TermDictionary {
+    /// Opens the dictionary of the index at `root_path`, loading the terms
+    /// which were already stored
+    pub fn open(root_path: &Path) -> Result<Self, BufIoError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(root_path.join("terms.dict"))?;
+        let bufman = BufferManager::new(file, 8192)?;
+
+        let mut terms = BTreeSet::new();
+        let cursor = bufman.open_cursor()?;
+        while bufman.cursor_position(cursor)? < bufman.file_size() {
+            let len = bufman.read_u32_with_cursor(cursor)?;
+            let mut bytes = vec![0; len as usize];
+            bufman.read_with_cursor(cursor, &mut bytes)?;
+            terms.insert(String::from_utf8_lossy(&bytes).into_owned());
+        }
+        bufman.close_cursor(cursor)?;
+
+        Ok(Self {
+            terms: RwLock::new(terms),
+            bufman,
+        })
+    }
+
+    pub fn insert(&self, term: &str) -> Result<(), BufIoError> {
+        if self
+            .terms
+            .read()
+            .map_err(|_| BufIoError::Locking)?
+            .contains(term)
+        {
+            return Ok(());
+        }
+        let mut terms = self.terms.write().map_err(|_| BufIoError::Locking)?;
+        if !terms.insert(term.to_string()) {
+            return Ok(());
+        }
+        let mut buf = Vec::with_capacity(term.len() + 4);
+        buf.extend((term.len() as u32).to_le_bytes());
+        buf.extend(term.as_bytes());
+        let cursor = self.bufman.open_cursor()?;
+        self.bufman.write_to_end_of_file(cursor, &buf)?;
+        self.bufman.close_cursor(cursor)
+    }
+
+    /// Terms starting with the prefix, in lexicographic order
+    pub fn expand_prefix(&self, prefix: &str) -> Result<Vec<String>, BufIoError> {
+        let terms = self.terms.read().map_err(|_| BufIoError::Locking)?;
+        Ok(terms
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|term| term.starts_with(prefix))
+            .take(MAX_EXPANSIONS)
+            .cloned()
+            .collect())
+    }
+
+    /// Terms within `max_edits` edits of the term, closest first
+    pub fn expand_fuzzy(&self, term: &str, max_edits: u32) -> Result<Vec<String>, BufIoError> {
+        let terms = self.terms.read().map_err(|_| BufIoError::Locking)?;
+        let term_len = term.chars().count();
+        let mut matches: Vec<(u32, &String)> = terms
+            .iter()
+            .filter(|candidate| {
+                // the length difference is a lower bound of the distance
+                candidate.chars().count().abs_diff(term_len) <= max_edits as usize
+            })
+            .filter_map(|candidate| {
+                let distance = edit_distance(term, candidate, max_edits)?;
+                Some((distance, candidate))
+            })
+            .collect();
+        matches.sort_by_key(|(distance, _)| *distance);
+        Ok(matches
+            .into_iter()
+            .take(MAX_EXPANSIONS)
+            .map(|(_, term)| term.clone())
+            .collect())
+    }
+
+    pub fn flush(&self) -> Result<(), BufIoError> {
+        self.bufman.flush()
+    }
+}
+
+/// Levenshtein distance between the two strings, in characters, or `None`
+/// if it's more than `max_edits`
+pub fn edit_distance(a: &str, b: &str, max_edits: u32) -> Option<u32> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<u32> = (0..=b.len() as u32).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i as u32 + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + u32::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        // the distance can't get lower than the minimum of the row
+        if current.iter().min().is_some_and(|&min| min > max_edits) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= max_edits).then_some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(Some(0), edit_distance("vector", "vector", 2));
+        assert_eq!(Some(1), edit_distance("databse", "database", 2));
+        assert_eq!(Some(2), edit_distance("vetcor", "vector", 2));
+        assert_eq!(None, edit_distance("vector", "victory", 1));
+        assert_eq!(Some(1), edit_distance("", "a", 1));
+    }
+
+    #[test]
+    fn test_expansions_and_persistence() {
+        let dir = tempdir().unwrap();
+        let dictionary = TermDictionary::open(dir.as_ref()).unwrap();
+        for term in ["vector", "vectorize", "victor", "database", "vector"] {
+            dictionary.insert(term).unwrap();
+        }
+
+        assert_eq!(
+            vec!["vector".to_string(), "vectorize".to_string()],
+            dictionary.expand_prefix("vect").unwrap()
+        );
+        assert!(dictionary.expand_prefix("x").unwrap().is_empty());
+        assert_eq!(
+            vec!["vector".to_string(), "victor".to_string()],
+            dictionary.expand_fuzzy("vectr", 2).unwrap()
+        );
+
+        dictionary.flush().unwrap();
+        drop(dictionary);
+        let dictionary = TermDictionary::open(dir.as_ref()).unwrap();
+        assert_eq!(
+            vec![
+                "database".to_string(),
+                "vector".to_string(),
+                "vectorize".to_string(),
+                "victor".to_string()
+            ],
+            dictionary.expand_prefix("").unwrap()
+        );
+    }
+}
@@ -5,37 +5,70 @@ use crate::{
         buffered_io::BufIoError,
         collection::{Collection, RawVectorEmbedding},
         common::WaCustomError,
-        meta_persist::store_average_document_length,
-        sparse_ann_query::SparseAnnQueryBasic,
+        meta_persist::store_tf_idf_configured,
+        sparse_ann_query::{
+            Bm25Field, Bm25Params, Bm25Query, Occur, PositionalPhrase, SparseAnnIDFResult,
+            SparseAnnQueryBasic,
+        },
         tf_idf_index::TFIDFIndexRoot,
         types::{InternalId, MetaDb, SparseVector},
         versioning::VersionNumber,
     },
 };
+use analyzer::{TextAnalyzer, TextAnalyzerConfig};
+use dictionary::TermDictionary;
+use query::{is_field_name_char, Clause, QueryNode, TextQuery};
 use rustc_hash::FxHashMap;
-use snowball_stemmer::Stemmer;
 use std::{
+    collections::HashMap,
+    fs,
     hash::Hasher,
-    path::PathBuf,
+    io,
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
-        RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock,
     },
 };
 use twox_hash::XxHash32;
 
-#[derive(Default)]
-pub struct SamplingData {
-    pub total_documents_length: AtomicU64,
-    pub total_documents_count: AtomicU32,
-}
+pub mod analyzer;
+pub mod dictionary;
+pub mod query;
+
+/// Name of the field indexing the `text` of the vectors
+pub const DEFAULT_FIELD: &str = "text";
+
+/// Text fields of a vector, as (field name, text) pairs
+pub struct TFIDFInputEmbedding(pub InternalId, pub Vec<(String, String)>);
 
-pub struct TFIDFInputEmbedding(pub InternalId, pub String);
+impl TFIDFInputEmbedding {
+    /// Returns `None` if the vector has no text
+    pub fn new(
+        id: InternalId,
+        text: Option<String>,
+        text_fields: Option<HashMap<String, String>>,
+    ) -> Option<Self> {
+        let mut fields: Vec<_> = text
+            .map(|text| (DEFAULT_FIELD.to_string(), text))
+            .into_iter()
+            .collect();
+        fields.extend(text_fields.into_iter().flatten());
+        (!fields.is_empty()).then_some(Self(id, fields))
+    }
+}
 
 pub struct TFIDFSearchInput(pub String);
 
 pub struct TFIDFSearchOptions {
     pub top_k: Option<usize>,
+    /// Weights of the fields matched by the clauses without a field,
+    /// missing fields aren't searched by them. All the fields have a weight
+    /// of 1 by default.
+    pub field_weights: Option<HashMap<String, f32>>,
+    /// Scores all the documents matching a plain text query, its faster
+    /// search only keeps the best document of each of its buckets
+    pub exact: bool,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -43,18 +76,33 @@ pub struct TFIDFIndexData {
     pub sample_threshold: usize,
     pub k1: f32,
     pub b: f32,
+    // indexes persisted before the analyzer was configurable use the
+    // default one
+    #[serde(default)]
+    pub analyzer: TextAnalyzerConfig,
+    #[serde(default)]
+    pub store_positions: bool,
+    #[serde(default)]
+    pub store_terms: bool,
 }
 
 pub struct TFIDFIndex {
     pub root: TFIDFIndexRoot,
-    pub average_document_length: RwLock<f32>,
     pub is_configured: AtomicBool,
     pub documents: RwLock<Vec<TFIDFInputEmbedding>>,
     pub documents_collected: AtomicUsize,
-    pub sampling_data: SamplingData,
     pub sample_threshold: usize,
     pub k1: f32,
     pub b: f32,
+    pub analyzer: TextAnalyzer,
+    // positional postings are required for phrase queries
+    pub store_positions: bool,
+    // required for prefix and fuzzy queries
+    pub term_dictionary: Option<TermDictionary>,
+    // named text fields, each one indexed in its own root under
+    // `fields_path`, `root` indexes the `text` field
+    pub fields: RwLock<FxHashMap<String, Arc<TFIDFIndexRoot>>>,
+    pub fields_path: PathBuf,
 }
 
 unsafe impl Send for TFIDFIndex {}
@@ -66,75 +114,437 @@ impl TFIDFIndex {
         sample_threshold: usize,
         k1: f32,
         b: f32,
+        analyzer: TextAnalyzerConfig,
+        store_positions: bool,
+        store_terms: bool,
     ) -> Result<Self, BufIoError> {
+        let term_dictionary = store_terms
+            .then(|| TermDictionary::open(&root_path))
+            .transpose()?;
+        let fields_path = root_path.join("fields");
         let root = TFIDFIndexRoot::new(root_path)?;
 
         Ok(Self {
             root,
-            average_document_length: RwLock::new(1.0),
             is_configured: AtomicBool::new(false),
-            sampling_data: SamplingData::default(),
             documents: RwLock::new(Vec::new()),
             documents_collected: AtomicUsize::new(0),
             sample_threshold,
             k1,
             b,
+            analyzer: TextAnalyzer::new(analyzer),
+            store_positions,
+            term_dictionary,
+            fields: RwLock::new(FxHashMap::default()),
+            fields_path,
         })
     }
 
+    /// Names of the named text fields, sorted
+    pub fn field_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.fields.read().unwrap().keys().cloned().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Loads the roots of the named fields of an index
+    pub fn load_fields(
+        fields_path: &Path,
+    ) -> Result<FxHashMap<String, Arc<TFIDFIndexRoot>>, BufIoError> {
+        let mut fields = FxHashMap::default();
+        if !fields_path.exists() {
+            return Ok(fields);
+        }
+        for entry in fs::read_dir(fields_path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            fields.insert(name, Arc::new(TFIDFIndexRoot::deserialize(entry.path())?));
+        }
+        Ok(fields)
+    }
+
+    // Returns the root of a named field, creating it if it doesn't exist
+    fn field_root(&self, field: &str) -> Result<Arc<TFIDFIndexRoot>, BufIoError> {
+        if let Some(root) = self
+            .fields
+            .read()
+            .map_err(|_| BufIoError::Locking)?
+            .get(field)
+        {
+            return Ok(root.clone());
+        }
+        // the name is a directory name
+        validate_field_name(field)
+            .map_err(|err| BufIoError::Io(io::Error::new(io::ErrorKind::InvalidInput, err)))?;
+        let mut fields = self.fields.write().map_err(|_| BufIoError::Locking)?;
+        if let Some(root) = fields.get(field) {
+            return Ok(root.clone());
+        }
+        let path = self.fields_path.join(field);
+        fs::create_dir_all(&path)?;
+        let root = TFIDFIndexRoot::new(path)?;
+        // the header is written right away, so that the field can be loaded
+        // before the next flush
+        root.serialize()?;
+        let root = Arc::new(root);
+        fields.insert(field.to_string(), root.clone());
+        Ok(root)
+    }
+
+    /// Indexes the text of the `text` field of a vector
     pub fn insert(
         &self,
         version: VersionNumber,
         id: InternalId,
         text: String,
     ) -> Result<(), BufIoError> {
-        self.root
-            .total_documents_count
-            .fetch_add(1, Ordering::Relaxed);
-
-        let terms = process_text(
-            &text,
-            40,
-            *self.average_document_length.read().unwrap(),
-            self.k1,
-            self.b,
-        );
+        self.insert_into(&self.root, version, id, &text)
+    }
+
+    pub fn insert_field(
+        &self,
+        version: VersionNumber,
+        id: InternalId,
+        field: &str,
+        text: String,
+    ) -> Result<(), BufIoError> {
+        if field == DEFAULT_FIELD {
+            return self.insert(version, id, text);
+        }
+        let root = self.field_root(field)?;
+        self.insert_into(&root, version, id, &text)
+    }
+
+    fn insert_into(
+        &self,
+        root: &TFIDFIndexRoot,
+        version: VersionNumber,
+        id: InternalId,
+        text: &str,
+    ) -> Result<(), BufIoError> {
+        let (terms, document_length) = process_text_with_positions(text, &self.analyzer);
 
         let id = id.into();
+        root.add_document(id, document_length, version);
 
-        for (term_hash, tf) in terms {
-            self.root.insert(term_hash, tf, id, version)?;
+        for (term_hash, term, positions) in terms {
+            if let Some(dictionary) = &self.term_dictionary {
+                dictionary.insert(&term)?;
+            }
+            let count = positions.len() as f32;
+            let positions = if self.store_positions {
+                &positions[..]
+            } else {
+                &[]
+            };
+            root.insert(term_hash, count, id, positions, version)?;
         }
 
         Ok(())
     }
 
+    /// Removes the text of the `text` field of a vector
     pub fn mark_embedding_as_deleted(
         &self,
         version: VersionNumber,
         id: InternalId,
         text: &str,
     ) -> Result<(), BufIoError> {
-        self.root
-            .total_documents_count
-            .fetch_sub(1, Ordering::Relaxed);
-
-        let terms = process_text(
-            text,
-            40,
-            *self.average_document_length.read().unwrap(),
-            self.k1,
-            self.b,
-        );
+        Self::delete_from(&self.root, &self.analyzer, version, id, text)
+    }
+
+    pub fn mark_field_as_deleted(
+        &self,
+        version: VersionNumber,
+        id: InternalId,
+        field: &str,
+        text: &str,
+    ) -> Result<(), BufIoError> {
+        if field == DEFAULT_FIELD {
+            return self.mark_embedding_as_deleted(version, id, text);
+        }
+        let root = self
+            .fields
+            .read()
+            .map_err(|_| BufIoError::Locking)?
+            .get(field)
+            .cloned();
+        match root {
+            Some(root) => Self::delete_from(&root, &self.analyzer, version, id, text),
+            None => Ok(()),
+        }
+    }
+
+    fn delete_from(
+        root: &TFIDFIndexRoot,
+        analyzer: &TextAnalyzer,
+        version: VersionNumber,
+        id: InternalId,
+        text: &str,
+    ) -> Result<(), BufIoError> {
+        let terms = process_text(text, analyzer);
 
         let id = id.into();
+        root.remove_document(id, version);
 
         for (term_hash, _) in terms {
-            self.root.delete(term_hash, id, version)?;
+            root.delete(term_hash, id, version)?;
         }
 
         Ok(())
     }
+
+    /// Evaluates a full-text query, see [`TextQuery`] for its syntax. Plain
+    /// text queries are scored exactly if `exact` is set, see
+    /// `TFIDFSearchOptions::exact`
+    pub fn search_text_with(
+        &self,
+        query: &str,
+        field_weights: Option<&HashMap<String, f32>>,
+        top_k: Option<usize>,
+        exact: bool,
+    ) -> Result<Vec<SparseAnnIDFResult>, WaCustomError> {
+        let query = TextQuery::parse(query).map_err(WaCustomError::InvalidData)?;
+        if query.has_phrases() && !self.store_positions {
+            return Err(WaCustomError::InvalidData(
+                "Phrase queries require a TF-IDF index created with `store_positions`".to_string(),
+            ));
+        }
+        if query.has_expansions() && self.term_dictionary.is_none() {
+            return Err(WaCustomError::InvalidData(
+                "Prefix and fuzzy queries require a TF-IDF index created with `store_terms`"
+                    .to_string(),
+            ));
+        }
+        if field_weights.is_some_and(|weights| {
+            weights
+                .values()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+        }) {
+            return Err(WaCustomError::InvalidData(
+                "Field weights must be non-negative numbers".to_string(),
+            ));
+        }
+
+        let named_fields = self.fields.read().map_err(|_| BufIoError::Locking)?;
+        let mut names = vec![DEFAULT_FIELD];
+        let mut roots = vec![&self.root];
+        let mut sorted_fields: Vec<_> = named_fields.iter().collect();
+        sorted_fields.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (name, root) in sorted_fields {
+            names.push(name);
+            roots.push(root);
+        }
+        let fields: Vec<_> = names
+            .iter()
+            .zip(roots)
+            .map(|(name, root)| Bm25Field {
+                root,
+                weight: field_weights
+                    .map_or(1.0, |weights| weights.get(*name).copied().unwrap_or(0.0)),
+            })
+            .collect();
+
+        let results = match query.plain_text() {
+            // a single field scored as usual is a plain BM25 search
+            Some(text) if !exact && fields.len() == 1 && fields[0].weight == 1.0 => {
+                let entries = process_text(&text, &self.analyzer);
+
+                let sparse_vec = SparseVector {
+                    vector_id: u32::MAX,
+                    entries,
+                };
+
+                SparseAnnQueryBasic::new(sparse_vec).search_bm25(
+                    &self.root,
+                    self.bm25_params(),
+                    top_k,
+                )?
+            }
+            _ => self
+                .build_bm25_query(&query.clauses, &names, None, 1.0)?
+                .search(&fields, self.bm25_params(), top_k)?,
+        };
+
+        Ok(results)
+    }
+
+    fn bm25_params(&self) -> Bm25Params {
+        Bm25Params {
+            k1: self.k1,
+            b: self.b,
+        }
+    }
+
+    // `fields` are the names of the searched fields and `field` the one the
+    // clauses are restricted to, if any
+    fn build_bm25_query(
+        &self,
+        clauses: &[Clause],
+        fields: &[&str],
+        field: Option<usize>,
+        boost: f32,
+    ) -> Result<Bm25Query, BufIoError> {
+        let mut bm25_clauses = Vec::with_capacity(clauses.len());
+        for clause in clauses {
+            if let Some(node) = self.build_bm25_node(&clause.node, fields, field)? {
+                bm25_clauses.push((clause.occur, node));
+            }
+        }
+        Ok(Bm25Query::Boolean {
+            clauses: bm25_clauses,
+            boost,
+        })
+    }
+
+    // Returns `None` for the nodes without any term after the analysis,
+    // e.g. stopwords
+    fn build_bm25_node(
+        &self,
+        node: &QueryNode,
+        fields: &[&str],
+        field: Option<usize>,
+    ) -> Result<Option<Bm25Query>, BufIoError> {
+        Ok(match node {
+            QueryNode::Term { text, boost } => {
+                let terms = self
+                    .analyzer
+                    .analyze(text)
+                    .iter()
+                    .map(|term| Bm25Query::Term {
+                        term_hash: hash_term(term),
+                        field,
+                        boost: 1.0,
+                    })
+                    .collect();
+                // words split by the analyzer, e.g. "rust-lang", require
+                // all their terms
+                all_terms(terms, *boost)
+            }
+            QueryNode::Phrase { text, slop, boost } => {
+                let terms: Vec<_> = self
+                    .analyzer
+                    .analyze_with_positions(text)
+                    .into_iter()
+                    .map(|(position, term)| (hash_term(&term), position))
+                    .collect();
+                if terms.is_empty() {
+                    return Ok(None);
+                }
+                Some(Bm25Query::Phrase {
+                    phrase: PositionalPhrase { terms, slop: *slop },
+                    field,
+                    boost: *boost,
+                })
+            }
+            QueryNode::Prefix { text, boost } => {
+                let prefix = self.analyzer.normalize_token(text);
+                if prefix.is_empty() {
+                    return Ok(None);
+                }
+                // checked before building the query
+                let Some(dictionary) = &self.term_dictionary else {
+                    return Ok(None);
+                };
+                Some(any_term(&dictionary.expand_prefix(&prefix)?, field, *boost))
+            }
+            QueryNode::Fuzzy {
+                text,
+                max_edits,
+                boost,
+            } => {
+                let Some(dictionary) = &self.term_dictionary else {
+                    return Ok(None);
+                };
+                let mut expansions = Vec::new();
+                for term in self.analyzer.analyze(text) {
+                    let terms = dictionary.expand_fuzzy(&term, *max_edits)?;
+                    expansions.push(any_term(&terms, field, 1.0));
+                }
+                all_terms(expansions, *boost)
+            }
+            QueryNode::Group { clauses, boost } => {
+                let query = self.build_bm25_query(clauses, fields, field, *boost)?;
+                let is_empty =
+                    matches!(&query, Bm25Query::Boolean { clauses, .. } if clauses.is_empty());
+                (!is_empty).then_some(query)
+            }
+            QueryNode::Field { name, node } => {
+                match fields.iter().position(|field| *field == name.as_str()) {
+                    Some(field) => self.build_bm25_node(node, fields, Some(field))?,
+                    // a field without any document matches nothing
+                    None => Some(any_term(&[], None, 1.0)),
+                }
+            }
+        })
+    }
+}
+
+// Query matching the documents with all the queries, `None` if there are
+// none
+fn all_terms(mut queries: Vec<Bm25Query>, boost: f32) -> Option<Bm25Query> {
+    match queries.len() {
+        0 => None,
+        1 => Some(match queries.remove(0) {
+            Bm25Query::Term {
+                term_hash, field, ..
+            } => Bm25Query::Term {
+                term_hash,
+                field,
+                boost,
+            },
+            Bm25Query::Boolean { clauses, .. } => Bm25Query::Boolean { clauses, boost },
+            query => Bm25Query::Boolean {
+                clauses: vec![(Occur::Must, query)],
+                boost,
+            },
+        }),
+        _ => Some(Bm25Query::Boolean {
+            clauses: queries
+                .into_iter()
+                .map(|query| (Occur::Must, query))
+                .collect(),
+            boost,
+        }),
+    }
+}
+
+// Query matching the documents with any of the terms, scored with the sum of
+// their BM25 scores. Without any term, it doesn't match any document.
+fn any_term(terms: &[String], field: Option<usize>, boost: f32) -> Bm25Query {
+    Bm25Query::Boolean {
+        clauses: terms
+            .iter()
+            .map(|term| {
+                (
+                    Occur::Should,
+                    Bm25Query::Term {
+                        term_hash: hash_term(term),
+                        field,
+                        boost: 1.0,
+                    },
+                )
+            })
+            .collect(),
+        boost,
+    }
+}
+
+/// Checks that a named text field can be indexed
+pub fn validate_field_name(name: &str) -> Result<(), String> {
+    if name == DEFAULT_FIELD {
+        return Err(format!(
+            "`{}` is reserved for the text of the vectors",
+            DEFAULT_FIELD
+        ));
+    }
+    if name.is_empty() || !name.chars().all(is_field_name_char) {
+        return Err(format!(
+            "Invalid text field name `{}`, names are made of ASCII letters, digits, `_` and `-`",
+            name
+        ));
+    }
+    Ok(())
 }
 
 impl IndexOps for TFIDFIndex {
@@ -143,7 +553,14 @@ impl IndexOps for TFIDFIndex {
     type SearchOptions = TFIDFSearchOptions;
     type Data = TFIDFIndexData;
 
-    fn validate_embedding(&self, _embedding: Self::IndexingInput) -> Result<(), WaCustomError> {
+    const INDEX_TYPE: &'static str = "tf_idf";
+
+    fn validate_embedding(&self, embedding: Self::IndexingInput) -> Result<(), WaCustomError> {
+        for (field, _) in &embedding.1 {
+            if field != DEFAULT_FIELD {
+                validate_field_name(field).map_err(WaCustomError::InvalidData)?;
+            }
+        }
         Ok(())
     }
 
@@ -154,9 +571,11 @@ impl IndexOps for TFIDFIndex {
         version: VersionNumber,
         _config: &Config,
     ) -> Result<(), WaCustomError> {
-        embeddings
-            .into_iter()
-            .try_for_each(|TFIDFInputEmbedding(id, text)| self.insert(version, id, text))?;
+        for TFIDFInputEmbedding(id, fields) in embeddings {
+            for (field, text) in fields {
+                self.insert_field(version, id, &field, text)?;
+            }
+        }
         Ok(())
     }
 
@@ -167,22 +586,18 @@ impl IndexOps for TFIDFIndex {
         version: VersionNumber,
         _config: &Config,
     ) -> Result<(), WaCustomError> {
-        let Some(text) = &raw_emb.text else {
-            return Ok(());
-        };
-        self.mark_embedding_as_deleted(version, id, text)?;
+        if let Some(text) = &raw_emb.text {
+            self.mark_embedding_as_deleted(version, id, text)?;
+        }
+        for (field, text) in raw_emb.text_fields.iter().flatten() {
+            self.mark_field_as_deleted(version, id, field, text)?;
+        }
         Ok(())
     }
 
-    fn sample_embedding(&self, embedding: &Self::IndexingInput) {
-        let len = count_tokens(&embedding.1, 40);
-        self.sampling_data
-            .total_documents_length
-            .fetch_add(len as u64, Ordering::Relaxed);
-        self.sampling_data
-            .total_documents_count
-            .fetch_add(1, Ordering::Relaxed);
-    }
+    // the scores use the live statistics of the index, there's nothing to
+    // sample
+    fn sample_embedding(&self, _embedding: &Self::IndexingInput) {}
 
     fn finalize_sampling(
         &self,
@@ -190,19 +605,8 @@ impl IndexOps for TFIDFIndex {
         _config: &Config,
         _embeddings: &[Self::IndexingInput],
     ) -> Result<(), WaCustomError> {
-        let total_documents_count = self
-            .sampling_data
-            .total_documents_count
-            .load(Ordering::Relaxed);
-        let total_documents_length = self
-            .sampling_data
-            .total_documents_length
-            .load(Ordering::Relaxed);
-
-        let avg_length = total_documents_length as f32 / total_documents_count as f32;
-        *self.average_document_length.write().unwrap() = avg_length;
         self.is_configured.store(true, Ordering::Release);
-        store_average_document_length(lmdb, avg_length)?;
+        store_tf_idf_configured(lmdb)?;
         Ok(())
     }
 
@@ -229,6 +633,18 @@ impl IndexOps for TFIDFIndex {
     ) -> Result<(), WaCustomError> {
         self.root.serialize()?;
         self.root.cache.flush_all()?;
+        for root in self
+            .fields
+            .read()
+            .map_err(|_| BufIoError::Locking)?
+            .values()
+        {
+            root.serialize()?;
+            root.cache.flush_all()?;
+        }
+        if let Some(dictionary) = &self.term_dictionary {
+            dictionary.flush()?;
+        }
         Ok(())
     }
 
@@ -237,6 +653,9 @@ impl IndexOps for TFIDFIndex {
             sample_threshold: self.sample_threshold,
             k1: self.k1,
             b: self.b,
+            analyzer: self.analyzer.config().clone(),
+            store_positions: self.store_positions,
+            store_terms: self.term_dictionary.is_some(),
         }
     }
 
@@ -248,21 +667,12 @@ impl IndexOps for TFIDFIndex {
         _config: &Config,
         _return_raw_text: bool,
     ) -> Result<Vec<InternalSearchResult>, WaCustomError> {
-        let entries = process_text(
+        let results = self.search_text_with(
             &query.0,
-            40,
-            *self.average_document_length.read().unwrap(),
-            self.k1,
-            self.b,
-        );
-
-        let sparse_vec = SparseVector {
-            vector_id: u32::MAX,
-            entries,
-        };
-
-        let results =
-            SparseAnnQueryBasic::new(sparse_vec).search_bm25(&self.root, options.top_k)?;
+            options.field_weights.as_ref(),
+            options.top_k,
+            options.exact,
+        )?;
 
         Ok(results
             .into_iter()
@@ -279,111 +689,282 @@ impl IndexOps for TFIDFIndex {
     }
 }
 
-const STOPWORDS: [&str; 35] = [
-    "a", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
-    "not", "of", "on", "or", "s", "such", "t", "that", "the", "their", "then", "there", "these",
-    "they", "this", "to", "was", "will", "with", "www",
-];
-
-pub fn tokenize(text: &str) -> Vec<&str> {
-    let mut result = Vec::new();
-    let mut start = None;
-
-    for (i, c) in text.char_indices() {
-        if c.is_alphanumeric() || c == '_' {
-            if start.is_none() {
-                start = Some(i);
-            }
-        } else if let Some(s) = start {
-            result.push(&text[s..i]);
-            start = None;
-        }
-    }
+/// Returns the hash of each term of the text and its number of occurrences
+pub fn process_text(input: &str, analyzer: &TextAnalyzer) -> Vec<(u32, f32)> {
+    process_text_with_positions(input, analyzer)
+        .0
+        .into_iter()
+        .map(|(hash, _, positions)| (hash, positions.len() as f32))
+        .collect()
+}
 
-    if let Some(s) = start {
-        result.push(&text[s..]);
+/// Returns the hash, the term and the positions of each term of the text,
+/// along with the length of the text in terms
+pub fn process_text_with_positions(
+    input: &str,
+    analyzer: &TextAnalyzer,
+) -> (Vec<(u32, String, Vec<u32>)>, u32) {
+    let terms = analyzer.analyze_with_positions(input);
+    let document_length = terms.len() as u32;
+    // Create a fast hash map for collecting the positions; FxHashMap is chosen for performance.
+    let mut positions: FxHashMap<u32, (String, Vec<u32>)> = FxHashMap::default();
+
+    for (position, term) in terms {
+        positions
+            .entry(hash_term(&term))
+            .or_insert_with(|| (term, Vec::new()))
+            .1
+            .push(position);
     }
 
-    result
+    (
+        positions
+            .into_iter()
+            .map(|(hash, (term, positions))| (hash, term, positions))
+            .collect(),
+        document_length,
+    )
 }
 
-pub fn process_text(
-    input: &str,
-    max_token_len: usize,
-    average_document_length: f32,
-    k1: f32,
-    b: f32,
-) -> Vec<(u32, f32)> {
-    // Create an English stemmer.
-    let stemmer = Stemmer::create();
-    // Create a fast hash map for counting; FxHashMap is chosen for performance.
-    let mut freq: FxHashMap<u32, u32> = FxHashMap::default();
-    let document_length = count_tokens(input, max_token_len);
-
-    // Split input by whitespace to minimize unnecessary cloning.
-    for token in tokenize(input) {
-        if token.len() > max_token_len {
-            continue;
-        }
-
-        // Convert to lowercase.
-        // Since to_lowercase allocates a new String, this is necessary for case insensitive matching.
-        let lower = token.to_lowercase();
+// Hashes the term using xxhash32
+fn hash_term(term: &str) -> u32 {
+    let mut hasher = XxHash32::with_seed(0);
+    hasher.write(term.as_bytes());
+    hasher.finish() as u32
+}
 
-        // Skip token if it is a stopword.
-        if STOPWORDS.contains(&lower.as_str()) {
-            continue;
+#[cfg(test)]
+mod tests {
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    const DOCUMENTS: [&str; 3] = [
+        "a vector database written in rust",
+        "the database stores every vector",
+        "vector search over a large database",
+    ];
+
+    fn create_index(store_positions: bool, store_terms: bool) -> (TFIDFIndex, TempDir) {
+        let dir = tempdir().unwrap();
+        let index = TFIDFIndex::new(
+            dir.as_ref().into(),
+            100,
+            1.2,
+            0.75,
+            TextAnalyzerConfig::default(),
+            store_positions,
+            store_terms,
+        )
+        .unwrap();
+        for (id, text) in DOCUMENTS.iter().enumerate() {
+            index
+                .insert(0.into(), InternalId::from(id as u32), text.to_string())
+                .unwrap();
         }
+        (index, dir)
+    }
 
-        // Stem the token.
-        // Note: stemmer.stem returns a Cow<&str>; we call to_string() to get an owned String.
-        let stemmed = stemmer.stem(&lower).to_string();
+    fn search(index: &TFIDFIndex, query: &str) -> Vec<u32> {
+        let mut ids: Vec<_> = index
+            .search_text_with(query, None, None, false)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.document_id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
 
-        // Hash the stemmed token using xxhash32.
-        let mut hasher = XxHash32::with_seed(0);
-        hasher.write(stemmed.as_bytes());
-        let token_hash = hasher.finish() as u32;
+    #[test]
+    fn test_phrase_queries() {
+        let (index, _dir) = create_index(true, false);
+
+        assert_eq!(vec![0, 1, 2], search(&index, "vector database"));
+        assert_eq!(vec![0], search(&index, r#""vector database""#));
+        assert_eq!(vec![0], search(&index, r#""vector database"~3"#));
+        assert_eq!(vec![0, 2], search(&index, r#""vector database"~4"#));
+        assert_eq!(vec![2], search(&index, r#""vector database"~4 "large""#));
+        assert!(search(&index, r#""database vector""#).is_empty());
+
+        index
+            .mark_embedding_as_deleted(1.into(), InternalId::from(0), DOCUMENTS[0])
+            .unwrap();
+        assert!(search(&index, r#""vector database""#).is_empty());
+    }
 
-        // Increment the count for this hash.
-        *freq.entry(token_hash).or_insert(0) += 1;
+    #[test]
+    fn test_boolean_queries() {
+        let (index, _dir) = create_index(false, false);
+
+        assert_eq!(vec![0], search(&index, "+rust vector"));
+        assert_eq!(vec![1, 2], search(&index, "vector -rust"));
+        assert_eq!(vec![0, 1], search(&index, "database AND NOT large"));
+        assert_eq!(vec![0, 2], search(&index, "(rust OR search) AND database"));
+        assert!(search(&index, "-rust").is_empty());
+        assert!(matches!(
+            index.search_text_with("(rust", None, None, false),
+            Err(WaCustomError::InvalidData(_))
+        ));
+
+        let ranked: Vec<_> = index
+            .search_text_with("rust stores^10", None, None, false)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.document_id)
+            .collect();
+        assert_eq!(vec![1, 0], ranked);
     }
 
-    // Convert the hash map into a Vec of (hash, count) pairs.
-    freq.into_iter()
-        .map(|(hash, count)| {
-            (
-                hash,
-                compute_bm25_term_frequency(count, document_length, average_document_length, k1, b),
-            )
-        })
-        .collect()
-}
+    #[test]
+    fn test_phrase_queries_require_positions() {
+        let (index, _dir) = create_index(false, false);
 
-fn compute_bm25_term_frequency(
-    count: u32,
-    document_length: u32,
-    average_document_length: f32,
-    k1: f32,
-    b: f32,
-) -> f32 {
-    count as f32 * (k1 + 1.0)
-        / (count as f32 + k1 * (1.0 - b + b * (document_length as f32 / average_document_length)))
-}
+        assert_eq!(vec![0, 1, 2], search(&index, "vector database"));
+        assert!(matches!(
+            index.search_text_with(r#""vector database""#, None, None, false),
+            Err(WaCustomError::InvalidData(_))
+        ));
+    }
 
-pub fn count_tokens(input: &str, max_token_len: usize) -> u32 {
-    let mut count = 0;
+    #[test]
+    fn test_bm25_uses_live_statistics() {
+        let (index, _dir) = create_index(false, false);
+        let score = |index: &TFIDFIndex| {
+            let results = index.search_text_with("rust", None, None, false).unwrap();
+            assert_eq!(1, results.len());
+            results[0].score
+        };
+        let initial_score = score(&index);
+
+        // a longer document makes the others shorter than the average and
+        // lowers the document frequency of `rust`
+        let text = "rust vectors are indexed next to the text of every other document";
+        index
+            .insert(1.into(), InternalId::from(3), text.to_string())
+            .unwrap();
+        assert_eq!(4, index.root.total_documents_count.load(Ordering::Relaxed));
+        let results = index.search_text_with("rust", None, None, false).unwrap();
+        assert_eq!(2, results.len());
+        assert_eq!(0, results[0].document_id);
+        assert_ne!(initial_score, results[0].score);
+
+        index
+            .mark_embedding_as_deleted(2.into(), InternalId::from(3), text)
+            .unwrap();
+        assert_eq!(3, index.root.total_documents_count.load(Ordering::Relaxed));
+        assert!((initial_score - score(&index)).abs() < 1e-6);
+    }
 
-    for token in tokenize(input) {
-        if token.len() > max_token_len {
-            continue;
-        }
+    #[test]
+    fn test_prefix_and_fuzzy_queries() {
+        let (index, _dir) = create_index(false, true);
+
+        assert_eq!(vec![0, 1, 2], search(&index, "vect*"));
+        assert_eq!(vec![0], search(&index, "writ*"));
+        assert_eq!(vec![0], search(&index, "rst~1"));
+        assert_eq!(vec![2], search(&index, "serch~"));
+        assert_eq!(vec![1, 2], search(&index, "vect* -rst~1"));
+        assert!(search(&index, "xyz*").is_empty());
+        // a required expansion without any term matches nothing
+        assert!(search(&index, "+xyz* vector").is_empty());
+
+        let (index, _dir) = create_index(false, false);
+        assert!(matches!(
+            index.search_text_with("vect*", None, None, false),
+            Err(WaCustomError::InvalidData(_))
+        ));
+    }
 
-        let lower = token.to_lowercase();
-        if STOPWORDS.contains(&lower.as_str()) {
-            continue;
+    #[test]
+    fn test_field_restrictions_and_weights() {
+        let (index, _dir) = create_index(true, false);
+        index
+            .insert_field(0.into(), InternalId::from(0), "title", "rust".to_string())
+            .unwrap();
+        index
+            .insert_field(
+                0.into(),
+                InternalId::from(1),
+                "title",
+                "a database guide".to_string(),
+            )
+            .unwrap();
+        index
+            .insert_field(
+                0.into(),
+                InternalId::from(2),
+                "tags",
+                "rust search".to_string(),
+            )
+            .unwrap();
+
+        assert_eq!(vec![0], search(&index, "title:rust"));
+        assert_eq!(vec![2], search(&index, "tags:rust"));
+        assert_eq!(vec![0, 2], search(&index, "rust"));
+        assert_eq!(vec![0], search(&index, "text:rust"));
+        assert_eq!(vec![1], search(&index, r#"title:"database guide""#));
+        assert_eq!(vec![1, 2], search(&index, "vector -title:rust"));
+        assert!(search(&index, "author:rust").is_empty());
+        assert!(search(&index, "+author:rust vector").is_empty());
+
+        let weighted = |weights: &[(&str, f32)], query: &str| {
+            let weights = weights
+                .iter()
+                .map(|(field, weight)| (field.to_string(), *weight))
+                .collect();
+            index
+                .search_text_with(query, Some(&weights), None, false)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.document_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![2], weighted(&[("tags", 1.0)], "rust"));
+        // field restrictions ignore the weights
+        assert_eq!(vec![0], weighted(&[("tags", 1.0)], "title:rust"));
+        assert_eq!(
+            vec![2, 0],
+            weighted(&[("text", 1.0), ("tags", 10.0)], "rust")
+        );
+        assert!(matches!(
+            index.search_text_with(
+                "rust",
+                Some(&HashMap::from([("tags".to_string(), -1.0)])),
+                None,
+                false
+            ),
+            Err(WaCustomError::InvalidData(_))
+        ));
+
+        index
+            .mark_field_as_deleted(1.into(), InternalId::from(2), "tags", "rust search")
+            .unwrap();
+        assert!(search(&index, "tags:rust").is_empty());
+
+        for root in index.fields.read().unwrap().values() {
+            root.serialize().unwrap();
+            root.cache.flush_all().unwrap();
         }
-        count += 1;
+        let fields = TFIDFIndex::load_fields(&index.fields_path).unwrap();
+        let mut names: Vec<_> = fields.keys().cloned().collect();
+        names.sort_unstable();
+        assert_eq!(vec!["tags".to_string(), "title".to_string()], names);
+        assert_eq!(
+            2,
+            fields["title"]
+                .total_documents_count
+                .load(Ordering::Relaxed)
+        );
     }
 
-    count
+    #[test]
+    fn test_field_names() {
+        assert!(validate_field_name("title").is_ok());
+        assert!(validate_field_name("image_alt-text2").is_ok());
+        assert!(validate_field_name(DEFAULT_FIELD).is_err());
+        assert!(validate_field_name("").is_err());
+        assert!(validate_field_name("a b").is_err());
+        assert!(validate_field_name("../x").is_err());
+    }
 }
@@ -0,0 +1,553 @@
+use crate::models::sparse_ann_query::Occur;
+
+/// Full-text query of the TF-IDF index
+///
+/// The query is a list of clauses, each one optionally prefixed with `+`
+/// (required) or `-` / `NOT` (excluded), and optionally followed by a boost
+/// (`term^2`). Clauses are:
+///
+/// - terms, which are optional by default
+/// - prefix terms (`vect*`) and fuzzy terms (`databse~1`), which match the
+///   terms of the index starting with the prefix or within the given
+///   number of edits (at most 2, the default) of the term
+/// - quoted phrases, which are required by default and can be followed by
+///   `~N` to allow up to `N` other tokens between their terms, e.g.
+///   `"vector database"~2`
+/// - parenthesized groups of clauses
+/// - any of the above restricted to a text field, e.g. `title:rust`,
+///   `title:"vector database"` or `tags:(rust OR go)`. Clauses without a
+///   field match all the fields.
+///
+/// `a AND b` makes both clauses required and `a OR b` makes both optional,
+/// unless they have an explicit prefix. A query made only of excluded
+/// clauses matches no documents.
+#[derive(Debug, Default, PartialEq)]
+pub struct TextQuery {
+    pub clauses: Vec<Clause>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Clause {
+    pub occur: Occur,
+    pub node: QueryNode,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryNode {
+    Term {
+        text: String,
+        boost: f32,
+    },
+    Phrase {
+        text: String,
+        /// Maximum number of extra tokens allowed between the terms
+        slop: u32,
+        boost: f32,
+    },
+    Prefix {
+        text: String,
+        boost: f32,
+    },
+    Fuzzy {
+        text: String,
+        max_edits: u32,
+        boost: f32,
+    },
+    Group {
+        clauses: Vec<Clause>,
+        boost: f32,
+    },
+    /// Node matching only the given text field
+    Field {
+        name: String,
+        node: Box<QueryNode>,
+    },
+}
+
+pub const MAX_FUZZY_EDITS: u32 = 2;
+
+#[derive(Clone, Copy)]
+enum Conjunction {
+    And,
+    Or,
+}
+
+impl TextQuery {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parser = Parser { rest: query };
+        let clauses = parser.parse_clauses(false)?;
+        Ok(Self { clauses })
+    }
+
+    pub fn has_phrases(&self) -> bool {
+        self.any_node(&|node| matches!(node, QueryNode::Phrase { .. }))
+    }
+
+    /// Whether the query has prefix or fuzzy terms, which are expanded with
+    /// the term dictionary of the index
+    pub fn has_expansions(&self) -> bool {
+        self.any_node(&|node| matches!(node, QueryNode::Prefix { .. } | QueryNode::Fuzzy { .. }))
+    }
+
+    fn any_node(&self, predicate: &impl Fn(&QueryNode) -> bool) -> bool {
+        fn any_node(node: &QueryNode, predicate: &impl Fn(&QueryNode) -> bool) -> bool {
+            match node {
+                QueryNode::Group { clauses, .. } => clauses
+                    .iter()
+                    .any(|clause| any_node(&clause.node, predicate)),
+                QueryNode::Field { node, .. } => any_node(node, predicate),
+                node => predicate(node),
+            }
+        }
+        self.clauses
+            .iter()
+            .any(|clause| any_node(&clause.node, predicate))
+    }
+
+    /// Returns the text of the query if it's a plain list of optional terms
+    /// without boosts, which can be scored as a bag of words
+    pub fn plain_text(&self) -> Option<String> {
+        let mut words = Vec::with_capacity(self.clauses.len());
+        for clause in &self.clauses {
+            match &clause.node {
+                QueryNode::Term { text, boost }
+                    if clause.occur == Occur::Should && *boost == 1.0 =>
+                {
+                    words.push(text.as_str())
+                }
+                _ => return None,
+            }
+        }
+        Some(words.join(" "))
+    }
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_clauses(&mut self, in_group: bool) -> Result<Vec<Clause>, String> {
+        let mut clauses: Vec<Clause> = Vec::new();
+        // whether the occur of each clause was set by a prefix
+        let mut explicit: Vec<bool> = Vec::new();
+        let mut conjunction = None;
+
+        loop {
+            self.rest = self.rest.trim_start();
+            let Some(c) = self.rest.chars().next() else {
+                if in_group {
+                    return Err("Missing closing parenthesis".to_string());
+                }
+                break;
+            };
+            if c == ')' {
+                if !in_group {
+                    return Err("Unexpected closing parenthesis".to_string());
+                }
+                self.rest = &self.rest[1..];
+                break;
+            }
+
+            let mut occur = None;
+            match self.peek_word() {
+                "AND" => {
+                    self.rest = &self.rest[3..];
+                    conjunction = Some(Conjunction::And);
+                    continue;
+                }
+                "OR" => {
+                    self.rest = &self.rest[2..];
+                    conjunction = Some(Conjunction::Or);
+                    continue;
+                }
+                "NOT" => {
+                    self.rest = self.rest[3..].trim_start();
+                    occur = Some(Occur::MustNot);
+                }
+                _ => {}
+            }
+            if occur.is_none() {
+                if let Some(rest) = self.rest.strip_prefix('+') {
+                    self.rest = rest;
+                    occur = Some(Occur::Must);
+                } else if let Some(rest) = self.rest.strip_prefix('-') {
+                    self.rest = rest;
+                    occur = Some(Occur::MustNot);
+                }
+            }
+
+            let Some(node) = self.parse_node()? else {
+                continue;
+            };
+            let is_explicit = occur.is_some();
+            let mut occur = occur.unwrap_or(match &node {
+                QueryNode::Phrase { .. } => Occur::Must,
+                QueryNode::Field { node, .. } if matches!(**node, QueryNode::Phrase { .. }) => {
+                    Occur::Must
+                }
+                _ => Occur::Should,
+            });
+
+            if let Some(conjunction) = conjunction.take() {
+                let implicit_occur = match conjunction {
+                    Conjunction::And => Occur::Must,
+                    Conjunction::Or => Occur::Should,
+                };
+                if let (Some(previous), Some(false)) = (clauses.last_mut(), explicit.last()) {
+                    previous.occur = implicit_occur;
+                }
+                if !is_explicit {
+                    occur = implicit_occur;
+                }
+            }
+
+            clauses.push(Clause { occur, node });
+            explicit.push(is_explicit);
+        }
+
+        Ok(clauses)
+    }
+
+    // Parses a term, phrase or group with its boost and optional field,
+    // returns `None` if there is nothing to parse (e.g. a dangling `+`)
+    fn parse_node(&mut self) -> Result<Option<QueryNode>, String> {
+        if let Some((name, rest)) = self.split_field() {
+            self.rest = rest;
+            let node = self
+                .parse_node()?
+                .ok_or_else(|| format!("Field `{}` without a query", name))?;
+            return Ok(Some(QueryNode::Field {
+                name: name.to_string(),
+                node: Box::new(node),
+            }));
+        }
+
+        let node = if let Some(rest) = self.rest.strip_prefix('(') {
+            self.rest = rest;
+            let clauses = self.parse_clauses(true)?;
+            let boost = self.parse_boost()?;
+            QueryNode::Group { clauses, boost }
+        } else if let Some(quoted) = self.rest.strip_prefix('"') {
+            // an unterminated quote is ignored
+            let Some(end) = quoted.find('"') else {
+                self.rest = quoted;
+                return self.parse_node();
+            };
+            let text = quoted[..end].to_string();
+            self.rest = &quoted[end + 1..];
+            let slop = self.parse_slop();
+            let boost = self.parse_boost()?;
+            QueryNode::Phrase { text, slop, boost }
+        } else {
+            let word = self.peek_word();
+            if self.rest.starts_with('^') {
+                return Err("Boost without a term".to_string());
+            }
+            if word.is_empty() {
+                return Ok(None);
+            }
+            self.rest = &self.rest[word.len()..];
+            let boost = self.parse_boost()?;
+            if let Some(prefix) = word.strip_suffix('*') {
+                QueryNode::Prefix {
+                    text: prefix.to_string(),
+                    boost,
+                }
+            } else if let Some((text, edits)) = word.rsplit_once('~') {
+                let max_edits = if edits.is_empty() {
+                    MAX_FUZZY_EDITS
+                } else {
+                    edits
+                        .parse()
+                        .ok()
+                        .filter(|edits| *edits <= MAX_FUZZY_EDITS)
+                        .ok_or_else(|| {
+                            format!(
+                                "Invalid fuzziness `~{}`, at most {} edits are allowed",
+                                edits, MAX_FUZZY_EDITS
+                            )
+                        })?
+                };
+                QueryNode::Fuzzy {
+                    text: text.to_string(),
+                    max_edits,
+                    boost,
+                }
+            } else {
+                QueryNode::Term {
+                    text: word.to_string(),
+                    boost,
+                }
+            }
+        };
+        Ok(Some(node))
+    }
+
+    // Splits the `field:` prefix of the next node, a colon at the end of a
+    // word is part of the word
+    fn split_field(&self) -> Option<(&'a str, &'a str)> {
+        let end = self
+            .rest
+            .find(|c: char| !is_field_name_char(c))
+            .filter(|&end| end > 0)?;
+        let rest = self.rest[end..].strip_prefix(':')?;
+        if rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == ')') {
+            return None;
+        }
+        Some((&self.rest[..end], rest))
+    }
+
+    fn peek_word(&self) -> &'a str {
+        let end = self
+            .rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '^'))
+            .unwrap_or(self.rest.len());
+        &self.rest[..end]
+    }
+
+    fn parse_slop(&mut self) -> u32 {
+        let Some(after_tilde) = self.rest.strip_prefix('~') else {
+            return 0;
+        };
+        let digits = after_tilde
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after_tilde.len());
+        match after_tilde[..digits].parse() {
+            Ok(slop) => {
+                self.rest = &after_tilde[digits..];
+                slop
+            }
+            Err(_) => 0,
+        }
+    }
+
+    fn parse_boost(&mut self) -> Result<f32, String> {
+        let Some(after_caret) = self.rest.strip_prefix('^') else {
+            return Ok(1.0);
+        };
+        let end = after_caret
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(after_caret.len());
+        let boost = after_caret[..end]
+            .parse::<f32>()
+            .map_err(|_| format!("Invalid boost `^{}`", &after_caret[..end]))?;
+        self.rest = &after_caret[end..];
+        Ok(boost)
+    }
+}
+
+/// Field names are made of ASCII letters, digits, `_` and `-`
+pub fn is_field_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str, boost: f32) -> QueryNode {
+        QueryNode::Term {
+            text: text.to_string(),
+            boost,
+        }
+    }
+
+    fn clause(occur: Occur, node: QueryNode) -> Clause {
+        Clause { occur, node }
+    }
+
+    #[test]
+    fn test_plain_query() {
+        let query = TextQuery::parse("vector database").unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Should, term("vector", 1.0)),
+                clause(Occur::Should, term("database", 1.0)),
+            ],
+            query.clauses
+        );
+        assert_eq!(Some("vector database".to_string()), query.plain_text());
+    }
+
+    #[test]
+    fn test_phrases_and_slop() {
+        let query = TextQuery::parse(r#"rust "vector database"~3 "exact match" search"#).unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Should, term("rust", 1.0)),
+                clause(
+                    Occur::Must,
+                    QueryNode::Phrase {
+                        text: "vector database".to_string(),
+                        slop: 3,
+                        boost: 1.0,
+                    }
+                ),
+                clause(
+                    Occur::Must,
+                    QueryNode::Phrase {
+                        text: "exact match".to_string(),
+                        slop: 0,
+                        boost: 1.0,
+                    }
+                ),
+                clause(Occur::Should, term("search", 1.0)),
+            ],
+            query.clauses
+        );
+        assert!(query.has_phrases());
+        assert_eq!(None, query.plain_text());
+    }
+
+    #[test]
+    fn test_unterminated_quote_is_plain_text() {
+        let query = TextQuery::parse(r#"vector "database"#).unwrap();
+        assert!(!query.has_phrases());
+        assert_eq!(
+            vec![
+                clause(Occur::Should, term("vector", 1.0)),
+                clause(Occur::Should, term("database", 1.0)),
+            ],
+            query.clauses
+        );
+    }
+
+    #[test]
+    fn test_prefixes_and_boosts() {
+        let query = TextQuery::parse("+rust -java NOT go c++^2.5").unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Must, term("rust", 1.0)),
+                clause(Occur::MustNot, term("java", 1.0)),
+                clause(Occur::MustNot, term("go", 1.0)),
+                clause(Occur::Should, term("c++", 2.5)),
+            ],
+            query.clauses
+        );
+    }
+
+    #[test]
+    fn test_conjunctions_and_groups() {
+        let query = TextQuery::parse(r#"rust AND (vector OR "graph db")^2 -java"#).unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Must, term("rust", 1.0)),
+                clause(
+                    Occur::Must,
+                    QueryNode::Group {
+                        clauses: vec![
+                            clause(Occur::Should, term("vector", 1.0)),
+                            clause(
+                                Occur::Should,
+                                QueryNode::Phrase {
+                                    text: "graph db".to_string(),
+                                    slop: 0,
+                                    boost: 1.0,
+                                }
+                            ),
+                        ],
+                        boost: 2.0,
+                    }
+                ),
+                clause(Occur::MustNot, term("java", 1.0)),
+            ],
+            query.clauses
+        );
+
+        // explicit prefixes win over conjunctions
+        let query = TextQuery::parse("rust AND -java").unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Must, term("rust", 1.0)),
+                clause(Occur::MustNot, term("java", 1.0)),
+            ],
+            query.clauses
+        );
+    }
+
+    #[test]
+    fn test_prefix_and_fuzzy_terms() {
+        let query = TextQuery::parse("vect* +databse~1 rust~ search").unwrap();
+        assert_eq!(
+            vec![
+                clause(
+                    Occur::Should,
+                    QueryNode::Prefix {
+                        text: "vect".to_string(),
+                        boost: 1.0,
+                    }
+                ),
+                clause(
+                    Occur::Must,
+                    QueryNode::Fuzzy {
+                        text: "databse".to_string(),
+                        max_edits: 1,
+                        boost: 1.0,
+                    }
+                ),
+                clause(
+                    Occur::Should,
+                    QueryNode::Fuzzy {
+                        text: "rust".to_string(),
+                        max_edits: 2,
+                        boost: 1.0,
+                    }
+                ),
+                clause(Occur::Should, term("search", 1.0)),
+            ],
+            query.clauses
+        );
+        assert!(query.has_expansions());
+        assert!(!query.has_phrases());
+        assert_eq!(None, query.plain_text());
+        assert!(!TextQuery::parse("vector database")
+            .unwrap()
+            .has_expansions());
+    }
+
+    #[test]
+    fn test_field_restrictions() {
+        let field = |name: &str, node| QueryNode::Field {
+            name: name.to_string(),
+            node: Box::new(node),
+        };
+        let query =
+            TextQuery::parse(r#"title:rust^2 -tags:go body:"vector database" rust:"#).unwrap();
+        assert_eq!(
+            vec![
+                clause(Occur::Should, field("title", term("rust", 2.0))),
+                clause(Occur::MustNot, field("tags", term("go", 1.0))),
+                clause(
+                    Occur::Must,
+                    field(
+                        "body",
+                        QueryNode::Phrase {
+                            text: "vector database".to_string(),
+                            slop: 0,
+                            boost: 1.0,
+                        }
+                    )
+                ),
+                clause(Occur::Should, term("rust:", 1.0)),
+            ],
+            query.clauses
+        );
+        assert!(query.has_phrases());
+        assert_eq!(None, query.plain_text());
+
+        let query = TextQuery::parse("title:(rust OR vect*)").unwrap();
+        assert!(query.has_expansions());
+        assert!(TextQuery::parse("title:^2").is_err());
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        assert!(TextQuery::parse("(rust").is_err());
+        assert!(TextQuery::parse("rust)").is_err());
+        assert!(TextQuery::parse("rust^x").is_err());
+        assert!(TextQuery::parse("+^2").is_err());
+        assert!(TextQuery::parse("rust~3").is_err());
+        assert!(TextQuery::parse("rust~x").is_err());
+    }
+}
@@ -40,8 +40,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(feature = "grpc-server")]
     actix_web::rt::spawn(async move {
-        const DEFAULT_GRPC_PORT: u16 = 50051;
-        if let Err(e) = grpc::server::start_grpc_server(grpc_context, DEFAULT_GRPC_PORT).await {
+        if let Err(e) = grpc::server::start_grpc_server(grpc_context).await {
             log::error!("gRPC server error: {}", e);
         }
     });
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use super::{decimal_to_binary_vec, schema::MetadataSchema, Error, FieldName, FieldValue};
+use super::{
+    decimal_to_binary_vec, schema::MetadataSchema, Error, FieldName, FieldValue, MetadataFields,
+};
 use serde::Deserialize;
 
 #[derive(Clone, Debug, PartialEq, Deserialize)]
@@ -24,6 +26,31 @@ pub enum Filter {
     Or(Vec<Predicate>),
 }
 
+impl Predicate {
+    fn matches(&self, fields: Option<&MetadataFields>) -> bool {
+        let value = fields.and_then(|fields| fields.get(&self.field_name));
+        match self.operator {
+            Operator::Equal => value == Some(&self.field_value),
+            Operator::NotEqual => value != Some(&self.field_value),
+        }
+    }
+}
+
+impl Filter {
+    /// Returns whether the metadata fields of a vector match the filter
+    ///
+    /// Unlike the filtered search on the HNSW index, this checks the raw
+    /// field values and is hence exact. A missing field is not equal to
+    /// any value.
+    pub fn matches(&self, fields: Option<&MetadataFields>) -> bool {
+        match self {
+            Filter::Is(pred) => pred.matches(fields),
+            Filter::And(preds) => preds.iter().all(|pred| pred.matches(fields)),
+            Filter::Or(preds) => preds.iter().any(|pred| pred.matches(fields)),
+        }
+    }
+}
+
 pub type QueryFilterDimensions = Vec<i8>;
 
 fn query_filter_encoding(value_id: u16, size: usize, operator: &Operator) -> QueryFilterDimensions {
@@ -130,6 +157,42 @@ mod tests {
         assert_eq!(vec![1, 1, -1, -1, -1], e2);
     }
 
+    #[test]
+    fn test_filter_matches() {
+        let fields: MetadataFields = [
+            ("age".to_string(), FieldValue::Int(2)),
+            ("group".to_string(), FieldValue::String("b".to_owned())),
+        ]
+        .into_iter()
+        .collect();
+        let predicate = |name: &str, value: FieldValue, operator: Operator| Predicate {
+            field_name: name.to_string(),
+            field_value: value,
+            operator,
+        };
+
+        let filter = Filter::And(vec![
+            predicate("age", FieldValue::Int(2), Operator::Equal),
+            predicate(
+                "group",
+                FieldValue::String("a".to_owned()),
+                Operator::NotEqual,
+            ),
+        ]);
+        assert!(filter.matches(Some(&fields)));
+        assert!(!filter.matches(None));
+
+        let filter = Filter::Or(vec![
+            predicate("age", FieldValue::Int(3), Operator::Equal),
+            predicate("group", FieldValue::String("b".to_owned()), Operator::Equal),
+        ]);
+        assert!(filter.matches(Some(&fields)));
+
+        let filter = Filter::Is(predicate("age", FieldValue::Int(2), Operator::NotEqual));
+        assert!(!filter.matches(Some(&fields)));
+        assert!(filter.matches(None));
+    }
+
     #[test]
     fn test_filter_encoded_dimensions() {
         let age_values: HashSet<FieldValue> = (1..=10).map(FieldValue::Int).collect();
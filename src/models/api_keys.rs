@@ -0,0 +1,242 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use dashmap::DashMap;
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, Transaction, WriteFlags};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use super::common::WaCustomError;
+use super::crypto::{get_current_timestamp, DoubleSHA256Hash};
+use super::rbac::{Permission, GLOBAL_SCOPE};
+
+/// All API keys start with this prefix, which is how they are told apart
+/// from session tokens
+pub const API_KEY_PREFIX: &str = "cos_";
+
+/// A long-lived credential acting on behalf of a user
+///
+/// The key itself is only returned once on creation, only its double hash
+/// is persisted. The permissions of the key are the permissions of its user,
+/// optionally narrowed down to some collections and/or to read-only
+/// operations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub username: String,
+    key_hash: [u8; 32],
+    /// Collections the key is restricted to, `None` means no restriction
+    pub collections: Option<Vec<String>>,
+    pub read_only: bool,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+impl ApiKey {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| get_current_timestamp() >= expires_at)
+    }
+
+    /// Checks the scope of the key, the permissions of its user are checked
+    /// separately
+    pub fn allows(&self, collection: &str, permission: Permission) -> bool {
+        if self.read_only && !permission.is_read_only() {
+            return false;
+        }
+        match &self.collections {
+            None => true,
+            Some(collections) => {
+                collection != GLOBAL_SCOPE && collections.iter().any(|c| c == collection)
+            }
+        }
+    }
+}
+
+// Splits a key of the form `cos_<id>_<secret>`
+fn parse_key(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(API_KEY_PREFIX)?.split_once('_')
+}
+
+pub struct ApiKeyStore {
+    env: Arc<Environment>,
+    db: Database,
+    keys: DashMap<String, ApiKey>,
+}
+
+impl ApiKeyStore {
+    pub fn new(env: Arc<Environment>) -> Result<Self, WaCustomError> {
+        let db = env.create_db(Some("api_keys"), DatabaseFlags::empty())?;
+        let keys = DashMap::new();
+
+        let txn = env.begin_ro_txn()?;
+        let mut cursor = txn.open_ro_cursor(db)?;
+        for (_, value) in cursor.iter() {
+            let api_key: ApiKey = serde_cbor::from_slice(value)
+                .map_err(|e| WaCustomError::DeserializationError(e.to_string()))?;
+            keys.insert(api_key.id.clone(), api_key);
+        }
+        drop(cursor);
+        txn.abort();
+
+        Ok(Self { env, db, keys })
+    }
+
+    /// Creates a new API key, returns it along with the key to be handed out
+    /// to the client
+    pub fn create_key(
+        &self,
+        name: String,
+        username: String,
+        collections: Option<Vec<String>>,
+        read_only: bool,
+        expires_at: Option<u64>,
+    ) -> Result<(ApiKey, String), WaCustomError> {
+        let rng = SystemRandom::new();
+        let mut id = [0u8; 8];
+        let mut secret = [0u8; 32];
+        rng.fill(&mut id)
+            .and_then(|_| rng.fill(&mut secret))
+            .map_err(|_| WaCustomError::CalculationError)?;
+
+        let id = id.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        });
+        let secret = URL_SAFE_NO_PAD.encode(secret);
+        let api_key = ApiKey {
+            id: id.clone(),
+            name,
+            username,
+            key_hash: DoubleSHA256Hash::new(secret.as_bytes()).0,
+            collections,
+            read_only,
+            created_at: get_current_timestamp(),
+            expires_at,
+        };
+
+        let value = serde_cbor::to_vec(&api_key)
+            .map_err(|e| WaCustomError::SerializationError(e.to_string()))?;
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.put(self.db, &id, &value, WriteFlags::empty())?;
+        txn.commit()?;
+
+        self.keys.insert(id.clone(), api_key.clone());
+        Ok((api_key, format!("{}{}_{}", API_KEY_PREFIX, id, secret)))
+    }
+
+    pub fn get_key(&self, id: &str) -> Option<ApiKey> {
+        self.keys.get(id).map(|api_key| api_key.value().clone())
+    }
+
+    pub fn list_keys(&self) -> Vec<ApiKey> {
+        let mut keys: Vec<_> = self
+            .keys
+            .iter()
+            .map(|api_key| api_key.value().clone())
+            .collect();
+        keys.sort_unstable_by_key(|api_key| api_key.created_at);
+        keys
+    }
+
+    /// Deletes the API key, returns `None` if it doesn't exist
+    pub fn revoke_key(&self, id: &str) -> Result<Option<ApiKey>, WaCustomError> {
+        if !self.keys.contains_key(id) {
+            return Ok(None);
+        }
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.del(self.db, &id, None)?;
+        txn.commit()?;
+
+        Ok(self.keys.remove(id).map(|(_, api_key)| api_key))
+    }
+
+    /// Revokes all the API keys of a user, to be called when the user is
+    /// deleted
+    pub fn remove_user(&self, username: &str) -> Result<(), WaCustomError> {
+        let ids: Vec<_> = self
+            .keys
+            .iter()
+            .filter(|api_key| api_key.username == username)
+            .map(|api_key| api_key.key().clone())
+            .collect();
+        for id in ids {
+            self.revoke_key(&id)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves a key handed out by `create_key`, returns `None` if the key
+    /// is unknown, revoked or expired
+    pub fn validate_key(&self, key: &str) -> Option<ApiKey> {
+        let (id, secret) = parse_key(key)?;
+        let api_key = self.get_key(id)?;
+        let hash = DoubleSHA256Hash::new(secret.as_bytes());
+        // constant time comparison to prevent timing attacks
+        if !hash.verify_eq(&DoubleSHA256Hash(api_key.key_hash)) || api_key.is_expired() {
+            return None;
+        }
+        Some(api_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn setup_store() -> (ApiKeyStore, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let env = Environment::new().set_max_dbs(4).open(dir.path()).unwrap();
+        (ApiKeyStore::new(Arc::new(env)).unwrap(), dir)
+    }
+
+    #[test]
+    fn test_api_key_lifecycle() {
+        let (store, _dir) = setup_store();
+        let (api_key, key) = store
+            .create_key(
+                "ingest".to_string(),
+                "alice".to_string(),
+                Some(vec!["docs".to_string()]),
+                true,
+                None,
+            )
+            .unwrap();
+
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert_eq!(store.validate_key(&key).unwrap().id, api_key.id);
+        assert!(store.validate_key(&format!("{}x", key)).is_none());
+        assert!(store.validate_key("not-a-key").is_none());
+
+        assert!(api_key.allows("docs", Permission::QueryDenseVectors));
+        assert!(!api_key.allows("docs", Permission::UpsertVectors));
+        assert!(!api_key.allows("other", Permission::QueryDenseVectors));
+        assert!(!api_key.allows(GLOBAL_SCOPE, Permission::ListCollections));
+
+        // keys survive a reload of the store
+        let reloaded = ApiKeyStore::new(store.env.clone()).unwrap();
+        assert!(reloaded.validate_key(&key).is_some());
+
+        assert!(store.revoke_key(&api_key.id).unwrap().is_some());
+        assert!(store.validate_key(&key).is_none());
+        assert!(store.revoke_key(&api_key.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_expired_api_key() {
+        let (store, _dir) = setup_store();
+        let (_, key) = store
+            .create_key(
+                "expired".to_string(),
+                "alice".to_string(),
+                None,
+                false,
+                Some(get_current_timestamp() - 1),
+            )
+            .unwrap();
+        assert!(store.validate_key(&key).is_none());
+    }
+}
@@ -134,6 +134,15 @@ impl<K: Hash + Eq + Clone> BufferManagerFactory<K> {
         }
         Ok(())
     }
+
+    /// Total number of regions with unflushed writes across all the
+    /// buffer managers created so far
+    pub fn dirty_regions_count(&self) -> usize {
+        self.bufmans
+            .iter()
+            .map(|bufman| bufman.dirty_regions_count())
+            .sum()
+    }
 }
 
 pub struct BufferManager {
@@ -441,6 +450,15 @@ impl BufferManager {
     pub fn file_size(&self) -> u64 {
         *self.file_size.read().unwrap()
     }
+
+    /// Number of cached regions with writes that are not flushed to the
+    /// file yet
+    pub fn dirty_regions_count(&self) -> usize {
+        self.regions
+            .values()
+            .filter(|region| region.should_final_flush())
+            .count()
+    }
 }
 
 pub struct FilelessBufferManager {
@@ -799,6 +817,15 @@ impl FilelessBufferManager {
     pub fn file_size(&self) -> u64 {
         *self.file_size.read().unwrap()
     }
+
+    /// Number of cached regions with writes that are not flushed to the
+    /// file yet
+    pub fn dirty_regions_count(&self) -> usize {
+        self.regions
+            .iter()
+            .filter(|region| region.should_flush())
+            .count()
+    }
 }
 
 #[cfg(test)]
@@ -112,6 +112,10 @@ impl HNSWIndexCache {
         }
     }
 
+    pub fn dirty_regions_count(&self) -> usize {
+        self.bufmans.dirty_regions_count() + self.latest_version_links_bufman.dirty_regions_count()
+    }
+
     pub fn get_prop(
         &self,
         offset: FileOffset,
@@ -377,6 +381,10 @@ impl InvertedIndexCache {
         self.dim_bufman.flush()?;
         self.data_bufmans.flush_all()
     }
+
+    pub fn dirty_regions_count(&self) -> usize {
+        self.dim_bufman.dirty_regions_count() + self.data_bufmans.dirty_regions_count()
+    }
 }
 
 pub struct TFIDFIndexCache {
@@ -482,4 +490,8 @@ impl TFIDFIndexCache {
         self.dim_bufman.flush()?;
         self.data_bufmans.flush_all()
     }
+
+    pub fn dirty_regions_count(&self) -> usize {
+        self.dim_bufman.dirty_regions_count() + self.data_bufmans.dirty_regions_count()
+    }
 }
@@ -6,6 +6,7 @@ use super::common::WaCustomError;
 use super::indexing_manager::IndexingManager;
 use super::meta_persist::store_highest_internal_id;
 use super::paths::get_data_path;
+use super::rbac::GLOBAL_SCOPE;
 use super::tree_map::{TreeMap, TreeMapVec};
 use super::types::{get_collections_path, DocumentId, InternalId, MetaDb, VectorId};
 use super::versioning::{VersionControl, VersionNumber, VersionSource};
@@ -24,6 +25,7 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use serde_cbor::to_vec;
 use siphasher::sip::SipHasher24;
+use std::collections::HashMap;
 use std::fs::{create_dir_all, OpenOptions};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::thread;
@@ -34,6 +36,21 @@ use utoipa::ToSchema;
 pub struct DenseVectorOptions {
     pub enabled: bool,
     pub dimension: usize,
+    /// Named dense vector fields, indexed separately from the default
+    /// dense vectors
+    #[serde(default)]
+    pub fields: Vec<DenseVectorField>,
+    /// Each embedding holds a bag of token vectors of `dimension` instead
+    /// of a single dense vector, scored against the query vectors with
+    /// MaxSim
+    #[serde(default)]
+    pub multi_vector: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DenseVectorField {
+    pub name: String,
+    pub dimension: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -60,6 +77,15 @@ pub struct RawVectorEmbedding {
     pub metadata: Option<MetadataFields>,
     pub sparse_values: Option<Vec<SparsePair>>,
     pub text: Option<String>,
+    /// Named text fields, each one indexed in its own field of the TF-IDF
+    /// index
+    pub text_fields: Option<HashMap<String, String>>,
+    /// Vectors of the named dense fields of the collection, each one
+    /// indexed in the HNSW index of its field
+    pub dense_fields: Option<HashMap<String, Vec<f32>>>,
+    /// Token vectors of the embedding in a multi-vector collection, each
+    /// one indexed as a separate node of the dense index
+    pub multi_vector_values: Option<Vec<Vec<f32>>>,
 }
 
 #[derive(Deserialize, Clone, Serialize, Debug)]
@@ -110,9 +136,13 @@ pub struct Collection {
     pub internal_to_external_map: TreeMap<InternalId, RawVectorEmbedding>,
     pub external_to_internal_map: TreeMap<VectorId, InternalId>,
     pub document_to_internals_map: TreeMapVec<DocumentId, InternalId>,
+    /// Maps the ids of the token vectors of multi-vector embeddings to the
+    /// ids of their embeddings
+    pub token_to_internal_map: TreeMap<InternalId, InternalId>,
     pub transaction_status_map: TreeMap<ExplicitTransactionID, RwLock<TransactionStatus>>,
     pub internal_id_counter: AtomicU32,
     pub hnsw_index: RwLock<Option<Arc<HNSWIndex>>>,
+    pub dense_field_indexes: RwLock<HashMap<String, Arc<HNSWIndex>>>,
     pub inverted_index: RwLock<Option<Arc<InvertedIndex>>>,
     pub tf_idf_index: RwLock<Option<Arc<TFIDFIndex>>>,
     // this field is actually NOT optional, the only reason it is wrapped in
@@ -123,6 +153,51 @@ pub struct Collection {
     pub is_indexing: AtomicBool,
 }
 
+// the names of the fields are used as the names of the directories of
+// their indexes
+fn validate_dense_vector_fields(fields: &[DenseVectorField]) -> Result<(), WaCustomError> {
+    for (i, field) in fields.iter().enumerate() {
+        if field.name.is_empty()
+            || !field
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(WaCustomError::InvalidData(format!(
+                "Invalid dense vector field name: '{}', expected letters, digits, '_' or '-'",
+                field.name
+            )));
+        }
+        if field.dimension == 0 {
+            return Err(WaCustomError::InvalidData(format!(
+                "Dimension of dense vector field '{}' must be greater than 0",
+                field.name
+            )));
+        }
+        if fields[..i].iter().any(|other| other.name == field.name) {
+            return Err(WaCustomError::InvalidData(format!(
+                "Duplicate dense vector field: '{}'",
+                field.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the internal id of the `index`th token vector of a multi-vector
+/// embedding
+///
+/// The ids of the token vectors are reserved right after the id of their
+/// embedding, each one followed by the ids of its metadata replicas, just
+/// like the id of the embedding itself
+pub fn token_internal_id(
+    internal_id: InternalId,
+    index: usize,
+    num_nodes_per_emb: usize,
+) -> InternalId {
+    InternalId::from(*internal_id + ((index + 1) * num_nodes_per_emb) as u32)
+}
+
 impl Collection {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -142,6 +217,23 @@ impl Collection {
         if name.is_empty() {
             return Err(WaCustomError::InvalidParams);
         }
+        // role assignments on the global scope apply to all collections
+        if name == GLOBAL_SCOPE {
+            return Err(WaCustomError::InvalidData(format!(
+                "Invalid collection name: '{}' is reserved",
+                name
+            )));
+        }
+        // the names are used as the names of the directories of the
+        // collections, and the indexes of their dense fields are persisted
+        // as `{name}/{field}`
+        if name.contains('/') {
+            return Err(WaCustomError::InvalidData(format!(
+                "Invalid collection name: '{}', names can't contain '/'",
+                name
+            )));
+        }
+        validate_dense_vector_fields(&dense_vector_options.fields)?;
 
         let collection_path: Arc<Path> = get_collections_path().join(&name).into();
         fs::create_dir_all(&collection_path).map_err(|e| WaCustomError::FsError(e.to_string()))?;
@@ -197,6 +289,23 @@ impl Collection {
             8192,
         );
 
+        let token_to_internal_map_dim_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(false)
+            .create(true)
+            .open(collection_path.join("ttoi.dim"))
+            .map_err(BufIoError::Io)?;
+
+        let token_to_internal_map_dim_bufman =
+            BufferManager::new(token_to_internal_map_dim_file, 8192).map_err(BufIoError::Io)?;
+
+        let token_to_internal_map_data_bufmans = BufferManagerFactory::new(
+            collection_path.clone(),
+            |root, version: &VersionNumber| root.join(format!("ttoi.{}.data", **version)),
+            8192,
+        );
+
         let transaction_status_map_dim_file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -243,12 +352,17 @@ impl Collection {
                 document_to_internals_map_dim_bufman,
                 document_to_internals_map_data_bufmans,
             ),
+            token_to_internal_map: TreeMap::new(
+                token_to_internal_map_dim_bufman,
+                token_to_internal_map_data_bufmans,
+            ),
             transaction_status_map: TreeMap::new(
                 transaction_status_map_dim_bufman,
                 transaction_status_map_data_bufmans,
             ),
             internal_id_counter: AtomicU32::new(0),
             hnsw_index: RwLock::new(None),
+            dense_field_indexes: RwLock::new(HashMap::new()),
             inverted_index: RwLock::new(None),
             tf_idf_index: RwLock::new(None),
             indexing_manager: RwLock::new(None),
@@ -347,6 +461,58 @@ impl Collection {
         self.hnsw_index.read().clone()
     }
 
+    pub fn get_dense_field_index(&self, field: &str) -> Option<Arc<HNSWIndex>> {
+        self.dense_field_indexes.read().get(field).cloned()
+    }
+
+    /// Returns the HNSW index of a named dense field, or the default dense
+    /// index if no field is given
+    pub fn get_dense_index(&self, field: Option<&str>) -> Option<Arc<HNSWIndex>> {
+        match field {
+            Some(field) => self.get_dense_field_index(field),
+            None => self.get_hnsw_index(),
+        }
+    }
+
+    /// Returns the dimension of the vectors of a named dense field, or of
+    /// the default dense vectors if no field is given
+    pub fn dense_dimension(&self, field: Option<&str>) -> Option<usize> {
+        let dense_vector = &self.meta.dense_vector;
+        match field {
+            Some(field) => dense_vector
+                .fields
+                .iter()
+                .find(|dense_field| dense_field.name == field)
+                .map(|dense_field| dense_field.dimension),
+            None => Some(dense_vector.dimension),
+        }
+    }
+
+    /// Returns all the HNSW indexes of the collection, the default dense
+    /// index first
+    pub fn dense_indexes(&self) -> Vec<Arc<HNSWIndex>> {
+        self.get_hnsw_index()
+            .into_iter()
+            .chain(self.dense_field_indexes.read().values().cloned())
+            .collect()
+    }
+
+    /// Returns the number of internal ids reserved for each embedding, one
+    /// for the base node and one for each metadata replica node
+    ///
+    /// All the dense indexes of a collection share the metadata schema of
+    /// the collection, so they have the same number of replicas
+    fn max_replica_per_node(&self) -> Option<u8> {
+        if let Some(hnsw_index) = &*self.hnsw_index.read() {
+            return Some(hnsw_index.max_replica_per_node);
+        }
+        self.dense_field_indexes
+            .read()
+            .values()
+            .next()
+            .map(|hnsw_index| hnsw_index.max_replica_per_node)
+    }
+
     pub fn get_inverted_index(&self) -> Option<Arc<InvertedIndex>> {
         self.inverted_index.read().clone()
     }
@@ -369,18 +535,45 @@ impl Collection {
         &self,
         internal_id: &InternalId,
     ) -> Option<&RawVectorEmbedding> {
-        let mapped_internal_id = if let Some(hnsw_index) = self.get_hnsw_index() {
+        self.internal_to_external_map
+            .get_latest(&self.base_internal_id(internal_id))
+    }
+
+    /// Returns the internal id of the multi-vector embedding a token vector
+    /// node of the dense index belongs to, handling metadata replica nodes
+    /// like `get_raw_emb_by_internal_id`
+    pub fn get_internal_id_by_token_id(&self, token_id: &InternalId) -> Option<InternalId> {
+        self.token_to_internal_map
+            .get_latest(&self.base_internal_id(token_id))
+            .copied()
+    }
+
+    // maps the id of a metadata replica node to the id of its base node
+    fn base_internal_id(&self, internal_id: &InternalId) -> InternalId {
+        if let Some(max_replica_per_node) = self.max_replica_per_node() {
             if self.meta.metadata_schema.is_some() {
                 let id = **internal_id;
-                InternalId::from(id - id % hnsw_index.max_replica_per_node as u32)
-            } else {
-                *internal_id
+                return InternalId::from(id - id % max_replica_per_node as u32);
             }
-        } else {
-            *internal_id
-        };
-        self.internal_to_external_map
-            .get_latest(&mapped_internal_id)
+        }
+        *internal_id
+    }
+
+    fn num_nodes_per_emb(&self) -> usize {
+        self.max_replica_per_node()
+            .map_or(1, |max_replica_per_node| max_replica_per_node as usize)
+    }
+
+    /// Returns the latest raw embeddings of the collection with their
+    /// internal ids, skipping the deleted ones
+    pub fn raw_embeddings(&self) -> impl Iterator<Item = (InternalId, &RawVectorEmbedding)> {
+        // ids reserved for metadata replicas aren't mapped to any embedding
+        (0..self.internal_id_counter.load(Ordering::Relaxed)).filter_map(|id| {
+            let internal_id = InternalId::from(id);
+            self.internal_to_external_map
+                .get_latest(&internal_id)
+                .map(|raw_emb| (internal_id, raw_emb))
+        })
     }
 
     pub fn run_upload(
@@ -403,6 +596,32 @@ impl Collection {
         }
 
         for embedding in embeddings.clone() {
+            if let Some(multi_vector_values) = embedding.multi_vector_values {
+                if !self.meta.dense_vector.multi_vector {
+                    return Err(WaCustomError::InvalidData(
+                        "Collection doesn't store multi-vector embeddings".to_string(),
+                    ));
+                }
+                if multi_vector_values.is_empty() {
+                    return Err(WaCustomError::InvalidData(format!(
+                        "Multi-vector embedding without token vectors: {}",
+                        embedding.id
+                    )));
+                }
+                if let Some(hnsw_index) = self.get_hnsw_index() {
+                    for values in multi_vector_values {
+                        let dense_emb =
+                            DenseInputEmbedding(InternalId::from(u32::MAX), values, None, false);
+                        hnsw_index.validate_embedding(dense_emb)?;
+                    }
+                }
+            } else if self.meta.dense_vector.multi_vector && embedding.dense_values.is_some() {
+                return Err(WaCustomError::InvalidData(
+                    "Multi-vector collection expects multi_vector_values instead of dense_values"
+                        .to_string(),
+                ));
+            }
+
             if let Some(dense_values) = embedding.dense_values {
                 if let Some(hnsw_index) = self.get_hnsw_index() {
                     let dense_emb = DenseInputEmbedding(
@@ -415,6 +634,25 @@ impl Collection {
                 }
             }
 
+            // the fields are checked against their configuration, as their
+            // indexes may not exist yet
+            for (field, values) in embedding.dense_fields.into_iter().flatten() {
+                let Some(dimension) = self.dense_dimension(Some(&field)) else {
+                    return Err(WaCustomError::InvalidData(format!(
+                        "Dense vector field not found: {}",
+                        field
+                    )));
+                };
+                if values.len() != dimension {
+                    return Err(WaCustomError::InvalidData(format!(
+                        "Expected dimension of dense vector field '{}' to be {}, found {}",
+                        field,
+                        dimension,
+                        values.len()
+                    )));
+                }
+            }
+
             if let Some(sparse_values) = embedding.sparse_values {
                 if let Some(inverted_index) = self.get_inverted_index() {
                     let sparse_emb =
@@ -423,9 +661,12 @@ impl Collection {
                 }
             }
 
-            if let Some(text) = embedding.text {
+            if let Some(tf_idf_emb) = TFIDFInputEmbedding::new(
+                InternalId::from(u32::MAX),
+                embedding.text,
+                embedding.text_fields,
+            ) {
                 if let Some(tf_idf_index) = self.get_tf_idf_index() {
-                    let tf_idf_emb = TFIDFInputEmbedding(InternalId::from(u32::MAX), text);
                     tf_idf_index.validate_embedding(tf_idf_emb)?;
                 }
             }
@@ -442,59 +683,90 @@ impl Collection {
         version: VersionNumber,
         config: &Config,
     ) -> Result<(), WaCustomError> {
-        let num_nodes_per_emb = if let Some(hnsw_index) = &*self.hnsw_index.read() {
-            hnsw_index.max_replica_per_node as usize
-        } else {
-            1
-        };
-        let num_ids_to_reserve = embeddings.len() * num_nodes_per_emb;
-        let id_start = self
+        let num_nodes_per_emb = self.num_nodes_per_emb();
+        // multi-vector embeddings also reserve ids for their token vectors
+        let num_ids_to_reserve = embeddings
+            .iter()
+            .map(|embedding| 1 + embedding.multi_vector_values.as_ref().map_or(0, Vec::len))
+            .sum::<usize>()
+            * num_nodes_per_emb;
+        let mut next_id = self
             .internal_id_counter
             .fetch_add(num_ids_to_reserve as u32, Ordering::Relaxed);
 
-        let (dense_embs, sparse_embs, tf_idf_embs): (Vec<_>, Vec<_>, Vec<_>) =
-            embeddings.into_iter().enumerate().fold(
-                (Vec::new(), Vec::new(), Vec::new()),
-                |mut acc, (i, mut embedding)| {
-                    let RawVectorEmbedding {
-                        id,
-                        document_id,
-                        dense_values,
-                        metadata,
-                        sparse_values,
-                        text,
-                    } = embedding.clone();
-
-                    let internal_id = InternalId::from(id_start + (i * num_nodes_per_emb) as u32);
+        let (dense_embs, sparse_embs, tf_idf_embs, dense_field_embs) = embeddings.into_iter().fold(
+            (
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                HashMap::<_, Vec<_>>::new(),
+            ),
+            |mut acc, mut embedding| {
+                let RawVectorEmbedding {
+                    id,
+                    document_id,
+                    dense_values,
+                    metadata,
+                    sparse_values,
+                    text,
+                    text_fields,
+                    dense_fields,
+                    multi_vector_values,
+                } = embedding.clone();
+
+                let internal_id = InternalId::from(next_id);
+                let num_tokens = multi_vector_values.as_ref().map_or(0, Vec::len);
+                next_id += ((1 + num_tokens) * num_nodes_per_emb) as u32;
+
+                for (i, values) in multi_vector_values.into_iter().flatten().enumerate() {
+                    let token_id = token_internal_id(internal_id, i, num_nodes_per_emb);
+                    self.token_to_internal_map
+                        .insert(version, &token_id, internal_id);
+                    acc.0.push(DenseInputEmbedding(
+                        token_id,
+                        values,
+                        metadata.clone(),
+                        false,
+                    ));
+                }
 
-                    if let Some(values) = dense_values {
-                        acc.0
-                            .push(DenseInputEmbedding(internal_id, values, metadata, false));
-                    }
-                    if let Some(values) = sparse_values {
-                        acc.1.push(SparseInputEmbedding(internal_id, values));
-                    }
-                    if let Some(text) = text {
-                        acc.2.push(TFIDFInputEmbedding(internal_id, text));
-                    }
+                for (field, values) in dense_fields.into_iter().flatten() {
+                    acc.3.entry(field).or_default().push(DenseInputEmbedding(
+                        internal_id,
+                        values,
+                        metadata.clone(),
+                        false,
+                    ));
+                }
+                if let Some(values) = dense_values {
+                    acc.0
+                        .push(DenseInputEmbedding(internal_id, values, metadata, false));
+                }
+                if let Some(values) = sparse_values {
+                    acc.1.push(SparseInputEmbedding(internal_id, values));
+                }
+                if let Some(tf_idf_emb) = TFIDFInputEmbedding::new(internal_id, text, text_fields) {
+                    acc.2.push(tf_idf_emb);
+                }
 
-                    if !self.meta.store_raw_text {
-                        embedding.text = None;
-                    }
+                if !self.meta.store_raw_text {
+                    embedding.text = None;
+                    embedding.text_fields = None;
+                }
 
-                    self.internal_to_external_map
-                        .insert(version, &internal_id, embedding);
-                    self.external_to_internal_map
-                        .insert(version, &id, internal_id);
+                self.internal_to_external_map
+                    .insert(version, &internal_id, embedding);
+                self.external_to_internal_map
+                    .insert(version, &id, internal_id);
 
-                    if let Some(document_id) = document_id {
-                        self.document_to_internals_map
-                            .push(version, &document_id, internal_id);
-                    }
+                if let Some(document_id) = document_id {
+                    self.document_to_internals_map
+                        .push(version, &document_id, internal_id);
+                }
 
-                    acc
-                },
-            );
+                acc
+            },
+        );
 
         if !dense_embs.is_empty() {
             if let Some(hnsw_index) = &*self.hnsw_index.read() {
@@ -502,6 +774,12 @@ impl Collection {
             }
         }
 
+        for (field, dense_embs) in dense_field_embs {
+            if let Some(hnsw_index) = self.get_dense_field_index(&field) {
+                hnsw_index.run_upload(self, dense_embs, version, config)?;
+            }
+        }
+
         if !sparse_embs.is_empty() {
             if let Some(inverted_index) = &*self.inverted_index.read() {
                 inverted_index.run_upload(self, sparse_embs, version, config)?;
@@ -535,7 +813,7 @@ impl Collection {
             return Ok(());
         };
 
-        if let Some(hnsw_index) = self.get_hnsw_index() {
+        for hnsw_index in self.dense_indexes() {
             hnsw_index.delete_embedding(internal_id, raw_emb, version, config)?;
         }
 
@@ -547,6 +825,12 @@ impl Collection {
             tf_idf_index.delete_embedding(internal_id, raw_emb, version, config)?;
         }
 
+        let num_nodes_per_emb = self.num_nodes_per_emb();
+        let num_tokens = raw_emb.multi_vector_values.as_ref().map_or(0, Vec::len);
+        for i in 0..num_tokens {
+            let token_id = token_internal_id(internal_id, i, num_nodes_per_emb);
+            self.token_to_internal_map.delete(version, &token_id);
+        }
         self.internal_to_external_map.delete(version, &internal_id);
         self.external_to_internal_map.delete(version, &vector_id);
         if let Some(document_id) = &raw_emb.document_id {
@@ -569,6 +853,7 @@ impl Collection {
         self.internal_to_external_map.serialize()?;
         self.external_to_internal_map.serialize()?;
         self.document_to_internals_map.serialize()?;
+        self.token_to_internal_map.serialize()?;
         self.transaction_status_map.serialize()?;
         store_highest_internal_id(&self.lmdb, self.internal_id_counter.load(Ordering::Relaxed))?;
         Ok(())
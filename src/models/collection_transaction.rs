@@ -32,7 +32,7 @@ pub struct BackgroundExplicitTransaction {
 
 impl BackgroundExplicitTransaction {
     pub fn from_version_id_and_number(collection: &Collection, version: VersionNumber) -> Self {
-        if let Some(hnsw_index) = &*collection.hnsw_index.read() {
+        for hnsw_index in collection.dense_indexes() {
             hnsw_index.offset_counter.write().unwrap().next_file_id();
         }
 
@@ -40,7 +40,7 @@ impl BackgroundExplicitTransaction {
     }
 
     pub fn pre_commit(self, collection: &Collection, config: &Config) -> Result<(), WaCustomError> {
-        if let Some(hnsw_index) = &*collection.hnsw_index.read() {
+        for hnsw_index in collection.dense_indexes() {
             hnsw_index.pre_commit_transaction(collection, self.version, config)?;
         }
         if let Some(inverted_index) = &*collection.inverted_index.read() {
@@ -253,7 +253,7 @@ impl ImplicitTransaction {
         let Some(data) = self.data.into_inner() else {
             return Ok(());
         };
-        if let Some(hnsw_index) = &*collection.hnsw_index.read() {
+        for hnsw_index in collection.dense_indexes() {
             hnsw_index.pre_commit_transaction(collection, data.version, config)?;
         }
         if let Some(inverted_index) = &*collection.inverted_index.read() {
@@ -379,7 +379,7 @@ pub fn get_max_insert_level(x: f64, levels: &[(f64, u8)]) -> u8 {
 }
 
 pub fn remove_duplicates_and_filter(
-    _hnsw_index: &HNSWIndex,
+    hnsw_index: &HNSWIndex,
     vec: Vec<(SharedLatestNode, MetricResult)>,
     k: Option<usize>,
     cache: &HNSWIndexCache,
@@ -406,7 +406,13 @@ pub fn remove_duplicates_and_filter(
 
     collected.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
     if let Some(k) = k {
-        collected.truncate(5 * k);
+        // only the binary indexes are oversampled as configured, their
+        // 1-bit distances being too coarse for the fixed factor
+        if hnsw_index.is_binary() {
+            collected.truncate(hnsw_index.num_rescored_candidates(k));
+        } else {
+            collected.truncate(5 * k);
+        }
     }
     collected
 }
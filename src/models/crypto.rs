@@ -1,7 +1,11 @@
 // Cryptographic utility functions and data types
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
-use ring::{constant_time::verify_slices_are_equal, digest, hmac};
+use ring::{
+    constant_time::verify_slices_are_equal,
+    digest, hmac,
+    rand::{SecureRandom, SystemRandom},
+};
 use rkyv::Infallible;
 use std::{
     str::FromStr,
@@ -128,6 +132,14 @@ pub fn get_current_timestamp() -> u64 {
         .as_secs()
 }
 
+// Generates a random URL-safe token, used where there is no user key to
+// derive a token from, like refresh tokens
+pub fn generate_random_token() -> String {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new().fill(&mut bytes).unwrap();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
 // Creates a new session with time-based access token
 // Flow:
 // 1. Derive master key from admin key and user key
@@ -159,8 +171,6 @@ pub fn create_session(
 
 #[cfg(test)]
 mod tests {
-    use ring::rand::{SecureRandom, SystemRandom};
-
     use super::*;
 
     fn random_bytes() -> [u8; 32] {
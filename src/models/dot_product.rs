@@ -108,7 +108,15 @@ pub fn dot_product_u8(a: &[u8], b: &[u8]) -> u64 {
 }
 
 pub fn dot_product_f16(x: &[f16], y: &[f16]) -> f32 {
-    // TODO: use SIMD if possible
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("fma")
+            && is_x86_feature_detected!("f16c")
+        {
+            return unsafe { x86_64::dot_product_f16_f16c(x, y) };
+        }
+    }
     dot_product_f16_scalar(x, y)
 }
 
@@ -2,6 +2,8 @@
 
 use std::arch::x86_64::*;
 
+use half::f16;
+
 #[allow(dead_code)]
 fn print_mm256i(name: &str, value: __m256i) {
     let mut array = [0u8; 32];
@@ -187,7 +189,7 @@ pub unsafe fn dot_product_binary_avx2(x_vec: &[Vec<u8>], y_vec: &[Vec<u8>], reso
 }
 
 #[target_feature(enable = "avx2")]
-unsafe fn count_ones_simd_avx2_256i(input: __m256i) -> u64 {
+pub unsafe fn count_ones_simd_avx2_256i(input: __m256i) -> u64 {
     let low_mask = _mm256_set1_epi8(0x0F);
     let lookup = _mm256_setr_epi8(
         0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4, 0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3,
@@ -443,6 +445,30 @@ pub unsafe fn dot_product_f32_simd(a: &[f32], b: &[f32]) -> f32 {
     result
 }
 
+#[target_feature(enable = "avx2", enable = "fma", enable = "f16c")]
+pub unsafe fn dot_product_f16_f16c(a: &[f16], b: &[f16]) -> f32 {
+    let len = a.len().min(b.len());
+    let mut sum = _mm256_setzero_ps();
+
+    let mut i = 0;
+    while i + 8 <= len {
+        // Converts 8 half precision floats to single precision
+        let va = _mm256_cvtph_ps(_mm_loadu_si128(a.as_ptr().add(i) as *const __m128i));
+        let vb = _mm256_cvtph_ps(_mm_loadu_si128(b.as_ptr().add(i) as *const __m128i));
+        sum = _mm256_fmadd_ps(va, vb, sum);
+        i += 8;
+    }
+
+    let mut lanes = [0f32; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), sum);
+    let mut result: f32 = lanes.iter().sum();
+    for (&x, &y) in a[i..len].iter().zip(&b[i..len]) {
+        result += f32::from(x) * f32::from(y);
+    }
+
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
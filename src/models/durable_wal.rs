@@ -6,7 +6,7 @@ use super::{
     buffered_io::{BufIoError, BufferManager},
     serializer::write_len,
     versioning::VersionNumber,
-    wal::VectorOp,
+    wal::{VectorOp, VERSIONED_RECORD_FLAG, WAL_RECORD_VERSION},
 };
 
 pub struct DurableWALFile {
@@ -74,6 +74,7 @@ impl DurableWALFile {
         match op {
             VectorOp::Upsert(vectors) => {
                 self.records_upserted += vectors.len() as u32;
+                buf.push(WAL_RECORD_VERSION);
                 write_len(&mut buf, vectors.len() as u32);
                 for vector in &*vectors {
                     write_len(&mut buf, vector.id.len() as u32);
@@ -130,9 +131,47 @@ impl DurableWALFile {
                     } else {
                         write_len(&mut buf, 0);
                     }
+
+                    if let Some(text_fields) = &vector.text_fields {
+                        write_len(&mut buf, text_fields.len() as u32);
+                        for (field, text) in text_fields {
+                            write_len(&mut buf, field.len() as u32);
+                            buf.extend(field.as_bytes());
+                            write_len(&mut buf, text.len() as u32);
+                            buf.extend(text.as_bytes());
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
+
+                    if let Some(dense_fields) = &vector.dense_fields {
+                        write_len(&mut buf, dense_fields.len() as u32);
+                        for (field, values) in dense_fields {
+                            write_len(&mut buf, field.len() as u32);
+                            buf.extend(field.as_bytes());
+                            write_len(&mut buf, values.len() as u32);
+                            for val in values {
+                                buf.extend(val.to_le_bytes());
+                            }
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
+
+                    if let Some(multi_vector_values) = &vector.multi_vector_values {
+                        write_len(&mut buf, multi_vector_values.len() as u32);
+                        for values in multi_vector_values {
+                            write_len(&mut buf, values.len() as u32);
+                            for val in values {
+                                buf.extend(val.to_le_bytes());
+                            }
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
                 }
                 let len = buf.len() as u32 - 4;
-                buf[0..4].copy_from_slice(&len.to_le_bytes());
+                buf[0..4].copy_from_slice(&(len | VERSIONED_RECORD_FLAG).to_le_bytes());
             }
             VectorOp::Delete(id) => {
                 write_len(&mut buf, id.len() as u32);
@@ -203,6 +242,19 @@ mod tests {
                     .collect(),
             ),
             text: Some(random_string(16)),
+            text_fields: Some(HashMap::from([
+                ("title".to_string(), random_string(8)),
+                ("tags".to_string(), random_string(4)),
+            ])),
+            dense_fields: Some(HashMap::from([(
+                "title_emb".to_string(),
+                (0..dense_len).map(|_| rng.gen()).collect(),
+            )])),
+            multi_vector_values: Some(
+                (0..rng.gen_range(1..4))
+                    .map(|_| (0..dense_len).map(|_| rng.gen()).collect())
+                    .collect(),
+            ),
         }
     }
 
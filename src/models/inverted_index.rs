@@ -21,6 +21,14 @@ use super::{
     versioning::VersionNumber,
 };
 
+/// Quantizes a value of a sparse vector the way an index with
+/// `quantization_bits` bits stores it
+pub fn quantize_value(value: f32, quantization_bits: u8, values_upper_bound: f32) -> u8 {
+    let quantization = ((1u32 << quantization_bits) - 1) as u8;
+    let max_val = quantization as f32;
+    (((value / values_upper_bound) * max_val).clamp(0.0, max_val) as u8).min(quantization)
+}
+
 #[cfg_attr(test, derive(PartialEq, Debug))]
 pub struct InvertedIndexNodeData {
     pub map: TSHashTable<u8, VersionedVec<u32>>,
@@ -166,9 +174,7 @@ impl InvertedIndexNode {
     }
 
     pub fn quantize(&self, value: f32, values_upper_bound: f32) -> u8 {
-        let quantization = ((1u32 << self.quantization_bits) - 1) as u8;
-        let max_val = quantization as f32;
-        (((value / values_upper_bound) * max_val).clamp(0.0, max_val) as u8).min(quantization)
+        quantize_value(value, self.quantization_bits, values_upper_bound)
     }
 
     /// Inserts a value into the index at the specified dimension index.
@@ -156,6 +156,9 @@ where
     evict_strategy: EvictStrategy,
     index: EvictionIndex,
     evict_hook: Option<fn(&V)>,
+    // Lookups served from / missing in the cache, for metrics
+    hits: AtomicU64,
+    misses: AtomicU64,
 }
 
 /// Wrapper for the value that's returned from the LRUCache when
@@ -189,6 +192,8 @@ where
             evict_hook: None,
             capacity,
             evict_strategy,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
 
@@ -213,8 +218,10 @@ where
             *counter_val = new_counter;
             self.index
                 .on_cache_hit(old_counter, new_counter, key.clone().into());
+            self.hits.fetch_add(1, Ordering::Relaxed);
             Some(value.clone())
         } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
             None
         }
     }
@@ -263,9 +270,11 @@ where
         match res {
             Ok(v) => {
                 if inserted {
+                    self.misses.fetch_add(1, Ordering::Relaxed);
                     self.evict();
                     Ok(CachedValue::Miss(v))
                 } else {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
                     Ok(CachedValue::Hit(v))
                 }
             }
@@ -361,6 +370,18 @@ where
         }
     }
 
+    /// Number of lookups (`get` and `get_or_insert`) that found the key
+    /// in the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups (`get` and `get_or_insert`) that didn't find
+    /// the key in the cache
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
     fn increment_counter(&self) -> u32 {
         self.counter.fetch_add(1, Ordering::SeqCst)
     }
@@ -570,6 +591,24 @@ mod tests {
         assert_eq!(vec!["value1", "value2", "value3", "value4"], values);
     }
 
+    #[test]
+    fn test_hit_and_miss_counts() {
+        let cache: LRUCache<u64, &'static str> = LRUCache::new(4, EvictStrategy::Immediate);
+
+        cache.insert(1, "value1");
+        assert_eq!(Some("value1"), cache.get(&1));
+        assert_eq!(None, cache.get(&2));
+
+        let value = cache.get_or_insert::<()>(2, || Ok("value2")).unwrap();
+        assert!(matches!(value, CachedValue::Miss("value2")));
+        let value = cache.get_or_insert::<()>(2, || Ok("value2")).unwrap();
+        assert!(matches!(value, CachedValue::Hit("value2")));
+
+        // `insert` is not a lookup
+        assert_eq!(2, cache.hits());
+        assert_eq!(2, cache.misses());
+    }
+
     fn gen_rand_nums(rng: &mut rand::rngs::ThreadRng, n: u64, min: u32, max: u32) -> Vec<u32> {
         (0..n).map(|_| rng.gen_range(min..max)).collect()
     }
@@ -57,12 +57,28 @@ pub fn update_background_version(
     Ok(())
 }
 
-pub fn store_values_range(lmdb: &MetaDb, range: (f32, f32)) -> lmdb::Result<()> {
+// the values range of a named dense field is stored under its own key
+fn values_range_key(field: Option<&str>) -> Vec<u8> {
+    let mut key = key!(m:values_range);
+    if let Some(field) = field {
+        key.push(b':');
+        key.extend_from_slice(field.as_bytes());
+    }
+    key
+}
+
+/// stores the values range of the dense index of a collection, or of the
+/// index of one of its named dense fields
+pub fn store_values_range(
+    lmdb: &MetaDb,
+    field: Option<&str>,
+    range: (f32, f32),
+) -> lmdb::Result<()> {
     let env = lmdb.env.clone();
     let db = lmdb.db;
 
     let mut txn = env.begin_rw_txn()?;
-    let key = key!(m:values_range);
+    let key = values_range_key(field);
     let mut bytes = Vec::with_capacity(8);
     bytes.extend(range.0.to_le_bytes());
     bytes.extend(range.1.to_le_bytes());
@@ -85,15 +101,14 @@ pub fn store_values_upper_bound(lmdb: &MetaDb, bound: f32) -> lmdb::Result<()> {
     Ok(())
 }
 
-pub fn store_average_document_length(lmdb: &MetaDb, len: f32) -> lmdb::Result<()> {
+pub fn store_tf_idf_configured(lmdb: &MetaDb) -> lmdb::Result<()> {
     let env = lmdb.env.clone();
     let db = lmdb.db;
 
     let mut txn = env.begin_rw_txn()?;
-    let key = key!(m:average_document_length);
-    let bytes = len.to_le_bytes();
+    let key = key!(m:tf_idf_configured);
 
-    txn.put(db, &key, &bytes, WriteFlags::empty())?;
+    txn.put(db, &key, &[1u8], WriteFlags::empty())?;
     txn.commit()?;
     Ok(())
 }
@@ -163,13 +178,16 @@ pub fn retrieve_background_version(lmdb: &MetaDb) -> Result<VersionNumber, WaCus
     Ok(hash)
 }
 
-pub fn retrieve_values_range(lmdb: &MetaDb) -> Result<Option<(f32, f32)>, WaCustomError> {
+pub fn retrieve_values_range(
+    lmdb: &MetaDb,
+    field: Option<&str>,
+) -> Result<Option<(f32, f32)>, WaCustomError> {
     let env = lmdb.env.clone();
     let db = lmdb.db;
     let txn = env
         .begin_ro_txn()
         .map_err(|e| WaCustomError::DatabaseError(format!("Failed to begin transaction: {}", e)))?;
-    let key = key!(m:values_range);
+    let key = values_range_key(field);
 
     let serialized_hash = match txn.get(db, &key) {
         Ok(bytes) => bytes,
@@ -212,28 +230,42 @@ pub fn retrieve_values_upper_bound(lmdb: &MetaDb) -> Result<Option<f32>, WaCusto
     Ok(Some(bound))
 }
 
-pub fn retrieve_average_document_length(lmdb: &MetaDb) -> Result<Option<f32>, WaCustomError> {
+pub fn retrieve_tf_idf_configured(lmdb: &MetaDb) -> Result<bool, WaCustomError> {
     let env = lmdb.env.clone();
     let db = lmdb.db;
     let txn = env
         .begin_ro_txn()
         .map_err(|e| WaCustomError::DatabaseError(format!("Failed to begin transaction: {}", e)))?;
-    let key = key!(m:average_document_length);
+    let key = key!(m:tf_idf_configured);
 
-    let serialized = match txn.get(db, &key) {
-        Ok(bytes) => bytes,
-        Err(lmdb::Error::NotFound) => return Ok(None),
-        Err(e) => return Err(WaCustomError::DatabaseError(e.to_string())),
-    };
+    match txn.get(db, &key) {
+        Ok(_) => Ok(true),
+        Err(lmdb::Error::NotFound) => Ok(false),
+        Err(e) => Err(WaCustomError::DatabaseError(e.to_string())),
+    }
+}
 
-    let bytes: [u8; 4] = serialized.try_into().map_err(|_| {
-        WaCustomError::DeserializationError(
-            "Failed to deserialize average document length: length mismatch".to_string(),
-        )
-    })?;
-    let len = f32::from_le_bytes(bytes);
+/// Replaces the sampled average document length, which marked the TF-IDF
+/// indexes of the format version 2 as configured, with the
+/// `m:tf_idf_configured` key, returns whether the index is configured
+pub fn migrate_tf_idf_configured(lmdb: &MetaDb) -> Result<bool, WaCustomError> {
+    let env = lmdb.env.clone();
+    let db = lmdb.db;
+    let mut txn = env
+        .begin_rw_txn()
+        .map_err(|e| WaCustomError::DatabaseError(format!("Failed to begin transaction: {}", e)))?;
+    let legacy_key = key!(m:average_document_length);
+    let key = key!(m:tf_idf_configured);
 
-    Ok(Some(len))
+    match txn.get(db, &legacy_key) {
+        Ok(_) => {}
+        Err(lmdb::Error::NotFound) => return Ok(false),
+        Err(e) => return Err(WaCustomError::DatabaseError(e.to_string())),
+    }
+    txn.put(db, &key, &[1u8], WriteFlags::empty())?;
+    txn.del(db, &legacy_key, None)?;
+    txn.commit()?;
+    Ok(true)
 }
 
 pub fn retrieve_highest_internal_id(lmdb: &MetaDb) -> Result<Option<u32>, WaCustomError> {
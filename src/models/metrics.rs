@@ -0,0 +1,194 @@
+use dashmap::DashMap;
+use std::fmt::{Display, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Upper bounds (in seconds) of the buckets of the latency histograms
+pub const LATENCY_BUCKETS: [f64; 14] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A latency histogram with the fixed `LATENCY_BUCKETS`
+pub struct Histogram {
+    // Non-cumulative counts, one per bucket and the last one for +Inf
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let idx = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Process-wide metrics which are recorded from places that don't have
+/// access to the `AppContext`. Metrics that can be derived from the
+/// state of the collections are computed while rendering instead.
+#[derive(Default)]
+pub struct Metrics {
+    // keyed by (method, route pattern)
+    http_requests: DashMap<(String, String), Histogram>,
+    // keyed by index type
+    searches: DashMap<&'static str, Histogram>,
+}
+
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    pub fn observe_http_request(&self, method: &str, route: &str, elapsed: Duration) {
+        self.http_requests
+            .entry((method.to_string(), route.to_string()))
+            .or_default()
+            .observe(elapsed);
+    }
+
+    pub fn observe_search(&self, index_type: &'static str, elapsed: Duration) {
+        self.searches
+            .entry(index_type)
+            .or_default()
+            .observe(elapsed);
+    }
+
+    pub fn write(&self, writer: &mut MetricsWriter) {
+        writer.header(
+            "cosdata_http_request_duration_seconds",
+            "Latency of the HTTP requests per route",
+            "histogram",
+        );
+        for entry in self.http_requests.iter() {
+            let (method, route) = entry.key();
+            writer.histogram(
+                "cosdata_http_request_duration_seconds",
+                &[("method", method), ("route", route)],
+                entry.value(),
+            );
+        }
+
+        writer.header(
+            "cosdata_search_duration_seconds",
+            "Latency of the searches per index type, one per query of a batch",
+            "histogram",
+        );
+        for entry in self.searches.iter() {
+            writer.histogram(
+                "cosdata_search_duration_seconds",
+                &[("index_type", entry.key())],
+                entry.value(),
+            );
+        }
+    }
+}
+
+/// Renders metrics in the Prometheus text exposition format
+#[derive(Default)]
+pub struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    pub fn header(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (label, label_value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{}=\"", label);
+                for c in label_value.chars() {
+                    match c {
+                        '\\' => self.out.push_str("\\\\"),
+                        '"' => self.out.push_str("\\\""),
+                        '\n' => self.out.push_str("\\n"),
+                        c => self.out.push(c),
+                    }
+                }
+                self.out.push('"');
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+
+    pub fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+        let bucket_name = format!("{}_bucket", name);
+        let mut cumulative = 0;
+        for (i, bucket) in histogram.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = LATENCY_BUCKETS
+                .get(i)
+                .map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(&bucket_name, &bucket_labels, cumulative);
+        }
+        let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        self.sample(&format!("{}_sum", name), labels, sum);
+        self.sample(&format!("{}_count", name), labels, cumulative);
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_rendering() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(300));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(20));
+
+        let mut writer = MetricsWriter::default();
+        writer.histogram("latency_seconds", &[("route", "/a")], &histogram);
+        let out = writer.finish();
+
+        assert!(out.contains("latency_seconds_bucket{route=\"/a\",le=\"0.0005\"} 1\n"));
+        assert!(out.contains("latency_seconds_bucket{route=\"/a\",le=\"0.01\"} 1\n"));
+        assert!(out.contains("latency_seconds_bucket{route=\"/a\",le=\"0.025\"} 2\n"));
+        assert!(out.contains("latency_seconds_bucket{route=\"/a\",le=\"10\"} 2\n"));
+        assert!(out.contains("latency_seconds_bucket{route=\"/a\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_seconds_sum{route=\"/a\"} 20.0203\n"));
+        assert!(out.contains("latency_seconds_count{route=\"/a\"} 3\n"));
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        let mut writer = MetricsWriter::default();
+        writer.sample("name", &[], 1);
+        writer.sample("name", &[("collection", "a\"b\\c\nd")], 2.5);
+        assert_eq!(
+            "name 1\nname{collection=\"a\\\"b\\\\c\\nd\"} 2.5\n",
+            writer.finish()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod api_keys;
 pub mod atomic_array;
 pub mod buffered_io;
 pub mod cache_loader;
@@ -18,11 +19,14 @@ pub mod kmeans;
 pub mod lazy_item;
 pub mod lru_cache;
 pub mod meta_persist;
+pub mod metrics;
 pub mod paths;
 pub mod prob_node;
+pub mod rbac;
 pub mod rpc;
 pub mod schema_traits;
 pub mod serializer;
+pub mod sessions;
 pub mod sparse_ann_query;
 pub mod tf_idf_index;
 pub mod tree_map;
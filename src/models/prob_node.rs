@@ -491,6 +491,7 @@ impl ProbNode {
             id: Some(&internal_id),
             quantized_vec: &self.prop_value.vec,
             metadata,
+            values_range: None,
         };
         vector_data.replica_node_kind()
     }
@@ -0,0 +1,492 @@
+// Role-based access control
+//
+// Roles are named sets of permissions. Users are granted roles per
+// collection, and the special `*` scope grants a role on every
+// collection (and on operations that are not tied to a collection,
+// e.g. creating one).
+//
+// Both roles and assignments are persisted in the `rbac` LMDB
+// database and cached in memory, as they are consulted on every
+// request.
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, Transaction, WriteFlags};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::common::WaCustomError;
+
+/// Scope used for role assignments that apply to all collections
+pub const GLOBAL_SCOPE: &str = "*";
+
+pub const ADMIN_ROLE: &str = "admin";
+pub const EDITOR_ROLE: &str = "editor";
+pub const VIEWER_ROLE: &str = "viewer";
+
+const ROLE_KEY_PREFIX: &str = "role:";
+const ACCESS_KEY_PREFIX: &str = "access:";
+
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    // Collection Management
+    ListCollections,
+    CreateCollection,
+    UpdateCollection,
+    DeleteCollection,
+
+    // Index Management
+    ListIndex,
+    CreateIndex,
+    DeleteIndex,
+
+    // Vector Management
+    UpsertVectors,
+    DeleteVectors,
+    ListVectors,
+    CheckVectorExistence,
+
+    // Querying
+    QueryDenseVectors,
+    QuerySparseVectors,
+    QueryHybridVectors,
+
+    // Version Control
+    ListVersions,
+    SetCurrentVersion,
+    GetCurrentVersion,
+
+    // RBAC & Admin
+    ManagePermissions,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 18] = [
+        Permission::ListCollections,
+        Permission::CreateCollection,
+        Permission::UpdateCollection,
+        Permission::DeleteCollection,
+        Permission::ListIndex,
+        Permission::CreateIndex,
+        Permission::DeleteIndex,
+        Permission::UpsertVectors,
+        Permission::DeleteVectors,
+        Permission::ListVectors,
+        Permission::CheckVectorExistence,
+        Permission::QueryDenseVectors,
+        Permission::QuerySparseVectors,
+        Permission::QueryHybridVectors,
+        Permission::ListVersions,
+        Permission::SetCurrentVersion,
+        Permission::GetCurrentVersion,
+        Permission::ManagePermissions,
+    ];
+
+    /// Permissions that don't modify any data
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Permission::ListCollections
+                | Permission::ListIndex
+                | Permission::ListVectors
+                | Permission::CheckVectorExistence
+                | Permission::QueryDenseVectors
+                | Permission::QuerySparseVectors
+                | Permission::QueryHybridVectors
+                | Permission::ListVersions
+                | Permission::GetCurrentVersion
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Role {
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<Permission>,
+}
+
+impl Role {
+    fn builtin_roles() -> Vec<Role> {
+        let read_only: Vec<Permission> = Permission::ALL
+            .into_iter()
+            .filter(Permission::is_read_only)
+            .collect();
+        let mut read_write = read_only.clone();
+        read_write.extend([
+            Permission::UpdateCollection,
+            Permission::CreateIndex,
+            Permission::DeleteIndex,
+            Permission::UpsertVectors,
+            Permission::DeleteVectors,
+            Permission::SetCurrentVersion,
+        ]);
+
+        vec![
+            Role {
+                name: ADMIN_ROLE.to_string(),
+                description: Some("Full access, including managing permissions".to_string()),
+                permissions: Permission::ALL.to_vec(),
+            },
+            Role {
+                name: EDITOR_ROLE.to_string(),
+                description: Some("Can read and write to collections".to_string()),
+                permissions: read_write,
+            },
+            Role {
+                name: VIEWER_ROLE.to_string(),
+                description: Some("Can read and query collections".to_string()),
+                permissions: read_only,
+            },
+        ]
+    }
+
+    pub fn is_builtin(name: &str) -> bool {
+        matches!(name, ADMIN_ROLE | EDITOR_ROLE | VIEWER_ROLE)
+    }
+}
+
+/// Roles granted to a user on a collection (or on `GLOBAL_SCOPE`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleAssignment {
+    pub collection: String,
+    pub username: String,
+    pub roles: Vec<String>,
+}
+
+impl RoleAssignment {
+    fn key(collection: &str, username: &str) -> String {
+        format!("{}{}/{}", ACCESS_KEY_PREFIX, collection, username)
+    }
+}
+
+pub struct RbacStore {
+    env: Arc<Environment>,
+    db: Database,
+    // (role name, role)
+    roles: DashMap<String, Role>,
+    // ((collection, username), assignment)
+    assignments: DashMap<(String, String), RoleAssignment>,
+}
+
+impl RbacStore {
+    pub fn new(env: Arc<Environment>) -> Result<Self, WaCustomError> {
+        let db = env.create_db(Some("rbac"), DatabaseFlags::empty())?;
+        let roles = DashMap::new();
+        let assignments = DashMap::new();
+
+        let txn = env.begin_ro_txn()?;
+        let mut cursor = txn.open_ro_cursor(db)?;
+        for (key, value) in cursor.iter() {
+            if key.starts_with(ROLE_KEY_PREFIX.as_bytes()) {
+                let role: Role = serde_cbor::from_slice(value)
+                    .map_err(|e| WaCustomError::DeserializationError(e.to_string()))?;
+                roles.insert(role.name.clone(), role);
+            } else if key.starts_with(ACCESS_KEY_PREFIX.as_bytes()) {
+                let assignment: RoleAssignment = serde_cbor::from_slice(value)
+                    .map_err(|e| WaCustomError::DeserializationError(e.to_string()))?;
+                assignments.insert(
+                    (assignment.collection.clone(), assignment.username.clone()),
+                    assignment,
+                );
+            }
+        }
+        drop(cursor);
+        txn.abort();
+
+        let store = Self {
+            env,
+            db,
+            roles,
+            assignments,
+        };
+
+        for role in Role::builtin_roles() {
+            if !store.roles.contains_key(&role.name) {
+                store.put_role(role)?;
+            }
+        }
+
+        Ok(store)
+    }
+
+    fn put_role(&self, role: Role) -> Result<(), WaCustomError> {
+        let key = format!("{}{}", ROLE_KEY_PREFIX, role.name);
+        let value = serde_cbor::to_vec(&role)
+            .map_err(|e| WaCustomError::SerializationError(e.to_string()))?;
+
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.put(self.db, &key, &value, WriteFlags::empty())?;
+        txn.commit()?;
+
+        self.roles.insert(role.name.clone(), role);
+        Ok(())
+    }
+
+    fn put_assignment(&self, assignment: RoleAssignment) -> Result<(), WaCustomError> {
+        let key = RoleAssignment::key(&assignment.collection, &assignment.username);
+        let mut txn = self.env.begin_rw_txn()?;
+        if assignment.roles.is_empty() {
+            match txn.del(self.db, &key, None) {
+                Ok(()) | Err(lmdb::Error::NotFound) => {}
+                Err(err) => return Err(err.into()),
+            }
+            txn.commit()?;
+            self.assignments
+                .remove(&(assignment.collection, assignment.username));
+            return Ok(());
+        }
+
+        let value = serde_cbor::to_vec(&assignment)
+            .map_err(|e| WaCustomError::SerializationError(e.to_string()))?;
+        txn.put(self.db, &key, &value, WriteFlags::empty())?;
+        txn.commit()?;
+
+        self.assignments.insert(
+            (assignment.collection.clone(), assignment.username.clone()),
+            assignment,
+        );
+        Ok(())
+    }
+
+    /// Creates a new role, returns error if a role with the same name exists
+    pub fn create_role(&self, role: Role) -> Result<(), WaCustomError> {
+        if self.roles.contains_key(&role.name) {
+            return Err(WaCustomError::InvalidData(format!(
+                "Role '{}' already exists",
+                role.name
+            )));
+        }
+        self.put_role(role)
+    }
+
+    pub fn get_role(&self, name: &str) -> Option<Role> {
+        self.roles.get(name).map(|role| role.value().clone())
+    }
+
+    pub fn list_roles(&self) -> Vec<Role> {
+        let mut roles: Vec<_> = self.roles.iter().map(|role| role.value().clone()).collect();
+        roles.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        roles
+    }
+
+    /// Deletes a role along with all of its assignments
+    ///
+    /// Built-in roles can't be deleted.
+    pub fn delete_role(&self, name: &str) -> Result<Option<Role>, WaCustomError> {
+        if Role::is_builtin(name) {
+            return Err(WaCustomError::InvalidData(format!(
+                "Built-in role '{}' can't be deleted",
+                name
+            )));
+        }
+        if !self.roles.contains_key(name) {
+            return Ok(None);
+        }
+
+        let affected: Vec<RoleAssignment> = self
+            .assignments
+            .iter()
+            .filter(|assignment| assignment.roles.iter().any(|role| role == name))
+            .map(|assignment| assignment.value().clone())
+            .collect();
+        for mut assignment in affected {
+            assignment.roles.retain(|role| role != name);
+            self.put_assignment(assignment)?;
+        }
+
+        let key = format!("{}{}", ROLE_KEY_PREFIX, name);
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.del(self.db, &key, None)?;
+        txn.commit()?;
+
+        Ok(self.roles.remove(name).map(|(_, role)| role))
+    }
+
+    /// Grants `role` to the user on the collection, no-op if already granted
+    pub fn assign_role(
+        &self,
+        collection: &str,
+        username: &str,
+        role: &str,
+    ) -> Result<(), WaCustomError> {
+        if !self.roles.contains_key(role) {
+            return Err(WaCustomError::NotFound(format!("role '{}'", role)));
+        }
+        let mut assignment = self
+            .assignments
+            .get(&(collection.to_string(), username.to_string()))
+            .map(|assignment| assignment.value().clone())
+            .unwrap_or_else(|| RoleAssignment {
+                collection: collection.to_string(),
+                username: username.to_string(),
+                roles: Vec::new(),
+            });
+        if assignment.roles.iter().any(|r| r == role) {
+            return Ok(());
+        }
+        assignment.roles.push(role.to_string());
+        self.put_assignment(assignment)
+    }
+
+    /// Revokes `role` from the user on the collection, returns false if it
+    /// wasn't granted
+    pub fn revoke_role(
+        &self,
+        collection: &str,
+        username: &str,
+        role: &str,
+    ) -> Result<bool, WaCustomError> {
+        let Some(mut assignment) = self
+            .assignments
+            .get(&(collection.to_string(), username.to_string()))
+            .map(|assignment| assignment.value().clone())
+        else {
+            return Ok(false);
+        };
+        let len_before = assignment.roles.len();
+        assignment.roles.retain(|r| r != role);
+        if assignment.roles.len() == len_before {
+            return Ok(false);
+        }
+        self.put_assignment(assignment)?;
+        Ok(true)
+    }
+
+    /// Roles granted to the user on the collection itself, excluding the
+    /// ones inherited from `GLOBAL_SCOPE`
+    pub fn get_user_roles(&self, collection: &str, username: &str) -> Vec<String> {
+        self.assignments
+            .get(&(collection.to_string(), username.to_string()))
+            .map(|assignment| assignment.roles.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn list_collection_access(&self, collection: &str) -> Vec<RoleAssignment> {
+        let mut assignments: Vec<_> = self
+            .assignments
+            .iter()
+            .filter(|assignment| assignment.collection == collection)
+            .map(|assignment| assignment.value().clone())
+            .collect();
+        assignments.sort_unstable_by(|a, b| a.username.cmp(&b.username));
+        assignments
+    }
+
+    /// Drops all the assignments on a collection, to be called when the
+    /// collection is deleted
+    pub fn remove_collection(&self, collection: &str) -> Result<(), WaCustomError> {
+        let keys: Vec<_> = self
+            .assignments
+            .iter()
+            .filter(|assignment| assignment.collection == collection)
+            .map(|assignment| assignment.key().clone())
+            .collect();
+        for (collection, username) in keys {
+            self.put_assignment(RoleAssignment {
+                collection,
+                username,
+                roles: Vec::new(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Drops all the assignments of a user, to be called when the user is
+    /// deleted
+    pub fn remove_user(&self, username: &str) -> Result<(), WaCustomError> {
+        let keys: Vec<_> = self
+            .assignments
+            .iter()
+            .filter(|assignment| assignment.username == username)
+            .map(|assignment| assignment.key().clone())
+            .collect();
+        for (collection, username) in keys {
+            self.put_assignment(RoleAssignment {
+                collection,
+                username,
+                roles: Vec::new(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Checks if any of the roles granted to the user, either on the
+    /// collection or globally, contains the permission
+    pub fn has_permission(&self, username: &str, collection: &str, permission: Permission) -> bool {
+        let has_permission_in_scope = |scope: &str| {
+            self.assignments
+                .get(&(scope.to_string(), username.to_string()))
+                .is_some_and(|assignment| {
+                    assignment.roles.iter().any(|role| {
+                        self.roles
+                            .get(role)
+                            .is_some_and(|role| role.permissions.contains(&permission))
+                    })
+                })
+        };
+
+        has_permission_in_scope(GLOBAL_SCOPE)
+            || (collection != GLOBAL_SCOPE && has_permission_in_scope(collection))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn open_store(path: &std::path::Path) -> RbacStore {
+        let env = Environment::new().set_max_dbs(1).open(path).unwrap();
+        RbacStore::new(Arc::new(env)).unwrap()
+    }
+
+    #[test]
+    fn test_permissions_are_scoped_to_collections() {
+        let dir = tempdir().unwrap();
+        let store = open_store(dir.path());
+
+        store.assign_role("books", "alice", VIEWER_ROLE).unwrap();
+        store.assign_role(GLOBAL_SCOPE, "bob", EDITOR_ROLE).unwrap();
+
+        assert!(store.has_permission("alice", "books", Permission::QueryDenseVectors));
+        assert!(!store.has_permission("alice", "books", Permission::UpsertVectors));
+        assert!(!store.has_permission("alice", "movies", Permission::QueryDenseVectors));
+
+        assert!(store.has_permission("bob", "movies", Permission::UpsertVectors));
+        assert!(!store.has_permission("bob", "movies", Permission::DeleteCollection));
+        assert!(!store.has_permission("carol", "books", Permission::ListCollections));
+
+        assert!(store.revoke_role("books", "alice", VIEWER_ROLE).unwrap());
+        assert!(!store.has_permission("alice", "books", Permission::QueryDenseVectors));
+    }
+
+    #[test]
+    fn test_roles_and_assignments_are_persisted() {
+        let dir = tempdir().unwrap();
+        {
+            let store = open_store(dir.path());
+            store
+                .create_role(Role {
+                    name: "ingest".to_string(),
+                    description: None,
+                    permissions: vec![Permission::UpsertVectors],
+                })
+                .unwrap();
+            store.assign_role("books", "alice", "ingest").unwrap();
+        }
+
+        let store = open_store(dir.path());
+        assert!(store.has_permission("alice", "books", Permission::UpsertVectors));
+        assert!(store.get_role(ADMIN_ROLE).is_some());
+
+        store.delete_role("ingest").unwrap();
+        assert!(!store.has_permission("alice", "books", Permission::UpsertVectors));
+        assert!(store.get_user_roles("books", "alice").is_empty());
+        assert!(store.delete_role(ADMIN_ROLE).is_err());
+    }
+}
@@ -12,9 +12,22 @@ use crate::{
 
 use super::{read_len, read_opt_string, read_string, write_len, SimpleSerialize};
 
+// Version of the layout of the serialized embeddings:
+//
+// 1. unversioned, the embeddings end with their text
+// 2. the embeddings also hold their text fields, dense fields & multi-vector
+//    values
+const RAW_VECTOR_EMBEDDING_VERSION: u8 = 2;
+
+// Starts the versioned embeddings, followed by their version. The unversioned
+// ones start with the length of the id instead, which is never that long.
+const VERSIONED_EMBEDDING_MARKER: u32 = (1 << 22) - 1;
+
 impl SimpleSerialize for RawVectorEmbedding {
     fn serialize(&self, bufman: &BufferManager, cursor: u64) -> Result<u32, BufIoError> {
         let mut buf = Vec::new();
+        write_len(&mut buf, VERSIONED_EMBEDDING_MARKER);
+        buf.push(RAW_VECTOR_EMBEDDING_VERSION);
         write_len(&mut buf, self.id.len() as u32);
         buf.extend(self.id.as_bytes());
         if let Some(document_id) = &self.document_id {
@@ -70,12 +83,62 @@ impl SimpleSerialize for RawVectorEmbedding {
             write_len(&mut buf, 0);
         }
 
+        if let Some(text_fields) = &self.text_fields {
+            write_len(&mut buf, text_fields.len() as u32);
+            for (field, text) in text_fields {
+                write_len(&mut buf, field.len() as u32);
+                buf.extend(field.as_bytes());
+                write_len(&mut buf, text.len() as u32);
+                buf.extend(text.as_bytes());
+            }
+        } else {
+            write_len(&mut buf, 0);
+        }
+
+        if let Some(dense_fields) = &self.dense_fields {
+            write_len(&mut buf, dense_fields.len() as u32);
+            for (field, values) in dense_fields {
+                write_len(&mut buf, field.len() as u32);
+                buf.extend(field.as_bytes());
+                write_len(&mut buf, values.len() as u32);
+                for val in values {
+                    buf.extend(val.to_le_bytes());
+                }
+            }
+        } else {
+            write_len(&mut buf, 0);
+        }
+
+        if let Some(multi_vector_values) = &self.multi_vector_values {
+            write_len(&mut buf, multi_vector_values.len() as u32);
+            for values in multi_vector_values {
+                write_len(&mut buf, values.len() as u32);
+                for val in values {
+                    buf.extend(val.to_le_bytes());
+                }
+            }
+        } else {
+            write_len(&mut buf, 0);
+        }
+
         Ok(bufman.write_to_end_of_file(cursor, &buf)? as u32)
     }
 
     fn deserialize(bufman: &BufferManager, offset: FileOffset) -> Result<Self, BufIoError> {
         let cursor = bufman.open_cursor()?;
         bufman.seek_with_cursor(cursor, offset.0 as u64)?;
+        let version = if read_len(bufman, cursor)? == VERSIONED_EMBEDDING_MARKER {
+            bufman.read_u8_with_cursor(cursor)?
+        } else {
+            bufman.seek_with_cursor(cursor, offset.0 as u64)?;
+            1
+        };
+        if version > RAW_VECTOR_EMBEDDING_VERSION {
+            return Err(BufIoError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported raw vector embedding version `{}`", version),
+            )));
+        }
         let id = VectorId::from(read_string(bufman, cursor)?);
         let document_id = read_opt_string(bufman, cursor)?.map(DocumentId::from);
         let dense_values_len = read_len(bufman, cursor)? as usize;
@@ -132,6 +195,63 @@ impl SimpleSerialize for RawVectorEmbedding {
         };
 
         let text = read_opt_string(bufman, cursor)?;
+        // the unversioned embeddings end with the text
+        let has_fields = version >= 2;
+        let text_fields_len = if has_fields {
+            read_len(bufman, cursor)? as usize
+        } else {
+            0
+        };
+        let text_fields = if text_fields_len == 0 {
+            None
+        } else {
+            let mut text_fields = HashMap::with_capacity(text_fields_len);
+            for _ in 0..text_fields_len {
+                let field = read_string(bufman, cursor)?;
+                let text = read_string(bufman, cursor)?;
+                text_fields.insert(field, text);
+            }
+            Some(text_fields)
+        };
+        let dense_fields_len = if has_fields {
+            read_len(bufman, cursor)? as usize
+        } else {
+            0
+        };
+        let dense_fields = if dense_fields_len == 0 {
+            None
+        } else {
+            let mut dense_fields = HashMap::with_capacity(dense_fields_len);
+            for _ in 0..dense_fields_len {
+                let field = read_string(bufman, cursor)?;
+                let values_len = read_len(bufman, cursor)? as usize;
+                let mut values = Vec::with_capacity(values_len);
+                for _ in 0..values_len {
+                    values.push(bufman.read_f32_with_cursor(cursor)?);
+                }
+                dense_fields.insert(field, values);
+            }
+            Some(dense_fields)
+        };
+        let multi_vector_values_len = if has_fields {
+            read_len(bufman, cursor)? as usize
+        } else {
+            0
+        };
+        let multi_vector_values = if multi_vector_values_len == 0 {
+            None
+        } else {
+            let mut multi_vector_values = Vec::with_capacity(multi_vector_values_len);
+            for _ in 0..multi_vector_values_len {
+                let values_len = read_len(bufman, cursor)? as usize;
+                let mut values = Vec::with_capacity(values_len);
+                for _ in 0..values_len {
+                    values.push(bufman.read_f32_with_cursor(cursor)?);
+                }
+                multi_vector_values.push(values);
+            }
+            Some(multi_vector_values)
+        };
 
         Ok(Self {
             id,
@@ -140,6 +260,9 @@ impl SimpleSerialize for RawVectorEmbedding {
             metadata,
             sparse_values,
             text,
+            text_fields,
+            dense_fields,
+            multi_vector_values,
         })
     }
 }
@@ -1,9 +1,11 @@
 use crate::indexes::hnsw::offset_counter::IndexFileId;
 use crate::models::buffered_io::BufferManagerFactory;
+use crate::models::collection::RawVectorEmbedding;
 use crate::models::serializer::*;
 use crate::models::types::*;
 use crate::storage::Storage;
 use half::f16;
+use std::collections::HashMap;
 use tempfile::TempDir;
 
 #[test]
@@ -41,3 +43,60 @@ fn test_storage_serialization() {
         assert_eq!(deserialized, storage);
     }
 }
+
+#[test]
+fn test_unversioned_raw_vector_embedding_deserialization() {
+    let tempdir = TempDir::new().unwrap();
+    let bufmans = BufferManagerFactory::new(
+        tempdir.as_ref().into(),
+        |root, ver: &IndexFileId| root.join(format!("{}.index", **ver)),
+        8192,
+    );
+    let bufman = bufmans.get(IndexFileId::from(0)).unwrap();
+    let cursor = bufman.open_cursor().unwrap();
+
+    // the layout before the text fields, dense fields & multi-vector values
+    let mut buf = Vec::new();
+    write_len(&mut buf, 2);
+    buf.extend(b"v1");
+    write_len(&mut buf, 0);
+    write_len(&mut buf, 2);
+    buf.extend(0.5f32.to_le_bytes());
+    buf.extend(1.5f32.to_le_bytes());
+    write_len(&mut buf, 0);
+    write_len(&mut buf, 0);
+    write_len(&mut buf, 4);
+    buf.extend(b"rust");
+    let offset = bufman.write_to_end_of_file(cursor, &buf).unwrap() as u32;
+    // followed by a versioned embedding, which mustn't be read as the fields
+    // of the unversioned one
+    let next = RawVectorEmbedding {
+        id: VectorId::from("v2".to_string()),
+        document_id: None,
+        dense_values: None,
+        metadata: None,
+        sparse_values: None,
+        text: Some("vector".to_string()),
+        text_fields: Some(HashMap::from([("title".to_string(), "db".to_string())])),
+        dense_fields: Some(HashMap::from([("image".to_string(), vec![0.25])])),
+        multi_vector_values: Some(vec![vec![1.0, 2.0]]),
+    };
+    let next_offset = SimpleSerialize::serialize(&next, &bufman, cursor).unwrap();
+
+    let embedding: RawVectorEmbedding =
+        SimpleSerialize::deserialize(&bufman, FileOffset(offset)).unwrap();
+    assert_eq!("v1", embedding.id.as_str());
+    assert_eq!(Some(vec![0.5, 1.5]), embedding.dense_values);
+    assert_eq!(Some("rust".to_string()), embedding.text);
+    assert_eq!(None, embedding.text_fields);
+    assert_eq!(None, embedding.dense_fields);
+    assert_eq!(None, embedding.multi_vector_values);
+
+    let embedding: RawVectorEmbedding =
+        SimpleSerialize::deserialize(&bufman, FileOffset(next_offset)).unwrap();
+    assert_eq!("v2", embedding.id.as_str());
+    assert_eq!(next.text, embedding.text);
+    assert_eq!(next.text_fields, embedding.text_fields);
+    assert_eq!(next.dense_fields, embedding.dense_fields);
+    assert_eq!(next.multi_vector_values, embedding.multi_vector_values);
+}
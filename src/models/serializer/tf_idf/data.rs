@@ -3,7 +3,10 @@ use std::sync::{
     Arc, RwLock,
 };
 
-use super::{TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE};
+use super::{
+    TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE, TF_IDF_INDEX_DATA_ENTRY_SIZE,
+    TF_IDF_TERM_INFO_SIZE,
+};
 use crate::models::{
     buffered_io::{BufIoError, BufferManager, BufferManagerFactory},
     cache_loader::TFIDFIndexCache,
@@ -35,26 +38,12 @@ impl TFIDFIndexSerialize for TFIDFIndexNodeData {
             for i in (chunk_idx * TF_IDF_INDEX_DATA_CHUNK_SIZE)
                 ..((chunk_idx + 1) * TF_IDF_INDEX_DATA_CHUNK_SIZE)
             {
-                let current_offset = dim_bufman.cursor_position(cursor)?;
-                if *num_entries_serialized > i as u16 {
-                    list[i]
-                        .1
-                        .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
-                    dim_bufman.seek_with_cursor(cursor, current_offset + 10)?;
-                } else if let Some((quotient, term)) = list.get(i) {
-                    let offset =
-                        term.serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
-                    let version = term
-                        .documents
-                        .read()
-                        .map_err(|_| BufIoError::Locking)?
-                        .version;
-                    dim_bufman.seek_with_cursor(cursor, current_offset)?;
+                if let Some((quotient, term)) = list.get(i) {
                     dim_bufman.update_u16_with_cursor(cursor, *quotient)?;
-                    dim_bufman.update_u32_with_cursor(cursor, offset)?;
-                    dim_bufman.update_u32_with_cursor(cursor, *version)?;
+                    term.serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
                 } else {
-                    dim_bufman.update_with_cursor(cursor, &[u8::MAX; 10])?;
+                    dim_bufman
+                        .update_with_cursor(cursor, &[u8::MAX; TF_IDF_INDEX_DATA_ENTRY_SIZE])?;
                 }
             }
             if *num_entries_serialized > ((chunk_idx + 1) * TF_IDF_INDEX_DATA_CHUNK_SIZE) as u16 {
@@ -64,7 +53,7 @@ impl TFIDFIndexSerialize for TFIDFIndexNodeData {
                 dim_bufman.update_with_cursor(cursor, &[u8::MAX; 4])?;
             } else {
                 let offset = offset_counter.fetch_add(
-                    (10 * TF_IDF_INDEX_DATA_CHUNK_SIZE + 4) as u32,
+                    (TF_IDF_INDEX_DATA_ENTRY_SIZE * TF_IDF_INDEX_DATA_CHUNK_SIZE + 4) as u32,
                     Ordering::Relaxed,
                 );
                 dim_bufman.update_u32_with_cursor(cursor, offset)?;
@@ -99,15 +88,16 @@ impl TFIDFIndexSerialize for TFIDFIndexNodeData {
                     break;
                 }
                 let quotient = dim_bufman.read_u16_with_cursor(cursor)?;
-                let term_offset = dim_bufman.read_u32_with_cursor(cursor)?;
-                let term_version = dim_bufman.read_u32_with_cursor(cursor)?;
+                let term_offset = dim_bufman.cursor_position(cursor)? as u32;
                 let mut term = TermInfo::deserialize(
                     dim_bufman,
                     data_bufmans,
                     FileOffset(term_offset),
-                    VersionNumber::from(term_version),
+                    VersionNumber::from(u32::MAX), // not used
                     cache,
                 )?;
+                dim_bufman
+                    .seek_with_cursor(cursor, term_offset as u64 + TF_IDF_TERM_INFO_SIZE as u64)?;
                 term.sequence_idx = i as u16;
                 map.insert(quotient, Arc::new(term));
             }
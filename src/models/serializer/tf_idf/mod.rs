@@ -19,6 +19,13 @@ use crate::models::{
 
 pub const TF_IDF_INDEX_DATA_CHUNK_SIZE: usize = 4;
 
+/// Serialized size of a `TermInfo`, see [`term`]
+pub const TF_IDF_TERM_INFO_SIZE: usize = 16;
+
+/// Serialized size of an entry of the data map of a node: the 2 bytes
+/// quotient followed by the `TermInfo`
+pub const TF_IDF_INDEX_DATA_ENTRY_SIZE: usize = 2 + TF_IDF_TERM_INFO_SIZE;
+
 pub trait TFIDFIndexSerialize: Sized {
     fn serialize(
         &self,
@@ -10,7 +10,11 @@ use crate::models::{
     versioning::VersionNumber,
 };
 
-use super::{TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE};
+use super::{TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE, TF_IDF_INDEX_DATA_ENTRY_SIZE};
+
+// Offset of the children of a node from the start of the node
+const CHILDREN_OFFSET: u32 =
+    (TF_IDF_INDEX_DATA_CHUNK_SIZE * TF_IDF_INDEX_DATA_ENTRY_SIZE + 10) as u32;
 
 // @SERIALIZED_SIZE:
 //
@@ -18,10 +22,11 @@ use super::{TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE};
 //   2 bytes for data map len +                      | 6
 //   INVERTED_INDEX_DATA_CHUNK_SIZE * (              |
 //     2 bytes for quotient +                        |
-//     4 + 4 bytes of versioned vec offset & version |
-//   ) +                                             | INVERTED_INDEX_DATA_CHUNK_SIZE * 10 + 6
-//   4 byte for next data chunk                      | INVERTED_INDEX_DATA_CHUNK_SIZE * 10 + 10
-//   16 * 4 bytes for dimension offsets +            | INVERTED_INDEX_DATA_CHUNK_SIZE * 10 + 74
+//     4 + 4 bytes of documents offset & version +   |
+//     4 + 4 bytes of positions offset & version     |
+//   ) +                                             | INVERTED_INDEX_DATA_CHUNK_SIZE * 18 + 6
+//   4 byte for next data chunk                      | INVERTED_INDEX_DATA_CHUNK_SIZE * 18 + 10
+//   16 * 4 bytes for dimension offsets +            | INVERTED_INDEX_DATA_CHUNK_SIZE * 18 + 74
 impl TFIDFIndexSerialize for TFIDFIndexNode {
     fn serialize(
         &self,
@@ -35,27 +40,18 @@ impl TFIDFIndexSerialize for TFIDFIndexNode {
             dim_bufman.update_u32_with_cursor(cursor, self.dim_index)?;
             self.data
                 .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
-            dim_bufman.seek_with_cursor(
-                cursor,
-                self.file_offset.0 as u64 + TF_IDF_INDEX_DATA_CHUNK_SIZE as u64 * 10 + 10,
-            )?;
+            dim_bufman.seek_with_cursor(cursor, (self.file_offset.0 + CHILDREN_OFFSET) as u64)?;
             self.children
                 .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
         } else if self.is_dirty.swap(false, Ordering::AcqRel) {
             dim_bufman.seek_with_cursor(cursor, self.file_offset.0 as u64 + 4)?;
             self.data
                 .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
-            dim_bufman.seek_with_cursor(
-                cursor,
-                self.file_offset.0 as u64 + TF_IDF_INDEX_DATA_CHUNK_SIZE as u64 * 10 + 10,
-            )?;
+            dim_bufman.seek_with_cursor(cursor, (self.file_offset.0 + CHILDREN_OFFSET) as u64)?;
             self.children
                 .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
         } else {
-            dim_bufman.seek_with_cursor(
-                cursor,
-                self.file_offset.0 as u64 + TF_IDF_INDEX_DATA_CHUNK_SIZE as u64 * 10 + 10,
-            )?;
+            dim_bufman.seek_with_cursor(cursor, (self.file_offset.0 + CHILDREN_OFFSET) as u64)?;
             self.children
                 .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
         };
@@ -82,7 +78,7 @@ impl TFIDFIndexSerialize for TFIDFIndexNode {
         let children = AtomicArray::deserialize(
             dim_bufman,
             data_bufmans,
-            FileOffset(file_offset.0 + TF_IDF_INDEX_DATA_CHUNK_SIZE as u32 * 10 + 10),
+            FileOffset(file_offset.0 + CHILDREN_OFFSET),
             version,
             cache,
         )?;
@@ -11,6 +11,10 @@ use crate::models::{
 
 use super::TFIDFIndexSerialize;
 
+// @SERIALIZED_SIZE:
+//
+//   4 + 4 bytes of documents versioned vec offset & version +
+//   4 + 4 bytes of positions versioned vec offset & version = 16
 impl TFIDFIndexSerialize for TermInfo {
     fn serialize(
         &self,
@@ -19,26 +23,53 @@ impl TFIDFIndexSerialize for TermInfo {
         offset_counter: &AtomicU32,
         cursor: u64,
     ) -> Result<u32, BufIoError> {
-        let offset = self
-            .documents
-            .read()
-            .map_err(|_| BufIoError::Locking)?
-            .serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
-        Ok(offset)
+        let start = dim_bufman.cursor_position(cursor)? as u32;
+        let documents = self.documents.read().map_err(|_| BufIoError::Locking)?;
+        let documents_offset =
+            documents.serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
+        let positions = self.positions.read().map_err(|_| BufIoError::Locking)?;
+        let positions_offset =
+            positions.serialize(dim_bufman, data_bufmans, offset_counter, cursor)?;
+        dim_bufman.update_u32_with_cursor(cursor, documents_offset)?;
+        dim_bufman.update_u32_with_cursor(cursor, *documents.version)?;
+        dim_bufman.update_u32_with_cursor(cursor, positions_offset)?;
+        dim_bufman.update_u32_with_cursor(cursor, *positions.version)?;
+        Ok(start)
     }
 
     fn deserialize(
         dim_bufman: &BufferManager,
         data_bufmans: &BufferManagerFactory<VersionNumber>,
         file_offset: FileOffset,
-        version: VersionNumber,
+        _version: VersionNumber,
         cache: &TFIDFIndexCache,
     ) -> Result<Self, BufIoError> {
-        let documents =
-            VersionedVec::deserialize(dim_bufman, data_bufmans, file_offset, version, cache)?;
+        let cursor = dim_bufman.open_cursor()?;
+        dim_bufman.seek_with_cursor(cursor, file_offset.0 as u64)?;
+        let documents_offset = dim_bufman.read_u32_with_cursor(cursor)?;
+        let documents_version = dim_bufman.read_u32_with_cursor(cursor)?;
+        let positions_offset = dim_bufman.read_u32_with_cursor(cursor)?;
+        let positions_version = dim_bufman.read_u32_with_cursor(cursor)?;
+        dim_bufman.close_cursor(cursor)?;
+
+        let documents = VersionedVec::deserialize(
+            dim_bufman,
+            data_bufmans,
+            FileOffset(documents_offset),
+            VersionNumber::from(documents_version),
+            cache,
+        )?;
+        let positions = VersionedVec::deserialize(
+            dim_bufman,
+            data_bufmans,
+            FileOffset(positions_offset),
+            VersionNumber::from(positions_version),
+            cache,
+        )?;
 
         Ok(Self {
             documents: RwLock::new(documents),
+            positions: RwLock::new(positions),
             sequence_idx: 0, // Handled by caller
         })
     }
@@ -12,8 +12,10 @@ use tempfile::{tempdir, TempDir};
 use crate::models::{
     buffered_io::{BufferManager, BufferManagerFactory},
     cache_loader::TFIDFIndexCache,
-    serializer::tf_idf::TF_IDF_INDEX_DATA_CHUNK_SIZE,
-    tf_idf_index::{TFIDFIndexNode, TFIDFIndexNodeData, TFIDFIndexRoot, TermInfo},
+    serializer::tf_idf::{TF_IDF_INDEX_DATA_CHUNK_SIZE, TF_IDF_INDEX_DATA_ENTRY_SIZE},
+    tf_idf_index::{
+        TFIDFIndexNode, TFIDFIndexNodeData, TFIDFIndexRoot, TermInfo, TF_IDF_INDEX_FORMAT_VERSION,
+    },
     types::FileOffset,
     versioned_vec::{VersionedVec, VersionedVecItem},
     versioning::VersionNumber,
@@ -70,10 +72,15 @@ fn add_random_items_to_term_info(
     version: VersionNumber,
 ) {
     for _ in 0..count {
-        term.documents.write().unwrap().push(
-            version,
-            (rng.gen_range(0..u32::MAX), rng.gen_range(0.0..1.0)),
-        );
+        let document_id = rng.gen_range(0..u32::MAX);
+        term.documents
+            .write()
+            .unwrap()
+            .push(version, (document_id, rng.gen_range(0.0..1.0)));
+        term.positions
+            .write()
+            .unwrap()
+            .push(version, (document_id, rng.gen_range(0..1000)));
     }
 }
 
@@ -109,6 +116,7 @@ fn add_random_items_to_tf_idf_index_data(
                 documents.push(version, (document_id, value));
                 Arc::new(TermInfo {
                     documents: RwLock::new(documents),
+                    positions: RwLock::new(VersionedVec::new(version)),
                     sequence_idx,
                 })
             },
@@ -217,7 +225,8 @@ fn test_tf_idf_index_data_serialization() {
 
     let data = get_random_tf_idf_index_data(&mut rng, 0.into());
 
-    let offset_counter = AtomicU32::new(TF_IDF_INDEX_DATA_CHUNK_SIZE as u32 * 10 + 6);
+    let offset_counter =
+        AtomicU32::new((TF_IDF_INDEX_DATA_CHUNK_SIZE * TF_IDF_INDEX_DATA_ENTRY_SIZE) as u32 + 6);
 
     let offset = data
         .serialize(&dim_bufman, &data_bufmans, &offset_counter, cursor)
@@ -244,7 +253,8 @@ fn test_tf_idf_index_data_incremental_serialization() {
 
     let data = get_random_tf_idf_index_data(&mut rng, 0.into());
 
-    let offset_counter = AtomicU32::new(TF_IDF_INDEX_DATA_CHUNK_SIZE as u32 * 10 + 6);
+    let offset_counter =
+        AtomicU32::new((TF_IDF_INDEX_DATA_CHUNK_SIZE * TF_IDF_INDEX_DATA_ENTRY_SIZE) as u32 + 6);
 
     let offset = data
         .serialize(&dim_bufman, &data_bufmans, &offset_counter, cursor)
@@ -286,6 +296,7 @@ fn test_tf_idf_index_node_serialization() {
                 rng.gen_range(0..10000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 &cache,
                 0.into(),
             )
@@ -324,6 +335,7 @@ fn test_tf_idf_index_node_incremental_serialization() {
                 rng.gen_range(0..10000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 &cache,
                 0.into(),
             )
@@ -342,6 +354,7 @@ fn test_tf_idf_index_node_incremental_serialization() {
                 rng.gen_range(0..10000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 &cache,
                 1.into(),
             )
@@ -358,6 +371,7 @@ fn test_tf_idf_index_node_incremental_serialization() {
                 rng.gen_range(0..10000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 &cache,
                 2.into(),
             )
@@ -374,6 +388,7 @@ fn test_tf_idf_index_node_incremental_serialization() {
                 rng.gen_range(0..10000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 &cache,
                 3.into(),
             )
@@ -410,11 +425,17 @@ fn test_tf_idf_index_root_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 0.into(),
             )
             .unwrap();
     }
 
+    for document_id in 0..100 {
+        tf_idf_index.add_document(document_id, rng.gen_range(1..100), 0.into());
+    }
+    tf_idf_index.remove_document(42, 0.into());
+
     tf_idf_index.serialize().unwrap();
     tf_idf_index.cache.dim_bufman.flush().unwrap();
     tf_idf_index.cache.data_bufmans.flush_all().unwrap();
@@ -422,6 +443,76 @@ fn test_tf_idf_index_root_serialization() {
     let deserialized = TFIDFIndexRoot::deserialize(temp_dir.as_ref().into()).unwrap();
 
     assert_eq!(tf_idf_index, deserialized);
+    assert_eq!(None, deserialized.document_lengths.lookup(&42));
+    assert_eq!(
+        tf_idf_index.average_document_length(),
+        deserialized.average_document_length()
+    );
+}
+
+#[test]
+fn test_tf_idf_index_root_rejects_unversioned_format() {
+    let temp_dir = tempdir().unwrap();
+    // the unversioned dim files start with the number of documents, directly
+    // followed by the root node
+    let mut dim_file = 100u32.to_le_bytes().to_vec();
+    dim_file.resize(4 + TFIDFIndexNode::get_serialized_size() as usize, 0);
+    std::fs::write(temp_dir.as_ref().join("index-tree.dim"), dim_file).unwrap();
+
+    let err = TFIDFIndexRoot::deserialize(temp_dir.as_ref().into()).unwrap_err();
+
+    assert!(err.to_string().contains("unversioned format"));
+}
+
+#[test]
+fn test_tf_idf_index_root_format_version() {
+    let temp_dir = tempdir().unwrap();
+    let tf_idf_index = TFIDFIndexRoot::new(temp_dir.as_ref().into()).unwrap();
+    tf_idf_index.serialize().unwrap();
+    tf_idf_index.cache.dim_bufman.flush().unwrap();
+    drop(tf_idf_index);
+    let format_version = TFIDFIndexRoot::format_version(temp_dir.as_ref()).unwrap();
+    assert_eq!(format_version, Some(TF_IDF_INDEX_FORMAT_VERSION));
+    assert!(TFIDFIndexRoot::is_supported_format(format_version));
+
+    let mut dim_file = 100u32.to_le_bytes().to_vec();
+    dim_file.resize(4 + TFIDFIndexNode::get_serialized_size() as usize, 0);
+    std::fs::write(temp_dir.as_ref().join("index-tree.dim"), dim_file).unwrap();
+    assert_eq!(
+        TFIDFIndexRoot::format_version(temp_dir.as_ref()).unwrap(),
+        None
+    );
+    assert!(!TFIDFIndexRoot::is_supported_format(None));
+}
+
+#[test]
+fn test_tf_idf_index_root_reads_older_format_version() {
+    let temp_dir = tempdir().unwrap();
+    let tf_idf_index = TFIDFIndexRoot::new(temp_dir.as_ref().into()).unwrap();
+    tf_idf_index.add_document(0, 10, 0.into());
+    tf_idf_index.serialize().unwrap();
+    tf_idf_index.cache.dim_bufman.flush().unwrap();
+
+    // the format version 2 has the same layout as the current one
+    let path = temp_dir.as_ref().join("index-tree.dim");
+    let dim_file = std::fs::read(&path).unwrap();
+    let mut older_dim_file = dim_file.clone();
+    older_dim_file[4..8].copy_from_slice(&2u32.to_le_bytes());
+    std::fs::write(&path, older_dim_file).unwrap();
+
+    let deserialized = TFIDFIndexRoot::deserialize(temp_dir.as_ref().into()).unwrap();
+    assert_eq!(tf_idf_index, deserialized);
+    drop(deserialized);
+
+    let mut newer_dim_file = dim_file;
+    newer_dim_file[4..8].copy_from_slice(&(TF_IDF_INDEX_FORMAT_VERSION + 1).to_le_bytes());
+    std::fs::write(&path, newer_dim_file).unwrap();
+
+    let err = TFIDFIndexRoot::deserialize(temp_dir.as_ref().into()).unwrap_err();
+    assert!(err.to_string().contains(&format!(
+        "format version {}",
+        TF_IDF_INDEX_FORMAT_VERSION + 1
+    )));
 }
 
 #[test]
@@ -436,6 +527,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 0.into(),
             )
             .unwrap();
@@ -449,6 +541,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 1.into(),
             )
             .unwrap();
@@ -462,6 +555,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 2.into(),
             )
             .unwrap();
@@ -475,6 +569,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 3.into(),
             )
             .unwrap();
@@ -488,6 +583,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 4.into(),
             )
             .unwrap();
@@ -501,6 +597,7 @@ fn test_tf_idf_index_root_incremental_serialization() {
                 rng.gen_range(0..10000000),
                 rng.gen_range(0.0..1.0),
                 rng.gen_range(0..u32::MAX),
+                &[rng.gen_range(0..1000)],
                 5.into(),
             )
             .unwrap();
@@ -107,6 +107,9 @@ fn random_raw_vector_embedding(rng: &mut impl Rng) -> RawVectorEmbedding {
         metadata: None,
         sparse_values: None,
         text: None,
+        text_fields: None,
+        dense_fields: None,
+        multi_vector_values: None,
     }
 }
 
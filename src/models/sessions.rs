@@ -0,0 +1,249 @@
+use dashmap::DashMap;
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, Transaction, WriteFlags};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::common::WaCustomError;
+use super::crypto::{get_current_timestamp, SingleSHA256Hash};
+use super::types::UsersMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDetails {
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub refresh_expires_at: u64,
+    pub username: String,
+}
+
+// Persisted session, keyed by the hash of its access token
+#[derive(Serialize, Deserialize)]
+struct SessionRecord {
+    details: SessionDetails,
+    refresh_token_hash: [u8; 32],
+}
+
+// Tokens are never stored as is, so that a copy of the database doesn't
+// leak usable tokens
+fn token_hash(token: &str) -> [u8; 32] {
+    SingleSHA256Hash::new(token.as_bytes()).0
+}
+
+/// Sessions of logged in users, persisted in LMDB so that they survive
+/// restarts
+///
+/// A session is kept until its refresh token expires, even after its access
+/// token has expired, so that it can still be refreshed.
+pub struct SessionStore {
+    env: Arc<Environment>,
+    db: Database,
+    // (access token hash, session)
+    sessions: DashMap<[u8; 32], SessionRecord>,
+    // (refresh token hash, access token hash)
+    refresh_tokens: DashMap<[u8; 32], [u8; 32]>,
+}
+
+impl SessionStore {
+    /// Loads the persisted sessions, dropping the expired ones and the ones
+    /// of users which no longer exist or are disabled
+    pub fn new(env: Arc<Environment>, users_map: &UsersMap) -> Result<Self, WaCustomError> {
+        let db = env.create_db(Some("sessions"), DatabaseFlags::empty())?;
+        let sessions = DashMap::new();
+        let refresh_tokens = DashMap::new();
+        let mut stale = Vec::new();
+        let now = get_current_timestamp();
+
+        let txn = env.begin_ro_txn()?;
+        let mut cursor = txn.open_ro_cursor(db)?;
+        for (key, value) in cursor.iter() {
+            let mut access_hash = [0u8; 32];
+            access_hash.copy_from_slice(key);
+            let record: SessionRecord = serde_cbor::from_slice(value)
+                .map_err(|e| WaCustomError::DeserializationError(e.to_string()))?;
+            let is_valid = now < record.details.refresh_expires_at
+                && users_map
+                    .get_user(&record.details.username)
+                    .is_some_and(|user| !user.disabled);
+            if is_valid {
+                refresh_tokens.insert(record.refresh_token_hash, access_hash);
+                sessions.insert(access_hash, record);
+            } else {
+                stale.push(access_hash);
+            }
+        }
+        drop(cursor);
+        txn.abort();
+
+        let store = Self {
+            env,
+            db,
+            sessions,
+            refresh_tokens,
+        };
+        store.delete_records(&stale)?;
+        Ok(store)
+    }
+
+    fn delete_records(&self, access_hashes: &[[u8; 32]]) -> Result<(), WaCustomError> {
+        if access_hashes.is_empty() {
+            return Ok(());
+        }
+        let mut txn = self.env.begin_rw_txn()?;
+        for access_hash in access_hashes {
+            match txn.del(self.db, access_hash, None) {
+                Ok(()) | Err(lmdb::Error::NotFound) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn remove_by_hash(
+        &self,
+        access_hash: &[u8; 32],
+    ) -> Result<Option<SessionDetails>, WaCustomError> {
+        let Some((_, record)) = self.sessions.remove(access_hash) else {
+            return Ok(None);
+        };
+        self.refresh_tokens.remove(&record.refresh_token_hash);
+        self.delete_records(&[*access_hash])?;
+        Ok(Some(record.details))
+    }
+
+    pub fn insert(
+        &self,
+        access_token: &str,
+        refresh_token: &str,
+        details: SessionDetails,
+    ) -> Result<(), WaCustomError> {
+        let access_hash = token_hash(access_token);
+        let record = SessionRecord {
+            details,
+            refresh_token_hash: token_hash(refresh_token),
+        };
+        let value = serde_cbor::to_vec(&record)
+            .map_err(|e| WaCustomError::SerializationError(e.to_string()))?;
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.put(self.db, &access_hash, &value, WriteFlags::empty())?;
+        txn.commit()?;
+
+        self.refresh_tokens
+            .insert(record.refresh_token_hash, access_hash);
+        self.sessions.insert(access_hash, record);
+        Ok(())
+    }
+
+    /// Returns the session of an access token, regardless of whether the
+    /// access token has expired
+    pub fn get(&self, access_token: &str) -> Option<SessionDetails> {
+        self.sessions
+            .get(&token_hash(access_token))
+            .map(|record| record.details.clone())
+    }
+
+    /// Removes the session of a refresh token and returns it, to be replaced
+    /// by a new session. Returns `None` if the refresh token is unknown or
+    /// has expired.
+    pub fn take_by_refresh_token(
+        &self,
+        refresh_token: &str,
+    ) -> Result<Option<SessionDetails>, WaCustomError> {
+        let Some(access_hash) = self
+            .refresh_tokens
+            .get(&token_hash(refresh_token))
+            .map(|access_hash| *access_hash)
+        else {
+            return Ok(None);
+        };
+        Ok(self
+            .remove_by_hash(&access_hash)?
+            .filter(|details| get_current_timestamp() < details.refresh_expires_at))
+    }
+
+    /// Revokes the session of either an access token or a refresh token,
+    /// returns `None` if the token doesn't belong to any session
+    pub fn revoke(&self, token: &str) -> Result<Option<SessionDetails>, WaCustomError> {
+        let hash = token_hash(token);
+        let access_hash = self
+            .refresh_tokens
+            .get(&hash)
+            .map_or(hash, |access_hash| *access_hash);
+        self.remove_by_hash(&access_hash)
+    }
+
+    /// Revokes all the sessions of a user
+    pub fn revoke_user_sessions(&self, username: &str) -> Result<(), WaCustomError> {
+        self.revoke_where(|details| details.username == username)
+    }
+
+    /// Drops the sessions which can no longer be refreshed
+    pub fn purge_expired(&self) -> Result<(), WaCustomError> {
+        let now = get_current_timestamp();
+        self.revoke_where(|details| now >= details.refresh_expires_at)
+    }
+
+    fn revoke_where(&self, f: impl Fn(&SessionDetails) -> bool) -> Result<(), WaCustomError> {
+        let access_hashes: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|record| f(&record.details))
+            .map(|record| *record.key())
+            .collect();
+        for access_hash in &access_hashes {
+            if let Some((_, record)) = self.sessions.remove(access_hash) {
+                self.refresh_tokens.remove(&record.refresh_token_hash);
+            }
+        }
+        self.delete_records(&access_hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crypto::DoubleSHA256Hash;
+    use tempfile::tempdir;
+
+    fn session(username: &str, refresh_expires_at: u64) -> SessionDetails {
+        let now = get_current_timestamp();
+        SessionDetails {
+            created_at: now,
+            expires_at: now + 60,
+            refresh_expires_at,
+            username: username.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sessions_survive_restart() {
+        let dir = tempdir().unwrap();
+        let env = Arc::new(Environment::new().set_max_dbs(2).open(dir.path()).unwrap());
+        let users_map = UsersMap::new(env.clone()).unwrap();
+        users_map
+            .add_user("alice".to_string(), DoubleSHA256Hash::new(b"secret"))
+            .unwrap();
+        let far = get_current_timestamp() + 3600;
+
+        let store = SessionStore::new(env.clone(), &users_map).unwrap();
+        store.insert("a1", "r1", session("alice", far)).unwrap();
+        store.insert("a2", "r2", session("alice", 1)).unwrap();
+        store.insert("a3", "r3", session("bob", far)).unwrap();
+        drop(store);
+
+        // expired sessions and sessions of unknown users are dropped
+        let store = SessionStore::new(env, &users_map).unwrap();
+        assert_eq!(store.get("a1").unwrap().username, "alice");
+        assert!(store.get("a2").is_none());
+        assert!(store.get("a3").is_none());
+
+        assert!(store.take_by_refresh_token("a1").unwrap().is_none());
+        assert!(store.take_by_refresh_token("r1").unwrap().is_some());
+        assert!(store.get("a1").is_none());
+        assert!(store.take_by_refresh_token("r1").unwrap().is_none());
+
+        store.insert("a4", "r4", session("alice", far)).unwrap();
+        assert!(store.revoke("r4").unwrap().is_some());
+        assert!(store.get("a4").is_none());
+        assert!(store.revoke("a4").unwrap().is_none());
+    }
+}
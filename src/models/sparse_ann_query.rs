@@ -1,4 +1,4 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::Serialize;
 
 use crate::models::buffered_io::BufIoError;
@@ -9,10 +9,10 @@ use std::cell::UnsafeCell;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::iter::Peekable;
-use std::sync::RwLockReadGuard;
+use std::sync::{Arc, RwLockReadGuard};
 
 use super::inverted_index::InvertedIndexRoot;
-use super::tf_idf_index::{TFIDFIndexRoot, TermQuotient};
+use super::tf_idf_index::{TFIDFIndexRoot, TermInfo, TermQuotient};
 use super::versioned_vec::VersionedVecIter;
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -54,6 +54,201 @@ impl Ord for SparseAnnIDFResult {
     }
 }
 
+/// Phrase of a BM25 query, matching the documents that contain it
+#[derive(Debug, Clone)]
+pub struct PositionalPhrase {
+    /// Term hashes with their position in the query
+    pub terms: Vec<(u32, u32)>,
+    /// The terms must appear in order, with at most `slop` extra tokens
+    /// between them
+    pub slop: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occur {
+    /// Optional clause, adds to the score of the documents it matches
+    Should,
+    /// Documents must match the clause
+    Must,
+    /// Documents matching the clause are dropped
+    MustNot,
+}
+
+/// Text field of a TF-IDF index searched by a [`Bm25Query`]
+#[derive(Clone, Copy)]
+pub struct Bm25Field<'a> {
+    pub root: &'a TFIDFIndexRoot,
+    /// Weight of the term frequencies of the field in the scores of the
+    /// terms without a field, `0.0` leaves the field out of them
+    pub weight: f32,
+}
+
+/// Boolean query over the posting lists of a TF-IDF index, scored with BM25
+///
+/// Terms and phrases with a `field`, the position of a field in the
+/// searched ones, only match that field and are scored with its BM25. The
+/// others match all the weighted fields and are scored with BM25F: the
+/// weighted, length-normalized term frequencies of all the fields are
+/// summed before the saturation.
+#[derive(Debug, Clone)]
+pub enum Bm25Query {
+    Term {
+        term_hash: u32,
+        field: Option<usize>,
+        boost: f32,
+    },
+    Phrase {
+        phrase: PositionalPhrase,
+        field: Option<usize>,
+        boost: f32,
+    },
+    /// Matches the documents matching all the `Must` clauses and none of the
+    /// `MustNot` ones. Without `Must` clauses, the documents must match at
+    /// least one `Should` clause.
+    Boolean {
+        clauses: Vec<(Occur, Bm25Query)>,
+        boost: f32,
+    },
+}
+
+impl Bm25Query {
+    pub fn search(
+        &self,
+        fields: &[Bm25Field],
+        params: Bm25Params,
+        k: Option<usize>,
+    ) -> Result<Vec<SparseAnnIDFResult>, BufIoError> {
+        let scorer = Bm25Scorer::new(fields, params);
+        let mut results: Vec<_> = self
+            .evaluate(&scorer)?
+            .into_iter()
+            .map(|(document_id, score)| SparseAnnIDFResult { document_id, score })
+            .collect();
+
+        results.sort_unstable_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(k) = k {
+            results.truncate(k);
+        }
+
+        Ok(results)
+    }
+
+    // Returns the scores of the matching documents
+    fn evaluate(&self, scorer: &Bm25Scorer) -> Result<FxHashMap<u32, f32>, BufIoError> {
+        match self {
+            Self::Term {
+                term_hash,
+                field,
+                boost,
+            } => {
+                let fields = scorer.searched_fields(*field);
+                let mut frequencies: FxHashMap<u32, f32> = FxHashMap::default();
+                for &(field, weight) in &fields {
+                    let Some(term) = find_term(scorer.fields[field].root, *term_hash)? else {
+                        continue;
+                    };
+                    for (document_id, count) in term.documents.read().unwrap().iter() {
+                        *frequencies.entry(document_id).or_default() +=
+                            weight * scorer.normalized_frequency(field, document_id, count);
+                    }
+                }
+                let idf = scorer.idf(*field, frequencies.len() as u32);
+                Ok(frequencies
+                    .into_iter()
+                    .map(|(document_id, tf)| (document_id, scorer.saturate(tf) * idf * boost))
+                    .collect())
+            }
+            Self::Phrase {
+                phrase,
+                field,
+                boost,
+            } => {
+                let fields = scorer.searched_fields(*field);
+                // documents matching the phrase in each field
+                let mut matches = Vec::with_capacity(fields.len());
+                for &(field, _) in &fields {
+                    matches.push(match_phrase(scorer.fields[field].root, phrase)?);
+                }
+                let mut scores: FxHashMap<u32, f32> = matches
+                    .iter()
+                    .flatten()
+                    .map(|&document_id| (document_id, 0.0))
+                    .collect();
+                for &(term_hash, _) in &phrase.terms {
+                    // the idf counts all the documents with the term, the
+                    // frequencies only the fields matching the phrase
+                    let mut documents_with_term = FxHashSet::default();
+                    let mut frequencies: FxHashMap<u32, f32> = FxHashMap::default();
+                    for (&(field, weight), matched) in fields.iter().zip(&matches) {
+                        let Some(term) = find_term(scorer.fields[field].root, term_hash)? else {
+                            continue;
+                        };
+                        for (document_id, count) in term.documents.read().unwrap().iter() {
+                            documents_with_term.insert(document_id);
+                            if matched.contains(&document_id) {
+                                *frequencies.entry(document_id).or_default() +=
+                                    weight * scorer.normalized_frequency(field, document_id, count);
+                            }
+                        }
+                    }
+                    let idf = scorer.idf(*field, documents_with_term.len() as u32);
+                    for (document_id, tf) in frequencies {
+                        if let Some(score) = scores.get_mut(&document_id) {
+                            *score += scorer.saturate(tf) * idf * boost;
+                        }
+                    }
+                }
+                Ok(scores)
+            }
+            Self::Boolean { clauses, boost } => {
+                let mut must: Option<FxHashMap<u32, f32>> = None;
+                let mut should: FxHashMap<u32, f32> = FxHashMap::default();
+                let mut must_not: FxHashSet<u32> = FxHashSet::default();
+
+                for (occur, clause) in clauses {
+                    let scores = clause.evaluate(scorer)?;
+                    match occur {
+                        Occur::Must => {
+                            must = Some(match must {
+                                Some(previous) => previous
+                                    .into_iter()
+                                    .filter_map(|(document_id, score)| {
+                                        Some((document_id, score + scores.get(&document_id)?))
+                                    })
+                                    .collect(),
+                                None => scores,
+                            });
+                        }
+                        Occur::Should => {
+                            for (document_id, score) in scores {
+                                *should.entry(document_id).or_default() += score;
+                            }
+                        }
+                        Occur::MustNot => must_not.extend(scores.into_keys()),
+                    }
+                }
+
+                let mut scores = match must {
+                    Some(mut must) => {
+                        for (document_id, score) in should {
+                            if let Some(must_score) = must.get_mut(&document_id) {
+                                *must_score += score;
+                            }
+                        }
+                        must
+                    }
+                    None => should,
+                };
+                scores.retain(|document_id, _| !must_not.contains(document_id));
+                for score in scores.values_mut() {
+                    *score *= boost;
+                }
+                Ok(scores)
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SparseAnnQueryBasic {
     /// Query vector is a pair of non-zero values and its dimension
@@ -149,12 +344,17 @@ impl SparseAnnQueryBasic {
     pub fn search_bm25(
         self,
         index: &TFIDFIndexRoot,
+        params: Bm25Params,
         k: Option<usize>,
     ) -> Result<Vec<SparseAnnIDFResult>, BufIoError> {
         const BUCKETS: usize = 512;
-        let documents_count = index
-            .total_documents_count
-            .load(std::sync::atomic::Ordering::Relaxed);
+        let scorer = Bm25Scorer::new(
+            &[Bm25Field {
+                root: index,
+                weight: 1.0,
+            }],
+            params,
+        );
         let mut heads = BinaryHeap::new();
         let mut locks = Vec::new();
 
@@ -165,7 +365,8 @@ impl SparseAnnQueryBasic {
                 let data = unsafe { &*node.data }.try_get_data(&index.cache)?;
                 if let Some(term) = data.map.lookup(&quotient) {
                     let documents = term.documents.read().unwrap();
-                    let idf = get_idf(documents_count, documents.len() as u32);
+                    // the length of the list includes the deleted documents
+                    let idf = scorer.idf(Some(0), documents.iter().count() as u32);
 
                     let head = PostingListHead::new(&documents, idf);
                     locks.push(unsafe {
@@ -182,18 +383,18 @@ impl SparseAnnQueryBasic {
         let mut buckets = [(u32::MAX, f32::NEG_INFINITY); BUCKETS];
 
         while let Some(mut head) = heads.pop() {
-            let Some((doc_id, tf)) = head.pop() else {
+            let Some((doc_id, count)) = head.pop() else {
                 continue;
             };
 
-            let mut score = tf * head.idf;
+            let mut score = scorer.term_frequency(0, doc_id, count) * head.idf;
 
             if head.peek().is_some() {
                 heads.push(head);
             }
 
             while let Some(head) = heads.peek() {
-                let Some((doc_id1, tf)) = head.peek() else {
+                let Some((doc_id1, count)) = head.peek() else {
                     heads.pop();
                     continue;
                 };
@@ -201,7 +402,7 @@ impl SparseAnnQueryBasic {
                     break;
                 }
 
-                score += tf * head.idf;
+                score += scorer.term_frequency(0, doc_id, *count) * head.idf;
                 let mut head = heads.pop().unwrap();
                 head.pop();
                 if head.peek().is_some() {
@@ -295,8 +496,188 @@ impl PartialEq for PostingListHead<'_> {
     }
 }
 
+fn find_term(index: &TFIDFIndexRoot, term_hash: u32) -> Result<Option<Arc<TermInfo>>, BufIoError> {
+    let dim_index = term_hash & (u16::MAX as u32);
+    let quotient = (term_hash >> 16) as TermQuotient;
+    let Some(node) = index.find_node(dim_index) else {
+        return Ok(None);
+    };
+    let data = unsafe { &*node.data }.try_get_data(&index.cache)?;
+    Ok(data.map.lookup(&quotient))
+}
+
+// Returns the documents containing the phrase
+fn match_phrase(
+    index: &TFIDFIndexRoot,
+    phrase: &PositionalPhrase,
+) -> Result<FxHashSet<u32>, BufIoError> {
+    let mut term_positions = Vec::with_capacity(phrase.terms.len());
+    for &(term_hash, _) in &phrase.terms {
+        let Some(term) = find_term(index, term_hash)? else {
+            return Ok(FxHashSet::default());
+        };
+        term_positions.push(term.document_positions());
+    }
+    let offsets: Vec<u32> = phrase.terms.iter().map(|(_, offset)| *offset).collect();
+
+    let (first, rest) = term_positions.split_first().unwrap();
+    Ok(first
+        .iter()
+        .filter_map(|(document_id, first_positions)| {
+            let mut positions = vec![&first_positions[..]];
+            for term in rest {
+                positions.push(term.get(document_id)?.as_slice());
+            }
+            phrase_matches_positions(&positions, &offsets, phrase.slop).then_some(*document_id)
+        })
+        .collect())
+}
+
+// Checks if the (sorted) positions of the terms of a phrase in a document
+// contain the phrase. For each start position the earliest possible position
+// of each following term is picked, which minimizes the span of the match.
+fn phrase_matches_positions(positions: &[&[u32]], offsets: &[u32], slop: u32) -> bool {
+    let phrase_span = offsets[offsets.len() - 1] - offsets[0];
+
+    for &start in positions[0] {
+        let mut previous = start;
+        for (term_positions, gap) in positions[1..]
+            .iter()
+            .zip(offsets.windows(2).map(|w| w[1] - w[0]))
+        {
+            let min_position = previous + gap;
+            let idx = term_positions.partition_point(|&position| position < min_position);
+            // later start positions can't match either
+            let Some(&position) = term_positions.get(idx) else {
+                return false;
+            };
+            previous = position;
+        }
+        if previous - start - phrase_span <= slop {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// BM25 parameters, applied at query time
+#[derive(Debug, Clone, Copy)]
+pub struct Bm25Params {
+    pub k1: f32,
+    pub b: f32,
+}
+
+// Computes BM25 scores from the raw term counts of the postings and the live
+// statistics of the fields
+struct Bm25Scorer<'a> {
+    fields: Vec<FieldStatistics<'a>>,
+    // number of documents of the weighted fields, the largest number of
+    // documents of any of them as a document can miss some fields
+    documents_count: u32,
+    params: Bm25Params,
+}
+
+struct FieldStatistics<'a> {
+    root: &'a TFIDFIndexRoot,
+    weight: f32,
+    documents_count: u32,
+    average_document_length: f32,
+}
+
+impl<'a> Bm25Scorer<'a> {
+    fn new(fields: &[Bm25Field<'a>], params: Bm25Params) -> Self {
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|field| FieldStatistics {
+                root: field.root,
+                weight: field.weight,
+                documents_count: field
+                    .root
+                    .total_documents_count
+                    .load(std::sync::atomic::Ordering::Relaxed),
+                average_document_length: field.root.average_document_length(),
+            })
+            .collect();
+        let documents_count = fields
+            .iter()
+            .filter(|field| field.weight > 0.0)
+            .map(|field| field.documents_count)
+            .max()
+            .unwrap_or_default();
+        Self {
+            fields,
+            documents_count,
+            params,
+        }
+    }
+
+    // Fields matched by a term or phrase, with their weights
+    fn searched_fields(&self, field: Option<usize>) -> Vec<(usize, f32)> {
+        match field {
+            Some(field) => vec![(field, 1.0)],
+            None => self
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| field.weight > 0.0)
+                .map(|(i, field)| (i, field.weight))
+                .collect(),
+        }
+    }
+
+    fn idf(&self, field: Option<usize>, documents_containing_term: u32) -> f32 {
+        let documents_count = match field {
+            Some(field) => self.fields[field].documents_count,
+            None => self.documents_count,
+        };
+        get_idf(documents_count, documents_containing_term)
+    }
+
+    // Term count divided by the relative length of the document in the field
+    fn normalized_frequency(&self, field: usize, document_id: u32, count: f32) -> f32 {
+        let field = &self.fields[field];
+        let document_length = field
+            .root
+            .document_lengths
+            .lookup(&document_id)
+            .map_or(field.average_document_length, |length| length as f32);
+        let b = self.params.b;
+        count / (1.0 - b + b * (document_length / field.average_document_length))
+    }
+
+    fn saturate(&self, term_frequency: f32) -> f32 {
+        let k1 = self.params.k1;
+        term_frequency * (k1 + 1.0) / (term_frequency + k1)
+    }
+
+    fn term_frequency(&self, field: usize, document_id: u32, count: f32) -> f32 {
+        self.saturate(self.normalized_frequency(field, document_id, count))
+    }
+}
+
 fn get_idf(documents_count: u32, documents_containing_term: u32) -> f32 {
-    (((documents_count - documents_containing_term) as f32 + 0.5)
+    (((documents_count.saturating_sub(documents_containing_term)) as f32 + 0.5)
         / (documents_containing_term as f32 + 0.5))
         .ln_1p()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::phrase_matches_positions;
+
+    #[test]
+    fn test_phrase_matches_positions() {
+        // exact phrase
+        assert!(phrase_matches_positions(&[&[1, 7], &[8]], &[0, 1], 0));
+        assert!(!phrase_matches_positions(&[&[1, 7], &[9]], &[0, 1], 0));
+        // slop allows extra tokens between the terms, but not reordering
+        assert!(phrase_matches_positions(&[&[1, 7], &[9]], &[0, 1], 1));
+        assert!(!phrase_matches_positions(&[&[7], &[1]], &[0, 1], 10));
+        // gaps of the query (e.g. removed stopwords) must be kept
+        assert!(!phrase_matches_positions(&[&[1], &[2]], &[0, 2], 0));
+        assert!(phrase_matches_positions(&[&[1], &[3]], &[0, 2], 0));
+        // repeated terms
+        assert!(phrase_matches_positions(&[&[4, 5], &[4, 5]], &[0, 1], 0));
+    }
+}
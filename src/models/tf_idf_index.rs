@@ -1,19 +1,24 @@
 use std::{
-    fs::OpenOptions,
-    path::PathBuf,
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering},
         Arc, RwLock,
     },
 };
 
+use rustc_hash::FxHashMap;
+
 use super::{
     atomic_array::AtomicArray,
     buffered_io::{BufIoError, BufferManager, BufferManagerFactory},
     cache_loader::TFIDFIndexCache,
     common::TSHashTable,
     lazy_item::LazyItem,
-    serializer::tf_idf::{TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE},
+    serializer::tf_idf::{
+        TFIDFIndexSerialize, TF_IDF_INDEX_DATA_CHUNK_SIZE, TF_IDF_INDEX_DATA_ENTRY_SIZE,
+    },
     types::FileOffset,
     utils::calculate_path,
     versioned_vec::VersionedVec,
@@ -27,6 +32,9 @@ pub type QuotientMap = TSHashTable<TermQuotient, Arc<TermInfo>>;
 
 pub struct TermInfo {
     pub documents: RwLock<VersionedVec<(u32, f32)>>,
+    // (document id, position) pairs, only populated if the index stores
+    // positions
+    pub positions: RwLock<VersionedVec<(u32, u32)>>,
     pub sequence_idx: u16,
 }
 
@@ -35,15 +43,29 @@ impl TermInfo {
     pub fn new(sequence_idx: u16, version: VersionNumber) -> Self {
         Self {
             documents: RwLock::new(VersionedVec::new(version)),
+            positions: RwLock::new(VersionedVec::new(version)),
             sequence_idx,
         }
     }
+
+    /// Positions of the term in each document, sorted
+    pub fn document_positions(&self) -> FxHashMap<u32, Vec<u32>> {
+        let mut positions: FxHashMap<u32, Vec<u32>> = FxHashMap::default();
+        for (document_id, position) in self.positions.read().unwrap().iter() {
+            positions.entry(document_id).or_default().push(position);
+        }
+        for document_positions in positions.values_mut() {
+            document_positions.sort_unstable();
+        }
+        positions
+    }
 }
 
 #[cfg(test)]
 impl PartialEq for TermInfo {
     fn eq(&self, other: &Self) -> bool {
         *self.documents.read().unwrap() == *other.documents.read().unwrap()
+            && *self.positions.read().unwrap() == *other.positions.read().unwrap()
             && self.sequence_idx == other.sequence_idx
     }
 }
@@ -53,6 +75,7 @@ impl std::fmt::Debug for TermInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TermInfo")
             .field("documents", &self.documents)
+            .field("positions", &self.positions)
             .field("sequence_idx", &self.sequence_idx)
             .finish()
     }
@@ -135,11 +158,42 @@ impl std::fmt::Debug for TFIDFIndexNode {
     }
 }
 
+/// Version of the on-disk format of the TF-IDF index, bumped on every
+/// incompatible change of the index:
+///
+/// 1. unversioned, the postings hold the weights of the terms, computed with
+///    the sampled average document length
+/// 2. the postings hold the raw term counts and the offset & version of the
+///    positions of the terms, and the header the offset & version of the
+///    document lengths
+/// 3. same layout as 2, the index is marked as configured by the
+///    `m:tf_idf_configured` key instead of the sampled average document
+///    length
+pub const TF_IDF_INDEX_FORMAT_VERSION: u32 = 3;
+
+// Oldest format version `deserialize` can read
+const TF_IDF_INDEX_MIN_FORMAT_VERSION: u32 = 2;
+
+// Starts the dim files of the versioned formats, the unversioned ones start
+// with the number of documents instead
+const TF_IDF_INDEX_MAGIC: u32 = u32::from_le_bytes(*b"TFIX");
+
+// The dim file starts with the magic number, the format version, the total
+// number of documents and the offset & version of the document lengths,
+// followed by the root node
+const TF_IDF_INDEX_HEADER_SIZE: u32 = 20;
+
 pub struct TFIDFIndexRoot {
     pub root: TFIDFIndexNode,
     pub cache: TFIDFIndexCache,
     // total number of documents in the index
     pub total_documents_count: AtomicU32,
+    // sum of the lengths (in terms) of the documents in the index
+    pub total_documents_length: AtomicU64,
+    // length of each document, for the BM25 length normalization
+    pub document_lengths: TSHashTable<u32, u32>,
+    // (document id, length) pairs, the persisted form of `document_lengths`
+    pub document_lengths_list: RwLock<VersionedVec<(u32, u32)>>,
 }
 
 #[cfg(test)]
@@ -148,6 +202,10 @@ impl PartialEq for TFIDFIndexRoot {
         self.root == other.root
             && self.total_documents_count.load(Ordering::Relaxed)
                 == other.total_documents_count.load(Ordering::Relaxed)
+            && self.total_documents_length.load(Ordering::Relaxed)
+                == other.total_documents_length.load(Ordering::Relaxed)
+            && *self.document_lengths_list.read().unwrap()
+                == *other.document_lengths_list.read().unwrap()
     }
 }
 
@@ -160,6 +218,11 @@ impl std::fmt::Debug for TFIDFIndexRoot {
                 "total_documents_count",
                 &self.total_documents_count.load(Ordering::Relaxed),
             )
+            .field(
+                "total_documents_length",
+                &self.total_documents_length.load(Ordering::Relaxed),
+            )
+            .field("document_lengths_list", &self.document_lengths_list)
             .finish()
     }
 }
@@ -214,6 +277,7 @@ impl TFIDFIndexNode {
         quotient: TermQuotient,
         value: f32,
         document_id: u32,
+        positions: &[u32],
         cache: &TFIDFIndexCache,
         version: VersionNumber,
     ) -> Result<(), BufIoError> {
@@ -227,14 +291,23 @@ impl TFIDFIndexNode {
                     .write()
                     .unwrap()
                     .push_sorted(version, (document_id, value));
+                let mut term_positions = term.positions.write().unwrap();
+                for &position in positions {
+                    term_positions.push(version, (document_id, position));
+                }
             },
             || {
                 // Create new inner map if quotient not found
                 let mut documents = VersionedVec::new(version);
+                let mut term_positions = VersionedVec::new(version);
                 let sequence_idx = data.map_len.fetch_add(1, Ordering::Relaxed);
                 documents.push(version, (document_id, value));
+                for &position in positions {
+                    term_positions.push(version, (document_id, position));
+                }
                 Arc::new(TermInfo {
                     documents: RwLock::new(documents),
+                    positions: RwLock::new(term_positions),
                     sequence_idx,
                 })
             },
@@ -257,6 +330,14 @@ impl TFIDFIndexNode {
         // Get or create inner map for this quotient
         data.map.with_value_mut(&quotient, |term| {
             term.documents.write().unwrap().delete(version, document_id);
+            let mut positions = term.positions.write().unwrap();
+            let count = positions
+                .iter()
+                .filter(|(id, _)| *id == document_id)
+                .count();
+            for _ in 0..count {
+                positions.delete(version, document_id);
+            }
         });
 
         // Mark node as dirty
@@ -266,7 +347,7 @@ impl TFIDFIndexNode {
 
     /// See [`crate::models::serializer::tf_idf::node`] for how its calculated
     pub fn get_serialized_size() -> u32 {
-        TF_IDF_INDEX_DATA_CHUNK_SIZE as u32 * 10 + 74
+        TF_IDF_INDEX_DATA_CHUNK_SIZE as u32 * TF_IDF_INDEX_DATA_ENTRY_SIZE as u32 + 74
     }
 }
 
@@ -280,7 +361,7 @@ impl TFIDFIndexRoot {
             .open(root_path.join("index-tree.dim"))?;
         let node_size = TFIDFIndexNode::get_serialized_size();
         let dim_bufman = Arc::new(BufferManager::new(dim_file, node_size as usize * 1000)?);
-        let offset_counter = AtomicU32::new(node_size + 4);
+        let offset_counter = AtomicU32::new(node_size + TF_IDF_INDEX_HEADER_SIZE);
         let data_bufmans = Arc::new(BufferManagerFactory::new(
             root_path.clone().into(),
             |root, version: &VersionNumber| root.join(format!("{}.idat", **version)),
@@ -289,12 +370,49 @@ impl TFIDFIndexRoot {
         let cache = TFIDFIndexCache::new(dim_bufman, data_bufmans, offset_counter);
 
         Ok(TFIDFIndexRoot {
-            root: TFIDFIndexNode::new(0, FileOffset(4)),
+            root: TFIDFIndexNode::new(0, FileOffset(TF_IDF_INDEX_HEADER_SIZE)),
             cache,
             total_documents_count: AtomicU32::new(0),
+            total_documents_length: AtomicU64::new(0),
+            document_lengths: TSHashTable::new(16),
+            document_lengths_list: RwLock::new(VersionedVec::new(VersionNumber::from(0))),
         })
     }
 
+    /// Records a new document and its length in terms
+    pub fn add_document(&self, document_id: u32, length: u32, version: VersionNumber) {
+        self.total_documents_count.fetch_add(1, Ordering::Relaxed);
+        self.total_documents_length
+            .fetch_add(length as u64, Ordering::Relaxed);
+        self.document_lengths.insert(document_id, length);
+        self.document_lengths_list
+            .write()
+            .unwrap()
+            .push(version, (document_id, length));
+    }
+
+    pub fn remove_document(&self, document_id: u32, version: VersionNumber) {
+        self.total_documents_count.fetch_sub(1, Ordering::Relaxed);
+        if let Some(length) = self.document_lengths.lookup(&document_id) {
+            self.total_documents_length
+                .fetch_sub(length as u64, Ordering::Relaxed);
+            self.document_lengths.delete(&document_id);
+        }
+        self.document_lengths_list
+            .write()
+            .unwrap()
+            .delete(version, document_id);
+    }
+
+    /// Average length of the documents currently in the index
+    pub fn average_document_length(&self) -> f32 {
+        let count = self.total_documents_count.load(Ordering::Relaxed);
+        if count == 0 {
+            return 1.0;
+        }
+        self.total_documents_length.load(Ordering::Relaxed) as f32 / count as f32
+    }
+
     /// Finds the node at a given dimension
     /// Traverses the tree iteratively and returns a reference to the node.
     pub fn find_node(&self, dim_index: u32) -> Option<&TFIDFIndexNode> {
@@ -311,12 +429,14 @@ impl TFIDFIndexRoot {
         Some(current_node)
     }
 
-    // Inserts vec_id, value at particular node based on path
+    // Inserts vec_id, value and the positions of the term in the document
+    // at particular node based on path
     pub fn insert(
         &self,
         hash_dim: u32,
         value: f32,
         document_id: u32,
+        positions: &[u32],
         version: VersionNumber,
     ) -> Result<(), BufIoError> {
         // Split the hash dimension
@@ -330,7 +450,14 @@ impl TFIDFIndexRoot {
                 .fetch_add(TFIDFIndexNode::get_serialized_size(), Ordering::Relaxed)
         });
         debug_assert_eq!(node.dim_index, storage_dim);
-        node.insert(quotient, value, document_id, &self.cache, version)
+        node.insert(
+            quotient,
+            value,
+            document_id,
+            positions,
+            &self.cache,
+            version,
+        )
     }
 
     pub fn delete(
@@ -353,9 +480,29 @@ impl TFIDFIndexRoot {
 
     pub fn serialize(&self) -> Result<(), BufIoError> {
         let cursor = self.cache.dim_bufman.open_cursor()?;
+        self.cache
+            .dim_bufman
+            .update_u32_with_cursor(cursor, TF_IDF_INDEX_MAGIC)?;
+        self.cache
+            .dim_bufman
+            .update_u32_with_cursor(cursor, TF_IDF_INDEX_FORMAT_VERSION)?;
         self.cache
             .dim_bufman
             .update_u32_with_cursor(cursor, self.total_documents_count.load(Ordering::Relaxed))?;
+        let document_lengths = self.document_lengths_list.read().unwrap();
+        let document_lengths_offset = document_lengths.serialize(
+            &self.cache.dim_bufman,
+            &self.cache.data_bufmans,
+            &self.cache.offset_counter,
+            cursor,
+        )?;
+        self.cache
+            .dim_bufman
+            .update_u32_with_cursor(cursor, document_lengths_offset)?;
+        self.cache
+            .dim_bufman
+            .update_u32_with_cursor(cursor, *document_lengths.version)?;
+        drop(document_lengths);
         self.root.serialize(
             &self.cache.dim_bufman,
             &self.cache.data_bufmans,
@@ -382,21 +529,88 @@ impl TFIDFIndexRoot {
             8192,
         ));
         let cache = TFIDFIndexCache::new(dim_bufman, data_bufmans, offset_counter);
+        let cursor = cache.dim_bufman.open_cursor()?;
+        let magic = cache.dim_bufman.read_u32_with_cursor(cursor)?;
+        let format_version = cache.dim_bufman.read_u32_with_cursor(cursor)?;
+        let format_version = (magic == TF_IDF_INDEX_MAGIC).then_some(format_version);
+        if !Self::is_supported_format(format_version) {
+            cache.dim_bufman.close_cursor(cursor)?;
+            return Err(unsupported_format_error(&root_path, format_version));
+        }
+        let total_documents_count = AtomicU32::new(cache.dim_bufman.read_u32_with_cursor(cursor)?);
+        let document_lengths_offset = cache.dim_bufman.read_u32_with_cursor(cursor)?;
+        let document_lengths_version = cache.dim_bufman.read_u32_with_cursor(cursor)?;
+        cache.dim_bufman.close_cursor(cursor)?;
         let root = TFIDFIndexNode::deserialize(
             &cache.dim_bufman,
             &cache.data_bufmans,
-            FileOffset(4),
+            FileOffset(TF_IDF_INDEX_HEADER_SIZE),
             VersionNumber::from(u32::MAX), // not used
             &cache,
         )?;
-        let cursor = cache.dim_bufman.open_cursor()?;
-        let total_documents_count = AtomicU32::new(cache.dim_bufman.read_u32_with_cursor(cursor)?);
-        cache.dim_bufman.close_cursor(cursor)?;
+        let document_lengths_list = VersionedVec::<(u32, u32)>::deserialize(
+            &cache.dim_bufman,
+            &cache.data_bufmans,
+            FileOffset(document_lengths_offset),
+            VersionNumber::from(document_lengths_version),
+            &cache,
+        )?;
+        let document_lengths = TSHashTable::new(16);
+        let mut total_documents_length = 0;
+        for (document_id, length) in document_lengths_list.iter() {
+            document_lengths.insert(document_id, length);
+            total_documents_length += length as u64;
+        }
 
         Ok(Self {
             root,
             cache,
             total_documents_count,
+            total_documents_length: AtomicU64::new(total_documents_length),
+            document_lengths,
+            document_lengths_list: RwLock::new(document_lengths_list),
         })
     }
+
+    /// Reads the format version of the index at `root_path`, `None` for the
+    /// unversioned format
+    pub fn format_version(root_path: &Path) -> Result<Option<u32>, BufIoError> {
+        let mut header = [0u8; 8];
+        let mut dim_file = File::open(root_path.join("index-tree.dim"))?;
+        match dim_file.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let magic = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let format_version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        Ok((magic == TF_IDF_INDEX_MAGIC).then_some(format_version))
+    }
+
+    /// Whether `deserialize` can read the indexes of the format version
+    pub fn is_supported_format(format_version: Option<u32>) -> bool {
+        format_version.is_some_and(|version| {
+            (TF_IDF_INDEX_MIN_FORMAT_VERSION..=TF_IDF_INDEX_FORMAT_VERSION).contains(&version)
+        })
+    }
+}
+
+// There's no migration from the unversioned format, as it lacks the data of
+// the newer ones. The collections are loaded without the indexes in the
+// formats that can't be read, which can then be dropped and recreated from
+// the documents.
+pub fn unsupported_format_error(root_path: &Path, format_version: Option<u32>) -> BufIoError {
+    let format = match format_version {
+        Some(version) => format!("format version {}", version),
+        None => "the unversioned format".to_string(),
+    };
+    BufIoError::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "TF-IDF index at {} uses {}, expected format version {}; delete and recreate the index",
+            root_path.display(),
+            format,
+            TF_IDF_INDEX_FORMAT_VERSION
+        ),
+    ))
 }
@@ -1,19 +1,22 @@
 use super::{
+    api_keys::ApiKeyStore,
     buffered_io::{BufIoError, BufferManagerFactory},
     cache_loader::HNSWIndexCache,
     collection::{Collection, CollectionMetadata},
     collection_transaction::ImplicitTransaction,
-    crypto::{DoubleSHA256Hash, SingleSHA256Hash},
+    crypto::{get_current_timestamp, DoubleSHA256Hash, SingleSHA256Hash},
     indexing_manager::IndexingManager,
     inverted_index::InvertedIndexRoot,
     meta_persist::{
-        lmdb_init_collections_db, lmdb_init_db, load_collections, retrieve_average_document_length,
+        lmdb_init_collections_db, lmdb_init_db, load_collections, migrate_tf_idf_configured,
         retrieve_background_version, retrieve_current_version, retrieve_highest_internal_id,
-        retrieve_values_upper_bound,
+        retrieve_tf_idf_configured, retrieve_values_upper_bound,
     },
     paths::get_data_path,
     prob_node::ProbNode,
-    tf_idf_index::TFIDFIndexRoot,
+    rbac::{RbacStore, ADMIN_ROLE, GLOBAL_SCOPE},
+    sessions::SessionStore,
+    tf_idf_index::{unsupported_format_error, TFIDFIndexRoot},
     tree_map::{TreeMap, TreeMapKey, TreeMapVec},
     versioning::{VersionControl, VersionNumber},
 };
@@ -33,7 +36,7 @@ use crate::{
             HNSWIndex,
         },
         inverted::InvertedIndex,
-        tf_idf::TFIDFIndex,
+        tf_idf::{analyzer::TextAnalyzer, dictionary::TermDictionary, TFIDFIndex},
         IndexOps,
     },
     metadata::{schema::MetadataDimensions, QueryFilterDimensions, HIGH_WEIGHT},
@@ -59,11 +62,12 @@ use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use siphasher::sip::SipHasher24;
 use std::{
+    collections::HashMap,
     fmt,
     fs::{self, create_dir_all, OpenOptions},
     hash::{Hash as StdHash, Hasher},
     io::Write,
-    ops::{Deref, Div, Mul},
+    ops::{Deref, Div, Mul, RangeInclusive},
     path::{Path, PathBuf},
     str::FromStr,
     sync::{
@@ -209,6 +213,10 @@ pub struct VectorData<'a> {
     pub id: Option<&'a InternalId>,
     pub quantized_vec: &'a Storage,
     pub metadata: Option<&'a Metadata>,
+    // Range of values the stored vectors were quantized with, only
+    // used by query vectors kept in full or half precision to
+    // de-quantize the stored vectors on the fly.
+    pub values_range: Option<(f32, f32)>,
 }
 
 impl<'a> VectorData<'a> {
@@ -217,6 +225,7 @@ impl<'a> VectorData<'a> {
             id,
             quantized_vec: qvec,
             metadata: None,
+            values_range: None,
         }
     }
 
@@ -552,6 +561,9 @@ pub struct CollectionsMap {
     lmdb_hnsw_index_db: Database,
     lmdb_inverted_index_db: Database,
     lmdb_tf_idf_index_db: Database,
+    // Versions which were committed but not indexed before the last
+    // shutdown, see `recover_unindexed_versions`
+    unindexed_versions: parking_lot::Mutex<Vec<(Arc<Collection>, RangeInclusive<u32>)>>,
 }
 
 impl CollectionsMap {
@@ -567,6 +579,7 @@ impl CollectionsMap {
             lmdb_hnsw_index_db: hnsw_index_db,
             lmdb_inverted_index_db: inverted_index_db,
             lmdb_tf_idf_index_db: tf_idf_index_db,
+            unindexed_versions: parking_lot::Mutex::new(Vec::new()),
         };
         Ok(res)
     }
@@ -591,6 +604,11 @@ impl CollectionsMap {
             let current_version = retrieve_current_version(&lmdb)?;
             let vcs = VersionControl::from_existing(lmdb.env.clone(), lmdb.db);
 
+            let max_replicas_per_node = collection_meta
+                .metadata_schema
+                .as_ref()
+                .map_or(1, |schema| schema.max_num_replicas());
+
             // if collection has dense index load it from the lmdb
             let hnsw_index = if collection_meta.dense_vector.enabled {
                 collections_map
@@ -598,11 +616,9 @@ impl CollectionsMap {
                         &collection_meta,
                         &lmdb,
                         &config,
-                        collection_meta
-                            .metadata_schema
-                            .as_ref()
-                            .map_or(1, |schema| schema.max_num_replicas()),
+                        max_replicas_per_node,
                         current_version,
+                        None,
                     )
                     .unwrap()
                     .map(Arc::new)
@@ -610,6 +626,21 @@ impl CollectionsMap {
                 None
             };
 
+            // and the indexes of its named dense fields that have one
+            let mut dense_field_indexes = HashMap::new();
+            for field in &collection_meta.dense_vector.fields {
+                if let Some(hnsw_index) = collections_map.load_hnsw_index(
+                    &collection_meta,
+                    &lmdb,
+                    &config,
+                    max_replicas_per_node,
+                    current_version,
+                    Some(&field.name),
+                )? {
+                    dense_field_indexes.insert(field.name.clone(), Arc::new(hnsw_index));
+                }
+            }
+
             // if collection has inverted index load it from the lmdb
             let inverted_index = if collection_meta.sparse_vector.enabled {
                 collections_map
@@ -684,6 +715,23 @@ impl CollectionsMap {
                 8192,
             );
 
+            let token_to_internal_map_dim_file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .truncate(false)
+                .create(true)
+                .open(collection_path.join("ttoi.dim"))
+                .map_err(BufIoError::Io)?;
+
+            let token_to_internal_map_dim_bufman =
+                BufferManager::new(token_to_internal_map_dim_file, 8192).map_err(BufIoError::Io)?;
+
+            let token_to_internal_map_data_bufmans = BufferManagerFactory::new(
+                collection_path.clone(),
+                |root, version: &VersionNumber| root.join(format!("ttoi.{}.data", **version)),
+                8192,
+            );
+
             let transaction_status_map_dim_file = OpenOptions::new()
                 .read(true)
                 .write(true)
@@ -726,12 +774,17 @@ impl CollectionsMap {
                     document_to_internals_map_dim_bufman,
                     document_to_internals_map_data_bufmans,
                 )?,
+                token_to_internal_map: TreeMap::deserialize(
+                    token_to_internal_map_dim_bufman,
+                    token_to_internal_map_data_bufmans,
+                )?,
                 transaction_status_map: TreeMap::deserialize(
                     transaction_status_map_dim_bufman,
                     transaction_status_map_data_bufmans,
                 )?,
                 internal_id_counter: AtomicU32::new(id_counter_value),
                 hnsw_index: parking_lot::RwLock::new(hnsw_index),
+                dense_field_indexes: parking_lot::RwLock::new(dense_field_indexes),
                 inverted_index: parking_lot::RwLock::new(inverted_index),
                 tf_idf_index: parking_lot::RwLock::new(tf_idf_index),
                 indexing_manager: parking_lot::RwLock::new(None),
@@ -747,16 +800,10 @@ impl CollectionsMap {
             let background_version = retrieve_background_version(&collection.lmdb)?;
 
             if background_version != current_version {
-                for version in *background_version..*current_version {
-                    let version = VersionNumber::from(version + 1);
-                    IndexingManager::index_version_on_restart(
-                        &collection,
-                        &config,
-                        &threadpool,
-                        version,
-                    )
-                    .unwrap();
-                }
+                collections_map.unindexed_versions.lock().push((
+                    collection.clone(),
+                    *background_version + 1..=*current_version,
+                ));
             }
 
             collections_map
@@ -766,7 +813,8 @@ impl CollectionsMap {
         Ok(collections_map)
     }
 
-    /// loads and initiates the dense index of a collection from lmdb
+    /// loads and initiates the dense index of a collection, or of one of its
+    /// named dense fields, from lmdb
     ///
     /// In doing so, the root vec for all collections' dense indexes are loaded into
     /// memory, which also ends up warming the cache (NodeRegistry)
@@ -777,9 +825,10 @@ impl CollectionsMap {
         config: &Config,
         max_replicas_per_node: u8,
         current_version: VersionNumber,
+        field: Option<&str>,
     ) -> Result<Option<HNSWIndex>, WaCustomError> {
         let collection_path: Arc<Path> = get_collections_path().join(&collection_meta.name).into();
-        let index_path = collection_path.join("dense_hnsw");
+        let index_path = HNSWIndex::index_path(&collection_path, field);
 
         // Check if the path exists before proceeding
         if !index_path.exists() {
@@ -789,7 +838,7 @@ impl CollectionsMap {
         let Some(hnsw_index_data) = HNSWIndex::load_data(
             &self.lmdb_env,
             self.lmdb_hnsw_index_db,
-            &collection_meta.name,
+            &HNSWIndex::persisted_name(&collection_meta.name, field),
         )?
         else {
             return Ok(None);
@@ -858,7 +907,7 @@ impl CollectionsMap {
             distance_metric.clone(),
         );
 
-        let values_range_result = retrieve_values_range(lmdb);
+        let values_range_result = retrieve_values_range(lmdb, field);
         let values_range = match values_range_result {
             Ok(vr) => vr,
             Err(e) => {
@@ -1096,6 +1145,7 @@ impl CollectionsMap {
             values_range.is_some(),
             max_replicas_per_node,
             offset_counter,
+            field.map(str::to_string),
         );
 
         Ok(Some(hnsw_index))
@@ -1162,17 +1212,39 @@ impl CollectionsMap {
             return Ok(None);
         };
 
-        let average_document_length = retrieve_average_document_length(lmdb)?;
+        let format_version = TFIDFIndexRoot::format_version(&index_path)?;
+        if !TFIDFIndexRoot::is_supported_format(format_version) {
+            log::warn!(
+                "Loading collection '{}' without its TF-IDF index: {}",
+                collection_meta.name,
+                unsupported_format_error(&index_path, format_version)
+            );
+            return Ok(None);
+        }
+
+        let mut is_configured = retrieve_tf_idf_configured(lmdb)?;
+        if !is_configured && format_version == Some(2) {
+            is_configured = migrate_tf_idf_configured(lmdb)?;
+        }
+        let term_dictionary = inverted_index_data
+            .store_terms
+            .then(|| TermDictionary::open(&index_path))
+            .transpose()?;
+        let fields_path = index_path.join("fields");
+        let fields = TFIDFIndex::load_fields(&fields_path)?;
         let inverted_index = TFIDFIndex {
             root: TFIDFIndexRoot::deserialize(index_path)?,
-            average_document_length: RwLock::new(average_document_length.unwrap_or(1.0)),
-            is_configured: AtomicBool::new(average_document_length.is_some()),
+            is_configured: AtomicBool::new(is_configured),
             documents: RwLock::new(Vec::new()),
             documents_collected: AtomicUsize::new(0),
-            sampling_data: crate::indexes::tf_idf::SamplingData::default(),
             sample_threshold: inverted_index_data.sample_threshold,
             k1: inverted_index_data.k1,
             b: inverted_index_data.b,
+            analyzer: TextAnalyzer::new(inverted_index_data.analyzer),
+            store_positions: inverted_index_data.store_positions,
+            term_dictionary,
+            fields: RwLock::new(fields),
+            fields_path,
         };
 
         Ok(Some(inverted_index))
@@ -1184,11 +1256,19 @@ impl CollectionsMap {
         hnsw_index: Arc<HNSWIndex>,
     ) -> Result<(), WaCustomError> {
         hnsw_index.persist(
-            &collection.meta.name,
+            &HNSWIndex::persisted_name(&collection.meta.name, hnsw_index.field.as_deref()),
             &self.lmdb_env,
             self.lmdb_hnsw_index_db,
         )?;
-        *collection.hnsw_index.write() = Some(hnsw_index);
+        match hnsw_index.field.clone() {
+            Some(field) => {
+                collection
+                    .dense_field_indexes
+                    .write()
+                    .insert(field, hnsw_index);
+            }
+            None => *collection.hnsw_index.write() = Some(hnsw_index),
+        }
         Ok(())
     }
 
@@ -1258,6 +1338,27 @@ impl CollectionsMap {
         }
     }
 
+    pub fn remove_dense_field_index(
+        &self,
+        name: &str,
+        field: &str,
+    ) -> Result<Option<Arc<HNSWIndex>>, WaCustomError> {
+        match self.inner_collections.get(name) {
+            Some(collection) => match collection.dense_field_indexes.write().remove(field) {
+                Some(hnsw_index) => {
+                    HNSWIndex::delete(
+                        &self.lmdb_env,
+                        self.lmdb_hnsw_index_db,
+                        &HNSWIndex::persisted_name(name, Some(field)),
+                    )?;
+                    Ok(Some(hnsw_index))
+                }
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
     pub fn remove_inverted_index(
         &self,
         name: &str,
@@ -1284,7 +1385,16 @@ impl CollectionsMap {
                     TFIDFIndex::delete(&self.lmdb_env, self.lmdb_tf_idf_index_db, name)?;
                     Ok(Some(tf_idf_index))
                 }
-                None => Ok(None),
+                // the indexes in a format that can't be read aren't loaded,
+                // but their data is still persisted
+                None => {
+                    if TFIDFIndex::load_data(&self.lmdb_env, self.lmdb_tf_idf_index_db, name)?
+                        .is_some()
+                    {
+                        TFIDFIndex::delete(&self.lmdb_env, self.lmdb_tf_idf_index_db, name)?;
+                    }
+                    Ok(None)
+                }
             },
             None => Ok(None),
         }
@@ -1318,8 +1428,35 @@ impl CollectionsMap {
     > {
         self.inner_collections.iter()
     }
+
+    /// Indexes the versions which were committed but not indexed before the
+    /// last shutdown, using their WAL files
+    ///
+    /// This is deferred from `load` so that the server can start (and report
+    /// that it's not ready yet) while the recovery is running.
+    pub fn recover_unindexed_versions(
+        &self,
+        config: &Config,
+        threadpool: &ThreadPool,
+    ) -> Result<(), WaCustomError> {
+        let unindexed_versions = std::mem::take(&mut *self.unindexed_versions.lock());
+        for (collection, versions) in unindexed_versions {
+            for version in versions {
+                IndexingManager::index_version_on_restart(
+                    &collection,
+                    config,
+                    threadpool,
+                    VersionNumber::from(version),
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
 
+/// The bootstrapped admin user, which can't be deleted or disabled
+pub const ADMIN_USERNAME: &str = "admin";
+
 pub struct UsersMap {
     env: Arc<Environment>,
     users_db: Database,
@@ -1346,13 +1483,9 @@ impl UsersMap {
         Ok(Self { env, users_db, map })
     }
 
-    pub fn add_user(&self, username: String, password_hash: DoubleSHA256Hash) -> lmdb::Result<()> {
-        let user = User {
-            username: username.clone(),
-            password_hash,
-        };
+    fn put_user(&self, user: User) -> lmdb::Result<()> {
         let user_bytes = user.serialize();
-        let username_bytes = username.as_bytes();
+        let username_bytes = user.username.as_bytes();
 
         let mut txn = self.env.begin_rw_txn()?;
         txn.put(
@@ -1363,32 +1496,158 @@ impl UsersMap {
         )?;
         txn.commit()?;
 
-        self.map.insert(username, user);
+        self.map.insert(user.username.clone(), user);
 
         Ok(())
     }
 
+    /// adds a user, overwriting the existing user with the same username
+    pub fn add_user(&self, username: String, password_hash: DoubleSHA256Hash) -> lmdb::Result<()> {
+        let created_at = self
+            .map
+            .get(&username)
+            .map_or_else(get_current_timestamp, |user| user.created_at);
+        self.put_user(User {
+            username,
+            password_hash,
+            disabled: false,
+            created_at,
+        })
+    }
+
+    /// creates a new user, returns error if the username is already taken
+    pub fn create_user(
+        &self,
+        username: String,
+        password_hash: DoubleSHA256Hash,
+    ) -> Result<User, WaCustomError> {
+        if self.map.contains_key(&username) {
+            return Err(WaCustomError::InvalidData(format!(
+                "User '{}' already exists",
+                username
+            )));
+        }
+        let user = User {
+            username,
+            password_hash,
+            disabled: false,
+            created_at: get_current_timestamp(),
+        };
+        self.put_user(user.clone())?;
+        Ok(user)
+    }
+
     pub fn get_user(&self, username: &str) -> Option<User> {
         self.map.get(username).map(|user| user.value().clone())
     }
+
+    pub fn list_users(&self) -> Vec<User> {
+        let mut users: Vec<_> = self.map.iter().map(|user| user.value().clone()).collect();
+        users.sort_unstable_by(|a, b| a.username.cmp(&b.username));
+        users
+    }
+
+    fn update_user(
+        &self,
+        username: &str,
+        f: impl FnOnce(&mut User),
+    ) -> Result<User, WaCustomError> {
+        let mut user = self
+            .get_user(username)
+            .ok_or_else(|| WaCustomError::NotFound(format!("user '{}'", username)))?;
+        f(&mut user);
+        self.put_user(user.clone())?;
+        Ok(user)
+    }
+
+    pub fn set_password(
+        &self,
+        username: &str,
+        password_hash: DoubleSHA256Hash,
+    ) -> Result<User, WaCustomError> {
+        self.update_user(username, |user| user.password_hash = password_hash)
+    }
+
+    pub fn set_disabled(&self, username: &str, disabled: bool) -> Result<User, WaCustomError> {
+        self.update_user(username, |user| user.disabled = disabled)
+    }
+
+    /// deletes a user, returns `None` if it doesn't exist
+    pub fn delete_user(&self, username: &str) -> Result<Option<User>, WaCustomError> {
+        if !self.map.contains_key(username) {
+            return Ok(None);
+        }
+        let mut txn = self.env.begin_rw_txn()?;
+        txn.del(self.users_db, &username.as_bytes(), None)?;
+        txn.commit()?;
+
+        Ok(self.map.remove(username).map(|(_, user)| user))
+    }
 }
 
 #[derive(Clone)]
 pub struct User {
     pub username: String,
     pub password_hash: DoubleSHA256Hash,
+    pub disabled: bool,
+    pub created_at: u64,
+}
+
+// Starts the serialized users, followed by the version of their layout.
+// Users used to be stored unprefixed, as their password hash followed by
+// their username, which only starts with the magic if the hash does.
+const USER_MAGIC: &[u8; 4] = b"CUSR";
+
+// Version of the layout of the serialized users:
+//
+// 1. a CBOR encoded `UserRecord`
+const USER_FORMAT_VERSION: u8 = 1;
+
+// On-disk representation of `User`
+#[derive(Serialize, Deserialize)]
+struct UserRecord {
+    username: String,
+    password_hash: [u8; 32],
+    disabled: bool,
+    created_at: u64,
 }
 
 impl User {
     fn serialize(&self) -> Vec<u8> {
-        let username_bytes = self.username.as_bytes();
-        let mut buf = Vec::with_capacity(32 + username_bytes.len());
-        buf.extend_from_slice(&self.password_hash.0);
-        buf.extend_from_slice(username_bytes);
+        let record = UserRecord {
+            username: self.username.clone(),
+            password_hash: self.password_hash.0,
+            disabled: self.disabled,
+            created_at: self.created_at,
+        };
+        let mut buf = USER_MAGIC.to_vec();
+        buf.push(USER_FORMAT_VERSION);
+        serde_cbor::to_writer(&mut buf, &record).unwrap();
         buf
     }
 
     fn deserialize(buf: &[u8]) -> Result<Self, String> {
+        let Some(buf) = buf.strip_prefix(USER_MAGIC) else {
+            return Self::deserialize_legacy(buf);
+        };
+        let Some((&version, buf)) = buf.split_first() else {
+            return Err("Missing user format version".to_string());
+        };
+        if version != USER_FORMAT_VERSION {
+            return Err(format!("Unsupported user format version {}", version));
+        }
+        let record: UserRecord = serde_cbor::from_slice(buf).map_err(|err| err.to_string())?;
+        Ok(Self {
+            username: record.username,
+            password_hash: DoubleSHA256Hash(record.password_hash),
+            disabled: record.disabled,
+            created_at: record.created_at,
+        })
+    }
+
+    // Users used to be stored as the 32 byte password hash followed by
+    // the username
+    fn deserialize_legacy(buf: &[u8]) -> Result<Self, String> {
         if buf.len() < 32 {
             return Err("Input must be at least 32 bytes".to_string());
         }
@@ -1399,25 +1658,23 @@ impl User {
         Ok(Self {
             username,
             password_hash: DoubleSHA256Hash(password_hash),
+            disabled: false,
+            created_at: 0,
         })
     }
 }
 
-pub struct SessionDetails {
-    pub created_at: u64,
-    pub expires_at: u64,
-    pub user: User,
-}
-
 // Define the AppEnv struct
 pub struct AppEnv {
     pub collections_map: CollectionsMap,
     pub users_map: UsersMap,
+    pub rbac: RbacStore,
+    pub api_keys: ApiKeyStore,
     pub persist: Arc<Environment>,
     // Single hash, must not be persisted to disk, only the double hash must be
     // written to disk
     pub admin_key: SingleSHA256Hash,
-    pub active_sessions: Arc<DashMap<String, SessionDetails>>,
+    pub active_sessions: SessionStore,
 }
 
 fn get_admin_key(env: Arc<Environment>, args: CosdataArgs) -> lmdb::Result<SingleSHA256Hash> {
@@ -1541,7 +1798,8 @@ pub fn get_app_env(
     create_dir_all(&db_path).map_err(|e| WaCustomError::DatabaseError(e.to_string()))?;
     // Initialize the environment
     let env = Environment::new()
-        .set_max_dbs(10)
+        // every collection has its own named db besides the global ones
+        .set_max_dbs(256)
         .set_map_size(1048576000) // Set the maximum size of the database to 1GB
         .open(&db_path)
         .map_err(|e| WaCustomError::DatabaseError(e.to_string()))?;
@@ -1563,12 +1821,12 @@ pub fn get_app_env(
     };
 
     // Use the admin key as the password instead of hardcoded "admin"
-    let username = "admin".to_string();
+    let username = ADMIN_USERNAME.to_string();
     let password = args.admin_key.clone();
     let password_hash = DoubleSHA256Hash::from_str(&password).unwrap();
 
     // Don't fail if user already exists
-    match users_map.add_user(username, password_hash) {
+    match users_map.add_user(username.clone(), password_hash) {
         Ok(_) => {}
         Err(err) => {
             println!(
@@ -1578,12 +1836,21 @@ pub fn get_app_env(
         }
     };
 
+    let rbac = RbacStore::new(env_arc.clone())?;
+    // The bootstrapped admin user always has full access
+    rbac.assign_role(GLOBAL_SCOPE, &username, ADMIN_ROLE)?;
+
+    let api_keys = ApiKeyStore::new(env_arc.clone())?;
+    let active_sessions = SessionStore::new(env_arc.clone(), &users_map)?;
+
     Ok(Arc::new(AppEnv {
         collections_map,
         users_map,
+        rbac,
+        api_keys,
         persist: env_arc,
         admin_key,
-        active_sessions: Arc::new(DashMap::new()),
+        active_sessions,
     }))
 }
 
@@ -1604,8 +1871,9 @@ impl SparseVector {
 #[cfg(test)]
 mod tests {
     use crate::distance::cosine::CosineSimilarity;
+    use crate::models::crypto::DoubleSHA256Hash;
 
-    use super::MetricResult;
+    use super::{MetricResult, User, USER_FORMAT_VERSION, USER_MAGIC};
 
     #[test]
     fn test_metric_result_ordering() {
@@ -1631,4 +1899,36 @@ mod tests {
 
         assert_eq!(metric_results, correctly_ordered_metric_results);
     }
+
+    #[test]
+    fn test_user_serialization() {
+        let user = User {
+            username: "alice".to_string(),
+            password_hash: DoubleSHA256Hash([7; 32]),
+            disabled: true,
+            created_at: 1234,
+        };
+        let serialized = user.serialize();
+        assert!(serialized.starts_with(USER_MAGIC));
+        assert_eq!(serialized[USER_MAGIC.len()], USER_FORMAT_VERSION);
+        let deserialized = User::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized.username, "alice");
+        assert_eq!(deserialized.password_hash.0, [7; 32]);
+        assert!(deserialized.disabled);
+        assert_eq!(deserialized.created_at, 1234);
+
+        // users persisted before the user management API
+        let mut legacy = vec![9; 32];
+        legacy.extend_from_slice(b"bob");
+        let deserialized = User::deserialize(&legacy).unwrap();
+        assert_eq!(deserialized.username, "bob");
+        assert_eq!(deserialized.password_hash.0, [9; 32]);
+        assert!(!deserialized.disabled);
+
+        // users of a newer layout are rejected rather than misread
+        let mut newer = serialized.clone();
+        newer[USER_MAGIC.len()] = USER_FORMAT_VERSION + 1;
+        assert!(User::deserialize(&newer).is_err());
+        assert!(User::deserialize(USER_MAGIC).is_err());
+    }
 }
@@ -31,6 +31,22 @@ impl VersionedVecItem for (u32, f32) {
     }
 }
 
+impl VersionedVecItem for (u32, u32) {
+    type Id = u32;
+
+    fn id(storage: u64) -> Self::Id {
+        (storage >> 32) as u32
+    }
+
+    fn into_storage(self) -> u64 {
+        ((self.0 as u64) << 32) | (self.1 as u64)
+    }
+
+    fn from_storage(storage: u64) -> Self {
+        ((storage >> 32) as u32, storage as u32)
+    }
+}
+
 impl VersionedVecItem for u32 {
     type Id = u32;
 
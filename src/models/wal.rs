@@ -26,6 +26,17 @@ pub enum VectorOp {
     Delete(VectorId),
 }
 
+/// Version of the layout of the vectors of the upsert records:
+///
+/// 1. unversioned, the vectors end with their text
+/// 2. the vectors also hold their text fields, dense fields & multi-vector
+///    values
+pub const WAL_RECORD_VERSION: u8 = 2;
+
+/// Set in the length of the versioned upsert records, which start with their
+/// version. The unversioned ones start with the number of vectors instead.
+pub const VERSIONED_RECORD_FLAG: u32 = 1 << 30;
+
 pub struct WALFile {
     bufman: FilelessBufferManager,
     cursor: u64,
@@ -179,6 +190,7 @@ impl WALFile {
             VectorOp::Upsert(vectors) => {
                 self.records_upserted
                     .fetch_add(vectors.len() as u32, Ordering::Relaxed);
+                buf.push(WAL_RECORD_VERSION);
                 write_len(&mut buf, vectors.len() as u32);
                 for vector in &*vectors {
                     write_len(&mut buf, vector.id.len() as u32);
@@ -235,9 +247,47 @@ impl WALFile {
                     } else {
                         write_len(&mut buf, 0);
                     }
+
+                    if let Some(text_fields) = &vector.text_fields {
+                        write_len(&mut buf, text_fields.len() as u32);
+                        for (field, text) in text_fields {
+                            write_len(&mut buf, field.len() as u32);
+                            buf.extend(field.as_bytes());
+                            write_len(&mut buf, text.len() as u32);
+                            buf.extend(text.as_bytes());
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
+
+                    if let Some(dense_fields) = &vector.dense_fields {
+                        write_len(&mut buf, dense_fields.len() as u32);
+                        for (field, values) in dense_fields {
+                            write_len(&mut buf, field.len() as u32);
+                            buf.extend(field.as_bytes());
+                            write_len(&mut buf, values.len() as u32);
+                            for val in values {
+                                buf.extend(val.to_le_bytes());
+                            }
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
+
+                    if let Some(multi_vector_values) = &vector.multi_vector_values {
+                        write_len(&mut buf, multi_vector_values.len() as u32);
+                        for values in multi_vector_values {
+                            write_len(&mut buf, values.len() as u32);
+                            for val in values {
+                                buf.extend(val.to_le_bytes());
+                            }
+                        }
+                    } else {
+                        write_len(&mut buf, 0);
+                    }
                 }
                 let len = buf.len() as u32 - 4;
-                buf[0..4].copy_from_slice(&len.to_le_bytes());
+                buf[0..4].copy_from_slice(&(len | VERSIONED_RECORD_FLAG).to_le_bytes());
             }
             VectorOp::Delete(id) => {
                 self.records_deleted.fetch_add(1, Ordering::Relaxed);
@@ -267,7 +317,7 @@ impl WALFile {
         let (len, is_delete) = if len_with_tag & (1u32 << 31) != 0 {
             (0x7FFFFFFF & len_with_tag, true)
         } else {
-            (len_with_tag, false)
+            (len_with_tag & !VERSIONED_RECORD_FLAG, false)
         };
         self.bufman
             .seek_with_cursor(self.cursor, len as u64 + 4 + cursor_pos)?;
@@ -280,6 +330,19 @@ impl WALFile {
             let id = read_string(&self.bufman, cursor)?;
             VectorOp::Delete(VectorId::from(id))
         } else {
+            let version = if len_with_tag & VERSIONED_RECORD_FLAG != 0 {
+                self.bufman.read_u8_with_cursor(cursor)?
+            } else {
+                1
+            };
+            if version > WAL_RECORD_VERSION {
+                return Err(BufIoError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unsupported WAL record version `{}`", version),
+                )));
+            }
+            // the vectors of the unversioned records end with the text
+            let has_fields = version >= 2;
             let len = read_len(&self.bufman, cursor)? as usize;
             let mut vectors = Vec::with_capacity(len);
 
@@ -340,6 +403,61 @@ impl WALFile {
                 };
 
                 let text = read_opt_string(&self.bufman, cursor)?;
+                let text_fields_len = if has_fields {
+                    read_len(&self.bufman, cursor)? as usize
+                } else {
+                    0
+                };
+                let text_fields = if text_fields_len == 0 {
+                    None
+                } else {
+                    let mut text_fields = HashMap::with_capacity(text_fields_len);
+                    for _ in 0..text_fields_len {
+                        let field = read_string(&self.bufman, cursor)?;
+                        let text = read_string(&self.bufman, cursor)?;
+                        text_fields.insert(field, text);
+                    }
+                    Some(text_fields)
+                };
+                let dense_fields_len = if has_fields {
+                    read_len(&self.bufman, cursor)? as usize
+                } else {
+                    0
+                };
+                let dense_fields = if dense_fields_len == 0 {
+                    None
+                } else {
+                    let mut dense_fields = HashMap::with_capacity(dense_fields_len);
+                    for _ in 0..dense_fields_len {
+                        let field = read_string(&self.bufman, cursor)?;
+                        let values_len = read_len(&self.bufman, cursor)? as usize;
+                        let mut values = Vec::with_capacity(values_len);
+                        for _ in 0..values_len {
+                            values.push(self.bufman.read_f32_with_cursor(cursor)?);
+                        }
+                        dense_fields.insert(field, values);
+                    }
+                    Some(dense_fields)
+                };
+                let multi_vector_values_len = if has_fields {
+                    read_len(&self.bufman, cursor)? as usize
+                } else {
+                    0
+                };
+                let multi_vector_values = if multi_vector_values_len == 0 {
+                    None
+                } else {
+                    let mut multi_vector_values = Vec::with_capacity(multi_vector_values_len);
+                    for _ in 0..multi_vector_values_len {
+                        let values_len = read_len(&self.bufman, cursor)? as usize;
+                        let mut values = Vec::with_capacity(values_len);
+                        for _ in 0..values_len {
+                            values.push(self.bufman.read_f32_with_cursor(cursor)?);
+                        }
+                        multi_vector_values.push(values);
+                    }
+                    Some(multi_vector_values)
+                };
 
                 let vector = RawVectorEmbedding {
                     id,
@@ -348,6 +466,9 @@ impl WALFile {
                     metadata,
                     sparse_values,
                     text,
+                    text_fields,
+                    dense_fields,
+                    multi_vector_values,
                 };
                 vectors.push(vector);
             }
@@ -404,6 +525,19 @@ mod tests {
                     .collect(),
             ),
             text: Some(random_string(16)),
+            text_fields: Some(HashMap::from([
+                ("title".to_string(), random_string(8)),
+                ("tags".to_string(), random_string(4)),
+            ])),
+            dense_fields: Some(HashMap::from([(
+                "title_emb".to_string(),
+                (0..dense_len).map(|_| rng.gen()).collect(),
+            )])),
+            multi_vector_values: Some(
+                (0..rng.gen_range(1..4))
+                    .map(|_| (0..dense_len).map(|_| rng.gen()).collect())
+                    .collect(),
+            ),
         }
     }
 
@@ -415,10 +549,10 @@ mod tests {
     fn test_random_upsert_persistence() {
         let dir = tempdir().unwrap();
         let version = 0;
+        let vectors: Vec<_> = (0..3).map(|_| random_vector()).collect();
 
         {
             let wal = WALFile::new().unwrap();
-            let vectors: Vec<_> = (0..3).map(|_| random_vector()).collect();
             wal.append(VectorOp::Upsert(vectors.clone())).unwrap();
             wal.flush(dir.as_ref(), VersionNumber::from(version))
                 .unwrap();
@@ -430,6 +564,12 @@ mod tests {
             match result {
                 Some(VectorOp::Upsert(read_vecs)) => {
                     assert_eq!(read_vecs.len(), 3);
+                    for (read_vec, vector) in read_vecs.iter().zip(&vectors) {
+                        assert_eq!(read_vec.text, vector.text);
+                        assert_eq!(read_vec.text_fields, vector.text_fields);
+                        assert_eq!(read_vec.dense_fields, vector.dense_fields);
+                        assert_eq!(read_vec.multi_vector_values, vector.multi_vector_values);
+                    }
                 }
                 _ => panic!("Expected VectorOp::Upsert"),
             }
@@ -523,4 +663,56 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_unversioned_upsert_records() {
+        let dir = tempdir().unwrap();
+        let version = 0;
+        let wal = WALFile::new().unwrap();
+        // an upsert record of the layout before the text fields, dense fields
+        // & multi-vector values
+        let mut buf = Vec::new();
+        write_len(&mut buf, 1);
+        write_len(&mut buf, 2);
+        buf.extend(b"v1");
+        write_len(&mut buf, 0);
+        write_len(&mut buf, 1);
+        buf.extend(0.5f32.to_le_bytes());
+        write_len(&mut buf, 0);
+        write_len(&mut buf, 0);
+        write_len(&mut buf, 4);
+        buf.extend(b"rust");
+        let mut record = (buf.len() as u32).to_le_bytes().to_vec();
+        record.extend(buf);
+        wal.bufman
+            .write_to_end_of_file(wal.cursor, &record)
+            .unwrap();
+        let vector = random_vector();
+        wal.append(VectorOp::Upsert(vec![vector.clone()])).unwrap();
+        wal.flush(dir.as_ref(), VersionNumber::from(version))
+            .unwrap();
+
+        let wal = reopen_wal(dir.path(), version);
+        match wal.read().unwrap() {
+            Some(VectorOp::Upsert(read_vecs)) => {
+                assert_eq!(read_vecs.len(), 1);
+                assert_eq!(read_vecs[0].id, VectorId::from("v1".to_string()));
+                assert_eq!(read_vecs[0].dense_values, Some(vec![0.5]));
+                assert_eq!(read_vecs[0].text, Some("rust".to_string()));
+                assert_eq!(read_vecs[0].text_fields, None);
+                assert_eq!(read_vecs[0].dense_fields, None);
+                assert_eq!(read_vecs[0].multi_vector_values, None);
+            }
+            _ => panic!("Expected VectorOp::Upsert"),
+        }
+        match wal.read().unwrap() {
+            Some(VectorOp::Upsert(read_vecs)) => {
+                assert_eq!(read_vecs[0].id, vector.id);
+                assert_eq!(read_vecs[0].text_fields, vector.text_fields);
+                assert_eq!(read_vecs[0].multi_vector_values, vector.multi_vector_values);
+            }
+            _ => panic!("Expected VectorOp::Upsert"),
+        }
+        assert!(wal.read().unwrap().is_none());
+    }
 }
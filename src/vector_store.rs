@@ -1,6 +1,9 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
 use crate::config_loader::Config;
+use crate::distance::cosine::CosineSimilarity;
+use crate::distance::dotproduct::DotProductDistance;
+use crate::distance::euclidean::euclidean_distance_f32;
 use crate::distance::DistanceFunction;
 use crate::indexes::hnsw::offset_counter::HNSWIndexFileOffsetCounter;
 use crate::indexes::hnsw::offset_counter::IndexFileId;
@@ -13,12 +16,12 @@ use crate::indexes::InternalSearchResult;
 use crate::metadata;
 use crate::metadata::fields_to_dimensions;
 use crate::metadata::pseudo_level_probs;
+use crate::metadata::query_filtering::Filter;
 use crate::metadata::MetadataFields;
 use crate::metadata::MetadataSchema;
 use crate::metadata::HIGH_WEIGHT;
 use crate::models::cache_loader::HNSWIndexCache;
 use crate::models::collection::Collection;
-use crate::models::collection::RawVectorEmbedding;
 use crate::models::common::*;
 use crate::models::dot_product::dot_product_f32;
 use crate::models::file_persist::*;
@@ -253,6 +256,11 @@ pub fn create_pseudo_root_node(
     Ok(root_ptr)
 }
 
+/// Default maximum number of candidates kept per level by a filtered
+/// search, see `ann_search`
+pub const DEFAULT_FILTERED_CANDIDATES_LIMIT: usize = 100;
+
+#[allow(clippy::too_many_arguments)]
 pub fn ann_search(
     config: &Config,
     hnsw_index: &HNSWIndex,
@@ -261,6 +269,7 @@ pub fn ann_search(
     current_lazy_item_latest_ptr: SharedLatestNode,
     cur_level: HNSWLevel,
     hnsw_params: &HNSWHyperParams,
+    filtered_candidates_limit: usize,
 ) -> Result<Vec<(SharedLatestNode, MetricResult)>, WaCustomError> {
     let fvec = vector_emb.quantized_vec.clone();
     let mut skipm = PerformantFixedSet::new(if cur_level.0 == 0 {
@@ -308,7 +317,7 @@ pub fn ann_search(
             z_candidates.sort_by_key(|c| Reverse(c.1));
             z_candidates
                 .into_iter()
-                .take(100) // Limit the number of results
+                .take(filtered_candidates_limit)
                 .collect::<Vec<_>>()
         }
         None => traverse_find_nearest(
@@ -345,6 +354,7 @@ pub fn ann_search(
                     id: Some(cur_node_id),
                     quantized_vec: &current_node.prop_value.vec,
                     metadata: cur_node_metadata.as_deref(),
+                    values_range: None,
                 };
                 let mut dists = vec![];
                 for qfd in qf_dims {
@@ -353,6 +363,7 @@ pub fn ann_search(
                         id: None,
                         quantized_vec: &fvec,
                         metadata: Some(&fvec_metadata),
+                        values_range: Some(*hnsw_index.values_range.read().unwrap()),
                     };
                     let d = hnsw_index.distance_metric.read().unwrap().calculate(
                         &fvec_data,
@@ -364,7 +375,10 @@ pub fn ann_search(
                 dists.into_iter().max().unwrap()
             }
             None => {
-                let fvec_data = VectorData::without_metadata(None, &fvec);
+                let fvec_data = VectorData {
+                    values_range: Some(*hnsw_index.values_range.read().unwrap()),
+                    ..VectorData::without_metadata(None, &fvec)
+                };
                 let cur_node_data =
                     VectorData::without_metadata(Some(cur_node_id), &current_node.prop_value.vec);
                 hnsw_index.distance_metric.read().unwrap().calculate(
@@ -393,6 +407,7 @@ pub fn ann_search(
             child,
             HNSWLevel(cur_level.0 - 1),
             hnsw_params,
+            filtered_candidates_limit,
         )?;
 
         z.extend(results);
@@ -411,7 +426,7 @@ pub fn finalize_ann_results(
 ) -> Result<Vec<InternalSearchResult>, WaCustomError> {
     let filtered = remove_duplicates_and_filter(hnsw_index, results, top_k, &hnsw_index.cache);
     let mut results = Vec::with_capacity(top_k.unwrap_or(filtered.len()));
-    let mag_query = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let query = RawQuery::new(hnsw_index, query)?;
 
     for (internal_id, _) in filtered {
         let raw_emb = collection
@@ -419,17 +434,203 @@ pub fn finalize_ann_results(
             .ok_or_else(|| {
                 WaCustomError::NotFound(format!("raw embedding not found for id={internal_id:?}"))
             })?;
-        let dense_values = raw_emb.dense_values.as_ref().ok_or_else(|| {
+        let dense_values = hnsw_index.raw_vector(raw_emb).ok_or_else(|| {
             WaCustomError::NotFound("dense values not found for raw embedding".to_string())
         })?;
-        let dp = dot_product_f32(query, dense_values);
-        let mag_raw = dense_values.iter().map(|x| x * x).sum::<f32>().sqrt();
-        let cs = dp / (mag_query * mag_raw);
+        let distance = query.distance(hnsw_index, dense_values)?;
+        results.push((
+            distance,
+            (
+                internal_id,
+                Some(raw_emb.id.clone()),
+                raw_emb.document_id.clone(),
+                distance.get_value(),
+                if return_raw_text {
+                    raw_emb.text.clone()
+                } else {
+                    None
+                },
+            ),
+        ));
+    }
+    results.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    if let Some(k) = top_k {
+        results.truncate(k);
+    }
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Scans the raw dense vectors indexed by `hnsw_index` of all the
+/// embeddings of the collection for the `top_k` ones nearest to the
+/// query, skipping the ones not matching the filter if any
+///
+/// Unlike `ann_search`, the results are exact, which makes them the
+/// ground truth to measure the recall of the index against. The
+/// scores are the same distances of the metric of the index
+/// `finalize_ann_results` returns.
+pub fn exact_search(
+    collection: &Collection,
+    hnsw_index: &HNSWIndex,
+    query: &[f32],
+    filter: Option<&Filter>,
+    top_k: Option<usize>,
+    return_raw_text: bool,
+) -> Result<Vec<InternalSearchResult>, WaCustomError> {
+    let raw_query = RawQuery::new(hnsw_index, query)?;
+    let mut results = Vec::new();
+
+    for (internal_id, raw_emb) in collection.raw_embeddings() {
+        let Some(dense_values) = hnsw_index.raw_vector(raw_emb) else {
+            continue;
+        };
+        if filter.is_some_and(|filter| !filter.matches(raw_emb.metadata.as_ref())) {
+            continue;
+        }
+        if dense_values.len() != query.len() {
+            return Err(WaCustomError::InvalidData(format!(
+                "Expected dimension of dense vector to be {}, found {}",
+                dense_values.len(),
+                query.len()
+            )));
+        }
+        let distance = raw_query.distance(hnsw_index, dense_values)?;
+        results.push((
+            distance,
+            (
+                internal_id,
+                Some(raw_emb.id.clone()),
+                raw_emb.document_id.clone(),
+                distance.get_value(),
+                if return_raw_text {
+                    raw_emb.text.clone()
+                } else {
+                    None
+                },
+            ),
+        ));
+    }
+
+    if let Some(k) = top_k {
+        if results.len() > k {
+            results.select_nth_unstable_by(k, |(a, _), (b, _)| b.cmp(a));
+            results.truncate(k);
+        }
+    }
+    results.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+// A query scored against the raw dense vectors with the distance metric of
+// the index, so that the results are ranked as its traversal ranks them
+struct RawQuery<'a> {
+    metric: DistanceMetric,
+    values: &'a [f32],
+    mag: f32,
+    // hamming distances are only defined between quantized vectors, so for
+    // them the query and the raw vectors are quantized like the stored ones
+    quantized: Option<Storage>,
+}
+
+impl<'a> RawQuery<'a> {
+    fn new(hnsw_index: &HNSWIndex, values: &'a [f32]) -> Result<Self, WaCustomError> {
+        let metric = *hnsw_index.distance_metric.read().unwrap();
+        let quantized = match metric {
+            DistanceMetric::Hamming => Some(quantize_raw_vector(hnsw_index, values)?),
+            _ => None,
+        };
+        Ok(Self {
+            metric,
+            values,
+            mag: values.iter().map(|x| x * x).sum::<f32>().sqrt(),
+            quantized,
+        })
+    }
+
+    fn distance(&self, hnsw_index: &HNSWIndex, raw: &[f32]) -> Result<MetricResult, WaCustomError> {
+        let distance = match (self.metric, &self.quantized) {
+            (DistanceMetric::Cosine, _) => {
+                let mag_raw = raw.iter().map(|x| x * x).sum::<f32>().sqrt();
+                MetricResult::CosineSimilarity(CosineSimilarity(
+                    dot_product_f32(self.values, raw) / (self.mag * mag_raw),
+                ))
+            }
+            (DistanceMetric::Euclidean, _) => {
+                MetricResult::EuclideanDistance(euclidean_distance_f32(self.values, raw))
+            }
+            (DistanceMetric::DotProduct, _) => MetricResult::DotProductDistance(
+                DotProductDistance(dot_product_f32(self.values, raw)),
+            ),
+            (DistanceMetric::Hamming, Some(quantized)) => {
+                let raw = quantize_raw_vector(hnsw_index, raw)?;
+                self.metric.calculate(
+                    &VectorData::without_metadata(None, quantized),
+                    &VectorData::without_metadata(None, &raw),
+                    false,
+                )?
+            }
+            (DistanceMetric::Hamming, None) => unreachable!("hamming queries are quantized"),
+        };
+        Ok(distance)
+    }
+}
+
+fn quantize_raw_vector(hnsw_index: &HNSWIndex, values: &[f32]) -> Result<Storage, WaCustomError> {
+    Ok(hnsw_index.quantization_metric.read().unwrap().quantize(
+        values,
+        *hnsw_index.storage_type.read().unwrap(),
+        *hnsw_index.values_range.read().unwrap(),
+    )?)
+}
+
+/// Returns the late interaction score of a multi-vector embedding, the sum
+/// over the query vectors of their highest cosine similarity to any of the
+/// token vectors of the embedding
+pub fn maxsim_score(query_vectors: &[Vec<f32>], token_vectors: &[Vec<f32>]) -> f32 {
+    let token_mags = token_vectors
+        .iter()
+        .map(|token| token.iter().map(|x| x * x).sum::<f32>().sqrt())
+        .collect::<Vec<_>>();
+
+    query_vectors
+        .iter()
+        .map(|query| {
+            let mag_query = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+            token_vectors
+                .iter()
+                .zip(&token_mags)
+                .map(|(token, mag_token)| dot_product_f32(query, token) / (mag_query * mag_token))
+                .fold(f32::NEG_INFINITY, f32::max)
+        })
+        .sum()
+}
+
+/// Re-ranks the candidate multi-vector embeddings by the MaxSim of their
+/// raw token vectors against the query vectors, see `maxsim_score`, and
+/// returns the `top_k` best ones
+///
+/// Candidates without token vectors, or deleted since they were found,
+/// are skipped.
+pub fn rerank_multi_vector_candidates(
+    collection: &Collection,
+    candidates: impl IntoIterator<Item = InternalId>,
+    query_vectors: &[Vec<f32>],
+    top_k: Option<usize>,
+    return_raw_text: bool,
+) -> Vec<InternalSearchResult> {
+    let mut results = Vec::new();
+
+    for internal_id in candidates {
+        let Some(raw_emb) = collection.internal_to_external_map.get_latest(&internal_id) else {
+            continue;
+        };
+        let Some(token_vectors) = &raw_emb.multi_vector_values else {
+            continue;
+        };
         results.push((
             internal_id,
             Some(raw_emb.id.clone()),
             raw_emb.document_id.clone(),
-            cs,
+            maxsim_score(query_vectors, token_vectors),
             if return_raw_text {
                 raw_emb.text.clone()
             } else {
@@ -437,11 +638,15 @@ pub fn finalize_ann_results(
             },
         ));
     }
-    results.sort_unstable_by(|(_, _, _, a, _), (_, _, _, b, _)| b.total_cmp(a));
+
     if let Some(k) = top_k {
-        results.truncate(k);
+        if results.len() > k {
+            results.select_nth_unstable_by(k, |(_, _, _, a, _), (_, _, _, b, _)| b.total_cmp(a));
+            results.truncate(k);
+        }
     }
-    Ok(results)
+    results.sort_unstable_by(|(_, _, _, a, _), (_, _, _, b, _)| b.total_cmp(a));
+    results
 }
 
 /// Intermediate representation of the embedding in a form that's
@@ -832,12 +1037,14 @@ pub fn index_embedding(
             id: None,
             quantized_vec: fvec,
             metadata: mdims.as_deref(),
+            values_range: None,
         };
         let cur_node_metadata = current_node.prop_metadata.clone().map(|pm| pm.vec.clone());
         let cur_node_data = VectorData {
             id: Some(cur_node_id),
             quantized_vec: &current_node.prop_value.vec,
             metadata: cur_node_metadata.as_deref(),
+            values_range: None,
         };
         let dist = hnsw_index.distance_metric.read().unwrap().calculate(
             &fvec_data,
@@ -1132,6 +1339,7 @@ fn traverse_find_nearest(
         id: fvec_id,
         quantized_vec: fvec,
         metadata: mdims,
+        values_range: Some(*hnsw_index.values_range.read().unwrap()),
     };
 
     let start_metadata = start_node.prop_metadata.clone().map(|pm| pm.vec.clone());
@@ -1140,6 +1348,7 @@ fn traverse_find_nearest(
         id: Some(&start_node_id),
         quantized_vec: &start_node.prop_value.vec,
         metadata: start_metadata.as_deref(),
+        values_range: None,
     };
     let start_dist = distance_metric.calculate(&fvec_data, &start_vec_data, is_indexing)?;
 
@@ -1182,6 +1391,7 @@ fn traverse_find_nearest(
                     id: Some(&neighbor_node_id),
                     quantized_vec: &neighbor_node.prop_value.vec,
                     metadata: neighbor_metadata.as_deref(),
+                    values_range: None,
                 };
                 let dist =
                     distance_metric.calculate(&fvec_data, &neighbor_vec_data, is_indexing)?;
@@ -1208,12 +1418,8 @@ pub fn delete_embedding(
     hnsw_index: &HNSWIndex,
     version: VersionNumber,
     id: InternalId,
-    raw_emb: &RawVectorEmbedding,
+    raw_vec: &[f32],
 ) -> Result<(), WaCustomError> {
-    let Some(raw_vec) = &raw_emb.dense_values else {
-        return Ok(());
-    };
-
     let quantized_vec = hnsw_index.quantization_metric.read().unwrap().quantize(
         raw_vec,
         *hnsw_index.storage_type.read().unwrap(),
@@ -1311,6 +1517,7 @@ pub fn delete_embedding(
                     id: Some(&neighbor_id),
                     quantized_vec: &neighbor_node.prop_value.vec,
                     metadata: neighbor_metadata.as_deref(),
+                    values_range: None,
                 };
                 let mut results = results.clone();
                 let mut neighbor_idx = None;
@@ -1334,6 +1541,7 @@ pub fn delete_embedding(
                         id: Some(&node_id),
                         quantized_vec: &node.prop_value.vec,
                         metadata: metadata.as_deref(),
+                        values_range: None,
                     };
                     *score = distance_metric.calculate(&neighbor_vec, &vec_data, false)?;
                 }
@@ -1369,3 +1577,38 @@ pub fn delete_embedding(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maxsim_score_sums_best_token_matches() {
+        let tokens = vec![vec![1.0, 0.0], vec![0.0, 2.0]];
+
+        // each query vector matches one of the tokens exactly
+        let score = maxsim_score(&[vec![3.0, 0.0], vec![0.0, 1.0]], &tokens);
+        assert!((score - 2.0).abs() < 1e-6);
+
+        // the best match of each query vector counts, not the sum of all
+        let score = maxsim_score(&[vec![1.0, 1.0]], &tokens);
+        assert!((score - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_maxsim_score_ignores_token_order() {
+        let query = vec![vec![0.5, 0.2, 0.1], vec![-0.3, 0.9, 0.4]];
+        let tokens = vec![
+            vec![0.1, 0.8, 0.3],
+            vec![0.7, 0.1, 0.0],
+            vec![0.2, 0.2, 0.9],
+        ];
+        let mut reversed = tokens.clone();
+        reversed.reverse();
+
+        assert_eq!(
+            maxsim_score(&query, &tokens),
+            maxsim_score(&query, &reversed)
+        );
+    }
+}
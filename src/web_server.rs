@@ -1,5 +1,8 @@
 use crate::api::auth::{auth_module, authentication_middleware::AuthenticationMiddleware};
 use crate::api::docs::api_docs_module;
+use crate::api::health::{health_module, middleware::RecoveryGateMiddleware};
+use crate::api::metrics::{metrics_module, middleware::RequestMetricsMiddleware};
+use crate::api::vectordb::access::access_module;
 use crate::api::vectordb::collections::collections_module;
 use crate::api::vectordb::indexes::indexes_module;
 use crate::api::vectordb::search::search_module;
@@ -49,23 +52,26 @@ pub async fn run_actix_server_with_context(ctx: Data<AppContext>) -> std::io::Re
             // ensure the CORS middleware is wrapped around the httpauth middleware
             // so it is able to add headers to error responses
             .wrap(Cors::permissive())
+            .wrap(RequestMetricsMiddleware)
             // register simple handler, handle all methods
             .app_data(web::JsonConfig::default().limit(8_388_608)) // 8 MB)
             .app_data(ctx.clone())
             .service(api_docs_module())
-            .service(auth_module())
+            .service(metrics_module(ctx.ain_env.clone()))
+            .configure(|cfg| health_module(cfg, ctx.ain_env.clone()))
+            .service(auth_module(ctx.ain_env.clone()))
             .service(
                 web::scope("/vectordb")
-                    .wrap(AuthenticationMiddleware(
-                        ctx.ain_env.active_sessions.clone(),
-                    ))
+                    .wrap(AuthenticationMiddleware(ctx.ain_env.clone()))
                     // vectors module must be registered before collections module
                     // as its scope path is more specific than collections module
-                    .service(search_module())
-                    .service(indexes_module())
-                    .service(vectors_module())
-                    .service(transactions_module())
-                    .service(streaming_module())
+                    .service(access_module())
+                    // searches and writes wait for the restart recovery
+                    .service(search_module().wrap(RecoveryGateMiddleware))
+                    .service(indexes_module().wrap(RecoveryGateMiddleware))
+                    .service(vectors_module().wrap(RecoveryGateMiddleware))
+                    .service(transactions_module().wrap(RecoveryGateMiddleware))
+                    .service(streaming_module().wrap(RecoveryGateMiddleware))
                     .service(version_module())
                     .service(collections_module()),
             )
@@ -81,9 +87,8 @@ pub async fn run_actix_server_with_context(ctx: Data<AppContext>) -> std::io::Re
 }
 
 fn load_rustls_config(ssl_config: &Ssl) -> rustls::ServerConfig {
-    rustls::crypto::aws_lc_rs::default_provider()
-        .install_default()
-        .unwrap();
+    // the gRPC server may have installed it already
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
     // init server config builder with safe defaults
     let config = ServerConfig::builder().with_no_client_auth();